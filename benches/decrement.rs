@@ -0,0 +1,109 @@
+// `cargo bench --bench decrement`: the classic decrement loop, which runs
+// opcodes 0, 4, 5, 6 and 9 in twenty steps per iteration, interpreted,
+// compiled, on the closure and bytecode engines, and transpiled to Rust by
+// `nuuk compile`. With `--features jit`, on the jit engine as well. With `--features road`, everything runs on the road
+// allocator, and the interpreter once more with a road per evaluation. With `--features sync`, four loops at once make
+// a tuple for `parallel(true)`, timed per iteration of one of them. With
+// `--features slab` and not `road`, everything runs on the slab allocator,
+// whose counts are printed at the end. Each size is run a few times and the
+// fastest kept, timings on a busy machine only get worse.
+
+use std::time::{Duration, Instant};
+
+use nuuk::{CompiledFormula, Engine, Formula, Interpreter, Noun, Subject, bench::DECREMENT};
+
+include!("kernels/decrement.rs");
+
+const RUNS: u32 = 5;
+
+#[cfg(feature = "road")]
+#[global_allocator]
+static ALLOC: nuuk::road::Road = nuuk::road::Road;
+
+#[cfg(all(feature = "slab", not(feature = "road")))]
+#[global_allocator]
+static ALLOC: nuuk::slab::Slab = nuuk::slab::Slab;
+
+fn main() {
+  let formula: Noun = DECREMENT.parse().unwrap();
+  let compiled = CompiledFormula::new(&formula);
+  let mut it = Interpreter::new();
+  let mut closures = Interpreter::builder().engine(Engine::Closure).build();
+  let mut vm = Interpreter::builder().engine(Engine::Bytecode).build();
+  #[cfg(feature = "jit")]
+  let mut jit = Interpreter::builder().engine(Engine::Jit).build();
+  #[cfg(feature = "road")]
+  let mut roads = Interpreter::builder().road(true).build();
+  #[cfg(feature = "sync")]
+  let mut par = Interpreter::builder().parallel(true).build();
+
+  for n in [10_000u64, 100_000, 1_000_000] {
+    bench(&format!("decrement {n:>9}"), n, || {
+      it.eval(Subject(Noun::from(n)), Formula(formula.clone()))
+        .unwrap()
+    });
+    bench(&format!("compiled  {n:>9}"), n, || {
+      it.eval_compiled(Subject(Noun::from(n)), &compiled).unwrap()
+    });
+    bench(&format!("closure   {n:>9}"), n, || {
+      closures
+        .eval(Subject(Noun::from(n)), Formula(formula.clone()))
+        .unwrap()
+    });
+    bench(&format!("bytecode  {n:>9}"), n, || {
+      vm.eval(Subject(Noun::from(n)), Formula(formula.clone()))
+        .unwrap()
+    });
+    #[cfg(feature = "jit")]
+    bench(&format!("jit       {n:>9}"), n, || {
+      jit
+        .eval(Subject(Noun::from(n)), Formula(formula.clone()))
+        .unwrap()
+    });
+    bench(&format!("rust      {n:>9}"), n, || {
+      decrement::eval(&mut it, Noun::from(n)).unwrap()
+    });
+    #[cfg(feature = "sync")]
+    bench(&format!("parallel  {n:>9}"), n, || {
+      // `{*{n decrement} *{n decrement} ...}`
+      let one = Noun::cell(
+        Noun::from(2u64),
+        Noun::cell(
+          Noun::cell(Noun::from(1u64), Noun::from(n)),
+          Noun::cell(Noun::from(1u64), formula.clone()),
+        ),
+      );
+      let four = (0..3).fold(one.clone(), |tuple, _| Noun::cell(one.clone(), tuple));
+      par
+        .eval(Subject(Noun::from(0u64)), Formula(four))
+        .unwrap()
+        .slot(2)
+        .unwrap()
+    });
+    #[cfg(feature = "road")]
+    bench(&format!("road      {n:>9}"), n, || {
+      roads
+        .eval(Subject(Noun::from(n)), Formula(formula.clone()))
+        .unwrap()
+    });
+  }
+
+  #[cfg(all(feature = "slab", not(feature = "road")))]
+  println!("{:?}", nuuk::slab::stats());
+}
+
+fn bench(name: &str, n: u64, mut decrement: impl FnMut() -> Noun) {
+  let mut best = Duration::MAX;
+
+  for _ in 0..RUNS {
+    let start = Instant::now();
+    let prod = decrement();
+    best = best.min(start.elapsed());
+    assert_eq!(prod, Noun::from(n - 1));
+  }
+
+  println!(
+    "{name}: {best:>12.3?}, {:.1} ns per iteration",
+    best.as_nanos() as f64 / n as f64
+  );
+}
@@ -0,0 +1,63 @@
+// generated by `nuuk compile --emit rust`, don't edit
+
+#[allow(unused, clippy::all)]
+pub mod decrement {
+  use std::rc::Rc;
+
+  use nuuk::{Formula, Interpreter, NockError, Noun, Subject};
+
+  pub fn eval(it: &mut Interpreter, subject: Noun) -> Result<Noun, NockError> {
+    let k = consts();
+    run(it, &k, subject, k[0].clone())
+  }
+
+  enum Next {
+    Done(Noun),
+    Tail(Noun, Noun),
+  }
+
+  fn run(it: &mut Interpreter, k: &[Noun], mut s: Noun, mut f: Noun) -> Result<Noun, NockError> {
+    loop {
+      let next = if f == k[0] {
+        f0(it, k, s)?
+      } else if f == k[2] {
+        f2(it, k, s)?
+      } else {
+        return it.eval(Subject(s), Formula(f));
+      };
+      match next {
+        Next::Done(product) => return Ok(product),
+        Next::Tail(subject, formula) => (s, f) = (subject, formula),
+      }
+    }
+  }
+
+  fn cond(noun: Noun) -> Result<bool, NockError> {
+    match noun.as_atom().and_then(|atom| atom.as_u64()) {
+      Some(0) => Ok(true),
+      Some(1) => Ok(false),
+      _ => Err(NockError::NonLoobeanCondition { value: noun }),
+    }
+  }
+
+  fn consts() -> Rc<[Noun]> {
+    thread_local! {
+      static K: Rc<[Noun]> = {
+        let mut k: Vec<Noun> = vec![];
+        k.push("{8 {1 0} 8 {1 6 {5 {0 7} 4 0 6} {0 6} 9 2 {0 2} {4 0 6} 0 7} 9 2 0 1}".parse().unwrap());
+        k.push("0".parse().unwrap());
+        k.push("{6 {5 {0 7} 4 0 6} {0 6} 9 2 {0 2} {4 0 6} 0 7}".parse().unwrap());
+        k.into()
+      };
+    }
+    K.with(Rc::clone)
+  }
+
+  fn f0(it: &mut Interpreter, k: &[Noun], s: Noun) -> Result<Next, NockError> {
+    { let s1 = Noun::cell(k[1].clone(), s.clone()); { let s2 = Noun::cell(k[2].clone(), s1.clone()); { let c3 = s2.slot(1)?; let arm = c3.slot(2)?; return Ok(Next::Tail(c3, arm)); } } }
+  }
+
+  fn f2(it: &mut Interpreter, k: &[Noun], s: Noun) -> Result<Next, NockError> {
+    if cond(Noun::from(s.slot(7)? == Noun::atom((s.slot(6)?).try_atom()?.incr())))? { return Ok(Next::Done(s.slot(6)?)); } else { { let c1 = Noun::cell(s.slot(2)?, Noun::cell(Noun::atom((s.slot(6)?).try_atom()?.incr()), s.slot(7)?)); let arm = c1.slot(2)?; return Ok(Next::Tail(c1, arm)); } }
+  }
+}
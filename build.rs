@@ -0,0 +1,5 @@
+fn main() {
+  // linker flags a Node.js addon needs on some platforms
+  #[cfg(feature = "node")]
+  napi_build::setup();
+}
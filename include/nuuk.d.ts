@@ -0,0 +1,23 @@
+// declarations for the Node.js addon the `node` feature builds, see
+// src/node.rs
+
+/// <reference types="node" />
+
+export declare class Noun {
+  static parse(text: string): Noun
+  static cue(bytes: Buffer): Noun
+  static cell(head: Noun, tail: Noun): Noun
+  jam(): Buffer
+  toString(): string
+  isCell(): boolean
+  head(): Noun | null
+  tail(): Noun | null
+  equals(other: Noun): boolean
+}
+
+export declare function evaluate(subject: Noun, formula: Noun): Noun
+export declare function evaluateAsync(subject: Noun, formula: Noun): Promise<Noun>
+export declare function parse(text: string): Noun
+export declare function print(noun: Noun): string
+export declare function jam(noun: Noun): Buffer
+export declare function cue(bytes: Buffer): Noun
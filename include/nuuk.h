@@ -0,0 +1,76 @@
+#ifndef NUUK_H
+#define NUUK_H
+
+// generated by cbindgen from src/ffi.rs, see `test_header`
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum NuukStatus {
+  NUUK_STATUS_OK = 0,
+  NUUK_STATUS_CRASH = 1,
+  NUUK_STATUS_BAD_INPUT = 2,
+  NUUK_STATUS_PANIC = 3,
+} NuukStatus;
+
+typedef struct NuukBuffer {
+  uint8_t *ptr;
+  size_t len;
+} NuukBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Evaluates the jammed `formula` against the jammed `subject`, and writes
+ * the jammed product, or why there isn't one, to `out`.
+ *
+ * # Safety
+ *
+ * `subject` and `formula` are null, for no bytes, or valid for reads of
+ * their lengths, and `out` is valid for writes.
+ */
+enum NuukStatus nuuk_eval(const uint8_t *subject,
+                          size_t subject_len,
+                          const uint8_t *formula,
+                          size_t formula_len,
+                          struct NuukBuffer *out);
+
+/**
+ * Jams the noun written as UTF-8 `text`, and writes its bytes to `out`.
+ *
+ * # Safety
+ *
+ * `text` is null, for no bytes, or valid for reads of `len` bytes, and
+ * `out` is valid for writes.
+ */
+enum NuukStatus nuuk_jam(const uint8_t *text, size_t len, struct NuukBuffer *out);
+
+/**
+ * Cues the jammed `bytes`, and writes the noun as UTF-8 text to `out`.
+ *
+ * # Safety
+ *
+ * `bytes` is null, for no bytes, or valid for reads of `len` bytes, and
+ * `out` is valid for writes.
+ */
+enum NuukStatus nuuk_cue(const uint8_t *bytes, size_t len, struct NuukBuffer *out);
+
+/**
+ * Frees a buffer one of the other functions wrote.
+ *
+ * # Safety
+ *
+ * `buffer` came from this library and hasn't been freed already.
+ */
+void nuuk_free(struct NuukBuffer buffer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NUUK_H */
@@ -0,0 +1,140 @@
+// nock! builds a formula noun from Nock notation, checking its shape at
+// compile time:
+//
+//   nock!([4 0 1])              increment the subject
+//   nock!([[0 2] 1 42])         autocons
+//   nock!([6 [0 1] [1 2] ~])    `~` is 0
+//   nock!([2 (formula) 0 1])    `(expr)` splices anything `Into<Noun>`
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+mod tree;
+
+use tree::{Tree, validate};
+
+#[proc_macro]
+pub fn nock(input: TokenStream) -> TokenStream {
+  let mut splices = vec![];
+
+  let tree = match parse(input, Span::call_site(), &mut splices) {
+    Ok(tree) => tree,
+    Err((span, message)) => return compile_error(span, &message),
+  };
+
+  if let Err((span, message)) = validate(&tree) {
+    return compile_error(span, &message);
+  }
+
+  generate(&tree, &mut splices.into_iter())
+}
+
+fn parse(
+  input: TokenStream,
+  span: Span,
+  splices: &mut Vec<TokenStream>,
+) -> Result<Tree<Span>, (Span, String)> {
+  let mut items = vec![];
+
+  for token in input {
+    items.push(match token {
+      TokenTree::Literal(literal) => atom(&literal)?,
+      TokenTree::Punct(punct) if punct.as_char() == '~' => Tree::Atom(0, punct.span()),
+      TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+        let tree = parse(group.stream(), group.span(), splices)?;
+        if !matches!(tree, Tree::Cell(..)) {
+          return Err((group.span(), "brackets need at least two nouns".to_string()));
+        }
+        tree
+      }
+      TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+        splices.push(group.stream());
+        Tree::Splice(group.span())
+      }
+      token => {
+        return Err((
+          token.span(),
+          "expected an atom, `~`, `[..]` or `(expr)`".to_string(),
+        ));
+      }
+    });
+  }
+
+  let mut items = items.into_iter().rev();
+  let Some(mut tree) = items.next() else {
+    return Err((span, "expected a noun".to_string()));
+  };
+
+  for item in items {
+    tree = Tree::Cell(Box::new(item), Box::new(tree), span);
+  }
+
+  Ok(tree)
+}
+
+fn atom(literal: &Literal) -> Result<Tree<Span>, (Span, String)> {
+  let text = literal.to_string().replace('_', "");
+  let text = text.strip_suffix("u64").unwrap_or(&text);
+
+  let parsed = if let Some(hex) = text.strip_prefix("0x") {
+    u64::from_str_radix(hex, 16)
+  } else if let Some(bin) = text.strip_prefix("0b") {
+    u64::from_str_radix(bin, 2)
+  } else {
+    text.parse()
+  };
+
+  parsed
+    .map(|atom| Tree::Atom(atom, literal.span()))
+    .map_err(|_| (literal.span(), "expected an unsigned integer".to_string()))
+}
+
+// pre-order, so splices are consumed in source order
+fn generate(tree: &Tree<Span>, splices: &mut impl Iterator<Item = TokenStream>) -> TokenStream {
+  match tree {
+    Tree::Atom(atom, _) => call(
+      "::nuuk::Noun::atom",
+      call(
+        "::nuuk::Atom::from",
+        TokenTree::Literal(Literal::u64_suffixed(*atom)).into(),
+      ),
+    ),
+    Tree::Cell(car, cdr, _) => {
+      let mut args = generate(car, splices);
+      args.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+      args.extend(generate(cdr, splices));
+      call("::nuuk::Noun::cell", args)
+    }
+    Tree::Splice(_) => call("::nuuk::Noun::from", splices.next().unwrap_or_default()),
+  }
+}
+
+fn call(path: &str, args: TokenStream) -> TokenStream {
+  let mut call: TokenStream = path.parse().unwrap();
+  call.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+  call
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+  let mut message = Literal::string(message);
+  message.set_span(span);
+
+  let mut group = Group::new(Delimiter::Brace, TokenTree::Literal(message).into());
+  group.set_span(span);
+
+  [
+    TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+    TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+    TokenTree::Ident(Ident::new("core", span)),
+    TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+    TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+    TokenTree::Ident(Ident::new("compile_error", span)),
+    TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+    TokenTree::Group(group),
+  ]
+  .into_iter()
+  .map(|mut token| {
+    token.set_span(span);
+    token
+  })
+  .collect()
+}
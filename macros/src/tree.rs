@@ -0,0 +1,149 @@
+// The parsed literal, generic over the span type so validation can be
+// tested without a compiler session.
+pub enum Tree<S> {
+  Atom(u64, S),
+  Cell(Box<Tree<S>>, Box<Tree<S>>, S),
+  // a spliced Rust expression, unknown until runtime
+  Splice(S),
+}
+
+impl<S: Copy> Tree<S> {
+  pub fn span(&self) -> S {
+    match self {
+      Tree::Atom(_, span) | Tree::Cell(_, _, span) | Tree::Splice(span) => *span,
+    }
+  }
+
+  fn cell(&self) -> Option<(&Tree<S>, &Tree<S>)> {
+    match self {
+      Tree::Cell(car, cdr, _) => Some((car, cdr)),
+      _ => None,
+    }
+  }
+}
+
+pub type Invalid<S> = (S, String);
+
+// Checks opcode arity and shapes of a formula per Nock 4K. Splices are
+// accepted anywhere.
+pub fn validate<S: Copy>(formula: &Tree<S>) -> Result<(), Invalid<S>> {
+  let mut stack = vec![formula];
+
+  while let Some(formula) = stack.pop() {
+    let (op, arg) = match formula {
+      Tree::Splice(_) => continue,
+      Tree::Atom(_, span) => return Err((*span, "a formula must be a cell".to_string())),
+      Tree::Cell(op, arg, _) => (&**op, &**arg),
+    };
+
+    let opcode = match op {
+      Tree::Splice(_) => continue,
+      Tree::Cell(..) => {
+        stack.push(op);
+        stack.push(arg);
+        continue;
+      }
+      Tree::Atom(opcode, _) => *opcode,
+    };
+
+    match opcode {
+      0 => axis(arg)?,
+      1 => {}
+      3 | 4 => stack.push(arg),
+      2 | 5 | 7 | 8 | 12 => {
+        let (b, c) = pair(arg, opcode)?;
+        stack.push(b);
+        stack.push(c);
+      }
+      6 => {
+        let (b, cd) = pair(arg, opcode)?;
+        let (c, d) = pair(cd, opcode)?;
+        stack.push(b);
+        stack.push(c);
+        stack.push(d);
+      }
+      9 => {
+        let (b, c) = pair(arg, opcode)?;
+        axis(b)?;
+        stack.push(c);
+      }
+      10 => {
+        let (bc, d) = pair(arg, opcode)?;
+        let (b, c) = pair(bc, opcode)?;
+        axis(b)?;
+        stack.push(c);
+        stack.push(d);
+      }
+      11 => {
+        let (b, c) = pair(arg, opcode)?;
+        if let Some((_tag, clue)) = b.cell() {
+          stack.push(clue);
+        }
+        stack.push(c);
+      }
+      _ => return Err((op.span(), format!("unknown opcode {opcode}"))),
+    }
+  }
+
+  Ok(())
+}
+
+fn pair<S: Copy>(arg: &Tree<S>, opcode: u64) -> Result<(&Tree<S>, &Tree<S>), Invalid<S>> {
+  match arg {
+    Tree::Splice(span) => Err((
+      *span,
+      format!("opcode {opcode} needs more arguments than a single splice"),
+    )),
+    _ => arg
+      .cell()
+      .ok_or_else(|| (arg.span(), format!("opcode {opcode} needs more arguments"))),
+  }
+}
+
+fn axis<S: Copy>(arg: &Tree<S>) -> Result<(), Invalid<S>> {
+  match arg {
+    Tree::Atom(0, span) => Err((*span, "axis can't be zero".to_string())),
+    Tree::Atom(..) | Tree::Splice(_) => Ok(()),
+    Tree::Cell(_, _, span) => Err((*span, "an axis must be an atom".to_string())),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Tree, validate};
+
+  fn a(atom: u64) -> Tree<()> {
+    Tree::Atom(atom, ())
+  }
+
+  fn c(car: Tree<()>, cdr: Tree<()>) -> Tree<()> {
+    Tree::Cell(Box::new(car), Box::new(cdr), ())
+  }
+
+  #[test]
+  fn test_valid_formulas() {
+    assert!(validate(&c(a(0), a(1))).is_ok());
+    assert!(validate(&c(a(4), c(a(0), a(1)))).is_ok());
+    assert!(validate(&c(c(a(0), a(2)), c(a(1), a(42)))).is_ok());
+    assert!(validate(&c(a(6), c(c(a(1), a(0)), c(c(a(1), a(2)), c(a(1), a(3)))))).is_ok());
+    assert!(validate(&c(a(9), c(a(2), c(a(0), a(1))))).is_ok());
+    assert!(validate(&c(a(10), c(c(a(2), c(a(0), a(3))), c(a(0), a(1))))).is_ok());
+    assert!(validate(&c(a(11), c(c(a(1), c(a(0), a(1))), c(a(0), a(1))))).is_ok());
+    assert!(validate(&c(a(2), c(Tree::Splice(()), c(a(1), a(0))))).is_ok());
+  }
+
+  #[test]
+  fn test_invalid_formulas() {
+    let err = |t: Tree<()>| validate(&t).unwrap_err().1;
+
+    assert_eq!(err(a(1)), "a formula must be a cell");
+    assert_eq!(err(c(a(0), a(0))), "axis can't be zero");
+    assert_eq!(err(c(a(0), c(a(1), a(2)))), "an axis must be an atom");
+    assert_eq!(
+      err(c(a(6), c(c(a(0), a(1)), a(7)))),
+      "opcode 6 needs more arguments"
+    );
+    assert_eq!(err(c(a(4), a(3))), "a formula must be a cell");
+    assert_eq!(err(c(a(13), a(0))), "unknown opcode 13");
+  }
+}
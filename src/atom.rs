@@ -0,0 +1,508 @@
+#[cfg(not(feature = "tagged"))]
+use crate::noun::Rc;
+use crate::{bits, math};
+
+// a natural number of any size. Atoms that fit in a word are stored directly,
+// larger ones as little-endian words without trailing zero words, so equal
+// atoms always have the same words. With the mmap feature, the big atoms of a
+// jam file cued out of a map of it keep their bits in the map until they're
+// read, see src/serial.rs.
+#[derive(Clone, Debug)]
+#[repr(transparent)]
+pub struct Atom(Repr);
+
+// with the tagged feature, an atom is one word, see src/tagged.rs
+#[cfg(feature = "tagged")]
+use crate::tagged::Word as Repr;
+
+#[cfg(not(feature = "tagged"))]
+#[derive(Clone, Debug)]
+enum Repr {
+  Direct(u64),
+  Indirect(Rc<[u64]>),
+  // bits still in a mapped jam file, see src/serial.rs
+  #[cfg(feature = "mmap")]
+  Mapped(Rc<crate::serial::Mapped>),
+}
+
+impl Atom {
+  #[cfg(not(feature = "tagged"))]
+  pub const fn new(atom: u64) -> Self {
+    Self(Repr::Direct(atom))
+  }
+
+  // not const with the tagged feature, where atoms of 2^63 and up have words
+  // of their own
+  #[cfg(feature = "tagged")]
+  pub fn new(atom: u64) -> Self {
+    Self(Repr::new(atom))
+  }
+
+  // direct either way, so it can be made in a const
+  pub(crate) const fn small(atom: u8) -> Self {
+    Self(Repr::small(atom))
+  }
+
+  // little-endian words, trailing zero words are ignored
+  pub fn from_words(words: &[u64]) -> Self {
+    let len = words
+      .iter()
+      .rposition(|&word| word != 0)
+      .map_or(0, |i| i + 1);
+
+    match &words[..len] {
+      [] => Self::new(0),
+      [word] => Self::new(*word),
+      words => Self(Repr::indirect(words)),
+    }
+  }
+
+  // an atom of more than one word, with its top bit set
+  #[cfg(feature = "mmap")]
+  pub(crate) fn mapped(mapped: crate::serial::Mapped) -> Self {
+    Self(Repr::mapped(mapped))
+  }
+
+  // little-endian words, at least one and without trailing zero words
+  pub fn words(&self) -> &[u64] {
+    self.0.words()
+  }
+
+  // `f` over the words, without copying a mapped atom's out of its file
+  pub(crate) fn fold_words(&self, init: u64, f: impl Fn(u64, u64) -> u64) -> u64 {
+    #[cfg(feature = "mmap")]
+    if let Some(mapped) = self.0.as_mapped() {
+      return mapped.fold(init, f);
+    }
+    self.words().iter().fold(init, |acc, &word| f(acc, word))
+  }
+
+  // whether the words haven't been copied out of a mapped file yet
+  #[cfg(all(test, feature = "mmap"))]
+  pub(crate) fn is_mapped(&self) -> bool {
+    self.0.as_mapped().is_some_and(|mapped| mapped.in_map())
+  }
+
+  // trailing zero bytes are ignored
+  pub fn from_le_bytes(bytes: &[u8]) -> Self {
+    let words: Vec<u64> = bytes
+      .chunks(8)
+      .map(|chunk| {
+        let mut word = [0; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u64::from_le_bytes(word)
+      })
+      .collect();
+
+    Self::from_words(&words)
+  }
+
+  // little-endian bytes without trailing zero bytes, so 0 is empty
+  pub fn as_le_bytes(&self) -> Vec<u8> {
+    let mut bytes: Vec<u8> = self
+      .words()
+      .iter()
+      .flat_map(|word| word.to_le_bytes())
+      .collect();
+
+    let len = bytes
+      .iter()
+      .rposition(|&byte| byte != 0)
+      .map_or(0, |i| i + 1);
+    bytes.truncate(len);
+    bytes
+  }
+
+  // text stored LSB first, so the first character is the lowest byte.
+  // Trailing NULs don't survive the trip, as in every other cord.
+  pub fn from_cord(cord: &str) -> Self {
+    Self::from_le_bytes(cord.as_bytes())
+  }
+
+  pub fn to_cord(&self) -> Result<String, std::string::FromUtf8Error> {
+    String::from_utf8(self.as_le_bytes())
+  }
+
+  pub fn as_u64(&self) -> Option<u64> {
+    self.0.as_u64()
+  }
+
+  // the allocation the words are in and about how big it is, None if they're
+  // in the atom itself or a mapped file
+  #[cfg(feature = "road")]
+  pub(crate) fn block(&self) -> Option<(*const u8, usize)> {
+    self.0.block()
+  }
+
+  pub fn as_u128(&self) -> Option<u128> {
+    match *self.words() {
+      [lo] => Some(lo.into()),
+      [lo, hi] => Some(u128::from(hi) << 64 | u128::from(lo)),
+      _ => None,
+    }
+  }
+
+  // zigzag signed integers, as in @s: 0, -1, 1, -2, 2... are 0, 1, 2, 3, 4...
+  pub fn from_i64(n: i64) -> Self {
+    Self::new(((n << 1) ^ (n >> 63)) as u64)
+  }
+
+  pub fn to_i64(&self) -> Option<i64> {
+    let n = self.as_u64()?;
+    Some(((n >> 1) as i64) ^ -((n & 1) as i64))
+  }
+
+  // zigzag for any magnitude
+  pub fn from_signed(negative: bool, magnitude: &Atom) -> Self {
+    let doubled = bits::lsh(0, 1, magnitude).expect("one bit more than the magnitude fits");
+
+    if negative && !magnitude.is_zero() {
+      math::sub(&doubled, &Self::new(1)).expect("the doubled magnitude is positive")
+    } else {
+      doubled
+    }
+  }
+
+  // whether the zigzag atom is negative, and its magnitude
+  pub fn to_signed(&self) -> (bool, Atom) {
+    let negative = self.words()[0] & 1 == 1;
+    let magnitude = if negative { self.incr() } else { self.clone() };
+
+    (negative, bits::rsh(0, 1, &magnitude))
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.as_u64() == Some(0)
+  }
+
+  pub fn incr(&self) -> Atom {
+    if let Some(atom) = self.as_u64()
+      && let Some(atom) = atom.checked_add(1)
+    {
+      return Self::new(atom);
+    }
+
+    let mut words = self.words().to_vec();
+    mul_add(&mut words, 1, 1);
+    Self::from_words(&words)
+  }
+
+  // the atom written in `radix` (2 to 36), None if `digits` is empty or has
+  // anything but digits
+  pub(crate) fn from_digits(digits: &str, radix: u32) -> Option<Atom> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+      return None;
+    }
+
+    if let Ok(atom) = u64::from_str_radix(digits, radix) {
+      return Some(Self::new(atom));
+    }
+
+    let mut words = vec![0];
+    for c in digits.chars() {
+      mul_add(&mut words, radix.into(), c.to_digit(radix)?.into());
+    }
+
+    Some(Self::from_words(&words))
+  }
+}
+
+// words = words * mul + add
+pub(crate) fn mul_add(words: &mut Vec<u64>, mul: u64, add: u64) {
+  let mut carry = u128::from(add);
+
+  for word in words.iter_mut() {
+    let wide = u128::from(*word) * u128::from(mul) + carry;
+    *word = wide as u64;
+    carry = wide >> 64;
+  }
+
+  if carry != 0 {
+    words.push(carry as u64);
+  }
+}
+
+// words = words / div, returning the remainder
+pub(crate) fn div_rem(words: &mut [u64], div: u64) -> u64 {
+  let mut rem = 0u128;
+
+  for word in words.iter_mut().rev() {
+    let wide = (rem << 64) | u128::from(*word);
+    *word = (wide / u128::from(div)) as u64;
+    rem = wide % u128::from(div);
+  }
+
+  rem as u64
+}
+
+pub const YES: u64 = 0;
+pub const NAH: u64 = 1;
+
+// words compared this many at a time, as one slice, which is a memcmp
+const CHUNK: usize = 8;
+
+// the numbers in little-endian `a` and `b`, which may have trailing zero
+// words. The most significant words are compared first, a chunk at a time,
+// and only the chunk that differs word by word.
+pub(crate) fn cmp_words(a: &[u64], b: &[u64]) -> std::cmp::Ordering {
+  let trim = |words: &[u64]| {
+    words
+      .iter()
+      .rposition(|&word| word != 0)
+      .map_or(0, |i| i + 1)
+  };
+  let (a, b) = (&a[..trim(a)], &b[..trim(b)]);
+
+  a.len().cmp(&b.len()).then_with(|| {
+    a.rchunks(CHUNK)
+      .zip(b.rchunks(CHUNK))
+      .find(|(x, y)| x != y)
+      .map_or(std::cmp::Ordering::Equal, |(x, y)| {
+        x.iter().rev().cmp(y.iter().rev())
+      })
+  })
+}
+
+impl PartialEq for Atom {
+  fn eq(&self, other: &Self) -> bool {
+    match (self.as_u64(), other.as_u64()) {
+      (Some(a), Some(b)) => a == b,
+      (Some(_), _) | (_, Some(_)) => false,
+      // shared words are equal without reading them
+      _ => self.0.same(&other.0) || self.words() == other.words(),
+    }
+  }
+}
+
+impl Eq for Atom {}
+
+impl std::hash::Hash for Atom {
+  // equal atoms have the same words
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.words().hash(state);
+  }
+}
+
+#[cfg(not(feature = "tagged"))]
+impl Repr {
+  const fn small(atom: u8) -> Self {
+    Repr::Direct(atom as u64)
+  }
+
+  // two words or more
+  fn indirect(words: &[u64]) -> Self {
+    Repr::Indirect(words.into())
+  }
+
+  #[cfg(feature = "mmap")]
+  fn mapped(mapped: crate::serial::Mapped) -> Self {
+    Repr::Mapped(Rc::new(mapped))
+  }
+
+  fn as_u64(&self) -> Option<u64> {
+    match self {
+      Repr::Direct(atom) => Some(*atom),
+      _ => None,
+    }
+  }
+
+  fn words(&self) -> &[u64] {
+    match self {
+      Repr::Direct(word) => std::slice::from_ref(word),
+      Repr::Indirect(words) => words,
+      #[cfg(feature = "mmap")]
+      Repr::Mapped(mapped) => mapped.words(),
+    }
+  }
+
+  #[cfg(feature = "mmap")]
+  fn as_mapped(&self) -> Option<&crate::serial::Mapped> {
+    match self {
+      Repr::Mapped(mapped) => Some(mapped),
+      _ => None,
+    }
+  }
+
+  // whether both are the same shared words
+  fn same(&self, other: &Self) -> bool {
+    match (self, other) {
+      (Repr::Indirect(a), Repr::Indirect(b)) => Rc::ptr_eq(a, b),
+      #[cfg(feature = "mmap")]
+      (Repr::Mapped(a), Repr::Mapped(b)) => Rc::ptr_eq(a, b),
+      _ => false,
+    }
+  }
+
+  // `Rc<[u64]>` keeps its counts in front of the words
+  #[cfg(feature = "road")]
+  fn block(&self) -> Option<(*const u8, usize)> {
+    match self {
+      Repr::Indirect(words) => Some((words.as_ptr().cast(), (words.len() + 2) * size_of::<u64>())),
+      _ => None,
+    }
+  }
+}
+
+impl PartialOrd for Atom {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Atom {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    match (self.as_u64(), other.as_u64()) {
+      (Some(a), Some(b)) => a.cmp(&b),
+      _ if self.0.same(&other.0) => std::cmp::Ordering::Equal,
+      _ => cmp_words(self.words(), other.words()),
+    }
+  }
+}
+
+impl std::fmt::Display for Atom {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+    if let Some(atom) = self.as_u64() {
+      return write!(f, "{atom}");
+    }
+
+    // 19 decimal digits at a time, least significant first
+    let mut words = self.words().to_vec();
+    let mut chunks = vec![];
+
+    while words.iter().any(|&word| word != 0) {
+      chunks.push(div_rem(&mut words, CHUNK));
+    }
+
+    let mut chunks = chunks.into_iter().rev();
+    write!(f, "{}", chunks.next().unwrap_or(0))?;
+    for chunk in chunks {
+      write!(f, "{chunk:019}")?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::Atom;
+
+  #[test]
+  fn test_incr_promotes() {
+    let max = Atom::new(u64::MAX);
+    let big = max.incr();
+
+    assert_eq!(big.as_u64(), None);
+    assert_eq!(big.words(), [0, 1]);
+    assert_eq!(big.to_string(), "18446744073709551616");
+    assert!(big > max);
+
+    let bigger = Atom::from_words(&[u64::MAX, u64::MAX]).incr();
+    assert_eq!(bigger.words(), [0, 0, 1]);
+  }
+
+  #[test]
+  fn test_words() {
+    assert_eq!(Atom::from_words(&[]), Atom::new(0));
+    assert_eq!(Atom::from_words(&[7, 0, 0]), Atom::new(7));
+    assert_eq!(Atom::from_words(&[7, 1, 0]).words(), [7, 1]);
+    assert!(Atom::from_words(&[0, 1]) > Atom::from_words(&[u64::MAX]));
+    assert!(Atom::from_words(&[1, 2]) < Atom::from_words(&[0, 3]));
+  }
+
+  #[test]
+  fn test_compare() {
+    use std::cmp::Ordering;
+
+    use super::cmp_words;
+
+    // more words than a chunk, differing in the top chunk, a lower one, or
+    // in no chunk at all
+    let words: Vec<u64> = (1..=20).collect();
+    let atom = Atom::from_words(&words);
+    for i in [0, 7, 8, 11, 19] {
+      let mut more = words.clone();
+      more[i] += 1;
+      let more = Atom::from_words(&more);
+      assert!(atom < more, "word {i}");
+      assert!(more > atom, "word {i}");
+      assert_ne!(atom, more);
+    }
+    assert_eq!(atom, Atom::from_words(&words));
+    assert_eq!(atom.cmp(&atom.clone()), Ordering::Equal);
+    assert!(atom > Atom::from_words(&words[..19]));
+
+    // trailing zero words don't count
+    assert_eq!(cmp_words(&[1, 2, 0, 0], &[1, 2]), Ordering::Equal);
+    assert_eq!(cmp_words(&[0, 0, 1], &[u64::MAX, 0]), Ordering::Greater);
+    assert_eq!(cmp_words(&[], &[0]), Ordering::Equal);
+  }
+
+  #[test]
+  fn test_le_bytes() {
+    assert_eq!(Atom::from_le_bytes(&[]), Atom::new(0));
+    assert_eq!(Atom::from_le_bytes(&[1, 2, 0, 0]), Atom::new(0x0201));
+    assert_eq!(Atom::new(0).as_le_bytes(), [0u8; 0]);
+    assert_eq!(Atom::new(0x0201).as_le_bytes(), [1, 2]);
+
+    let hash: Vec<u8> = (1..=32).collect();
+    let atom = Atom::from_le_bytes(&hash);
+    assert_eq!(atom.words().len(), 4);
+    assert_eq!(atom.as_le_bytes(), hash);
+
+    assert_eq!(
+      Atom::from_words(&[0, 1]).as_le_bytes(),
+      [0, 0, 0, 0, 0, 0, 0, 0, 1]
+    );
+  }
+
+  #[test]
+  fn test_cord() {
+    assert_eq!(Atom::from_cord("a"), Atom::new(97));
+    assert_eq!(Atom::from_cord("memo"), Atom::new(0x6f6d_656d));
+    assert_eq!(Atom::from_cord(""), Atom::new(0));
+    assert_eq!(Atom::new(0x6f6d_656d).to_cord().unwrap(), "memo");
+
+    let long = "a cord longer than a single word";
+    assert_eq!(Atom::from_cord(long).to_cord().unwrap(), long);
+    assert_eq!(Atom::from_cord("ŋ").to_cord().unwrap(), "ŋ");
+
+    assert!(Atom::new(0xff).to_cord().is_err());
+  }
+
+  #[test]
+  fn test_zigzag() {
+    for (n, atom) in [(0, 0), (-1, 1), (1, 2), (-2, 3), (2, 4)] {
+      assert_eq!(Atom::from_i64(n), Atom::new(atom));
+      assert_eq!(Atom::new(atom).to_i64(), Some(n));
+    }
+
+    assert_eq!(Atom::from_i64(i64::MIN), Atom::new(u64::MAX));
+    assert_eq!(Atom::from_i64(i64::MAX), Atom::new(u64::MAX - 1));
+    assert_eq!(Atom::new(u64::MAX).to_i64(), Some(i64::MIN));
+    assert_eq!(Atom::new(u64::MAX).incr().to_i64(), None);
+
+    let big = Atom::from_words(&[0, 1]);
+    let negative = Atom::from_signed(true, &big);
+    assert_eq!(negative.words(), [u64::MAX, 1]);
+    assert_eq!(negative.to_signed(), (true, big.clone()));
+    assert_eq!(Atom::from_signed(false, &big).to_signed(), (false, big));
+    assert_eq!(Atom::from_signed(true, &Atom::new(0)), Atom::new(0));
+    assert_eq!(Atom::from_signed(true, &Atom::new(5)), Atom::from_i64(-5));
+  }
+
+  #[test]
+  fn test_digits() {
+    let digits = "340282366920938463463374607431768211456";
+    let atom = Atom::from_digits(digits, 10).unwrap();
+
+    assert_eq!(atom.words(), [0, 0, 1]);
+    assert_eq!(atom.to_string(), digits);
+    assert_eq!(Atom::from_digits("ff", 16), Some(Atom::new(255)));
+    assert_eq!(Atom::from_digits("", 10), None);
+    assert_eq!(Atom::from_digits("12a", 10), None);
+    assert_eq!(Atom::from_digits("+1", 10), None);
+  }
+}
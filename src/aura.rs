@@ -0,0 +1,122 @@
+// Atoms don't know what they mean, so for traces and debugging output we guess
+// from the bytes whether one reads best as a number, as text or as a blob.
+
+use crate::atom::Atom;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aura {
+  // `42`
+  Decimal,
+  // `%memo` or `'hello world'`
+  Cord,
+  // `0x1.e240.abcd`, for anything wider than a word
+  Hex,
+}
+
+pub fn guess(atom: &Atom) -> Aura {
+  let bytes = atom.as_le_bytes();
+
+  // mostly letters, so that numbers like 0x2020 stay numbers
+  let letters = bytes.iter().filter(|b| b.is_ascii_alphabetic()).count();
+  let text = bytes.len() >= 2
+    && letters * 2 >= bytes.len()
+    && bytes.iter().all(|b| (0x20..=0x7e).contains(b));
+
+  if text {
+    Aura::Cord
+  } else if bytes.len() > 8 {
+    Aura::Hex
+  } else {
+    Aura::Decimal
+  }
+}
+
+// displays an atom in its guessed aura
+pub struct Guess<'a>(pub &'a Atom);
+
+impl std::fmt::Display for Guess<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let atom = self.0;
+
+    match guess(atom) {
+      Aura::Decimal => write!(f, "{atom}"),
+      Aura::Cord => {
+        let text = String::from_utf8(atom.as_le_bytes()).expect("printable ascii");
+
+        let mut chars = text.chars();
+        let term = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+          && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+        if term {
+          return write!(f, "%{text}");
+        }
+
+        write!(f, "'")?;
+        for c in text.chars() {
+          if matches!(c, '\'' | '\\') {
+            write!(f, "\\")?;
+          }
+          write!(f, "{c}")?;
+        }
+        write!(f, "'")
+      }
+      Aura::Hex => {
+        let mut bytes = atom.as_le_bytes();
+        bytes.reverse();
+
+        let digits: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        let digits = digits.trim_start_matches('0');
+
+        // groups of four from the right, like `0x1.e240`
+        let head = digits.len() % 4;
+        write!(f, "0x{}", &digits[..head])?;
+        for (i, group) in digits.as_bytes()[head..].chunks(4).enumerate() {
+          if head != 0 || i != 0 {
+            write!(f, ".")?;
+          }
+          write!(f, "{}", std::str::from_utf8(group).unwrap())?;
+        }
+
+        Ok(())
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Aura, Guess, guess};
+  use crate::atom::Atom;
+
+  #[test]
+  fn test_guess() {
+    assert_eq!(guess(&Atom::new(42)), Aura::Decimal);
+    assert_eq!(guess(&Atom::new(0x2020)), Aura::Decimal);
+    assert_eq!(guess(&Atom::new(u64::MAX)), Aura::Decimal);
+    assert_eq!(guess(&Atom::from_cord("memo")), Aura::Cord);
+    assert_eq!(guess(&Atom::from_cord("hello world")), Aura::Cord);
+    assert_eq!(guess(&Atom::from_words(&[1, 2])), Aura::Hex);
+  }
+
+  #[test]
+  fn test_display() {
+    let show = |atom: Atom| Guess(&atom).to_string();
+
+    assert_eq!(show(Atom::new(42)), "42");
+    assert_eq!(show(Atom::from_cord("memo")), "%memo");
+    assert_eq!(show(Atom::from_cord("fast-2")), "%fast-2");
+    assert_eq!(show(Atom::from_cord("it's ok")), "'it\\'s ok'");
+    assert_eq!(
+      show(Atom::from_words(&[0xabcd, 0x1e240])),
+      "0x1.e240.0000.0000.0000.abcd"
+    );
+    assert_eq!(
+      show(Atom::from_words(&[0, 0xffff])),
+      "0xffff.0000.0000.0000.0000"
+    );
+
+    let n = noun!({(Atom::from_cord("slog")), {1, 2}});
+    assert_eq!(n.pretty().to_string(), "{%slog 1 2}");
+    assert_eq!(n.to_string(), "{1735355507 1 2}");
+  }
+}
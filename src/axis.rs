@@ -0,0 +1,147 @@
+// Tree address arithmetic. Axis 1 is the whole noun, `2a` is the head of `a`
+// and `2a + 1` its tail.
+
+use crate::{atom::Atom, bits, parse::ParseError};
+
+// the turns from the root to `axis`, true for a tail, which are the bits below
+// its leading 1 from the top down; empty for axes 0 and 1
+pub(crate) fn path(axis: &Atom) -> impl Iterator<Item = bool> + '_ {
+  let words = axis.words();
+  let below = bits::bits(axis).saturating_sub(1);
+
+  (0..below)
+    .rev()
+    .map(move |bit| words[(bit / 64) as usize] >> (bit % 64) & 1 == 1)
+}
+
+// the axis of `child` within the subtree at `parent`, or None if either is
+// zero or the result doesn't fit in a `u64`
+pub fn peg(parent: u64, child: u64) -> Option<u64> {
+  if parent == 0 || child == 0 {
+    return None;
+  }
+
+  let depth = 63 - child.leading_zeros();
+  let path = child ^ (1 << depth);
+
+  if parent.leading_zeros() < depth {
+    return None;
+  }
+
+  Some((parent << depth) | path)
+}
+
+// 2 if `axis` is within the head, 3 if within the tail
+pub fn cap(axis: u64) -> Option<u64> {
+  if axis < 2 {
+    return None;
+  }
+
+  let depth = 63 - axis.leading_zeros();
+  Some(2 | ((axis >> (depth - 1)) & 1))
+}
+
+// `axis` relative to the head or tail it's in, see `cap`
+pub fn mas(axis: u64) -> Option<u64> {
+  if axis < 2 {
+    return None;
+  }
+
+  let depth = 63 - axis.leading_zeros();
+  let rest = axis & ((1 << (depth - 1)) - 1);
+  Some((1 << (depth - 1)) | rest)
+}
+
+// lark syntax: `.` is the whole noun, then alternating `-`/`+` and `<`/`>`
+// pick head or tail, e.g. `-` is 2, `+>` is 7 and `+<-` is 12
+pub fn parse_lark(lark: &str) -> Result<u64, ParseError> {
+  if lark == "." {
+    return Ok(1);
+  }
+
+  if lark.is_empty() {
+    return Err(ParseError {
+      offset: 0,
+      message: "expected a lark expression".to_string(),
+    });
+  }
+
+  let mut axis: u64 = 1;
+
+  for (offset, c) in lark.char_indices() {
+    let (head, tail) = if offset % 2 == 0 {
+      ('-', '+')
+    } else {
+      ('<', '>')
+    };
+
+    let bit = match c {
+      c if c == head => 0,
+      c if c == tail => 1,
+      _ => {
+        return Err(ParseError {
+          offset,
+          message: format!("expected '{head}' or '{tail}'"),
+        });
+      }
+    };
+
+    axis = axis
+      .checked_mul(2)
+      .map(|axis| axis | bit)
+      .ok_or_else(|| ParseError {
+        offset,
+        message: "axis is too deep".to_string(),
+      })?;
+  }
+
+  Ok(axis)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{cap, mas, parse_lark, peg};
+
+  #[test]
+  fn test_peg() {
+    assert_eq!(peg(1, 7), Some(7));
+    assert_eq!(peg(7, 1), Some(7));
+    assert_eq!(peg(2, 3), Some(5));
+    assert_eq!(peg(6, 5), Some(25));
+    assert_eq!(peg(0, 2), None);
+    assert_eq!(peg(1 << 62, 4), None);
+
+    let n = noun!({{4, 5}, {6, {14, 15}}});
+    let sub = n.slot(7).unwrap();
+    assert_eq!(sub.slot(3).unwrap(), n.slot(peg(7, 3).unwrap()).unwrap());
+  }
+
+  #[test]
+  fn test_cap_mas() {
+    for axis in 2..1000 {
+      let (c, m) = (cap(axis).unwrap(), mas(axis).unwrap());
+      assert_eq!(peg(c, m), Some(axis));
+    }
+
+    assert_eq!(cap(1), None);
+    assert_eq!(mas(0), None);
+  }
+
+  #[test]
+  fn test_lark() {
+    assert_eq!(parse_lark(".").unwrap(), 1);
+    assert_eq!(parse_lark("-").unwrap(), 2);
+    assert_eq!(parse_lark("+").unwrap(), 3);
+    assert_eq!(parse_lark("-<").unwrap(), 4);
+    assert_eq!(parse_lark("->").unwrap(), 5);
+    assert_eq!(parse_lark("+<").unwrap(), 6);
+    assert_eq!(parse_lark("+>").unwrap(), 7);
+    assert_eq!(parse_lark("+<-").unwrap(), 12);
+    assert_eq!(parse_lark("+>+").unwrap(), 15);
+
+    assert!(parse_lark("").is_err());
+    assert!(parse_lark("<").is_err());
+    assert!(parse_lark("--").is_err());
+    assert_eq!(parse_lark("+>x").unwrap_err().offset, 2);
+  }
+}
@@ -0,0 +1,295 @@
+// Standard workloads for `nuuk bench`, so what a change does to the
+// interpreter's speed can be measured, commit to commit: the decrement loop,
+// naive Fibonacci and Ackermann over decrement and addition loops, deep slot
+// lookups, and the jam and cue of a large noun. Each is timed over a few runs
+// on one interpreter, with the fastest kept, and counted once with a trace
+// sink.
+//
+// Allocations are only counted when `Counting` is the global allocator, as it
+// is in the `nuuk` binary built with the `count-allocs` feature:
+//
+//   #[global_allocator]
+//   static ALLOC: nuuk::bench::Counting = nuuk::bench::Counting;
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+  },
+  time::{Duration, Instant},
+};
+
+use crate::{
+  atom::Atom,
+  error::NockError,
+  interp::{Engine, Formula, Interpreter, Subject},
+  noun::Noun,
+  serial,
+};
+
+// `*{n DECREMENT}` is n - 1, the loop the tests and benches lean on
+pub const DECREMENT: &str = "{8 {1 0} 8 {1 6 {5 {0 7} 4 0 6} {0 6} 9 2 {0 2} {4 0 6} 0 7} 9 2 0 1}";
+// `*{{a b} ADD}` is a + b, counting up to b
+const ADD: &str = "8 {1 0} 8 {1 6 {5 {0 6} 0 15} {0 14} 9 2 {0 2} {4 0 6} {4 0 14} 0 15} 9 2 0 1";
+// arms on cores `{arm n}` and `{arm m n}`
+const FIBONACCI: &str = "6 {5 {1 0} 0 3} {0 3} 6 {5 {1 1} 0 3} {0 3} 7 \
+  {{9 2 10 {3 7 {0 3} DEC} 0 1} 9 2 10 {3 7 {7 {0 3} DEC} DEC} 0 1} ADD";
+const ACKERMANN: &str = "6 {5 {1 0} 0 6} {4 0 7} 6 {5 {1 0} 0 7} \
+  {9 2 10 {3 {7 {0 6} DEC} 1 1} 0 1} \
+  9 2 10 {3 {7 {0 6} DEC} 9 2 10 {7 7 {0 7} DEC} 0 1} 0 1";
+
+pub struct Workload {
+  pub name: String,
+  pub job: Job,
+}
+
+pub enum Job {
+  Eval {
+    subject: Noun,
+    formula: Noun,
+    // what every run has to make
+    product: Noun,
+  },
+  // jammed and cued back
+  Serial(Noun),
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+  pub name: String,
+  // of the fastest run
+  pub time: Duration,
+  // nock calls in a run, as a trace sink sees them, or the nodes jammed and
+  // cued
+  pub steps: u64,
+  // made in the fastest run, 0 unless `Counting` is the global allocator
+  pub allocs: u64,
+}
+
+// the suite `nuuk bench` runs
+pub fn suite() -> Vec<Workload> {
+  vec![
+    decrement(100_000),
+    fibonacci(15),
+    ackermann(2, 10),
+    slots(10_000, 100),
+    serial(10_000),
+  ]
+}
+
+fn parse(formula: &str) -> Noun {
+  let formula = formula.replace("DEC", DECREMENT).replace("ADD", ADD);
+  format!("{{{formula}}}").parse().unwrap()
+}
+
+// a core with `arm` as its battery, and the subject as its payload
+fn core(arm: &str) -> Noun {
+  let arm = parse(arm);
+  noun!({invk, {2, {{idty, (arm)}, {addr, 1}}}})
+}
+
+pub fn decrement(n: u64) -> Workload {
+  Workload {
+    name: format!("decrement {n}"),
+    job: Job::Eval {
+      subject: Noun::from(n),
+      formula: DECREMENT.parse().unwrap(),
+      product: Noun::from(n.saturating_sub(1)),
+    },
+  }
+}
+
+pub fn fibonacci(n: u64) -> Workload {
+  let (mut a, mut b) = (0, 1);
+  for _ in 0..n {
+    (a, b) = (b, a + b);
+  }
+
+  Workload {
+    name: format!("fibonacci {n}"),
+    job: Job::Eval {
+      subject: Noun::from(n),
+      formula: core(FIBONACCI),
+      product: Noun::from(a),
+    },
+  }
+}
+
+pub fn ackermann(m: u64, n: u64) -> Workload {
+  fn ack(m: u64, n: u64) -> u64 {
+    match (m, n) {
+      (0, n) => n + 1,
+      (m, 0) => ack(m - 1, 1),
+      (m, n) => ack(m - 1, ack(m, n - 1)),
+    }
+  }
+
+  Workload {
+    name: format!("ackermann {m} {n}"),
+    job: Job::Eval {
+      subject: Noun::cell(Noun::from(m), Noun::from(n)),
+      formula: core(ACKERMANN),
+      product: Noun::from(ack(m, n)),
+    },
+  }
+}
+
+// a tuple of `lookups` slots of the atom at the bottom of `depth` cells
+pub fn slots(depth: u64, lookups: u64) -> Workload {
+  let subject = (0..depth).fold(Noun::from(42u64), |tail, n| Noun::cell(Noun::from(n), tail));
+
+  // the tail of each cell, all the way down
+  let bits = depth as usize + 1;
+  let mut words = vec![u64::MAX; bits / 64];
+  if !bits.is_multiple_of(64) {
+    words.push((1 << (bits % 64)) - 1);
+  }
+  let slot = noun!({addr, (Atom::from_words(&words))});
+
+  let tuple = |one: Noun| (1..lookups).fold(one.clone(), |tuple, _| Noun::cell(one.clone(), tuple));
+  Workload {
+    name: format!("slots {depth} x{lookups}"),
+    job: Job::Eval {
+      subject,
+      formula: tuple(slot),
+      product: tuple(Noun::from(42u64)),
+    },
+  }
+}
+
+// a list of `len` cells of a counter, a three-word atom made from it, and the
+// same cell every time, which is backreferenced
+pub fn serial(len: u64) -> Workload {
+  let shared = noun!({{1, 2}, {3, 4}});
+  let items = (0..len).map(|n| {
+    let big = Atom::from_words(&[n, n * n, n]);
+    Noun::cell(Noun::from(n), Noun::cell(Noun::from(big), shared.clone()))
+  });
+
+  Workload {
+    name: format!("jam/cue {len}"),
+    job: Job::Serial(Noun::list(items)),
+  }
+}
+
+// panics if a run makes the wrong product
+pub fn run(workload: &Workload, engine: Engine, runs: u32) -> Result<Report, NockError> {
+  let (mut time, mut allocs) = (Duration::MAX, 0);
+  let mut timed = |job: &mut dyn FnMut() -> Result<(), NockError>| {
+    for _ in 0..runs.max(1) {
+      let (start, before) = (Instant::now(), allocations());
+      job()?;
+      let elapsed = start.elapsed();
+      if elapsed < time {
+        (time, allocs) = (elapsed, allocations() - before);
+      }
+    }
+    Ok::<_, NockError>(())
+  };
+
+  let steps = match &workload.job {
+    Job::Eval {
+      subject,
+      formula,
+      product,
+    } => {
+      let steps = Arc::new(AtomicU64::new(0));
+      let counter = steps.clone();
+      let mut traced = Interpreter::builder()
+        .engine(engine)
+        .trace(move |_, _| {
+          counter.fetch_add(1, Ordering::Relaxed);
+        })
+        .build();
+      let mut it = Interpreter::builder().engine(engine).build();
+      let eval = |it: &mut Interpreter| {
+        let prod = it.eval(Subject(subject.clone()), Formula(formula.clone()))?;
+        assert_eq!(&prod, product, "{}", workload.name);
+        Ok(())
+      };
+
+      eval(&mut traced)?;
+      timed(&mut || eval(&mut it))?;
+      steps.load(Ordering::Relaxed)
+    }
+    Job::Serial(noun) => {
+      timed(&mut || {
+        let cued = serial::cue(&serial::jam(noun)).expect("jam makes what cue reads");
+        assert_eq!(&cued, noun, "{}", workload.name);
+        Ok(())
+      })?;
+      noun.nodes()
+    }
+  };
+
+  Ok(Report {
+    name: workload.name.clone(),
+    time,
+    steps,
+    allocs,
+  })
+}
+
+static ALLOCS: AtomicU64 = AtomicU64::new(0);
+
+pub struct Counting;
+
+// blocks handed out so far, on every thread
+pub fn allocations() -> u64 {
+  ALLOCS.load(Ordering::Relaxed)
+}
+
+// SAFETY: everything is the system allocator's
+unsafe impl GlobalAlloc for Counting {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCS.fetch_add(1, Ordering::Relaxed);
+    // SAFETY: as the caller promised
+    unsafe { System.alloc(layout) }
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    // SAFETY: as the caller promised
+    unsafe { System.dealloc(ptr, layout) }
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOCS.fetch_add(1, Ordering::Relaxed);
+    // SAFETY: as the caller promised
+    unsafe { System.realloc(ptr, layout, new_size) }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Job, ackermann, decrement, fibonacci, run, serial, slots};
+  use crate::interp::{Engine, Formula, Interpreter, Subject};
+
+  #[test]
+  fn test_workloads() {
+    for workload in [
+      decrement(10),
+      fibonacci(7),
+      ackermann(2, 2),
+      slots(100, 3),
+      serial(20),
+    ] {
+      for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+        let report = run(&workload, engine, 2).unwrap();
+        assert!(report.steps > 0, "{}", workload.name);
+      }
+    }
+
+    let Job::Eval {
+      subject,
+      formula,
+      product,
+    } = fibonacci(10).job
+    else {
+      unreachable!()
+    };
+    let prod = Interpreter::new().eval(Subject(subject), Formula(formula));
+    assert_eq!(prod.unwrap(), product);
+    assert_eq!(product, noun!(55));
+  }
+}
@@ -0,0 +1,206 @@
+// Bit twiddling over atoms, hoon style. A bloq is a block size of 2^bloq bits
+// (0 is a bit, 3 a byte, 6 a word) and counts and offsets are in blocks of
+// that size.
+
+use crate::atom::Atom;
+
+// saturating, which is as good as exact past the size of any atom, so every
+// bloq goes
+fn to_bits(bloq: u32, blocks: u64) -> u64 {
+  if blocks == 0 {
+    return 0;
+  }
+
+  blocks
+    .checked_shl(bloq)
+    .filter(|bits| bits >> bloq == blocks)
+    .unwrap_or(u64::MAX)
+}
+
+// the number of significant bits, 0 for 0
+pub fn bits(a: &Atom) -> u64 {
+  let words = a.words();
+  let last = words[words.len() - 1];
+
+  (words.len() as u64 - 1) * 64 + u64::from(64 - last.leading_zeros())
+}
+
+// the number of blocks needed to hold `a`
+pub fn met(bloq: u32, a: &Atom) -> u64 {
+  let bits = bits(a);
+  let size = to_bits(bloq, 1);
+
+  bits.div_ceil(size)
+}
+
+// the lowest `n` blocks of `a`
+pub fn end(bloq: u32, n: u64, a: &Atom) -> Atom {
+  let bits = to_bits(bloq, n);
+  let words = a.words();
+
+  let whole = usize::try_from(bits / 64).unwrap_or(usize::MAX);
+  if whole >= words.len() {
+    return a.clone();
+  }
+
+  let mut low = words[..whole].to_vec();
+  let part = bits % 64;
+  if part != 0 {
+    low.push(words[whole] & ((1 << part) - 1));
+  }
+
+  Atom::from_words(&low)
+}
+
+// `a` shifted up by `n` blocks, None if that's more than memory can hold
+pub fn lsh(bloq: u32, n: u64, a: &Atom) -> Option<Atom> {
+  if a.is_zero() {
+    return Some(a.clone());
+  }
+
+  let bits = to_bits(bloq, n);
+  let whole = usize::try_from(bits / 64).ok()?;
+  let part = bits % 64;
+
+  let mut words = vec![];
+  words
+    .try_reserve_exact(whole.checked_add(a.words().len() + 1)?)
+    .ok()?;
+  words.resize(whole, 0);
+  let mut carry = 0;
+
+  for &word in a.words() {
+    words.push((word << part) | carry);
+    carry = if part == 0 { 0 } else { word >> (64 - part) };
+  }
+  words.push(carry);
+
+  Some(Atom::from_words(&words))
+}
+
+// `a` shifted down by `n` blocks
+pub fn rsh(bloq: u32, n: u64, a: &Atom) -> Atom {
+  let bits = to_bits(bloq, n);
+  let words = a.words();
+
+  let whole = usize::try_from(bits / 64).unwrap_or(usize::MAX);
+  if whole >= words.len() {
+    return Atom::new(0);
+  }
+
+  let part = bits % 64;
+  let high = &words[whole..];
+
+  let shifted: Vec<u64> = (0..high.len())
+    .map(|i| {
+      let next = high.get(i + 1).copied().unwrap_or(0);
+      if part == 0 {
+        high[i]
+      } else {
+        (high[i] >> part) | (next << (64 - part))
+      }
+    })
+    .collect();
+
+  Atom::from_words(&shifted)
+}
+
+// `len` blocks of `a` starting at block `start`
+pub fn cut(bloq: u32, start: u64, len: u64, a: &Atom) -> Atom {
+  end(bloq, len, &rsh(bloq, start, a))
+}
+
+fn zip(a: &Atom, b: &Atom, op: impl Fn(u64, u64) -> u64) -> Atom {
+  let (a, b) = (a.words(), b.words());
+  let len = a.len().max(b.len());
+
+  let words: Vec<u64> = (0..len)
+    .map(|i| {
+      let x = a.get(i).copied().unwrap_or(0);
+      let y = b.get(i).copied().unwrap_or(0);
+      op(x, y)
+    })
+    .collect();
+
+  Atom::from_words(&words)
+}
+
+// bitwise or
+pub fn con(a: &Atom, b: &Atom) -> Atom {
+  zip(a, b, |x, y| x | y)
+}
+
+// bitwise and
+pub fn dis(a: &Atom, b: &Atom) -> Atom {
+  zip(a, b, |x, y| x & y)
+}
+
+// bitwise xor
+pub fn mix(a: &Atom, b: &Atom) -> Atom {
+  zip(a, b, |x, y| x ^ y)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{bits, con, cut, dis, end, lsh, met, mix, rsh};
+  use crate::atom::Atom;
+
+  #[test]
+  fn test_met() {
+    assert_eq!(bits(&Atom::new(0)), 0);
+    assert_eq!(met(0, &Atom::new(0xff)), 8);
+    assert_eq!(met(3, &Atom::new(0x1ff)), 2);
+    assert_eq!(met(3, &Atom::from_cord("hello")), 5);
+    assert_eq!(met(6, &Atom::from_words(&[0, 0, 1])), 3);
+    assert_eq!(met(0, &Atom::from_words(&[0, 1])), 65);
+
+    // blocks wider than any atom
+    assert_eq!(met(6, &Atom::new(u64::MAX)), 1);
+    assert_eq!(met(64, &Atom::from_words(&[0, 1])), 1);
+    assert_eq!(met(u32::MAX, &Atom::new(1)), 1);
+    assert_eq!(met(u32::MAX, &Atom::new(0)), 0);
+  }
+
+  #[test]
+  fn test_shifts() {
+    let a = Atom::new(0xabcd);
+
+    assert_eq!(lsh(3, 1, &a), Some(Atom::new(0xab_cd00)));
+    assert_eq!(rsh(3, 1, &a), Atom::new(0xab));
+    assert_eq!(rsh(3, 4, &a), Atom::new(0));
+    assert_eq!(end(3, 1, &a), Atom::new(0xcd));
+    assert_eq!(end(3, 9, &a), a);
+    assert_eq!(cut(2, 1, 2, &a), Atom::new(0xbc));
+
+    let big = lsh(0, 100, &Atom::new(3)).unwrap();
+    assert_eq!(bits(&big), 102);
+    assert_eq!(rsh(0, 100, &big), Atom::new(3));
+    assert_eq!(Some(end(0, 101, &big)), lsh(0, 100, &Atom::new(1)));
+    assert_eq!(
+      lsh(6, 1, &Atom::new(u64::MAX)).unwrap().words(),
+      [0, u64::MAX]
+    );
+    assert_eq!(lsh(0, 5, &Atom::new(0)), Some(Atom::new(0)));
+
+    // shifts past the size of any atom
+    assert_eq!(lsh(0, u64::MAX, &a), None);
+    assert_eq!(lsh(64, 1, &a), None);
+    assert_eq!(lsh(u32::MAX, u64::MAX, &Atom::new(0)), Some(Atom::new(0)));
+    assert_eq!(rsh(64, 1, &a), Atom::new(0));
+    assert_eq!(rsh(0, u64::MAX, &a), Atom::new(0));
+    assert_eq!(end(64, 1, &a), a);
+    assert_eq!(end(u32::MAX, 0, &a), Atom::new(0));
+    assert_eq!(cut(0, u64::MAX, u64::MAX, &a), Atom::new(0));
+  }
+
+  #[test]
+  fn test_logic() {
+    let a = Atom::new(0b1100);
+    let b = Atom::from_words(&[0b1010, 1]);
+
+    assert_eq!(con(&a, &b).words(), [0b1110, 1]);
+    assert_eq!(dis(&a, &b), Atom::new(0b1000));
+    assert_eq!(mix(&a, &b).words(), [0b0110, 1]);
+    assert_eq!(mix(&b, &b), Atom::new(0));
+  }
+}
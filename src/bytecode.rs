@@ -0,0 +1,776 @@
+// Formulas compiled to flat bytecode for a stack machine. Sub-formulas push
+// their products on a value stack and the instruction for their opcode pops
+// them; opcodes 6 and 11 become jumps, and 7 and 8 swap the subject in and out
+// instead of making a call. Only opcodes 2 and 9 call into another program,
+// and in tail position they replace the caller's frame.
+//
+// Programs are compiled on first use and cached per formula. Each call spends
+// one step of fuel and one level of depth, and is what the trace sink sees,
+// where the interpreter counts every formula it reduces.
+//
+// With the jit feature, the jit engine runs hot programs as native code as
+// far as it gets, and steps the rest here, see src/jit.rs.
+
+use crate::{
+  atom::{Atom, NAH, YES},
+  cache::Pending,
+  compile::{Op, arm, shape},
+  error::NockError,
+  interp::{DONT, FAST, Hinted, Interpreter, MEAN, MEMO, SPOT},
+  noun::{Noun, Rc},
+  opcode::Opcode,
+};
+#[cfg(feature = "jit")]
+use crate::{interp::Engine, jit::Hot};
+
+#[derive(Debug)]
+pub(crate) struct Program {
+  pub(crate) code: Vec<Step>,
+  #[cfg(feature = "jit")]
+  pub(crate) hot: Hot,
+}
+
+#[derive(Debug)]
+pub(crate) struct Step {
+  pub(crate) instr: Instr,
+  // the opcode an error here is reported in
+  ctx: Option<Opcode>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Instr {
+  Slot(Atom),
+  Quote(Noun),
+  // pops the tail, then the head
+  Cons,
+  IsCell,
+  Incr,
+  Eq,
+  // pops a loobean, and jumps on `NAH`
+  Branch(usize),
+  Jump(usize),
+  // pops the new subject, saving the old one
+  Enter,
+  // pops a noun, and enters it pinned to the subject
+  Pin,
+  Leave,
+  // pops the formula, then the subject
+  Call {
+    tail: bool,
+  },
+  // pops the core
+  Invk {
+    axis: Noun,
+    tail: bool,
+  },
+  // pops the target, then the value
+  Edit(Atom),
+  // pops the clue if there is one; `post` hints wait on the body's product in
+  // `HintLeave`, and a memoized product skips the body altogether
+  HintEnter {
+    tag: Noun,
+    clue: bool,
+    body: Noun,
+    post: bool,
+    skip: usize,
+  },
+  HintLeave,
+  // pops the path, then the reference
+  Scry,
+  Other(Atom, Noun),
+  Exit(Noun),
+  Crash(NockError),
+  Ret,
+}
+
+enum Task {
+  Formula(Noun, bool, Option<Opcode>),
+  Emit(Instr, Option<Opcode>),
+  Label(usize),
+}
+
+// without recursing: a formula expands to tasks for its sub-formulas and the
+// instructions between them, and labels are patched in once the code is laid out
+fn compile(formula: &Noun, hints: bool) -> Program {
+  let mut code = vec![];
+  let mut labels = vec![];
+  let mut tasks = vec![Task::Formula(formula.clone(), true, None)];
+
+  while let Some(task) = tasks.pop() {
+    match task {
+      Task::Formula(formula, tail, ctx) => {
+        let expanded = expand(formula, tail, ctx, hints, &mut labels);
+        tasks.extend(expanded.into_iter().rev());
+      }
+      Task::Emit(instr, ctx) => code.push(Step { instr, ctx }),
+      Task::Label(label) => labels[label] = code.len(),
+    }
+  }
+  code.push(Step {
+    instr: Instr::Ret,
+    ctx: None,
+  });
+
+  for step in &mut code {
+    if let Instr::Branch(to) | Instr::Jump(to) | Instr::HintEnter { skip: to, .. } = &mut step.instr
+    {
+      *to = labels[*to];
+    }
+  }
+
+  Program {
+    code,
+    #[cfg(feature = "jit")]
+    hot: Hot::default(),
+  }
+}
+
+// `tail` is set when nothing runs after the formula but the program's return
+fn expand(
+  formula: Noun,
+  tail: bool,
+  ctx: Option<Opcode>,
+  hints: bool,
+  labels: &mut Vec<usize>,
+) -> Vec<Task> {
+  let (op, kids) = shape(&formula);
+  let mut kids = kids.into_iter();
+  let mut kid = || kids.next().unwrap();
+  let mut label = || {
+    labels.push(0);
+    labels.len() - 1
+  };
+  let emit = Task::Emit;
+  let form = Task::Formula;
+
+  match op {
+    Op::Atom => vec![emit(Instr::Exit(formula), ctx)],
+    Op::Cons => vec![
+      form(kid(), false, ctx),
+      form(kid(), false, ctx),
+      emit(Instr::Cons, ctx),
+    ],
+    Op::Slot(axis) => vec![emit(Instr::Slot(axis), Some(Opcode::Addr))],
+    Op::Quote(noun) => vec![emit(Instr::Quote(noun), Some(Opcode::Idty))],
+    Op::Eval => {
+      let c = Some(Opcode::Eval);
+      vec![
+        form(kid(), false, c),
+        form(kid(), false, c),
+        emit(Instr::Call { tail }, c),
+      ]
+    }
+    Op::Cell => {
+      let c = Some(Opcode::Cell);
+      vec![form(kid(), false, c), emit(Instr::IsCell, c)]
+    }
+    Op::Incr => {
+      let c = Some(Opcode::Incr);
+      vec![form(kid(), false, c), emit(Instr::Incr, c)]
+    }
+    Op::Eqal => {
+      let c = Some(Opcode::Eqal);
+      vec![
+        form(kid(), false, c),
+        form(kid(), false, c),
+        emit(Instr::Eq, c),
+      ]
+    }
+    Op::Brch => {
+      let c = Some(Opcode::Brch);
+      let (nah, end) = (label(), label());
+      vec![
+        form(kid(), false, c),
+        emit(Instr::Branch(nah), c),
+        form(kid(), tail, c),
+        emit(if tail { Instr::Ret } else { Instr::Jump(end) }, c),
+        Task::Label(nah),
+        form(kid(), tail, c),
+        Task::Label(end),
+      ]
+    }
+    Op::Cmps => {
+      let c = Some(Opcode::Cmps);
+      let mut tasks = vec![
+        form(kid(), false, c),
+        emit(Instr::Enter, c),
+        form(kid(), tail, c),
+      ];
+      if !tail {
+        tasks.push(emit(Instr::Leave, c));
+      }
+      tasks
+    }
+    Op::Extn => {
+      let c = Some(Opcode::Extn);
+      let mut tasks = vec![
+        form(kid(), false, c),
+        emit(Instr::Pin, c),
+        form(kid(), tail, c),
+      ];
+      if !tail {
+        tasks.push(emit(Instr::Leave, c));
+      }
+      tasks
+    }
+    Op::Invk(axis) => {
+      let c = Some(Opcode::Invk);
+      vec![form(kid(), false, c), emit(Instr::Invk { axis, tail }, c)]
+    }
+    Op::Rplc(axis) => {
+      let c = Some(Opcode::Rplc);
+      vec![
+        form(kid(), false, c),
+        form(kid(), false, c),
+        emit(Instr::Edit(axis), c),
+      ]
+    }
+    Op::Hint { tag, clue } => {
+      let c = Some(Opcode::Hint);
+      if !hints {
+        // the clue isn't evaluated
+        if clue {
+          kid();
+        }
+        return vec![form(kid(), tail, c)];
+      }
+
+      let (post, body_ctx) = match tag.as_atom().and_then(Atom::as_u64) {
+        Some(MEMO | DONT) => (true, c),
+        Some(FAST) => (clue, c),
+        // errors in the body are traced rather than put in this opcode
+        Some(SPOT | MEAN) if clue => (true, None),
+        _ => (false, c),
+      };
+
+      let mut tasks = vec![];
+      if clue {
+        tasks.push(form(kid(), false, c));
+      }
+      let body = kid();
+      let skip = label();
+      tasks.push(emit(
+        Instr::HintEnter {
+          tag,
+          clue,
+          body: body.clone(),
+          post,
+          skip,
+        },
+        c,
+      ));
+      tasks.push(form(body, tail && !post, body_ctx));
+      if post {
+        tasks.push(emit(Instr::HintLeave, c));
+      }
+      tasks.push(Task::Label(skip));
+      tasks
+    }
+    Op::Scry => {
+      let c = Some(Opcode::Scry);
+      vec![
+        form(kid(), false, c),
+        form(kid(), false, c),
+        emit(Instr::Scry, c),
+      ]
+    }
+    Op::Other(opcode, arg) => vec![emit(Instr::Other(opcode, arg), ctx)],
+    Op::Crash(e) => vec![emit(Instr::Crash(e), ctx)],
+  }
+}
+
+struct Frame {
+  program: Rc<Program>,
+  pc: usize,
+  subj: Noun,
+  // the opcode that made the call
+  ctx: Option<Opcode>,
+  // where the frame's saved subjects and hint marks start
+  subjects: usize,
+  marks: usize,
+  // calls in tail position of each other, waiting on this frame's product
+  calls: Vec<Pending>,
+}
+
+#[derive(Default)]
+pub(crate) struct Vm {
+  pub(crate) stack: Vec<Noun>,
+  pub(crate) subjects: Vec<Noun>,
+  // hints waiting on their body's product
+  marks: Vec<Hinted>,
+}
+
+enum Control {
+  Next,
+  Jump(usize),
+  Call {
+    subj: Noun,
+    formula: Noun,
+    tail: bool,
+  },
+  Ret,
+}
+
+fn in_opcode(error: NockError, ctx: Option<Opcode>) -> NockError {
+  match ctx {
+    Some(opcode) => error.in_opcode(opcode),
+    None => error,
+  }
+}
+
+impl Interpreter {
+  fn program(&mut self, formula: &Noun) -> Rc<Program> {
+    if let Some(program) = self.programs.get(formula) {
+      return program.clone();
+    }
+
+    let program = Rc::new(compile(formula, self.hints));
+    self.programs.insert(formula.clone(), program.clone());
+    program
+  }
+
+  pub(crate) fn vm(&mut self, subj: Noun, formula: Noun) -> Result<Noun, NockError> {
+    self.enter(&subj, &formula)?;
+
+    let mut vm = Vm::default();
+    let mut frames = vec![];
+    let mut frame = self.frame(subj, &formula, None, &vm);
+
+    loop {
+      // native code takes the frame as far as it can
+      #[cfg(feature = "jit")]
+      if let Some(native) = frame.program.hot.native() {
+        frame.pc = native.run(self, &mut frame.subj, &mut vm, frame.pc);
+      }
+
+      let step = &frame.program.code[frame.pc];
+      let ctx = step.ctx;
+      frame.pc += 1;
+
+      let control = match self.step(&step.instr, &mut frame.subj, &mut vm) {
+        Ok(control) => control,
+        Err(e) => return Err(unwind(in_opcode(e, ctx), frame, frames, vm)),
+      };
+      // a call the eval cache answers is as good as returned
+      let control = match control {
+        Control::Call {
+          subj,
+          formula,
+          tail,
+        } => match self.cached(&subj, &formula) {
+          Some(prod) => {
+            vm.stack.push(prod);
+            if tail { Control::Ret } else { Control::Next }
+          }
+          None => Control::Call {
+            subj,
+            formula,
+            tail,
+          },
+        },
+        control => control,
+      };
+
+      match control {
+        Control::Next => {}
+        Control::Jump(to) => frame.pc = to,
+        Control::Call {
+          subj,
+          formula,
+          tail,
+        } => {
+          if tail {
+            self.depth -= 1;
+            vm.subjects.truncate(frame.subjects);
+          }
+          if let Err(e) = self.enter(&subj, &formula) {
+            return Err(unwind(in_opcode(e, ctx), frame, frames, vm));
+          }
+
+          let mut callee = self.frame(subj, &formula, ctx, &vm);
+          if tail {
+            callee.calls = std::mem::take(&mut frame.calls);
+          }
+          self.wait(&mut callee.calls, &callee.subj, &formula);
+          if tail {
+            frame = callee;
+          } else {
+            frames.push(std::mem::replace(&mut frame, callee));
+          }
+        }
+        Control::Ret => {
+          self.depth -= 1;
+          vm.subjects.truncate(frame.subjects);
+          let calls = std::mem::take(&mut frame.calls);
+          self.cache(calls, vm.stack.last().unwrap());
+          match frames.pop() {
+            Some(caller) => frame = caller,
+            None => return Ok(vm.stack.pop().unwrap()),
+          }
+        }
+      }
+    }
+  }
+
+  fn frame(&mut self, subj: Noun, formula: &Noun, ctx: Option<Opcode>, vm: &Vm) -> Frame {
+    let program = self.program(formula);
+    #[cfg(feature = "jit")]
+    if self.engine() == Engine::Jit {
+      program.hot.warm(&program, &subj, self);
+    }
+
+    Frame {
+      program,
+      pc: 0,
+      subj,
+      ctx,
+      subjects: vm.subjects.len(),
+      marks: vm.marks.len(),
+      calls: vec![],
+    }
+  }
+
+  fn step(&mut self, instr: &Instr, subj: &mut Noun, vm: &mut Vm) -> Result<Control, NockError> {
+    let stack = &mut vm.stack;
+
+    match instr {
+      Instr::Slot(axis) => stack.push(subj.slot_atom(axis)?),
+      Instr::Quote(noun) => stack.push(noun.clone()),
+      Instr::Cons => {
+        let cdr = stack.pop().unwrap();
+        let car = stack.pop().unwrap();
+        stack.push(self.cons(car, cdr));
+      }
+      Instr::IsCell => {
+        let noun = stack.pop().unwrap();
+        stack.push(Noun::from(noun.is_cell()));
+      }
+      Instr::Incr => {
+        let noun = stack.pop().unwrap();
+        stack.push(Noun::atom(noun.try_atom()?.incr()));
+      }
+      Instr::Eq => {
+        let b = stack.pop().unwrap();
+        let a = stack.pop().unwrap();
+        stack.push(Noun::from(self.eq(&a, &b)));
+      }
+      Instr::Branch(nah) => {
+        let cond = stack.pop().unwrap();
+        match cond.as_atom().and_then(Atom::as_u64) {
+          Some(YES) => {}
+          Some(NAH) => return Ok(Control::Jump(*nah)),
+          _ => return Err(NockError::NonLoobeanCondition { value: cond }),
+        }
+      }
+      Instr::Jump(to) => return Ok(Control::Jump(*to)),
+      Instr::Enter => {
+        let new = stack.pop().unwrap();
+        vm.subjects.push(std::mem::replace(subj, new));
+      }
+      Instr::Pin => {
+        let new = Noun::cell(stack.pop().unwrap(), subj.clone());
+        vm.subjects.push(std::mem::replace(subj, new));
+      }
+      Instr::Leave => *subj = vm.subjects.pop().unwrap(),
+      Instr::Call { tail } => {
+        let formula = stack.pop().unwrap();
+        let subj = stack.pop().unwrap();
+        return Ok(Control::Call {
+          subj,
+          formula,
+          tail: *tail,
+        });
+      }
+      Instr::Invk { axis, tail } => {
+        let core = stack.pop().unwrap();
+        if let Some(prod) = self.run_jet(&core, axis)? {
+          stack.push(prod);
+          return Ok(if *tail { Control::Ret } else { Control::Next });
+        }
+        let formula = arm(&core, axis)?;
+        return Ok(Control::Call {
+          subj: core,
+          formula,
+          tail: *tail,
+        });
+      }
+      Instr::Edit(axis) => {
+        let target = stack.pop().unwrap();
+        let value = stack.pop().unwrap();
+        stack.push(self.edit(&target, axis, value)?);
+      }
+      Instr::HintEnter {
+        tag,
+        clue,
+        body,
+        post,
+        skip,
+      } => {
+        let clue = clue.then(|| stack.pop().unwrap());
+        match self.hint_effect(subj, tag, clue, body) {
+          Hinted::Memoized(prod) => {
+            stack.push(prod);
+            return Ok(Control::Jump(*skip));
+          }
+          effect if *post => vm.marks.push(effect),
+          _ => {}
+        }
+      }
+      Instr::HintLeave => {
+        let prod = stack.last().unwrap();
+        match vm.marks.pop().unwrap() {
+          Hinted::Fast(clue) => self.register(&clue, prod),
+          Hinted::Memo(key) => self.remember(key, prod),
+          Hinted::Dont => self.bypass -= 1,
+          _ => {}
+        }
+      }
+      Instr::Scry => {
+        let path = stack.pop().unwrap();
+        let reference = stack.pop().unwrap();
+        stack.push(self.scry_at(&reference, path)?);
+      }
+      Instr::Other(opcode, arg) => match self.extension(opcode, subj, arg) {
+        Some(prod) => stack.push(prod?),
+        None => return Err(NockError::UnknownOpcode(opcode.clone())),
+      },
+      Instr::Exit(formula) => return Err(self.atom_formula(formula.clone())),
+      Instr::Crash(e) => return Err(e.clone()),
+      Instr::Ret => return Ok(Control::Ret),
+    }
+
+    Ok(Control::Next)
+  }
+}
+
+// traces each frame's spots, innermost first, and puts the error in the
+// opcode that made each call
+fn unwind(mut error: NockError, frame: Frame, frames: Vec<Frame>, mut vm: Vm) -> NockError {
+  for frame in std::iter::once(frame).chain(frames.into_iter().rev()) {
+    for mark in vm.marks.drain(frame.marks..).rev() {
+      if let Hinted::Spot(clue) = mark {
+        error = error.traced(clue);
+      }
+    }
+    error = in_opcode(error, frame.ctx);
+  }
+
+  error
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    error::NockError,
+    interp::{Engine, Formula, Interpreter, Subject},
+    noun::Noun,
+    testing::both,
+  };
+
+  #[test]
+  fn test_bytecode() {
+    let cord = |s| Noun::atom(Atom::from_cord(s));
+
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!({1, 2}),
+        noun!({{addr, 3}, {idty, 7}})
+      ),
+      Ok(noun!({2, 7}))
+    );
+    assert_eq!(
+      both(Engine::Bytecode, noun!(41), noun!({incr, {addr, 1}})),
+      Ok(noun!(42))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!({1, 1}),
+        noun!({eqal, {{addr, 2}, {addr, 3}}})
+      ),
+      Ok(noun!(0))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({brch, {{addr, 1}, {{idty, 5}, {idty, 6}}}})
+      ),
+      Ok(noun!(5))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!(1),
+        noun!({{brch, {{addr, 1}, {{idty, 5}, {idty, 6}}}}, {cell, {addr, 1}}})
+      ),
+      Ok(noun!({6, 1}))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!({1, 2}),
+        noun!({{cmps, {{addr, 3}, {incr, {addr, 1}}}}, {addr, 1}})
+      ),
+      Ok(noun!({3, {1, 2}}))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!(7),
+        noun!({{extn, {{idty, 1}, {addr, 1}}}, {addr, 1}})
+      ),
+      Ok(noun!({{1, 7}, 7}))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!({1, 2}),
+        noun!({rplc, {{2, {idty, 9}}, {addr, 1}}})
+      ),
+      Ok(noun!({9, 2}))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({invk, {2, {idty, {{incr, {addr, 3}}, 5}}}})
+      ),
+      Ok(noun!(6))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!(3),
+        noun!({eval, {{addr, 1}, {idty, {incr, {addr, 1}}}}})
+      ),
+      Ok(noun!(4))
+    );
+    assert_eq!(
+      both(
+        Engine::Bytecode,
+        noun!(3),
+        noun!({hint, {{(cord("memo")), {idty, 0}}, {incr, {addr, 1}}}})
+      ),
+      Ok(noun!(4))
+    );
+
+    // errors land in the same opcodes
+    assert!(both(Engine::Bytecode, noun!(0), noun!({incr, 7})).is_err());
+    assert!(both(Engine::Bytecode, noun!(0), noun!({{addr, 1}, 7})).is_err());
+    assert!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({eval, {{addr, 1}, {idty, 7}}})
+      )
+      .is_err()
+    );
+    assert!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({eval, {{addr, 1}, {idty, {cmps, {{addr, 1}, 7}}}}})
+      )
+      .is_err()
+    );
+    assert!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({brch, {{idty, 2}, {{idty, 5}, {idty, 6}}}})
+      )
+      .is_err()
+    );
+    assert!(both(Engine::Bytecode, noun!(0), noun!({invk, {2, {addr, 1}}})).is_err());
+    assert!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({rplc, {{6, {addr, 1}}, {addr, 1}}})
+      )
+      .is_err()
+    );
+    assert!(both(Engine::Bytecode, noun!(0), noun!({99, 0})).is_err());
+    assert!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({hint, {{(cord("spot")), {idty, 1}}, {eval, {{addr, 1}, {idty, 7}}}}})
+      )
+      .is_err()
+    );
+    assert!(
+      both(
+        Engine::Bytecode,
+        noun!(0),
+        noun!({hint, {{(cord("spot")), {idty, 1}}, {hint, {{(cord("mean")), {idty, 2}}, 7}}}})
+      )
+      .is_err()
+    );
+  }
+
+  #[test]
+  fn test_bytecode_hints() {
+    let cord = |s| Noun::atom(Atom::from_cord(s));
+    let memo = noun!({hint, {{(cord("memo")), {idty, 0}}, {incr, {addr, 1}}}});
+    let mut it = Interpreter::builder().engine(Engine::Bytecode).build();
+
+    assert_eq!(
+      it.eval(Subject(noun!(1)), Formula(memo.clone())).unwrap(),
+      noun!(2)
+    );
+    assert_eq!(it.eval(Subject(noun!(1)), Formula(memo)).unwrap(), noun!(2));
+
+    // the hint's clue isn't evaluated without hints
+    let mut it = Interpreter::builder()
+      .engine(Engine::Bytecode)
+      .hints(false)
+      .build();
+    let formula = noun!({hint, {{(cord("slog")), {addr, 0}}, {idty, 1}}});
+    assert_eq!(
+      it.eval(Subject(noun!(0)), Formula(formula)).unwrap(),
+      noun!(1)
+    );
+  }
+
+  #[test]
+  fn test_bytecode_deep() {
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let mut it = Interpreter::builder()
+      .engine(Engine::Bytecode)
+      .depth_limit(4)
+      .build();
+    assert_eq!(
+      it.eval(Subject(Noun::from(100_000u64)), Formula(decrement))
+        .unwrap(),
+      Noun::from(99_999u64)
+    );
+
+    // a non-tail call nests
+    let mut formula = noun!({addr, 1});
+    for _ in 0..100_000 {
+      formula = noun!({incr, {eval, {{addr, 1}, {idty, (formula)}}}});
+    }
+    let mut it = Interpreter::builder().engine(Engine::Bytecode).build();
+    assert_eq!(
+      it.eval(Subject(noun!(0)), Formula(formula.clone()))
+        .unwrap(),
+      Noun::from(100_000u64)
+    );
+
+    let mut it = Interpreter::builder()
+      .engine(Engine::Bytecode)
+      .depth_limit(100)
+      .build();
+    assert!(matches!(
+      it.eval(Subject(noun!(0)), Formula(formula))
+        .unwrap_err()
+        .root(),
+      NockError::DepthLimit
+    ));
+  }
+}
@@ -0,0 +1,175 @@
+// Products of opcode 2 and 9 calls, keyed by the subject and formula of the
+// call, for interpreters built with `eval_cache`. A call the cache knows is
+// answered without running: it spends no fuel or depth, and neither the trace
+// sink nor hint handlers see what it would have done.
+//
+// Entries are bounded in number and by a rough count of the bytes their
+// products take, and the least recently used go first. Subjects mostly share
+// their cells with the computation that made them, so keys are only charged
+// a fixed amount per entry.
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  mem::size_of,
+};
+
+use crate::noun::{Cell, Noun};
+
+type Key = (Noun, Noun);
+
+// a cell, behind the counts of its `Rc`
+const CELL: usize = size_of::<Cell>() + 2 * size_of::<usize>();
+// the key in both maps, and the entry
+const ENTRY: usize = 2 * size_of::<Key>() + size_of::<Entry>();
+
+struct Entry {
+  prod: Noun,
+  bytes: usize,
+  used: u64,
+}
+
+pub(crate) struct EvalCache {
+  max_entries: usize,
+  max_bytes: usize,
+  bytes: usize,
+  clock: u64,
+  entries: HashMap<Key, Entry>,
+  // keys by when they were last used, oldest first
+  order: BTreeMap<u64, Key>,
+}
+
+// a call waiting on its product
+pub(crate) struct Pending {
+  key: Key,
+  // scries made before the call, see `Interpreter::cache`
+  scries: u64,
+}
+
+impl EvalCache {
+  pub(crate) fn new(max_entries: usize, max_bytes: usize) -> Self {
+    Self {
+      max_entries,
+      max_bytes,
+      bytes: 0,
+      clock: 0,
+      entries: HashMap::new(),
+      order: BTreeMap::new(),
+    }
+  }
+
+  pub(crate) fn clear(&mut self) {
+    self.bytes = 0;
+    self.entries.clear();
+    self.order.clear();
+  }
+
+  pub(crate) fn get(&mut self, subj: &Noun, formula: &Noun) -> Option<Noun> {
+    let key = (subj.clone(), formula.clone());
+    let entry = self.entries.get_mut(&key)?;
+
+    self.clock += 1;
+    self.order.remove(&entry.used);
+    self.order.insert(self.clock, key);
+    entry.used = self.clock;
+    Some(entry.prod.clone())
+  }
+
+  // adds `*{subj formula}` to `calls`, which all wait on the same product;
+  // there's no point in waiting with more calls than the cache holds
+  pub(crate) fn wait(&self, calls: &mut Vec<Pending>, subj: &Noun, formula: &Noun, scries: u64) {
+    if calls.len() < self.max_entries {
+      calls.push(Pending {
+        key: (subj.clone(), formula.clone()),
+        scries,
+      });
+    }
+  }
+
+  pub(crate) fn insert(&mut self, call: Pending, prod: &Noun) {
+    let bytes = footprint(prod).saturating_add(ENTRY);
+    if bytes > self.max_bytes {
+      return;
+    }
+
+    if let Some(old) = self.entries.remove(&call.key) {
+      self.order.remove(&old.used);
+      self.bytes -= old.bytes;
+    }
+    while self.entries.len() >= self.max_entries || self.bytes + bytes > self.max_bytes {
+      let Some((_, key)) = self.order.pop_first() else {
+        return;
+      };
+      self.bytes -= self.entries.remove(&key).unwrap().bytes;
+    }
+
+    self.clock += 1;
+    self.bytes += bytes;
+    self.order.insert(self.clock, call.key.clone());
+    self.entries.insert(
+      call.key,
+      Entry {
+        prod: prod.clone(),
+        bytes,
+        used: self.clock,
+      },
+    );
+  }
+}
+
+impl Pending {
+  pub(crate) fn scries(&self) -> u64 {
+    self.scries
+  }
+}
+
+// bytes taken by the cells of `noun` as a tree, so shared cells count each
+// time they're reached
+fn footprint(noun: &Noun) -> usize {
+  let cells = usize::try_from(noun.nodes() / 2).unwrap_or(usize::MAX);
+  cells.saturating_mul(CELL)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{ENTRY, EvalCache};
+  use crate::noun::Noun;
+
+  fn insert(cache: &mut EvalCache, subj: Noun, formula: Noun, prod: Noun) {
+    let mut calls = vec![];
+    cache.wait(&mut calls, &subj, &formula, 0);
+    for call in calls {
+      cache.insert(call, &prod);
+    }
+  }
+
+  #[test]
+  fn test_eval_cache() {
+    let mut cache = EvalCache::new(2, usize::MAX);
+    let formula = noun!({incr, {addr, 1}});
+    insert(&mut cache, noun!(1), formula.clone(), noun!(2));
+    insert(&mut cache, noun!(2), formula.clone(), noun!(3));
+    assert_eq!(cache.get(&noun!(1), &formula), Some(noun!(2)));
+
+    // 2 was used longest ago
+    insert(&mut cache, noun!(3), formula.clone(), noun!(4));
+    assert_eq!(cache.entries.len(), 2);
+    assert_eq!(cache.get(&noun!(2), &formula), None);
+    assert_eq!(cache.get(&noun!(1), &formula), Some(noun!(2)));
+    assert_eq!(cache.get(&noun!(3), &formula), Some(noun!(4)));
+
+    // bounded by bytes, a product that can never fit isn't kept
+    let mut cache = EvalCache::new(100, 2 * ENTRY + 200);
+    let big = (0..100u64).fold(noun!(0), |list, i| Noun::cell(Noun::from(i), list));
+    insert(&mut cache, noun!(0), formula.clone(), big);
+    assert_eq!(cache.entries.len(), 0);
+    for n in 0..10u64 {
+      insert(&mut cache, Noun::from(n), formula.clone(), noun!({1, 2}));
+    }
+    assert!(cache.entries.len() < 10);
+    assert!(cache.bytes <= cache.max_bytes);
+    assert_eq!(cache.get(&noun!(9), &formula), Some(noun!({1, 2})));
+
+    cache.clear();
+    assert_eq!(cache.get(&noun!(9), &formula), None);
+  }
+}
@@ -0,0 +1,276 @@
+// Nouns as CBOR (RFC 8949): an atom that fits in a word is an unsigned
+// integer, a bigger one a positive bignum, which is tag 2 on a byte string of
+// its big-endian bytes, and a cell an array of its head and tail. Integers
+// are written as short as they go. Reading takes the same, and what the serde
+// support writes: a longer array is a tuple, right nested, and a bare byte
+// string an atom's little-endian bytes. Nothing else is a noun.
+
+use crate::{atom::Atom, noun::Noun};
+
+const UNSIGNED: u8 = 0;
+const BYTES: u8 = 2;
+const ARRAY: u8 = 4;
+const TAG: u8 = 6;
+const BIGNUM: u64 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CborError {
+  // the input ran out in the middle of the item at `offset`
+  Truncated { offset: usize },
+  // the item at `offset`, whose first byte is `byte`, has no noun
+  NotNoun { offset: usize, byte: u8 },
+  // the array at `offset` has fewer than two items
+  ShortArray { offset: usize, len: u64 },
+  // there's more input after the noun, from `offset`
+  Trailing { offset: usize },
+}
+
+impl std::fmt::Display for CborError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Truncated { offset } => write!(f, "input ends inside the item at byte {offset}"),
+      Self::NotNoun { offset, byte } => {
+        write!(f, "item {byte:#04x} at byte {offset} isn't a noun")
+      }
+      Self::ShortArray { offset, len } => {
+        write!(f, "array of {len} at byte {offset}, a cell needs two")
+      }
+      Self::Trailing { offset } => write!(f, "input goes on after the noun, at byte {offset}"),
+    }
+  }
+}
+
+impl std::error::Error for CborError {}
+
+// the first bytes of an item: its major type and the shortest form of `n`
+fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+  let major = major << 5;
+  match n {
+    0..24 => out.push(major | n as u8),
+    24..0x100 => out.extend([major | 24, n as u8]),
+    0x100..0x1_0000 => {
+      out.push(major | 25);
+      out.extend((n as u16).to_be_bytes());
+    }
+    0x1_0000..0x1_0000_0000 => {
+      out.push(major | 26);
+      out.extend((n as u32).to_be_bytes());
+    }
+    _ => {
+      out.push(major | 27);
+      out.extend(n.to_be_bytes());
+    }
+  }
+}
+
+pub fn to_cbor(noun: &Noun) -> Vec<u8> {
+  let mut out = vec![];
+  let mut stack = vec![noun];
+
+  while let Some(noun) = stack.pop() {
+    if let Some((head_, tail)) = noun.as_cell() {
+      head(&mut out, ARRAY, 2);
+      stack.push(tail);
+      stack.push(head_);
+      continue;
+    }
+
+    let atom = noun.as_atom().unwrap();
+    if let Some(atom) = atom.as_u64() {
+      head(&mut out, UNSIGNED, atom);
+      continue;
+    }
+
+    let mut bytes = atom.as_le_bytes();
+    bytes.reverse();
+    head(&mut out, TAG, BIGNUM);
+    head(&mut out, BYTES, bytes.len() as u64);
+    out.extend(bytes);
+  }
+
+  out
+}
+
+struct Input<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+  // where the item being read starts
+  start: usize,
+}
+
+impl<'a> Input<'a> {
+  fn truncated(&self) -> CborError {
+    CborError::Truncated { offset: self.start }
+  }
+
+  fn take(&mut self, n: u64) -> Result<&'a [u8], CborError> {
+    let end = usize::try_from(n)
+      .ok()
+      .and_then(|n| self.pos.checked_add(n))
+      .filter(|&end| end <= self.bytes.len())
+      .ok_or_else(|| self.truncated())?;
+
+    let bytes = &self.bytes[self.pos..end];
+    self.pos = end;
+    Ok(bytes)
+  }
+
+  // an item's major type and argument
+  fn head(&mut self) -> Result<(u8, u64), CborError> {
+    let byte = self.take(1)?[0];
+    let not_noun = CborError::NotNoun {
+      offset: self.start,
+      byte,
+    };
+
+    let n = match byte & 0x1f {
+      n @ 0..24 => u64::from(n),
+      24 => u64::from(self.take(1)?[0]),
+      25 => u64::from(u16::from_be_bytes(self.take(2)?.try_into().unwrap())),
+      26 => u64::from(u32::from_be_bytes(self.take(4)?.try_into().unwrap())),
+      27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+      // indefinite lengths, and nothing at all
+      _ => return Err(not_noun),
+    };
+    Ok((byte >> 5, n))
+  }
+
+  fn bignum(&mut self, len: u64) -> Result<Noun, CborError> {
+    let mut bytes = self.take(len)?.to_vec();
+    bytes.reverse();
+    Ok(Noun::from(Atom::from_le_bytes(&bytes)))
+  }
+}
+
+// an array being read, and the items read from it so far
+struct Array {
+  len: u64,
+  items: Vec<Noun>,
+}
+
+pub fn from_cbor(bytes: &[u8]) -> Result<Noun, CborError> {
+  let mut input = Input {
+    bytes,
+    pos: 0,
+    start: 0,
+  };
+  let mut stack: Vec<Array> = vec![];
+
+  loop {
+    input.start = input.pos;
+    let offset = input.pos;
+    let not_noun = |byte| CborError::NotNoun { offset, byte };
+
+    let mut noun = match input.head()? {
+      (UNSIGNED, n) => Noun::from(n),
+      (BYTES, len) => Noun::from(Atom::from_le_bytes(input.take(len)?)),
+      (TAG, BIGNUM) => match input.head()? {
+        (BYTES, len) => input.bignum(len)?,
+        _ => return Err(not_noun(bytes[offset])),
+      },
+      (ARRAY, len) if len < 2 => return Err(CborError::ShortArray { offset, len }),
+      (ARRAY, len) => {
+        // no bigger than the input could hold
+        let items = Vec::with_capacity(len.min((bytes.len() - input.pos) as u64) as usize);
+        stack.push(Array { len, items });
+        continue;
+      }
+      _ => return Err(not_noun(bytes[offset])),
+    };
+
+    // finish every array this was the last item of
+    loop {
+      let Some(array) = stack.last_mut() else {
+        if input.pos < bytes.len() {
+          return Err(CborError::Trailing { offset: input.pos });
+        }
+        return Ok(noun);
+      };
+
+      array.items.push(noun);
+      if (array.items.len() as u64) < array.len {
+        break;
+      }
+      let mut items = stack.pop().unwrap().items;
+      let last = items.pop().unwrap();
+      noun = items
+        .into_iter()
+        .rev()
+        .fold(last, |tail, head| Noun::cell(head, tail));
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{CborError, from_cbor, to_cbor};
+  use crate::{atom::Atom, noun::Noun};
+
+  #[test]
+  fn test_cbor() {
+    let big = Noun::from(Atom::from_words(&[0, 1]));
+    for (noun, cbor) in [
+      (noun!(0), vec![0x00]),
+      (noun!(23), vec![0x17]),
+      (noun!(24), vec![0x18, 0x18]),
+      (noun!(500), vec![0x19, 0x01, 0xf4]),
+      (noun!(0x1_0000_0000), vec![0x1b, 0, 0, 0, 1, 0, 0, 0, 0]),
+      (noun!({1, 2}), vec![0x82, 0x01, 0x02]),
+      (big.clone(), vec![0xc2, 0x49, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+    ] {
+      assert_eq!(to_cbor(&noun), cbor, "{noun}");
+      assert_eq!(from_cbor(&cbor), Ok(noun));
+    }
+
+    let deep = (0..100_000u64).fold(big, |list, n| Noun::cell(Noun::from(n), list));
+    assert_eq!(from_cbor(&to_cbor(&deep)).unwrap(), deep);
+
+    // tuples, bare byte strings and long forms
+    assert_eq!(from_cbor(&[0x83, 1, 2, 3]), Ok(noun!({1, {2, 3}})));
+    assert_eq!(from_cbor(&[0x42, 0, 1]), Ok(noun!(256)));
+    assert_eq!(from_cbor(&[0x19, 0, 5]), Ok(noun!(5)));
+  }
+
+  #[test]
+  fn test_cbor_errors() {
+    assert_eq!(from_cbor(&[]), Err(CborError::Truncated { offset: 0 }));
+    assert_eq!(
+      from_cbor(&[0x82, 1, 0x19, 0]),
+      Err(CborError::Truncated { offset: 2 })
+    );
+    assert_eq!(
+      from_cbor(&[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+      Err(CborError::Truncated { offset: 0 })
+    );
+
+    // negative numbers, text, floats, indefinite arrays and other tags
+    for (cbor, byte) in [
+      (vec![0x82, 1, 0x20], 0x20),
+      (vec![0x82, 1, 0x61, 0x61], 0x61),
+      (vec![0x82, 1, 0xf9, 0, 0], 0xf9),
+      (vec![0x82, 1, 0x9f], 0x9f),
+      (vec![0x82, 1, 0xc3, 0x41, 1], 0xc3),
+    ] {
+      assert_eq!(
+        from_cbor(&cbor),
+        Err(CborError::NotNoun { offset: 2, byte })
+      );
+    }
+    assert_eq!(
+      from_cbor(&[0xc2, 0x01]),
+      Err(CborError::NotNoun {
+        offset: 0,
+        byte: 0xc2
+      })
+    );
+
+    assert_eq!(
+      from_cbor(&[0x82, 0x81, 1, 2]),
+      Err(CborError::ShortArray { offset: 1, len: 1 })
+    );
+    assert_eq!(
+      from_cbor(&[0x82, 1, 2, 3]),
+      Err(CborError::Trailing { offset: 3 })
+    );
+  }
+}
@@ -0,0 +1,533 @@
+// Formulas compiled to a tree of closures, one per node, which call each other
+// instead of matching on cells at every step. A middle ground between the
+// interpreter and `bytecode`: building it is cheap, but sub-formulas and calls
+// outside tail position nest on the native stack. Running them with less
+// than `RED_ZONE` bytes of it left gives up with `NockError::StackExhausted`
+// rather than overflow it, and building them that low leaves the rest of the
+// formula to be built when it's reached.
+//
+// Like the bytecode engine, each call through opcode 2 or 9 spends one step of
+// fuel and one level of depth, and is what the trace sink sees.
+
+use crate::{
+  atom::{Atom, NAH, YES},
+  cache::Pending,
+  compile::{Op, arm, shape},
+  error::NockError,
+  interp::{FAST, Hinted, Interpreter, MEAN, MEMO, SPOT},
+  noun::{Noun, OnceCell, Rc, Shareable},
+  opcode::Opcode,
+};
+
+// enough for a step, and whatever jet or sink it calls
+const RED_ZONE: usize = 1 << 17;
+
+pub(crate) enum Next {
+  Done(Noun),
+  // a call in tail position, made by the driver in place of this one
+  Call(Opcode, Noun, Noun),
+}
+
+type Closure<T> = Box<dyn_shareable!(Fn(&mut Interpreter, &Noun) -> Result<T, NockError>)>;
+pub(crate) type Tail = Closure<Next>;
+
+// errors from `f` without an opcode of their own are put in `opcode`
+fn within<T: 'static>(
+  opcode: Opcode,
+  f: impl Fn(&mut Interpreter, &Noun) -> Result<T, NockError> + Shareable + 'static,
+) -> Closure<T> {
+  Box::new(move |it, subj| {
+    it.room()?;
+    f(it, subj).map_err(|e| e.in_opcode(opcode))
+  })
+}
+
+// whether this thread's stack is nearly all used, as far as it can be told
+fn low() -> bool {
+  stacker::remaining_stack().is_some_and(|left| left < RED_ZONE)
+}
+
+// `build(formula)` once it's first run, for formulas reached with the stack
+// too low to build them then
+fn later<T: 'static>(
+  formula: &Noun,
+  hints: bool,
+  build: fn(&Noun, bool) -> Closure<T>,
+) -> Closure<T> {
+  let formula = formula.clone();
+  let built = OnceCell::new();
+  Box::new(move |it, subj| {
+    it.room()?;
+    built.get_or_init(|| build(&formula, hints))(it, subj)
+  })
+}
+
+fn cond(prod: Noun) -> Result<bool, NockError> {
+  match prod.as_atom().and_then(Atom::as_u64) {
+    Some(YES) => Ok(true),
+    Some(NAH) => Ok(false),
+    _ => Err(NockError::NonLoobeanCondition { value: prod }),
+  }
+}
+
+// whether a hint does anything with its formula's product, which keeps the
+// formula out of tail position
+fn wraps(tag: &Noun, clue: bool) -> bool {
+  match tag.as_atom().and_then(Atom::as_u64) {
+    Some(MEMO) => true,
+    Some(FAST | SPOT | MEAN) => clue,
+    _ => false,
+  }
+}
+
+fn expr(formula: &Noun, hints: bool) -> Closure<Noun> {
+  if low() {
+    return later(formula, hints, expr);
+  }
+
+  let (op, formulas) = shape(formula);
+  let mut kids = formulas.iter().map(|kid| expr(kid, hints));
+  let mut kid = || kids.next().unwrap();
+
+  match op {
+    Op::Atom => {
+      let formula = formula.clone();
+      Box::new(move |it, _| Err(it.atom_formula(formula.clone())))
+    }
+    Op::Cons => {
+      let (b, c) = (kid(), kid());
+      Box::new(move |it, subj| {
+        it.room()?;
+        let (head, tail) = (b(it, subj)?, c(it, subj)?);
+        Ok(it.cons(head, tail))
+      })
+    }
+    Op::Slot(axis) => within(Opcode::Addr, move |_, subj| subj.slot_atom(&axis)),
+    Op::Quote(noun) => Box::new(move |_, _| Ok(noun.clone())),
+    Op::Eval => {
+      let (b, c) = (kid(), kid());
+      within(Opcode::Eval, move |it, subj| {
+        let (subj, form) = (b(it, subj)?, c(it, subj)?);
+        it.call_closure(subj, form)
+      })
+    }
+    Op::Cell => {
+      let b = kid();
+      within(Opcode::Cell, move |it, subj| {
+        Ok(Noun::from(b(it, subj)?.is_cell()))
+      })
+    }
+    Op::Incr => {
+      let b = kid();
+      within(Opcode::Incr, move |it, subj| {
+        Ok(Noun::atom(b(it, subj)?.try_atom()?.incr()))
+      })
+    }
+    Op::Eqal => {
+      let (b, c) = (kid(), kid());
+      within(Opcode::Eqal, move |it, subj| {
+        let (x, y) = (b(it, subj)?, c(it, subj)?);
+        Ok(Noun::from(it.eq(&x, &y)))
+      })
+    }
+    Op::Brch => {
+      let (b, c, d) = (kid(), kid(), kid());
+      within(Opcode::Brch, move |it, subj| {
+        if cond(b(it, subj)?)? {
+          c(it, subj)
+        } else {
+          d(it, subj)
+        }
+      })
+    }
+    Op::Cmps => {
+      let (b, c) = (kid(), kid());
+      within(Opcode::Cmps, move |it, subj| {
+        let subj = b(it, subj)?;
+        c(it, &subj)
+      })
+    }
+    Op::Extn => {
+      let (b, c) = (kid(), kid());
+      within(Opcode::Extn, move |it, subj| {
+        let subj = Noun::cell(b(it, subj)?, subj.clone());
+        c(it, &subj)
+      })
+    }
+    Op::Invk(axis) => {
+      let b = kid();
+      within(Opcode::Invk, move |it, subj| {
+        let core = b(it, subj)?;
+        if let Some(prod) = it.run_jet(&core, &axis)? {
+          return Ok(prod);
+        }
+        let arm = arm(&core, &axis)?;
+        it.call_closure(core, arm)
+      })
+    }
+    Op::Rplc(axis) => {
+      let (b, c) = (kid(), kid());
+      within(Opcode::Rplc, move |it, subj| {
+        let (value, target) = (b(it, subj)?, c(it, subj)?);
+        it.edit(&target, &axis, value)
+      })
+    }
+    Op::Hint { .. } if !hints => within(Opcode::Hint, expr(formulas.last().unwrap(), hints)),
+    Op::Hint { tag, clue } => {
+      let clue = clue.then(&mut kid);
+      let body = kid();
+      let body_formula = formula.slot(7).unwrap();
+
+      within(Opcode::Hint, move |it, subj| {
+        let clue = clue.as_ref().map(|clue| clue(it, subj)).transpose()?;
+        match it.hint_effect(subj, &tag, clue, &body_formula) {
+          Hinted::Run => body(it, subj),
+          Hinted::Memoized(prod) => Ok(prod),
+          Hinted::Spot(clue) => body(it, subj).map_err(|e| e.traced(clue)),
+          Hinted::Fast(clue) => {
+            let prod = body(it, subj)?;
+            it.register(&clue, &prod);
+            Ok(prod)
+          }
+          Hinted::Memo(key) => {
+            let prod = body(it, subj)?;
+            it.remember(key, &prod);
+            Ok(prod)
+          }
+          Hinted::Dont => {
+            let prod = body(it, subj);
+            it.bypass -= 1;
+            prod
+          }
+        }
+      })
+    }
+    Op::Scry => {
+      let (b, c) = (kid(), kid());
+      within(Opcode::Scry, move |it, subj| {
+        let (reference, path) = (b(it, subj)?, c(it, subj)?);
+        it.scry_at(&reference, path)
+      })
+    }
+    Op::Other(opcode, arg) => Box::new(move |it, subj| {
+      it.extension(&opcode, subj, &arg)
+        .unwrap_or_else(|| Err(NockError::UnknownOpcode(opcode.clone())))
+    }),
+    Op::Crash(e) => Box::new(move |_, _| Err(e.clone())),
+  }
+}
+
+// like `expr`, but calls are left to the driver
+fn tail(formula: &Noun, hints: bool) -> Tail {
+  if low() {
+    return later(formula, hints, tail);
+  }
+
+  let (op, kids) = shape(formula);
+
+  match op {
+    Op::Eval => {
+      let (b, c) = (expr(&kids[0], hints), expr(&kids[1], hints));
+      within(Opcode::Eval, move |it, subj| {
+        Ok(Next::Call(Opcode::Eval, b(it, subj)?, c(it, subj)?))
+      })
+    }
+    Op::Invk(axis) => {
+      let b = expr(&kids[0], hints);
+      within(Opcode::Invk, move |it, subj| {
+        let core = b(it, subj)?;
+        if let Some(prod) = it.run_jet(&core, &axis)? {
+          return Ok(Next::Done(prod));
+        }
+        let arm = arm(&core, &axis)?;
+        Ok(Next::Call(Opcode::Invk, core, arm))
+      })
+    }
+    Op::Brch => {
+      let b = expr(&kids[0], hints);
+      let (c, d) = (tail(&kids[1], hints), tail(&kids[2], hints));
+      within(Opcode::Brch, move |it, subj| {
+        if cond(b(it, subj)?)? {
+          c(it, subj)
+        } else {
+          d(it, subj)
+        }
+      })
+    }
+    Op::Cmps => {
+      let (b, c) = (expr(&kids[0], hints), tail(&kids[1], hints));
+      within(Opcode::Cmps, move |it, subj| {
+        let subj = b(it, subj)?;
+        c(it, &subj)
+      })
+    }
+    Op::Extn => {
+      let (b, c) = (expr(&kids[0], hints), tail(&kids[1], hints));
+      within(Opcode::Extn, move |it, subj| {
+        let subj = Noun::cell(b(it, subj)?, subj.clone());
+        c(it, &subj)
+      })
+    }
+    Op::Hint { .. } if !hints => within(Opcode::Hint, tail(kids.last().unwrap(), hints)),
+    Op::Hint { tag, clue } if !wraps(&tag, clue) => {
+      let clue = clue.then(|| expr(&kids[0], hints));
+      let body = tail(kids.last().unwrap(), hints);
+      let body_formula = formula.slot(7).unwrap();
+
+      within(Opcode::Hint, move |it, subj| {
+        let clue = clue.as_ref().map(|clue| clue(it, subj)).transpose()?;
+        // nothing to do afterwards, or `wraps` would have said so
+        it.hint_effect(subj, &tag, clue, &body_formula);
+        body(it, subj)
+      })
+    }
+    _ => {
+      let e = expr(formula, hints);
+      Box::new(move |it, subj| Ok(Next::Done(e(it, subj)?)))
+    }
+  }
+}
+
+impl Interpreter {
+  fn closure(&mut self, formula: &Noun) -> Rc<Tail> {
+    if let Some(closure) = self.closures.get(formula) {
+      return closure.clone();
+    }
+
+    let closure = Rc::new(tail(formula, self.hints));
+    self.closures.insert(formula.clone(), closure.clone());
+    closure
+  }
+
+  pub(crate) fn run_closure(&mut self, subj: Noun, formula: Noun) -> Result<Noun, NockError> {
+    self.drive(subj, formula, vec![])
+  }
+
+  fn room(&self) -> Result<(), NockError> {
+    if low() {
+      return Err(NockError::StackExhausted);
+    }
+    Ok(())
+  }
+
+  // an opcode 2 or 9 call, which the eval cache might know
+  fn call_closure(&mut self, subj: Noun, formula: Noun) -> Result<Noun, NockError> {
+    if let Some(prod) = self.cached(&subj, &formula) {
+      return Ok(prod);
+    }
+
+    let mut calls = vec![];
+    self.wait(&mut calls, &subj, &formula);
+    self.drive(subj, formula, calls)
+  }
+
+  // calls `formula` on `subj`, and whatever it calls in tail position, and
+  // caches the product for `calls`
+  fn drive(
+    &mut self,
+    mut subj: Noun,
+    mut formula: Noun,
+    mut calls: Vec<Pending>,
+  ) -> Result<Noun, NockError> {
+    let mut opcode = None;
+
+    loop {
+      let next = self.enter(&subj, &formula).and_then(|()| {
+        self.room()?;
+        let closure = self.closure(&formula);
+        closure(self, &subj)
+      });
+      let next = match (next, opcode) {
+        (Err(e), Some(opcode)) => return Err(e.in_opcode(opcode)),
+        (next, _) => next?,
+      };
+      self.depth -= 1;
+
+      let prod = match next {
+        Next::Done(prod) => prod,
+        Next::Call(by, s, f) => match self.cached(&s, &f) {
+          Some(prod) => prod,
+          None => {
+            self.wait(&mut calls, &s, &f);
+            (opcode, subj, formula) = (Some(by), s, f);
+            continue;
+          }
+        },
+      };
+      self.cache(calls, &prod);
+      return Ok(prod);
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    error::NockError,
+    interp::{Engine, Formula, Interpreter, Subject},
+    noun::Noun,
+    testing::both,
+  };
+
+  #[test]
+  fn test_closure() {
+    let cord = |s| Noun::atom(Atom::from_cord(s));
+
+    assert_eq!(
+      both(
+        Engine::Closure,
+        noun!({1, 2}),
+        noun!({{addr, 3}, {idty, 7}})
+      ),
+      Ok(noun!({2, 7}))
+    );
+    assert_eq!(
+      both(
+        Engine::Closure,
+        noun!(1),
+        noun!({{brch, {{addr, 1}, {{idty, 5}, {idty, 6}}}}, {cell, {addr, 1}}})
+      ),
+      Ok(noun!({6, 1}))
+    );
+    assert_eq!(
+      both(
+        Engine::Closure,
+        noun!(7),
+        noun!({{extn, {{idty, 1}, {addr, 1}}}, {eqal, {{addr, 1}, {addr, 1}}}})
+      ),
+      Ok(noun!({{1, 7}, 0}))
+    );
+    assert_eq!(
+      both(
+        Engine::Closure,
+        noun!({1, 2}),
+        noun!({rplc, {{2, {idty, 9}}, {addr, 1}}})
+      ),
+      Ok(noun!({9, 2}))
+    );
+    assert_eq!(
+      both(
+        Engine::Closure,
+        noun!(0),
+        noun!({invk, {2, {idty, {{incr, {addr, 3}}, 5}}}})
+      ),
+      Ok(noun!(6))
+    );
+    assert_eq!(
+      both(
+        Engine::Closure,
+        noun!(3),
+        noun!({hint, {{(cord("memo")), {idty, 0}}, {eval, {{addr, 1}, {idty, {incr, {addr, 1}}}}}}})
+      ),
+      Ok(noun!(4))
+    );
+
+    // errors land in the same opcodes
+    assert!(both(Engine::Closure, noun!(0), noun!({incr, 7})).is_err());
+    assert!(both(Engine::Closure, noun!(0), noun!({{addr, 1}, 7})).is_err());
+    assert!(
+      both(
+        Engine::Closure,
+        noun!(0),
+        noun!({eval, {{addr, 1}, {idty, 7}}})
+      )
+      .is_err()
+    );
+    assert!(
+      both(
+        Engine::Closure,
+        noun!(0),
+        noun!({brch, {{idty, 2}, {{idty, 5}, {idty, 6}}}})
+      )
+      .is_err()
+    );
+    assert!(both(Engine::Closure, noun!(0), noun!({invk, {2, {addr, 1}}})).is_err());
+    assert!(both(Engine::Closure, noun!(0), noun!({99, 0})).is_err());
+    assert!(
+      both(
+        Engine::Closure,
+        noun!(0),
+        noun!({hint, {{(cord("spot")), {idty, 1}}, {hint, {{(cord("mean")), {idty, 2}}, 7}}}})
+      )
+      .is_err()
+    );
+  }
+
+  #[test]
+  fn test_closure_tail() {
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let mut it = Interpreter::builder()
+      .engine(Engine::Closure)
+      .depth_limit(4)
+      .build();
+    assert_eq!(
+      it.eval(Subject(Noun::from(100_000u64)), Formula(decrement))
+        .unwrap(),
+      Noun::from(99_999u64)
+    );
+
+    let mut formula = noun!({addr, 1});
+    for _ in 0..1_000 {
+      formula = noun!({incr, {eval, {{addr, 1}, {idty, (formula)}}}});
+    }
+    let mut it = Interpreter::builder()
+      .engine(Engine::Closure)
+      .depth_limit(100)
+      .build();
+    assert!(matches!(
+      it.eval(Subject(noun!(0)), Formula(formula))
+        .unwrap_err()
+        .root(),
+      NockError::DepthLimit
+    ));
+  }
+
+  // formulas and calls nested too deep for the native stack give up instead
+  // of overflowing it, on a test thread's stack at that
+  #[test]
+  fn test_closure_deep() {
+    let run = |it: &mut Interpreter, formula| it.eval(Subject(noun!(0)), Formula(formula));
+    let mut it = Interpreter::builder().engine(Engine::Closure).build();
+
+    assert_eq!(run(&mut it, deep(100)).unwrap(), Noun::from(100u64));
+    assert!(matches!(
+      run(&mut it, deep(200_000)).unwrap_err().root(),
+      NockError::StackExhausted
+    ));
+
+    // counts up to a million, calling itself outside tail position
+    let count = noun!({brch, {
+      {eqal, {{addr, 2}, {idty, 1_000_000}}},
+      {{idty, 0}, {incr, {eval, {{{incr, {addr, 2}}, {addr, 3}}, {addr, 3}}}}}
+    }});
+    let p = it.eval(Subject(noun!({0, (count.clone())})), Formula(count));
+    assert!(matches!(p.unwrap_err().root(), NockError::StackExhausted));
+
+    // a formula first met with the stack nearly gone runs once there's room
+    let formula = deep(50);
+    low_stack(|| it.closure(&formula));
+    assert_eq!(run(&mut it, formula).unwrap(), Noun::from(50u64));
+  }
+
+  fn deep(n: usize) -> Noun {
+    let mut formula = noun!({addr, 1});
+    for _ in 0..n {
+      formula = noun!({incr, (formula)});
+    }
+    formula
+  }
+
+  // `f` with just a little more than `RED_ZONE` of the stack left
+  fn low_stack<T>(f: impl FnOnce() -> T) -> T {
+    let left = stacker::remaining_stack().unwrap();
+    if left < super::RED_ZONE + (1 << 12) {
+      return f();
+    }
+    let pad = std::hint::black_box([0u8; 256]);
+    let prod = low_stack(f);
+    std::hint::black_box(pad);
+    prod
+  }
+}
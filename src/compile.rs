@@ -0,0 +1,653 @@
+// Formulas parsed once into a tree of typed nodes, with the opcode decoded,
+// axes pulled out and children split off ahead of time, so a loop body isn't
+// pattern matched from raw cells on every iteration. Formulas only known at
+// run time, from opcodes 2 and 9, are compiled on first use and cached.
+//
+// Malformed formulas compile to nodes that crash when reached, as the
+// interpreter would. Evaluation counts one step of fuel per node, and unlike
+// the interpreter doesn't spend steps on the spec's own formulas for picking
+// a branch or pulling an arm out of a core.
+
+use crate::{
+  atom::{Atom, NAH, YES},
+  cache::Pending,
+  error::NockError,
+  interp::{Hinted, Interpreter, Subject},
+  noun::{Noun, Rc},
+  opcode::Opcode,
+};
+
+#[derive(Clone, Debug)]
+pub struct CompiledFormula(Rc<Node>);
+
+impl CompiledFormula {
+  pub fn new(formula: &Noun) -> Self {
+    Self(compile(formula))
+  }
+
+  pub fn formula(&self) -> &Noun {
+    &self.0.formula
+  }
+}
+
+impl From<&Noun> for CompiledFormula {
+  fn from(formula: &Noun) -> Self {
+    Self::new(formula)
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct Node {
+  formula: Noun,
+  op: Op,
+  // sub-formulas, in the order they're written
+  kids: Vec<Rc<Node>>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Op {
+  Atom,
+  Cons,
+  Slot(Atom),
+  Quote(Noun),
+  Eval,
+  Cell,
+  Incr,
+  Eqal,
+  Brch,
+  Cmps,
+  Extn,
+  // the axis is only checked once the core is known
+  Invk(Noun),
+  Rplc(Atom),
+  // the clue is the first kid, when there is one
+  Hint { tag: Noun, clue: bool },
+  Scry,
+  // left to the interpreter's extensions
+  Other(Atom, Noun),
+  Crash(NockError),
+}
+
+impl Drop for Node {
+  // like `Cell`, unlinks the kids nothing else holds instead of recursing
+  fn drop(&mut self) {
+    let mut pending: Vec<Node> = self.kids.drain(..).filter_map(Rc::into_inner).collect();
+
+    while let Some(mut node) = pending.pop() {
+      pending.extend(node.kids.drain(..).filter_map(Rc::into_inner));
+    }
+  }
+}
+
+// the node's op and the sub-formulas it needs compiled
+pub(crate) fn shape(formula: &Noun) -> (Op, Vec<Noun>) {
+  let Some((head, b)) = formula.as_cell() else {
+    return (Op::Atom, vec![]);
+  };
+  let Some(inst) = head.as_atom() else {
+    return (Op::Cons, vec![head.clone(), b.clone()]);
+  };
+  let Ok(opcode) = Opcode::try_from(inst.clone()) else {
+    return (Op::Other(inst.clone(), b.clone()), vec![]);
+  };
+
+  let crash = |e: NockError| (Op::Crash(e.in_opcode(opcode)), vec![]);
+  let cell_required = || crash(NockError::CellRequired { found: b.clone() });
+
+  match opcode {
+    Opcode::Addr => match b.as_atom() {
+      Some(axis) => (Op::Slot(axis.clone()), vec![]),
+      None => crash(NockError::AtomRequired { found: b.clone() }),
+    },
+    Opcode::Idty => (Op::Quote(b.clone()), vec![]),
+    Opcode::Cell => (Op::Cell, vec![b.clone()]),
+    Opcode::Incr => (Op::Incr, vec![b.clone()]),
+    Opcode::Brch => noun_match!(b,
+      {x, {y, z}} => (Op::Brch, vec![x.clone(), y.clone(), z.clone()]),
+      _ => cell_required(),
+    ),
+    Opcode::Rplc => noun_match!(b,
+      {{x, y}, z} => match x.as_atom() {
+        Some(axis) => (Op::Rplc(axis.clone()), vec![y.clone(), z.clone()]),
+        None => crash(NockError::AtomRequired { found: x.clone() }),
+      },
+      _ => cell_required(),
+    ),
+    _ => {
+      let Some((x, y)) = b.as_cell() else {
+        return cell_required();
+      };
+      let (x, y) = (x.clone(), y.clone());
+
+      match opcode {
+        Opcode::Invk => (Op::Invk(x), vec![y]),
+        Opcode::Hint => match x.as_cell() {
+          Some((tag, clue)) => (
+            Op::Hint {
+              tag: tag.clone(),
+              clue: true,
+            },
+            vec![clue.clone(), y],
+          ),
+          None => (
+            Op::Hint {
+              tag: x,
+              clue: false,
+            },
+            vec![y],
+          ),
+        },
+        Opcode::Eval => (Op::Eval, vec![x, y]),
+        Opcode::Eqal => (Op::Eqal, vec![x, y]),
+        Opcode::Cmps => (Op::Cmps, vec![x, y]),
+        Opcode::Extn => (Op::Extn, vec![x, y]),
+        _ => (Op::Scry, vec![x, y]),
+      }
+    }
+  }
+}
+
+// children before their parents, without recursing
+fn compile(formula: &Noun) -> Rc<Node> {
+  enum Pending {
+    Visit(Noun),
+    Build(Noun, Op, usize),
+  }
+
+  let mut stack = vec![Pending::Visit(formula.clone())];
+  let mut done: Vec<Rc<Node>> = vec![];
+
+  while let Some(pending) = stack.pop() {
+    match pending {
+      Pending::Visit(formula) => {
+        let (op, kids) = shape(&formula);
+        stack.push(Pending::Build(formula, op, kids.len()));
+        stack.extend(kids.into_iter().rev().map(Pending::Visit));
+      }
+      Pending::Build(formula, op, kids) => {
+        let kids = done.split_off(done.len() - kids);
+        done.push(Rc::new(Node { formula, op, kids }));
+      }
+    }
+  }
+
+  done.pop().unwrap()
+}
+
+enum Next {
+  Call(Noun, Rc<Node>),
+  Tail(Opcode, Noun, Rc<Node>),
+  Ret(Noun),
+}
+
+// see `interp::Frame`, these wait on nodes instead of formulas
+enum Frame {
+  Head { subj: Noun, tail: Rc<Node> },
+  Tail { head: Noun },
+  EvalSubject { subj: Noun, c: Rc<Node> },
+  EvalFormula { subj: Noun },
+  Cell,
+  Incr,
+  EqalLeft { subj: Noun, c: Rc<Node> },
+  EqalRight { left: Noun },
+  Brch { subj: Noun, node: Rc<Node> },
+  Cmps { c: Rc<Node> },
+  Extn { subj: Noun, c: Rc<Node> },
+  Invk { axis: Noun },
+  RplcValue { subj: Noun, axis: Atom, d: Rc<Node> },
+  RplcTarget { axis: Atom, value: Noun },
+  HintClue { subj: Noun, node: Rc<Node> },
+  Fast { clue: Noun },
+  Spot { clue: Noun },
+  Memo { key: Noun },
+  Dont,
+  Cache { calls: Vec<Pending> },
+  ScryRef { subj: Noun, c: Rc<Node> },
+  ScryPath { reference: Noun },
+}
+
+impl Frame {
+  fn opcode(&self) -> Option<Opcode> {
+    let opcode = match self {
+      Self::Head { .. } | Self::Tail { .. } | Self::Cache { .. } => return None,
+      Self::EvalSubject { .. } | Self::EvalFormula { .. } => Opcode::Eval,
+      Self::Cell => Opcode::Cell,
+      Self::Incr => Opcode::Incr,
+      Self::EqalLeft { .. } | Self::EqalRight { .. } => Opcode::Eqal,
+      Self::Brch { .. } => Opcode::Brch,
+      Self::Cmps { .. } => Opcode::Cmps,
+      Self::Extn { .. } => Opcode::Extn,
+      Self::Invk { .. } => Opcode::Invk,
+      Self::RplcValue { .. } | Self::RplcTarget { .. } => Opcode::Rplc,
+      Self::HintClue { .. }
+      | Self::Fast { .. }
+      | Self::Spot { .. }
+      | Self::Memo { .. }
+      | Self::Dont => Opcode::Hint,
+      Self::ScryRef { .. } | Self::ScryPath { .. } => Opcode::Scry,
+    };
+
+    Some(opcode)
+  }
+
+  fn unwind(self, error: NockError) -> NockError {
+    match self {
+      Self::Spot { clue } => error.traced(clue),
+      frame => match frame.opcode() {
+        Some(opcode) => error.in_opcode(opcode),
+        None => error,
+      },
+    }
+  }
+}
+
+impl Interpreter {
+  // like `eval`, on a formula compiled ahead of time
+  pub fn eval_compiled(
+    &mut self,
+    subject: Subject,
+    formula: &CompiledFormula,
+  ) -> Result<Noun, NockError> {
+    self.reset();
+
+    let prod = self.run(subject.0.clone(), formula.0.clone());
+    self.shadowed(&subject.0, formula.formula().clone(), prod)
+  }
+
+  fn compiled(&mut self, formula: Noun) -> Rc<Node> {
+    if let Some(node) = self.compiled.get(&formula) {
+      return node.0.clone();
+    }
+
+    let compiled = CompiledFormula::new(&formula);
+    self.compiled.insert(formula, compiled.clone());
+    compiled.0
+  }
+
+  fn run(&mut self, subj: Noun, node: Rc<Node>) -> Result<Noun, NockError> {
+    let mut frames = vec![];
+    let mut next = Next::Call(subj, node);
+
+    loop {
+      let step = match next {
+        Next::Call(subj, node) => self.call_node(&mut frames, subj, node),
+        Next::Tail(opcode, subj, node) => {
+          match self.tail_cached_node(&mut frames, opcode, &subj, &node) {
+            Some(prod) => Ok(Next::Ret(prod)),
+            None => self
+              .call_node(&mut frames, subj, node)
+              .map_err(|e| e.in_opcode(opcode)),
+          }
+        }
+        Next::Ret(prod) => match frames.pop() {
+          None => return Ok(prod),
+          Some(frame) => {
+            let opcode = frame.opcode();
+            self
+              .resume_node(&mut frames, frame, prod)
+              .map_err(|e| match opcode {
+                Some(opcode) => e.in_opcode(opcode),
+                None => e,
+              })
+          }
+        },
+      };
+
+      next = match step {
+        Ok(next) => next,
+        Err(e) => return Err(frames.into_iter().rev().fold(e, |e, frame| frame.unwind(e))),
+      };
+    }
+  }
+
+  // see `Interpreter::tail_cached`, but here opcode 9 calls its arm directly
+  fn tail_cached_node(
+    &mut self,
+    frames: &mut Vec<Frame>,
+    opcode: Opcode,
+    subj: &Noun,
+    node: &Node,
+  ) -> Option<Noun> {
+    if self.cache.is_none() || !matches!(opcode, Opcode::Eval | Opcode::Invk) {
+      return None;
+    }
+    if let Some(prod) = self.cached(subj, &node.formula) {
+      return Some(prod);
+    }
+
+    if !matches!(frames.last(), Some(Frame::Cache { .. })) {
+      frames.push(Frame::Cache { calls: vec![] });
+    }
+    if let Some(Frame::Cache { calls }) = frames.last_mut() {
+      self.wait(calls, subj, &node.formula);
+    }
+    None
+  }
+
+  fn done_node(&mut self, prod: Noun) -> Next {
+    self.depth -= 1;
+    Next::Ret(prod)
+  }
+
+  fn tail_node(&mut self, opcode: Opcode, subj: Noun, node: Rc<Node>) -> Next {
+    self.depth -= 1;
+    Next::Tail(opcode, subj, node)
+  }
+
+  fn call_node(
+    &mut self,
+    frames: &mut Vec<Frame>,
+    subj: Noun,
+    node: Rc<Node>,
+  ) -> Result<Next, NockError> {
+    self.enter(&subj, &node.formula)?;
+
+    let kid = |i: usize| node.kids[i].clone();
+    let mut then = |frame, subj, node| {
+      frames.push(frame);
+      Ok(Next::Call(subj, node))
+    };
+
+    match &node.op {
+      Op::Atom => Err(self.atom_formula(node.formula.clone())),
+      Op::Cons => then(
+        Frame::Head {
+          subj: subj.clone(),
+          tail: kid(1),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Slot(axis) => {
+        let prod = subj
+          .slot_atom(axis)
+          .map_err(|e| e.in_opcode(Opcode::Addr))?;
+        Ok(self.done_node(prod))
+      }
+      Op::Quote(noun) => Ok(self.done_node(noun.clone())),
+      Op::Eval => then(
+        Frame::EvalSubject {
+          subj: subj.clone(),
+          c: kid(1),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Cell => then(Frame::Cell, subj, kid(0)),
+      Op::Incr => then(Frame::Incr, subj, kid(0)),
+      Op::Eqal => then(
+        Frame::EqalLeft {
+          subj: subj.clone(),
+          c: kid(1),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Brch => then(
+        Frame::Brch {
+          subj: subj.clone(),
+          node: node.clone(),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Cmps => then(Frame::Cmps { c: kid(1) }, subj, kid(0)),
+      Op::Extn => then(
+        Frame::Extn {
+          subj: subj.clone(),
+          c: kid(1),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Invk(axis) => then(Frame::Invk { axis: axis.clone() }, subj, kid(0)),
+      Op::Rplc(axis) => then(
+        Frame::RplcValue {
+          subj: subj.clone(),
+          axis: axis.clone(),
+          d: kid(1),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Hint { .. } if !self.hints => {
+        let body = node.kids.last().unwrap().clone();
+        Ok(self.tail_node(Opcode::Hint, subj, body))
+      }
+      Op::Hint { clue: true, .. } => then(
+        Frame::HintClue {
+          subj: subj.clone(),
+          node: node.clone(),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Hint { clue: false, .. } => Ok(self.hint_node(frames, subj, &node, None)),
+      Op::Scry => then(
+        Frame::ScryRef {
+          subj: subj.clone(),
+          c: kid(1),
+        },
+        subj,
+        kid(0),
+      ),
+      Op::Other(opcode, arg) => match self.extension(opcode, &subj, arg) {
+        Some(prod) => Ok(self.done_node(prod?)),
+        None => Err(NockError::UnknownOpcode(opcode.clone())),
+      },
+      Op::Crash(e) => Err(e.clone()),
+    }
+  }
+
+  fn hint_node(
+    &mut self,
+    frames: &mut Vec<Frame>,
+    subj: Noun,
+    node: &Node,
+    clue: Option<Noun>,
+  ) -> Next {
+    let Op::Hint { tag, .. } = &node.op else {
+      unreachable!()
+    };
+    let body = node.kids.last().unwrap().clone();
+
+    let frame = match self.hint_effect(&subj, tag, clue, &body.formula) {
+      Hinted::Run => return self.tail_node(Opcode::Hint, subj, body),
+      Hinted::Memoized(prod) => return self.done_node(prod),
+      Hinted::Fast(clue) => Frame::Fast { clue },
+      Hinted::Spot(clue) => Frame::Spot { clue },
+      Hinted::Memo(key) => Frame::Memo { key },
+      Hinted::Dont => Frame::Dont,
+    };
+
+    frames.push(frame);
+    Next::Call(subj, body)
+  }
+
+  fn resume_node(
+    &mut self,
+    frames: &mut Vec<Frame>,
+    frame: Frame,
+    prod: Noun,
+  ) -> Result<Next, NockError> {
+    let mut then = |frame, subj, node| {
+      frames.push(frame);
+      Next::Call(subj, node)
+    };
+
+    let next = match frame {
+      Frame::Head { subj, tail } => then(Frame::Tail { head: prod }, subj, tail),
+      Frame::Tail { head } => {
+        let prod = self.cons(head, prod);
+        self.done_node(prod)
+      }
+      Frame::EvalSubject { subj, c } => then(Frame::EvalFormula { subj: prod }, subj, c),
+      Frame::EvalFormula { subj } => {
+        let node = self.compiled(prod);
+        self.tail_node(Opcode::Eval, subj, node)
+      }
+      Frame::Cell => self.done_node(Noun::from(prod.is_cell())),
+      Frame::Incr => {
+        let prod = Noun::atom(prod.try_atom()?.incr());
+        self.done_node(prod)
+      }
+      Frame::EqalLeft { subj, c } => then(Frame::EqalRight { left: prod }, subj, c),
+      Frame::EqalRight { left } => {
+        let eq = self.eq(&left, &prod);
+        self.done_node(Noun::from(eq))
+      }
+      Frame::Brch { subj, node } => {
+        let branch = match prod.as_atom().and_then(Atom::as_u64) {
+          Some(YES) => node.kids[1].clone(),
+          Some(NAH) => node.kids[2].clone(),
+          _ => return Err(NockError::NonLoobeanCondition { value: prod }),
+        };
+        self.tail_node(Opcode::Brch, subj, branch)
+      }
+      Frame::Cmps { c } => self.tail_node(Opcode::Cmps, prod, c),
+      Frame::Extn { subj, c } => self.tail_node(Opcode::Extn, Noun::cell(prod, subj), c),
+      Frame::Invk { axis } => {
+        if let Some(prod) = self.run_jet(&prod, &axis)? {
+          return Ok(self.done_node(prod));
+        }
+        let node = self.compiled(arm(&prod, &axis)?);
+        self.tail_node(Opcode::Invk, prod, node)
+      }
+      Frame::RplcValue { subj, axis, d } => then(Frame::RplcTarget { axis, value: prod }, subj, d),
+      Frame::RplcTarget { axis, value } => {
+        let prod = self.edit(&prod, &axis, value)?;
+        self.done_node(prod)
+      }
+      Frame::HintClue { subj, node } => self.hint_node(frames, subj, &node, Some(prod)),
+      Frame::Fast { clue } => {
+        self.register(&clue, &prod);
+        self.done_node(prod)
+      }
+      Frame::Spot { .. } => self.done_node(prod),
+      Frame::Memo { key } => {
+        self.remember(key, &prod);
+        self.done_node(prod)
+      }
+      Frame::Dont => {
+        self.bypass -= 1;
+        self.done_node(prod)
+      }
+      Frame::Cache { calls } => {
+        self.cache(calls, &prod);
+        Next::Ret(prod)
+      }
+      Frame::ScryRef { subj, c } => then(Frame::ScryPath { reference: prod }, subj, c),
+      Frame::ScryPath { reference } => {
+        let prod = self.scry_at(&reference, prod)?;
+        self.done_node(prod)
+      }
+    };
+
+    Ok(next)
+  }
+}
+
+// the arm at `axis` of `core`, for opcode 9, as the interpreter pulls it out
+// with `{0 axis}`
+pub(crate) fn arm(core: &Noun, axis: &Noun) -> Result<Noun, NockError> {
+  axis
+    .try_atom()
+    .and_then(|axis| core.slot_atom(axis))
+    .map_err(|e| e.in_opcode(Opcode::Addr))
+}
+
+#[cfg(test)]
+mod test {
+  use super::CompiledFormula;
+  use crate::{
+    bench::DECREMENT,
+    interp::{Interpreter, Subject},
+    noun::Noun,
+    testing::{Way, both},
+  };
+
+  #[test]
+  fn test_compiled() {
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    assert_eq!(
+      both(Way::Compiled, noun!(43), decrement.clone()).unwrap(),
+      noun!(42)
+    );
+
+    let s = noun!({42, 43});
+    assert_eq!(
+      both(Way::Compiled, s.clone(), noun!({{addr, 3}, {idty, 7}})).unwrap(),
+      noun!({43, 7})
+    );
+    assert_eq!(
+      both(
+        Way::Compiled,
+        s.clone(),
+        noun!({rplc, {{2, {idty, 5}}, {addr, 1}}})
+      )
+      .unwrap(),
+      noun!({5, 43})
+    );
+    assert_eq!(
+      both(
+        Way::Compiled,
+        s.clone(),
+        noun!({extn, {{idty, 1}, {cell, {addr, 3}}}})
+      )
+      .unwrap(),
+      noun!(0)
+    );
+    assert_eq!(
+      both(
+        Way::Compiled,
+        s.clone(),
+        noun!({hint, {{1, {addr, 2}}, {eval, {{addr, 1}, {idty, {addr, 3}}}}}})
+      )
+      .unwrap(),
+      noun!(43)
+    );
+
+    // malformed parts only crash once they're reached
+    assert!(
+      both(
+        Way::Compiled,
+        s.clone(),
+        noun!({brch, {{idty, 0}, {{idty, 1}, {addr, 0}}}})
+      )
+      .is_ok()
+    );
+    assert!(
+      both(
+        Way::Compiled,
+        s.clone(),
+        noun!({brch, {{idty, 2}, {{idty, 1}, 0}}})
+      )
+      .is_err()
+    );
+    assert!(both(Way::Compiled, s.clone(), noun!({incr, {addr, 1}})).is_err());
+    assert!(
+      both(
+        Way::Compiled,
+        s.clone(),
+        noun!({rplc, {{{1, 2}, {idty, 1}}, {addr, 1}}})
+      )
+      .is_err()
+    );
+    assert!(both(Way::Compiled, s.clone(), noun!({invk, {{1, 2}, {addr, 1}}})).is_err());
+    assert!(both(Way::Compiled, s.clone(), noun!({99, 0})).is_err());
+    assert!(both(Way::Compiled, s, noun!(7)).is_err());
+  }
+
+  #[test]
+  fn test_compiled_deep() {
+    let deep = (0..100_000).fold(noun!({addr, 1}), |f, _| Noun::cell(noun!(incr), f));
+    let compiled = CompiledFormula::new(&deep);
+    let prod = Interpreter::new().eval_compiled(Subject(noun!(0)), &compiled);
+    assert_eq!(prod.unwrap(), noun!(100_000));
+
+    let decrement = CompiledFormula::new(&DECREMENT.parse().unwrap());
+    let mut it = Interpreter::builder().depth_limit(16).build();
+    let prod = it.eval_compiled(Subject(noun!(100_000)), &decrement);
+    assert_eq!(prod.unwrap(), noun!(99_999));
+  }
+}
@@ -0,0 +1,146 @@
+use crate::{
+  atom::{Atom, NAH, YES},
+  error::NockError,
+  noun::Noun,
+};
+
+impl From<u64> for Atom {
+  fn from(atom: u64) -> Self {
+    Atom::new(atom)
+  }
+}
+
+impl From<u128> for Atom {
+  fn from(atom: u128) -> Self {
+    Atom::from_words(&[atom as u64, (atom >> 64) as u64])
+  }
+}
+
+impl From<Atom> for Noun {
+  fn from(atom: Atom) -> Self {
+    Noun::atom(atom)
+  }
+}
+
+impl From<u64> for Noun {
+  fn from(atom: u64) -> Self {
+    Noun::atom(Atom::new(atom))
+  }
+}
+
+// loobean: `true` is 0 and `false` is 1
+impl From<bool> for Noun {
+  fn from(b: bool) -> Self {
+    if b { Noun::YES } else { Noun::NAH }
+  }
+}
+
+impl<A: Into<Noun>, B: Into<Noun>> From<(A, B)> for Noun {
+  fn from((a, b): (A, B)) -> Self {
+    Noun::cell(a.into(), b.into())
+  }
+}
+
+impl<A: Into<Noun>, B: Into<Noun>, C: Into<Noun>> From<(A, B, C)> for Noun {
+  fn from((a, b, c): (A, B, C)) -> Self {
+    Noun::cell(a.into(), Noun::cell(b.into(), c.into()))
+  }
+}
+
+impl From<std::convert::Infallible> for NockError {
+  fn from(never: std::convert::Infallible) -> Self {
+    match never {}
+  }
+}
+
+impl TryFrom<Noun> for Atom {
+  type Error = NockError;
+
+  fn try_from(noun: Noun) -> Result<Self, Self::Error> {
+    noun.try_atom().cloned()
+  }
+}
+
+impl TryFrom<Noun> for u64 {
+  type Error = NockError;
+
+  fn try_from(noun: Noun) -> Result<Self, Self::Error> {
+    noun.try_atom()?.as_u64().ok_or(NockError::AtomOverflow)
+  }
+}
+
+impl TryFrom<Noun> for bool {
+  type Error = NockError;
+
+  fn try_from(noun: Noun) -> Result<Self, Self::Error> {
+    match noun.as_atom().and_then(Atom::as_u64) {
+      Some(YES) => Ok(true),
+      Some(NAH) => Ok(false),
+      _ => Err(NockError::NotLoobean { found: noun }),
+    }
+  }
+}
+
+// elements may be any type convertible from a noun, including `Noun` itself
+impl<A, B> TryFrom<Noun> for (A, B)
+where
+  A: TryFrom<Noun>,
+  B: TryFrom<Noun>,
+  NockError: From<A::Error> + From<B::Error>,
+{
+  type Error = NockError;
+
+  fn try_from(noun: Noun) -> Result<Self, Self::Error> {
+    let (a, b) = noun.try_cell()?;
+    Ok((A::try_from(a.clone())?, B::try_from(b.clone())?))
+  }
+}
+
+impl<A, B, C> TryFrom<Noun> for (A, B, C)
+where
+  A: TryFrom<Noun>,
+  B: TryFrom<Noun>,
+  C: TryFrom<Noun>,
+  NockError: From<A::Error> + From<B::Error> + From<C::Error>,
+{
+  type Error = NockError;
+
+  fn try_from(noun: Noun) -> Result<Self, Self::Error> {
+    let (a, (b, c)) = <(A, (B, C))>::try_from(noun)?;
+    Ok((a, b, c))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{error::NockError, noun::Noun};
+
+  #[test]
+  fn test_into_noun() {
+    assert_eq!(Noun::from((1, (2, 3))), noun!({1, {2, 3}}));
+    assert_eq!(Noun::from((1, 2, 3)), noun!({1, {2, 3}}));
+    assert_eq!(Noun::from(((true, false), 42)), noun!({{0, 1}, 42}));
+  }
+
+  #[test]
+  fn test_from_noun() {
+    let (a, (b, c)) = <(u64, (bool, u64))>::try_from(noun!({1, {0, 3}})).unwrap();
+    assert_eq!((a, b, c), (1, true, 3));
+
+    let (a, b, c) = <(u64, u64, Noun)>::try_from(noun!({1, {2, {3, 4}}})).unwrap();
+    assert_eq!((a, b, c), (1, 2, noun!({3, 4})));
+
+    assert!(matches!(
+      u64::try_from(noun!({1, 2})),
+      Err(NockError::AtomRequired { .. })
+    ));
+    assert!(matches!(
+      <(u64, u64)>::try_from(noun!(1)),
+      Err(NockError::CellRequired { .. })
+    ));
+    assert!(matches!(
+      bool::try_from(noun!(2)),
+      Err(NockError::NotLoobean { .. })
+    ));
+  }
+}
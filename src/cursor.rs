@@ -0,0 +1,129 @@
+use crate::{error::NockError, noun::Noun};
+
+// A zipper over a noun: the focused subtree plus the path back to the root.
+// Edits rebuild only the spine between the focus and the root; every subtree
+// that wasn't touched is shared with the original noun.
+#[derive(Clone, Debug)]
+pub struct NounCursor {
+  focus: Noun,
+  // (went right, parent) for each step from the root
+  path: Vec<(bool, Noun)>,
+}
+
+impl NounCursor {
+  pub fn new(noun: Noun) -> Self {
+    Self {
+      focus: noun,
+      path: vec![],
+    }
+  }
+
+  pub fn focus(&self) -> &Noun {
+    &self.focus
+  }
+
+  pub fn depth(&self) -> usize {
+    self.path.len()
+  }
+
+  // `None` once the focus is too deep for its axis to fit in a `u64`
+  pub fn axis(&self) -> Option<u64> {
+    self.path.iter().try_fold(1u64, |axis, (right, _)| {
+      axis.checked_mul(2)?.checked_add(*right as u64)
+    })
+  }
+
+  pub fn left(&mut self) -> Result<(), NockError> {
+    self.descend(false)
+  }
+
+  pub fn right(&mut self) -> Result<(), NockError> {
+    self.descend(true)
+  }
+
+  fn descend(&mut self, right: bool) -> Result<(), NockError> {
+    let (car, cdr) = self.focus.try_cell()?;
+    let child = if right { cdr.clone() } else { car.clone() };
+    let parent = std::mem::replace(&mut self.focus, child);
+    self.path.push((right, parent));
+    Ok(())
+  }
+
+  // moves the focus to its parent, returning false at the root
+  pub fn up(&mut self) -> bool {
+    let Some((right, parent)) = self.path.pop() else {
+      return false;
+    };
+
+    let (car, cdr) = parent.as_cell().expect("cursor parents are cells");
+    let child = if right { cdr } else { car };
+
+    if child.ptr_eq(&self.focus) {
+      self.focus = parent;
+    } else {
+      let focus = self.focus.clone();
+      self.focus = if right {
+        Noun::cell(car.clone(), focus)
+      } else {
+        Noun::cell(focus, cdr.clone())
+      };
+    }
+
+    true
+  }
+
+  // replaces the focused subtree, returning the old one
+  pub fn replace(&mut self, noun: Noun) -> Noun {
+    std::mem::replace(&mut self.focus, noun)
+  }
+
+  pub fn into_noun(mut self) -> Noun {
+    while self.up() {}
+    self.focus
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::NounCursor;
+
+  #[test]
+  fn test_cursor_navigation() {
+    let n = noun!({{4, 5}, {6, {14, 15}}});
+    let mut c = NounCursor::new(n.clone());
+
+    assert_eq!(c.axis(), Some(1));
+    c.right().unwrap();
+    c.right().unwrap();
+    c.left().unwrap();
+    assert_eq!(c.axis(), Some(14));
+    assert_eq!(c.focus(), &noun!(14));
+    assert!(c.left().is_err());
+
+    assert!(c.up());
+    assert_eq!(c.axis(), Some(7));
+
+    // nothing was edited, so the root is the very same noun
+    let root = c.into_noun();
+    assert!(root.ptr_eq(&n));
+  }
+
+  #[test]
+  fn test_cursor_edit() {
+    let n = noun!({{4, 5}, {6, {14, 15}}});
+    let mut c = NounCursor::new(n.clone());
+
+    c.right().unwrap();
+    c.right().unwrap();
+    c.right().unwrap();
+    assert_eq!(c.replace(noun!(0)), noun!(15));
+
+    let root = c.into_noun();
+    assert_eq!(root, noun!({{4, 5}, {6, {14, 0}}}));
+
+    // the untouched head is shared with the original
+    let (old_head, _) = n.as_cell().unwrap();
+    let (new_head, _) = root.as_cell().unwrap();
+    assert!(old_head.ptr_eq(new_head));
+  }
+}
@@ -0,0 +1,338 @@
+// Absolute dates (@da). The high 64 bits count seconds from an epoch far in
+// the past, with 1970-01-01 at `UNIX_EPOCH`, and the low 64 bits are
+// fractions of a second in units of 2^-64.
+
+use std::time::{Duration, SystemTime};
+
+use crate::{atom::Atom, parse::ParseError};
+
+const UNIX_EPOCH: u64 = 0x8000_000c_ce9e_0d80;
+const NANOS: u128 = 1_000_000_000;
+
+fn from_parts(seconds: i128, fraction: u64) -> Option<Atom> {
+  let seconds = u64::try_from(seconds + i128::from(UNIX_EPOCH)).ok()?;
+  Some(Atom::from(u128::from(seconds) << 64 | u128::from(fraction)))
+}
+
+// seconds relative to the unix epoch and the fraction of the second
+fn to_parts(date: &Atom) -> Option<(i128, u64)> {
+  let date = date.as_u128()?;
+  let seconds = i128::from((date >> 64) as u64) - i128::from(UNIX_EPOCH);
+  Some((seconds, date as u64))
+}
+
+// rounded up, so that `nanos_of` gives back the same count
+fn fraction_of(nanos: u32) -> u64 {
+  (u128::from(nanos) << 64).div_ceil(NANOS) as u64
+}
+
+fn nanos_of(fraction: u64) -> u32 {
+  ((u128::from(fraction) * NANOS) >> 64) as u32
+}
+
+pub fn from_system_time(time: SystemTime) -> Atom {
+  let (seconds, nanos) = match time.duration_since(SystemTime::UNIX_EPOCH) {
+    Ok(after) => (i128::from(after.as_secs()), after.subsec_nanos()),
+    Err(before) => {
+      let before = before.duration();
+      match before.subsec_nanos() {
+        0 => (-i128::from(before.as_secs()), 0),
+        nanos => (-i128::from(before.as_secs()) - 1, 1_000_000_000 - nanos),
+      }
+    }
+  };
+
+  from_parts(seconds, fraction_of(nanos)).expect("a system time fits in a date")
+}
+
+// None if the atom isn't a date or is out of `SystemTime`'s range
+pub fn to_system_time(date: &Atom) -> Option<SystemTime> {
+  let (seconds, fraction) = to_parts(date)?;
+  let nanos = nanos_of(fraction);
+
+  if seconds >= 0 {
+    let after = Duration::new(u64::try_from(seconds).ok()?, nanos);
+    SystemTime::UNIX_EPOCH.checked_add(after)
+  } else {
+    let before = Duration::new(u64::try_from(-seconds).ok()?, 0);
+    SystemTime::UNIX_EPOCH
+      .checked_sub(before)?
+      .checked_add(Duration::new(0, nanos))
+  }
+}
+
+// days since 1970-01-01 in the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let yoe = year.rem_euclid(400);
+  let month = i64::from(month);
+  let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+  era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let doe = days.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + i64::from(month <= 2);
+
+  (year, month, day)
+}
+
+struct Civil {
+  year: i64,
+  month: u32,
+  day: u32,
+  hour: u32,
+  minute: u32,
+  second: u32,
+  fraction: u64,
+}
+
+fn civil(date: &Atom) -> Option<Civil> {
+  let (seconds, fraction) = to_parts(date)?;
+  let days = i64::try_from(seconds.div_euclid(86_400)).ok()?;
+  let time = seconds.rem_euclid(86_400) as u32;
+  let (year, month, day) = civil_from_days(days);
+
+  Some(Civil {
+    year,
+    month,
+    day,
+    hour: time / 3600,
+    minute: time / 60 % 60,
+    second: time % 60,
+    fraction,
+  })
+}
+
+// `~2024.1.15..12.34.56..8000`, None if the atom isn't a date
+pub fn render(date: &Atom) -> Option<String> {
+  let c = civil(date)?;
+
+  let mut rendered = if c.year > 0 {
+    format!("~{}.{}.{}", c.year, c.month, c.day)
+  } else {
+    format!("~{}-.{}.{}", 1 - c.year, c.month, c.day)
+  };
+
+  if (c.hour, c.minute, c.second, c.fraction) == (0, 0, 0, 0) {
+    return Some(rendered);
+  }
+
+  rendered.push_str(&format!("..{}.{:02}.{:02}", c.hour, c.minute, c.second));
+
+  if c.fraction != 0 {
+    let mut groups: Vec<_> = (0..4)
+      .rev()
+      .map(|i| (c.fraction >> (i * 16)) as u16)
+      .collect();
+    while groups.last() == Some(&0) {
+      groups.pop();
+    }
+
+    let groups: Vec<_> = groups.iter().map(|group| format!("{group:04x}")).collect();
+    rendered.push_str(&format!("..{}", groups.join(".")));
+  }
+
+  Some(rendered)
+}
+
+// `2024-01-15T12:34:56.5Z` in UTC, None if the atom isn't a date or the year
+// has more than four digits
+pub fn to_iso8601(date: &Atom) -> Option<String> {
+  let c = civil(date)?;
+
+  if !(0..=9999).contains(&c.year) {
+    return None;
+  }
+
+  let mut rendered = format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+    c.year, c.month, c.day, c.hour, c.minute, c.second
+  );
+
+  let nanos = nanos_of(c.fraction);
+  if nanos != 0 {
+    let digits = format!("{nanos:09}");
+    rendered.push('.');
+    rendered.push_str(digits.trim_end_matches('0'));
+  }
+
+  rendered.push('Z');
+  Some(rendered)
+}
+
+// `YYYY-MM-DD`, optionally followed by `THH:MM:SS`, fractional seconds and a
+// `Z` or `±HH:MM` offset; times without an offset are taken as UTC
+pub fn parse_iso8601(input: &str) -> Result<Atom, ParseError> {
+  let mut offset = 0;
+
+  let year = field(input, &mut offset, 4, None)?;
+  let month = field(input, &mut offset, 2, Some('-'))?;
+  let day = field(input, &mut offset, 2, Some('-'))?;
+
+  let (mut hour, mut minute, mut second) = (0, 0, 0);
+  let mut nanos = 0;
+  let mut zone = 0i64;
+
+  if input[offset..].starts_with('T') {
+    hour = field(input, &mut offset, 2, Some('T'))?;
+    minute = field(input, &mut offset, 2, Some(':'))?;
+    second = field(input, &mut offset, 2, Some(':'))?;
+
+    if input[offset..].starts_with('.') {
+      offset += 1;
+      let digits = input[offset..]
+        .bytes()
+        .take_while(u8::is_ascii_digit)
+        .count();
+      if digits == 0 {
+        return Err(ParseError {
+          offset,
+          message: "expected fractional seconds".to_string(),
+        });
+      }
+
+      let fraction = &input[offset..offset + digits.min(9)];
+      nanos = fraction.parse::<u32>().unwrap() * 10u32.pow(9 - fraction.len() as u32);
+      offset += digits;
+    }
+
+    match input[offset..].chars().next() {
+      Some('Z') => offset += 1,
+      Some(sign @ ('+' | '-')) => {
+        let hours = field(input, &mut offset, 2, Some(sign))?;
+        let minutes = field(input, &mut offset, 2, Some(':'))?;
+        let zoned = i64::from(hours * 3600 + minutes * 60);
+        zone = if sign == '+' { zoned } else { -zoned };
+      }
+      _ => {}
+    }
+  }
+
+  if offset != input.len() {
+    return Err(ParseError {
+      offset,
+      message: "expected end of date".to_string(),
+    });
+  }
+
+  let year = i64::from(year);
+  let days_in_month = |month| match month {
+    12 => 31,
+    month => days_from_civil(year, month + 1, 1) - days_from_civil(year, month, 1),
+  };
+
+  if !(1..=12).contains(&month) || day == 0 || i64::from(day) > days_in_month(month) {
+    return Err(ParseError {
+      offset: 0,
+      message: "no such date".to_string(),
+    });
+  }
+
+  if hour > 23 || minute > 59 || second > 59 {
+    return Err(ParseError {
+      offset: 0,
+      message: "no such time".to_string(),
+    });
+  }
+
+  let days = days_from_civil(year, month, day);
+  let seconds =
+    i128::from(days) * 86_400 + i128::from(hour * 3600 + minute * 60 + second) - i128::from(zone);
+
+  Ok(from_parts(seconds, fraction_of(nanos)).expect("a four digit year fits in a date"))
+}
+
+// `digits` decimal digits at `offset`, after `before` if given
+fn field(
+  input: &str,
+  offset: &mut usize,
+  digits: usize,
+  before: Option<char>,
+) -> Result<u32, ParseError> {
+  if let Some(separator) = before {
+    if !input[*offset..].starts_with(separator) {
+      return Err(ParseError {
+        offset: *offset,
+        message: format!("expected '{separator}'"),
+      });
+    }
+    *offset += 1;
+  }
+
+  let field = input
+    .get(*offset..*offset + digits)
+    .filter(|field| field.bytes().all(|b| b.is_ascii_digit()));
+  let Some(field) = field else {
+    return Err(ParseError {
+      offset: *offset,
+      message: format!("expected {digits} digits"),
+    });
+  };
+
+  *offset += digits;
+  Ok(field.parse().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+  use std::time::{Duration, SystemTime};
+
+  use super::{from_system_time, parse_iso8601, render, to_iso8601, to_system_time};
+  use crate::atom::Atom;
+
+  #[test]
+  fn test_system_time() {
+    let epoch = from_system_time(SystemTime::UNIX_EPOCH);
+    assert_eq!(epoch.words(), [0, 0x8000_000c_ce9e_0d80]);
+    assert_eq!(render(&epoch).unwrap(), "~1970.1.1");
+
+    for time in [
+      SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789),
+      SystemTime::UNIX_EPOCH - Duration::new(86_400 * 365, 250_000_000),
+    ] {
+      assert_eq!(to_system_time(&from_system_time(time)), Some(time));
+    }
+
+    assert_eq!(to_system_time(&Atom::new(5)), None);
+  }
+
+  #[test]
+  fn test_render() {
+    let date = parse_iso8601("2024-01-15T12:34:56.5Z").unwrap();
+    assert_eq!(render(&date).unwrap(), "~2024.1.15..12.34.56..8000");
+    assert_eq!(to_iso8601(&date).unwrap(), "2024-01-15T12:34:56.5Z");
+
+    let date = parse_iso8601("2000-02-29").unwrap();
+    assert_eq!(render(&date).unwrap(), "~2000.2.29");
+    assert_eq!(to_iso8601(&date).unwrap(), "2000-02-29T00:00:00Z");
+
+    let date = parse_iso8601("1969-12-31T23:00:00-01:00").unwrap();
+    assert_eq!(date, from_system_time(SystemTime::UNIX_EPOCH));
+
+    let bc = Atom::from(u128::from(0x8000_000c_ce9e_0d80u64 - 2_100 * 31_556_952) << 64);
+    assert!(render(&bc).unwrap().contains("-."));
+    assert_eq!(to_iso8601(&bc), None);
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert!(parse_iso8601("2024-1-15").is_err());
+    assert!(parse_iso8601("2024-02-30").is_err());
+    assert!(parse_iso8601("2023-02-29").is_err());
+    assert!(parse_iso8601("2024-13-01").is_err());
+    assert!(parse_iso8601("2024-01-15T24:00:00Z").is_err());
+    assert!(parse_iso8601("2024-01-15T12:00:00.Z").is_err());
+    assert_eq!(parse_iso8601("2024-01-15 ").unwrap_err().offset, 10);
+  }
+}
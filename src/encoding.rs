@@ -0,0 +1,321 @@
+// Compact text encodings for big atoms: urbit's base-64 (@uw) and base-32
+// (@uv), both written in dot-separated groups of five digits, and plain
+// bitcoin-alphabet base-58. And standard base64 for bytes.
+
+use crate::{
+  atom::{Atom, div_rem, mul_add},
+  bits::bits,
+  parse::ParseError,
+};
+
+const UW: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ-~";
+const UV: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// most significant digit first, at least one digit
+fn digits(atom: &Atom, alphabet: &[u8]) -> String {
+  let mut words = atom.words().to_vec();
+  let mut digits = vec![];
+
+  loop {
+    digits.push(alphabet[div_rem(&mut words, alphabet.len() as u64) as usize]);
+    if words.iter().all(|&word| word == 0) {
+      break;
+    }
+  }
+
+  digits.reverse();
+  String::from_utf8(digits).unwrap()
+}
+
+fn from_digits(digits: &str, alphabet: &[u8]) -> Result<Atom, ParseError> {
+  if digits.is_empty() {
+    return Err(ParseError {
+      offset: 0,
+      message: "expected digits".to_string(),
+    });
+  }
+
+  let mut words = vec![0];
+
+  for (offset, byte) in digits.bytes().enumerate() {
+    let Some(digit) = alphabet.iter().position(|&d| d == byte) else {
+      return Err(ParseError {
+        offset,
+        message: "invalid digit".to_string(),
+      });
+    };
+    mul_add(&mut words, alphabet.len() as u64, digit as u64);
+  }
+
+  Ok(Atom::from_words(&words))
+}
+
+// `digits` for an alphabet of 2^n digits, read straight off the bits rather
+// than divided out, so it's linear in the size of the atom
+fn bit_digits(atom: &Atom, alphabet: &[u8]) -> String {
+  let size = u64::from(alphabet.len().trailing_zeros());
+  let mask = alphabet.len() as u64 - 1;
+  let words = atom.words();
+
+  (0..bits(atom).div_ceil(size).max(1))
+    .rev()
+    .map(|digit| {
+      let (word, bit) = ((digit * size / 64) as usize, digit * size % 64);
+      let mut digit = words[word] >> bit;
+      if bit + size > 64 && word + 1 < words.len() {
+        digit |= words[word + 1] << (64 - bit);
+      }
+      char::from(alphabet[(digit & mask) as usize])
+    })
+    .collect()
+}
+
+fn from_bit_digits(digits: &str, alphabet: &[u8]) -> Result<Atom, ParseError> {
+  if digits.is_empty() {
+    return Err(ParseError {
+      offset: 0,
+      message: "expected digits".to_string(),
+    });
+  }
+
+  let size = u64::from(alphabet.len().trailing_zeros());
+  let mut words = vec![0; (digits.len() as u64 * size).div_ceil(64) as usize];
+
+  for (offset, byte) in digits.bytes().enumerate() {
+    let Some(digit) = alphabet.iter().position(|&d| d == byte) else {
+      return Err(ParseError {
+        offset,
+        message: "invalid digit".to_string(),
+      });
+    };
+
+    let at = (digits.len() - 1 - offset) as u64 * size;
+    let (word, bit) = ((at / 64) as usize, at % 64);
+    words[word] |= (digit as u64) << bit;
+    if bit + size > 64 {
+      words[word + 1] |= (digit as u64) >> (64 - bit);
+    }
+  }
+
+  Ok(Atom::from_words(&words))
+}
+
+// `0w` or `0v`, then groups of five digits from the right
+fn grouped(prefix: &str, digits: &str) -> String {
+  let head = match digits.len() % 5 {
+    0 => 5,
+    head => head,
+  };
+
+  let mut encoded = format!("{prefix}{}", &digits[..head]);
+  for group in digits.as_bytes()[head..].chunks(5) {
+    encoded.push('.');
+    encoded.push_str(std::str::from_utf8(group).unwrap());
+  }
+
+  encoded
+}
+
+fn ungrouped(prefix: &str, input: &str, alphabet: &[u8]) -> Result<Atom, ParseError> {
+  let Some(grouped) = input.strip_prefix(prefix) else {
+    return Err(ParseError {
+      offset: 0,
+      message: format!("expected '{prefix}'"),
+    });
+  };
+
+  let mut groups = grouped.split('.');
+  let head = groups.next().unwrap_or_default();
+  if !(1..=5).contains(&head.len()) || groups.any(|group| group.len() != 5) {
+    return Err(ParseError {
+      offset: 0,
+      message: "expected groups of five digits".to_string(),
+    });
+  }
+
+  from_bit_digits(&grouped.replace('.', ""), alphabet).map_err(|e| {
+    let (offset, _) = grouped
+      .char_indices()
+      .filter(|&(_, c)| c != '.')
+      .nth(e.offset)
+      .unwrap();
+
+    ParseError {
+      offset: prefix.len() + offset,
+      ..e
+    }
+  })
+}
+
+pub fn encode_uw(atom: &Atom) -> String {
+  grouped("0w", &bit_digits(atom, UW))
+}
+
+pub fn decode_uw(input: &str) -> Result<Atom, ParseError> {
+  ungrouped("0w", input, UW)
+}
+
+pub fn encode_uv(atom: &Atom) -> String {
+  grouped("0v", &bit_digits(atom, UV))
+}
+
+pub fn decode_uv(input: &str) -> Result<Atom, ParseError> {
+  ungrouped("0v", input, UV)
+}
+
+pub fn encode_base58(atom: &Atom) -> String {
+  digits(atom, BASE58)
+}
+
+pub fn decode_base58(input: &str) -> Result<Atom, ParseError> {
+  from_digits(input, BASE58)
+}
+
+// RFC 4648, padded
+pub fn encode_base64(bytes: &[u8]) -> String {
+  let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+  for chunk in bytes.chunks(3) {
+    let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+      group | u32::from(byte) << (16 - 8 * i)
+    });
+    for i in 0..4 {
+      if i <= chunk.len() {
+        encoded.push(char::from(BASE64[(group >> (18 - 6 * i) & 0x3f) as usize]));
+      } else {
+        encoded.push('=');
+      }
+    }
+  }
+
+  encoded
+}
+
+// padded or not, and ignoring whitespace, which copying text around tends to
+// add
+pub fn decode_base64(input: &str) -> Result<Vec<u8>, ParseError> {
+  let mut bytes = Vec::with_capacity(input.len() / 4 * 3);
+  // bits not made into a byte yet, and how many
+  let (mut bits, mut len) = (0u32, 0);
+  let mut padding = 0;
+
+  for (offset, byte) in input.bytes().enumerate() {
+    let error = |message: &str| ParseError {
+      offset,
+      message: message.to_string(),
+    };
+
+    match byte {
+      _ if byte.is_ascii_whitespace() => continue,
+      b'=' if padding < 2 => padding += 1,
+      b'=' => return Err(error("too much padding")),
+      _ if padding > 0 => return Err(error("digits after padding")),
+      _ => {
+        let digit = BASE64
+          .iter()
+          .position(|&d| d == byte)
+          .ok_or_else(|| error("invalid digit"))?;
+        bits = bits << 6 | digit as u32;
+        len += 6;
+        if len >= 8 {
+          len -= 8;
+          bytes.push((bits >> len) as u8);
+          bits &= (1 << len) - 1;
+        }
+      }
+    }
+  }
+
+  // a single digit left over is less than a byte
+  if len == 6 {
+    return Err(ParseError {
+      offset: input.len(),
+      message: "expected another digit".to_string(),
+    });
+  }
+
+  Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{
+    UV, UW, bit_digits, decode_base58, decode_base64, decode_uv, decode_uw, digits, encode_base58,
+    encode_base64, encode_uv, encode_uw, from_bit_digits,
+  };
+  use crate::atom::Atom;
+
+  #[test]
+  fn test_uw_uv() {
+    assert_eq!(encode_uw(&Atom::new(0)), "0w0");
+    assert_eq!(encode_uw(&Atom::new(64)), "0w10");
+    assert_eq!(encode_uw(&Atom::new(u64::MAX)), "0wf.~~~~~.~~~~~");
+    assert_eq!(encode_uv(&Atom::new(32 * 32 * 32 * 32 * 32)), "0v1.00000");
+    assert_eq!(encode_uv(&Atom::new(31)), "0vv");
+
+    let hash = Atom::from_le_bytes(&(1..=32).collect::<Vec<u8>>());
+    assert_eq!(decode_uw(&encode_uw(&hash)).unwrap(), hash);
+    assert_eq!(decode_uv(&encode_uv(&hash)).unwrap(), hash);
+    assert_eq!(decode_uw("0w10").unwrap(), Atom::new(64));
+
+    assert!(decode_uw("10").is_err());
+    assert!(decode_uw("0w").is_err());
+    assert!(decode_uw("0w1.0000").is_err());
+    assert!(decode_uw("0w123456").is_err());
+    assert_eq!(decode_uv("0v1.0000w").unwrap_err().offset, 8);
+
+    // read off the bits, the same as dividing
+    for atom in [
+      Atom::from_words(&[u64::MAX; 7]),
+      Atom::from_words(&[0x0123_4567_89ab_cdef, 1 << 63, 5]),
+      Atom::from_le_bytes(&(0..=255).collect::<Vec<u8>>()),
+    ] {
+      for alphabet in [UW, UV] {
+        let bits = bit_digits(&atom, alphabet);
+        assert_eq!(bits, digits(&atom, alphabet));
+        assert_eq!(from_bit_digits(&bits, alphabet).unwrap(), atom);
+      }
+    }
+  }
+
+  #[test]
+  fn test_base58() {
+    assert_eq!(encode_base58(&Atom::new(0)), "1");
+    assert_eq!(encode_base58(&Atom::new(57)), "z");
+    assert_eq!(encode_base58(&Atom::new(58)), "21");
+    assert_eq!(
+      encode_base58(&Atom::from_le_bytes(b"dlrow olleh")),
+      "StV1DL6CwTryKyV"
+    );
+
+    let hash = Atom::from_words(&[u64::MAX, 7, 1 << 60]);
+    assert_eq!(decode_base58(&encode_base58(&hash)).unwrap(), hash);
+    assert_eq!(decode_base58("0").unwrap_err().offset, 0);
+  }
+
+  #[test]
+  fn test_base64() {
+    for (bytes, encoded) in [
+      (&b""[..], ""),
+      (b"f", "Zg=="),
+      (b"fo", "Zm8="),
+      (b"foo", "Zm9v"),
+      (b"foob", "Zm9vYg=="),
+      (b"fooba", "Zm9vYmE="),
+      (b"foobar", "Zm9vYmFy"),
+      (&[0xfb, 0xff], "+/8="),
+    ] {
+      assert_eq!(encode_base64(bytes), encoded);
+      assert_eq!(decode_base64(encoded).unwrap(), bytes);
+    }
+
+    assert_eq!(decode_base64("Zm9vYg").unwrap(), b"foob");
+    assert_eq!(decode_base64("Zm9v\n YmFy\n").unwrap(), b"foobar");
+    assert_eq!(decode_base64("Zm9v!").unwrap_err().offset, 4);
+    assert_eq!(decode_base64("Zg==Zg").unwrap_err().offset, 4);
+    assert_eq!(decode_base64("Zg===").unwrap_err().offset, 4);
+    assert_eq!(decode_base64("Zm9vY").unwrap_err().offset, 5);
+  }
+}
@@ -0,0 +1,225 @@
+use crate::{
+  atom::Atom,
+  noun::{Divergence, Noun},
+  opcode::Opcode,
+};
+
+#[derive(Clone, Debug)]
+pub enum NockError {
+  CellRequired {
+    found: Noun,
+  },
+  AtomRequired {
+    found: Noun,
+  },
+  NotLoobean {
+    found: Noun,
+  },
+  // the condition of opcode 6 produced `value` rather than 0 or 1
+  NonLoobeanCondition {
+    value: Noun,
+  },
+  ImproperList {
+    tail: Noun,
+  },
+  ZeroAxis,
+  AxisOutOfTree {
+    axis: Atom,
+  },
+  UnknownOpcode(Atom),
+  // `*{a b}` with `b` an atom, which the spec leaves looping forever
+  Exit {
+    formula: Noun,
+  },
+  // an atom too large where a word is expected
+  AtomOverflow,
+  SubtractUnderflow,
+  DivideByZero,
+  OutOfFuel,
+  DepthLimit,
+  // too little of the native stack was left to go on, see src/closure.rs
+  StackExhausted,
+  // stopped from outside, like a forked tail whose product isn't wanted
+  Cancelled,
+  // the interpreter panicked, say in a jet or a sink
+  Panicked,
+  // opcode 12 found nothing at `path`, or there's no scry handler to ask
+  Blocked {
+    path: Noun,
+  },
+  // a shadow backend disagreed with the interpreter on `formula`; the
+  // divergence is None when only one of them crashed
+  Diverged {
+    formula: Noun,
+    divergence: Option<Box<Divergence>>,
+  },
+  // the opcode that was executing when `error` was raised
+  InOpcode {
+    opcode: Opcode,
+    error: Box<NockError>,
+  },
+  // the clues of the `%spot` and `%mean` hints `error` crashed inside of,
+  // innermost first
+  Traced {
+    trace: Vec<Noun>,
+    error: Box<NockError>,
+  },
+}
+
+// a crash every correct nock interpreter agrees on, unlike this one running
+// out of fuel or stack
+#[derive(Clone, Debug)]
+pub struct Crash {
+  // the `%spot`/`%mean` clues as a null-terminated list, innermost first
+  pub trace: Noun,
+  pub error: NockError,
+}
+
+impl NockError {
+  pub(crate) fn in_opcode(self, opcode: Opcode) -> Self {
+    match self {
+      Self::InOpcode { .. } | Self::Traced { .. } => self,
+      error => Self::InOpcode {
+        opcode,
+        error: Box::new(error),
+      },
+    }
+  }
+
+  pub(crate) fn traced(self, clue: Noun) -> Self {
+    match self {
+      Self::Traced { mut trace, error } => {
+        trace.push(clue);
+        Self::Traced { trace, error }
+      }
+      error => Self::Traced {
+        trace: vec![clue],
+        error: Box::new(error),
+      },
+    }
+  }
+
+  // the error without its opcode or trace context
+  pub fn root(&self) -> &NockError {
+    match self {
+      Self::InOpcode { error, .. } | Self::Traced { error, .. } => error.root(),
+      error => error,
+    }
+  }
+
+  // false when the interpreter gave up or was blocked, rather than the formula
+  // being wrong
+  pub fn is_deterministic(&self) -> bool {
+    !matches!(
+      self.root(),
+      Self::OutOfFuel
+        | Self::DepthLimit
+        | Self::StackExhausted
+        | Self::Cancelled
+        | Self::Panicked
+        | Self::AtomOverflow
+        | Self::Blocked { .. }
+        | Self::Diverged { .. }
+    )
+  }
+
+  pub fn into_crash(self) -> Result<Crash, NockError> {
+    if !self.is_deterministic() {
+      return Err(self);
+    }
+
+    Ok(Crash {
+      trace: Noun::list(self.trace().iter().cloned()),
+      error: self,
+    })
+  }
+
+  pub fn trace(&self) -> &[Noun] {
+    match self {
+      Self::Traced { trace, .. } => trace,
+      _ => &[],
+    }
+  }
+}
+
+impl std::fmt::Display for NockError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::CellRequired { found } => write!(f, "expected a cell but found {found}"),
+      Self::AtomRequired { found } => write!(f, "expected an atom but found {found}"),
+      Self::NotLoobean { found } => write!(f, "expected a loobean but found {found}"),
+      Self::NonLoobeanCondition { value } => {
+        write!(f, "branch condition must be 0 or 1 but was {value}")
+      }
+      Self::ImproperList { tail } => write!(f, "expected a list but it ends with {tail}"),
+      Self::ZeroAxis => write!(f, "axis can't be zero"),
+      Self::AxisOutOfTree { axis } => write!(f, "axis {axis} is out of the tree"),
+      Self::UnknownOpcode(atom) => write!(f, "unknown opcode '{atom}'"),
+      Self::Exit { formula } => write!(f, "formula {formula} never reduces"),
+      Self::AtomOverflow => write!(f, "atom doesn't fit in 64 bits"),
+      Self::SubtractUnderflow => write!(f, "subtraction underflow"),
+      Self::DivideByZero => write!(f, "division by zero"),
+      Self::OutOfFuel => write!(f, "out of fuel"),
+      Self::DepthLimit => write!(f, "depth limit exceeded"),
+      Self::StackExhausted => write!(f, "out of native stack"),
+      Self::Cancelled => write!(f, "cancelled"),
+      Self::Panicked => write!(f, "the interpreter panicked"),
+      Self::Blocked { path } => write!(f, "blocked on {}", path.pretty()),
+      Self::Diverged {
+        formula,
+        divergence: Some(divergence),
+      } => {
+        let Divergence { axis, left, right } = &**divergence;
+        let axis = axis.map_or("deep".to_string(), |axis| axis.to_string());
+        write!(
+          f,
+          "backends diverged on {formula} at axis {axis}: {left} against {right}"
+        )
+      }
+      Self::Diverged {
+        formula,
+        divergence: None,
+      } => write!(
+        f,
+        "backends diverged on {formula}: only one of them crashed"
+      ),
+      Self::InOpcode { opcode, error } => write!(f, "opcode {opcode}: {error}"),
+      Self::Traced { trace, error } => {
+        write!(f, "{error}")?;
+        for clue in trace {
+          write!(f, "\n  at {}", clue.pretty())?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+impl std::error::Error for NockError {}
+
+impl std::fmt::Display for Crash {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.error)
+  }
+}
+
+impl std::error::Error for Crash {}
+
+#[cfg(test)]
+mod test {
+  use super::NockError;
+
+  #[test]
+  fn test_crash() {
+    let e = NockError::ZeroAxis.traced(noun!(1)).traced(noun!(2));
+    assert!(e.is_deterministic());
+    let crash = e.into_crash().unwrap();
+    assert_eq!(crash.trace, noun!([1 2 ~]));
+    assert!(matches!(crash.error.root(), NockError::ZeroAxis));
+
+    let e = NockError::OutOfFuel.traced(noun!(1));
+    assert!(!e.is_deterministic());
+    assert!(matches!(e.into_crash(), Err(NockError::Traced { .. })));
+    assert!(!NockError::Blocked { path: noun!(0) }.is_deterministic());
+  }
+}
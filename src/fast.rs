@@ -0,0 +1,179 @@
+// Cold state: every core labeled by a `%fast` hint, keyed by its battery.
+//
+// The clue is `{name parent hooks}`, where `parent` is `{0 axis}` for where
+// the parent core sits inside this one, or `{1 0}` for a root core. A child is
+// only registered once its parent is, so every label can be traced to a root.
+//
+// The whole registry is a noun with `to_noun`, so it can be kept with the
+// kernel whose cores it labels, and read back with `from_noun` by another
+// interpreter that runs the same kernel.
+
+use std::collections::HashMap;
+
+use crate::{atom::Atom, noun::Noun};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Registration {
+  pub name: Noun,
+  // None for a root core
+  pub parent: Option<u64>,
+  pub battery: Noun,
+  // names from the root down, like `k140/one/dec`
+  pub label: String,
+}
+
+#[derive(Clone, Default)]
+pub struct Cold {
+  cores: HashMap<Noun, Registration>,
+}
+
+impl Cold {
+  pub fn get(&self, battery: &Noun) -> Option<&Registration> {
+    self.cores.get(battery)
+  }
+
+  pub fn len(&self) -> usize {
+    self.cores.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.cores.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Registration> {
+    self.cores.values()
+  }
+
+  // a list of `{battery name parent label}`, with `parent` as in a clue and
+  // `label` a cord, ordered by label
+  pub fn to_noun(&self) -> Noun {
+    let mut cores: Vec<_> = self.cores.values().collect();
+    cores.sort_by_key(|registration| (&registration.label, registration.battery.mug()));
+
+    Noun::list(cores.into_iter().map(|registration| {
+      let parent = match registration.parent {
+        None => noun!({1, 0}),
+        Some(axis) => noun!({0, (axis)}),
+      };
+      let label = Noun::atom(Atom::from_cord(&registration.label));
+      noun!({(registration.battery.clone()), {(registration.name.clone()), {(parent), (label)}}})
+    }))
+  }
+
+  // None if `noun` isn't one `to_noun` made
+  pub fn from_noun(noun: &Noun) -> Option<Self> {
+    let mut cold = Self::default();
+
+    for registration in noun.iter_list() {
+      let registration = registration.ok()?;
+      let (battery, name, parent, label) = noun_match!(registration,
+        {battery, {name, {parent, label}}} => (battery, name, parent, label),
+        _ => return None,
+      );
+      let parent = noun_match!(parent,
+        {1, 0} => None,
+        {0, axis} => Some(axis.as_atom()?.as_u64()?),
+        _ => return None,
+      );
+
+      let registration = Registration {
+        name: name.clone(),
+        parent,
+        battery: battery.clone(),
+        label: label.as_atom()?.to_cord().ok()?,
+      };
+      cold.cores.insert(battery.clone(), registration);
+    }
+
+    Some(cold)
+  }
+
+  pub(crate) fn register(&mut self, clue: &Noun, core: &Noun) -> Option<&Registration> {
+    let registration = self.registration(clue, core)?;
+    let battery = registration.battery.clone();
+    self.cores.insert(battery.clone(), registration);
+    self.cores.get(&battery)
+  }
+
+  // what registering `core` under `clue` would add, without adding it.
+  // Malformed clues and cores are ignored, hints never crash a computation
+  pub(crate) fn registration(&self, clue: &Noun, core: &Noun) -> Option<Registration> {
+    let (name, parent) = noun_match!(clue,
+      {name, {parent, _hooks}} => (name, parent),
+      _ => return None,
+    );
+    let (battery, _) = core.as_cell()?;
+
+    let (parent, label) = noun_match!(parent,
+      {1, 0} => (None, label(name)?),
+      {0, axis} => {
+        let axis = axis.as_atom()?.as_u64()?;
+        let parent = core.slot_opt(axis)?;
+        let parent = self.cores.get(parent.as_cell()?.0)?;
+        (Some(axis), format!("{}/{}", parent.label, label(name)?))
+      },
+      _ => return None,
+    );
+
+    Some(Registration {
+      name: name.clone(),
+      parent,
+      battery: battery.clone(),
+      label,
+    })
+  }
+}
+
+// `%dec`, or `{%k 140}` for a versioned name
+fn label(name: &Noun) -> Option<String> {
+  noun_match!(name,
+    {name, version} => {
+      let version = version.as_atom()?;
+      Some(format!("{}{version}", name.as_atom()?.to_cord().ok()?))
+    },
+    name => name.as_atom()?.to_cord().ok(),
+  )
+}
+
+#[cfg(test)]
+mod test {
+  use super::Cold;
+  use crate::{atom::Atom, noun::Noun};
+
+  #[test]
+  fn test_register() {
+    let cord = |s| Noun::atom(Atom::from_cord(s));
+    let mut cold = Cold::default();
+
+    let root = noun!({{idty, 42}, 0});
+    let clue = noun!({{(cord("k")), 140}, {{idty, 0}, 0}});
+    let reg = cold.register(&clue, &root).unwrap();
+    assert_eq!(reg.label, "k140");
+    assert_eq!(reg.parent, None);
+
+    let kid = Noun::cell(noun!({incr, {addr, 6}}), root.clone());
+    let clue = noun!({(cord("dec")), {{addr, 3}, 0}});
+    let reg = cold.register(&clue, &kid).unwrap();
+    assert_eq!(reg.label, "k140/dec");
+    assert_eq!(reg.parent, Some(3));
+    assert_eq!(
+      cold.get(&noun!({incr, {addr, 6}})).unwrap().label,
+      "k140/dec"
+    );
+
+    // no registered parent, or no parent at that axis
+    let orphan = noun!({{incr, {addr, 6}}, {{idty, 7}, 0}});
+    assert!(cold.register(&clue, &orphan).is_none());
+    let clue = noun!({(cord("dec")), {{addr, 7}, 0}});
+    assert!(cold.register(&clue, &kid).is_none());
+    assert!(cold.register(&noun!(0), &kid).is_none());
+    assert_eq!(cold.len(), 2);
+
+    let saved = Cold::from_noun(&cold.to_noun()).unwrap();
+    assert_eq!(saved.len(), 2);
+    let reg = saved.get(&noun!({incr, {addr, 6}})).unwrap();
+    assert_eq!((reg.label.as_str(), reg.parent), ("k140/dec", Some(3)));
+    assert_eq!(saved.to_noun(), cold.to_noun());
+    assert!(Cold::from_noun(&noun!({1, 2})).is_none());
+  }
+}
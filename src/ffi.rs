@@ -0,0 +1,283 @@
+// A C interface, built into the cdylib with the `ffi` feature, declared in
+// include/nuuk.h. Nouns cross it jammed, as little-endian bytes, or as text
+// in the syntax `Noun` parses and prints. Whatever comes back is a buffer
+// owned by this side, with the product on success and a UTF-8 message
+// otherwise, and is handed back to `nuuk_free`.
+
+use std::{
+  panic::{AssertUnwindSafe, catch_unwind},
+  ptr, slice,
+};
+
+use crate::{
+  interp::Interpreter,
+  noun::Noun,
+  serial::{cue_bytes, jam_bytes},
+};
+
+#[repr(C)]
+pub struct NuukBuffer {
+  pub ptr: *mut u8,
+  pub len: usize,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NuukStatus {
+  Ok = 0,
+  // the formula crashed
+  Crash = 1,
+  // a null pointer, or input that isn't a jam or a noun
+  BadInput = 2,
+  // a bug on this side, caught before it could unwind into C
+  Panic = 3,
+}
+
+impl NuukBuffer {
+  fn new(bytes: impl Into<Box<[u8]>>) -> Self {
+    let bytes = Box::into_raw(bytes.into());
+    NuukBuffer {
+      ptr: bytes.cast(),
+      len: bytes.len(),
+    }
+  }
+}
+
+/// # Safety
+///
+/// `ptr` is null or valid for reads of `len` bytes.
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], String> {
+  match ptr.is_null() {
+    true if len == 0 => Ok(&[]),
+    true => Err("null input".to_string()),
+    // SAFETY: as the caller promised
+    false => Ok(unsafe { slice::from_raw_parts(ptr, len) }),
+  }
+}
+
+/// Runs `call`, writing what it returns to `out`.
+///
+/// # Safety
+///
+/// `out` is null or valid for writes.
+unsafe fn respond(
+  out: *mut NuukBuffer,
+  call: impl FnOnce() -> Result<Vec<u8>, (NuukStatus, String)>,
+) -> NuukStatus {
+  if out.is_null() {
+    return NuukStatus::BadInput;
+  }
+
+  let (status, bytes) = match catch_unwind(AssertUnwindSafe(call)) {
+    Ok(Ok(bytes)) => (NuukStatus::Ok, bytes),
+    Ok(Err((status, message))) => (status, message.into_bytes()),
+    Err(_) => (NuukStatus::Panic, b"panicked".to_vec()),
+  };
+  // SAFETY: as the caller promised
+  unsafe { out.write(NuukBuffer::new(bytes)) };
+  status
+}
+
+fn bad_input(e: impl std::fmt::Display) -> (NuukStatus, String) {
+  (NuukStatus::BadInput, e.to_string())
+}
+
+/// Evaluates the jammed `formula` against the jammed `subject`, and writes
+/// the jammed product, or why there isn't one, to `out`.
+///
+/// # Safety
+///
+/// `subject` and `formula` are null, for no bytes, or valid for reads of
+/// their lengths, and `out` is valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuuk_eval(
+  subject: *const u8,
+  subject_len: usize,
+  formula: *const u8,
+  formula_len: usize,
+  out: *mut NuukBuffer,
+) -> NuukStatus {
+  let call = || {
+    // SAFETY: as the caller promised
+    let (subject, formula) = unsafe {
+      (
+        input(subject, subject_len).map_err(bad_input)?,
+        input(formula, formula_len).map_err(bad_input)?,
+      )
+    };
+    let subject = cue_bytes(subject).map_err(bad_input)?;
+    let formula = cue_bytes(formula).map_err(bad_input)?;
+
+    let product = Interpreter::new()
+      .eval(subject.into(), formula.into())
+      .map_err(|e| (NuukStatus::Crash, e.to_string()))?;
+    Ok(jam_bytes(&product))
+  };
+  // SAFETY: as the caller promised
+  unsafe { respond(out, call) }
+}
+
+/// Jams the noun written as UTF-8 `text`, and writes its bytes to `out`.
+///
+/// # Safety
+///
+/// `text` is null, for no bytes, or valid for reads of `len` bytes, and
+/// `out` is valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuuk_jam(text: *const u8, len: usize, out: *mut NuukBuffer) -> NuukStatus {
+  let call = || {
+    // SAFETY: as the caller promised
+    let text = unsafe { input(text, len) }.map_err(bad_input)?;
+    let text = std::str::from_utf8(text).map_err(bad_input)?;
+    let noun = text.parse::<Noun>().map_err(bad_input)?;
+    Ok(jam_bytes(&noun))
+  };
+  // SAFETY: as the caller promised
+  unsafe { respond(out, call) }
+}
+
+/// Cues the jammed `bytes`, and writes the noun as UTF-8 text to `out`.
+///
+/// # Safety
+///
+/// `bytes` is null, for no bytes, or valid for reads of `len` bytes, and
+/// `out` is valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuuk_cue(
+  bytes: *const u8,
+  len: usize,
+  out: *mut NuukBuffer,
+) -> NuukStatus {
+  let call = || {
+    // SAFETY: as the caller promised
+    let bytes = unsafe { input(bytes, len) }.map_err(bad_input)?;
+    let noun = cue_bytes(bytes).map_err(bad_input)?;
+    Ok(noun.to_string().into_bytes())
+  };
+  // SAFETY: as the caller promised
+  unsafe { respond(out, call) }
+}
+
+/// Frees a buffer one of the other functions wrote.
+///
+/// # Safety
+///
+/// `buffer` came from this library and hasn't been freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuuk_free(buffer: NuukBuffer) {
+  if buffer.ptr.is_null() {
+    return;
+  }
+  // SAFETY: it's a boxed slice of this length, as the caller promised
+  drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.ptr, buffer.len)) });
+}
+
+#[cfg(test)]
+mod test {
+  use super::{NuukBuffer, NuukStatus, nuuk_cue, nuuk_eval, nuuk_free, nuuk_jam};
+  use crate::serial::jam_bytes;
+
+  const EMPTY: NuukBuffer = NuukBuffer {
+    ptr: std::ptr::null_mut(),
+    len: 0,
+  };
+
+  // a call's status, and what it wrote, freed
+  fn call(f: impl FnOnce(*mut NuukBuffer) -> NuukStatus) -> (NuukStatus, Vec<u8>) {
+    let mut out = EMPTY;
+    let status = f(&mut out);
+    // SAFETY: the call wrote a buffer of its own
+    let bytes = unsafe { std::slice::from_raw_parts(out.ptr, out.len) }.to_vec();
+    // SAFETY: and it's freed once
+    unsafe { nuuk_free(out) };
+    (status, bytes)
+  }
+
+  #[test]
+  fn test_ffi() {
+    let subject = jam_bytes(&noun!({4, 5}));
+    let formula = jam_bytes(&noun!({4, {0, 3}}));
+    let eval = |subject: &[u8], formula: &[u8]| {
+      // SAFETY: the slices are valid for their lengths
+      call(|out| unsafe {
+        nuuk_eval(
+          subject.as_ptr(),
+          subject.len(),
+          formula.as_ptr(),
+          formula.len(),
+          out,
+        )
+      })
+    };
+
+    assert_eq!(
+      eval(&subject, &formula),
+      (NuukStatus::Ok, jam_bytes(&noun!(6)))
+    );
+    let (status, message) = eval(&subject, &jam_bytes(&noun!({0, 7})));
+    assert_eq!(status, NuukStatus::Crash);
+    assert!(!message.is_empty());
+    assert_eq!(eval(&subject, &[]).0, NuukStatus::BadInput);
+
+    let text = b"{1 {2 3}}";
+    // SAFETY: as above
+    let (status, jam) = call(|out| unsafe { nuuk_jam(text.as_ptr(), text.len(), out) });
+    assert_eq!(
+      (status, &jam),
+      (NuukStatus::Ok, &jam_bytes(&noun!({1, {2, 3}})))
+    );
+    // SAFETY: as above
+    let (status, cued) = call(|out| unsafe { nuuk_cue(jam.as_ptr(), jam.len(), out) });
+    assert_eq!((status, &cued[..]), (NuukStatus::Ok, &b"{1 2 3}"[..]));
+
+    // SAFETY: a null input is refused unless it's empty
+    let (status, _) = call(|out| unsafe { nuuk_jam(std::ptr::null(), 1, out) });
+    assert_eq!(status, NuukStatus::BadInput);
+    // SAFETY: as above
+    let (status, _) = call(|out| unsafe { nuuk_jam([0xff].as_ptr(), 1, out) });
+    assert_eq!(status, NuukStatus::BadInput);
+    // SAFETY: a null `out` is refused before anything is written
+    let status = unsafe { nuuk_cue(jam.as_ptr(), jam.len(), std::ptr::null_mut()) };
+    assert_eq!(status, NuukStatus::BadInput);
+    // SAFETY: a null buffer is nothing to free
+    unsafe { nuuk_free(EMPTY) };
+  }
+
+  // include/nuuk.h is what cbindgen makes of this file. Set
+  // NUUK_BLESS_HEADER=1 to write it rather than check it.
+  #[test]
+  fn test_header() {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let mut config = cbindgen::Config {
+      language: cbindgen::Language::C,
+      include_guard: Some("NUUK_H".to_string()),
+      autogen_warning: Some(
+        "// generated by cbindgen from src/ffi.rs, see `test_header`".to_string(),
+      ),
+      cpp_compat: true,
+      usize_is_size_t: true,
+      ..Default::default()
+    };
+    // NUUK_STATUS_OK and so on, out of the way of anything else in C
+    config.enumeration.prefix_with_name = true;
+    config.enumeration.rename_variants = cbindgen::RenameRule::ScreamingSnakeCase;
+
+    let mut header = vec![];
+    cbindgen::Builder::new()
+      .with_config(config)
+      .with_src(format!("{root}/src/ffi.rs"))
+      .generate()
+      .unwrap()
+      .write(&mut header);
+
+    let path = format!("{root}/include/nuuk.h");
+    if std::env::var_os("NUUK_BLESS_HEADER").is_some() {
+      std::fs::write(&path, &header).unwrap();
+    }
+    let checked_in = std::fs::read(&path).unwrap_or_default();
+    assert!(
+      checked_in == header,
+      "include/nuuk.h is out of date, run with NUUK_BLESS_HEADER=1"
+    );
+  }
+}
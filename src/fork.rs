@@ -0,0 +1,281 @@
+// Autocons on a rayon pool, for interpreters built with `parallel(true)`.
+// When both formulas of `*{a {b c} d}` make calls of their own, and so might
+// take a while, the tail runs on the global pool while the head runs where it
+// is, which lets a wide tuple of products use as many threads as it has
+// elements.
+//
+// A forked tail runs on an interpreter of its own with the same limits, jets
+// and cold state, and only counts as pure if it gets by on that: its `%slog`,
+// `%fast` and `%memo` effects are held back and had by the joining
+// interpreter, after the head's, as if the tail ran in place. Anything it
+// can't do alone, like a scry, an extension or a crash, has the tail run
+// again in place, so products, errors and effects are what they'd be without
+// forking. Only the tree engine forks, and not with fuel, a trace sink, hint
+// handlers or jet profiling, which would all see the tail out of order or not
+// at all.
+
+use std::{
+  cell::Cell,
+  panic::{AssertUnwindSafe, catch_unwind},
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, TryRecvError},
+  },
+  time::Duration,
+};
+
+use crate::{
+  compile::{Op, shape},
+  fast::Cold,
+  interp::{AtomFormula, Formula, Interpreter, Subject},
+  jet::Dashboard,
+  noun::{Noun, Rc},
+};
+
+// an effect of a forked tail, for the interpreter that joins it
+pub(crate) enum Deferred {
+  Slog(Noun),
+  Fast(Noun, Noun),
+  Memo(Noun, Noun),
+}
+
+// what a forked tail's interpreter takes from the one it was forked from
+pub(crate) struct Settings {
+  pub(crate) depth_limit: Option<usize>,
+  pub(crate) hints: bool,
+  pub(crate) jets: bool,
+  pub(crate) check_jets: bool,
+  pub(crate) intern: bool,
+  pub(crate) atom_formula: AtomFormula,
+  pub(crate) cold: Rc<Cold>,
+  pub(crate) dash: Rc<Dashboard>,
+}
+
+type Joined = Option<(Noun, Vec<Deferred>)>;
+
+// a tail running on the pool, which gives up once nobody is waiting on it
+pub(crate) struct Fork {
+  joined: Receiver<Joined>,
+  cancel: Arc<AtomicBool>,
+}
+
+thread_local! {
+  // forked tails this thread used the product of
+  pub(crate) static JOINED: Cell<usize> = const { Cell::new(0) };
+}
+
+pub(crate) fn fork(settings: Settings, subj: Noun, formula: Noun) -> Fork {
+  let (sender, joined) = mpsc::channel();
+  let cancel = Arc::new(AtomicBool::new(false));
+  let cancelled = cancel.clone();
+
+  rayon::spawn(move || {
+    if cancelled.load(Ordering::Relaxed) {
+      return;
+    }
+    // a panic has the tail run again in place, to panic there
+    let prod = catch_unwind(AssertUnwindSafe(|| run(settings, cancelled, subj, formula)));
+    let _ = sender.send(prod.ok().flatten());
+  });
+
+  Fork { joined, cancel }
+}
+
+fn run(settings: Settings, cancel: Arc<AtomicBool>, subj: Noun, formula: Noun) -> Joined {
+  let mut builder = Interpreter::builder()
+    .hints(settings.hints)
+    .jets(settings.jets)
+    .check_jets(settings.check_jets)
+    .intern(settings.intern)
+    .atom_formula(settings.atom_formula)
+    .parallel(true);
+  if let Some(limit) = settings.depth_limit {
+    builder = builder.depth_limit(limit);
+  }
+  let mut it = builder.build();
+  it.cold = settings.cold;
+  it.dash = settings.dash;
+  it.deferred = Some(vec![]);
+  it.cancel = Some(cancel);
+
+  let prod = it.eval(Subject(subj), Formula(formula)).ok()?;
+  Some((prod, it.deferred.take().unwrap_or_default()))
+}
+
+impl Fork {
+  // the tail's product and effects, None if it has to run again in place
+  pub(crate) fn join(&self) -> Joined {
+    // the pool's own threads run other jobs while they wait, which may be
+    // the very tail they're waiting on
+    if rayon::current_thread_index().is_none() {
+      return self.count(self.joined.recv().ok().flatten());
+    }
+
+    loop {
+      match self.joined.try_recv() {
+        Ok(joined) => return self.count(joined),
+        Err(TryRecvError::Disconnected) => return None,
+        Err(TryRecvError::Empty) => {}
+      }
+      if rayon::yield_now() != Some(rayon::Yield::Executed)
+        && let Ok(joined) = self.joined.recv_timeout(Duration::from_micros(100))
+      {
+        return self.count(joined);
+      }
+    }
+  }
+
+  fn count(&self, joined: Joined) -> Joined {
+    if joined.is_some() {
+      JOINED.set(JOINED.get() + 1);
+    }
+    joined
+  }
+}
+
+impl Drop for Fork {
+  fn drop(&mut self) {
+    self.cancel.store(true, Ordering::Relaxed);
+  }
+}
+
+// whether `formula` makes a call with opcode 2 or 9 anywhere
+pub(crate) fn calls(formula: &Noun) -> bool {
+  let mut pending = vec![formula.clone()];
+
+  while let Some(formula) = pending.pop() {
+    let (op, kids) = shape(&formula);
+    if matches!(op, Op::Eval | Op::Invk(..)) {
+      return true;
+    }
+    pending.extend(kids);
+  }
+
+  false
+}
+
+#[cfg(test)]
+mod test {
+  use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+  };
+
+  use super::JOINED;
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    error::NockError,
+    interp::{Formula, Interpreter, Subject},
+    noun::Noun,
+  };
+
+  // `*{n decrement}` as a formula of the subject
+  fn dec(n: u64) -> Noun {
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    noun!({eval, {{idty, (n)}, {idty, (decrement)}}})
+  }
+
+  #[test]
+  fn test_parallel() {
+    // nouns go between threads
+    let big = Noun::atom(Atom::from_words(&[1, 2, 3]));
+    let noun = noun!({1, (big.clone())});
+    let sent = std::thread::spawn(move || noun).join().unwrap();
+    assert_eq!(sent, noun!({1, (big)}));
+
+    let wide = noun!({(dec(1000)), {(dec(2000)), {(dec(3000)), (dec(4000))}}});
+    let mut it = Interpreter::builder().parallel(true).build();
+    let joined = JOINED.get();
+    let prod = it.eval(Subject(noun!(0)), Formula(wide)).unwrap();
+    assert_eq!(prod, noun!({999, {1999, {2999, 3999}}}));
+    assert!(JOINED.get() > joined);
+
+    // a tail nobody waits on any more stops
+    let mut cancelled = Interpreter::new();
+    cancelled.cancel = Some(Arc::new(AtomicBool::new(true)));
+    let e = cancelled
+      .eval(Subject(noun!(0)), Formula(dec(10)))
+      .unwrap_err();
+    assert!(matches!(e.root(), NockError::Cancelled));
+
+    // crashes are the same as without forking
+    let crash = noun!({(dec(1000)), {eval, {{idty, 0}, {idty, {addr, 7}}}}});
+    let mut serial = Interpreter::new();
+    assert_eq!(
+      format!("{:?}", it.eval(Subject(noun!(0)), Formula(crash.clone()))),
+      format!("{:?}", serial.eval(Subject(noun!(0)), Formula(crash)))
+    );
+  }
+
+  #[test]
+  fn test_parallel_effects() {
+    let slogs = Arc::new(Mutex::new(vec![]));
+    let sink = slogs.clone();
+    let mut it = Interpreter::builder()
+      .parallel(true)
+      .slog(move |clue| sink.lock().unwrap().push(clue.clone()))
+      .scry(|_, path| Some(path.clone()))
+      .build();
+
+    // the tail's slogs come after the head's, and the core it labels is
+    // registered
+    let slog =
+      |n: u64, formula: Noun| noun!({hint, {{(Atom::from_cord("slog")), {idty, (n)}}, (formula)}});
+    let core = noun!({{idty, 42}, 0});
+    let clue = noun!({(Atom::from_cord("k")), {{idty, 0}, 0}});
+    let fast = noun!({hint, {{(Atom::from_cord("fast")), {idty, (clue)}}, {idty, (core.clone())}}});
+    let tail = noun!({(slog(2, dec(2000))), {(slog(3, dec(10))), (fast)}});
+    let wide = noun!({(slog(1, dec(1000))), (tail)});
+    let joined = JOINED.get();
+    let prod = it.eval(Subject(noun!(0)), Formula(wide)).unwrap();
+    assert_eq!(prod, noun!({999, {1999, {9, (core)}}}));
+    assert!(JOINED.get() > joined);
+    assert_eq!(*slogs.lock().unwrap(), vec![noun!(1), noun!(2), noun!(3)]);
+    assert_eq!(it.cold().get(&noun!({idty, 42})).unwrap().label, "k");
+
+    // a tail that scries runs again in place
+    let scry = noun!({(dec(1000)), {(dec(5)), {scry, {{idty, 0}, {idty, 7}}}}});
+    let prod = it.eval(Subject(noun!(0)), Formula(scry)).unwrap();
+    assert_eq!(prod, noun!({999, {4, 7}}));
+  }
+
+  #[test]
+  fn test_parallel_jets() {
+    // the battery of the gate in `DECREMENT`, whose arm is at 2
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let battery = decrement.slot(61).unwrap();
+    let wide = noun!({(dec(1000)), (dec(2000))});
+
+    // a forked tail has the jets of the interpreter it was forked from
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut it = Interpreter::builder()
+      .parallel(true)
+      .check_jets(true)
+      .jet_battery(battery.clone(), 2, move |core| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        let n = core.slot(7).ok()?.as_atom()?.as_u64()?;
+        Some(Noun::from(n - 1))
+      })
+      .build();
+    let joined = JOINED.get();
+    let prod = it.eval(Subject(noun!(0)), Formula(wide.clone())).unwrap();
+    assert_eq!(prod, noun!({999, 1999}));
+    assert!(JOINED.get() > joined);
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+    // and checks them, here one that's only wrong in the tail
+    let mut it = Interpreter::builder()
+      .parallel(true)
+      .check_jets(true)
+      .jet_battery(battery, 2, |core| {
+        let n = core.slot(7).ok()?.as_atom()?.as_u64()?;
+        Some(Noun::from(if n == 2000 { n } else { n - 1 }))
+      })
+      .build();
+    let e = it.eval(Subject(noun!(0)), Formula(wide)).unwrap_err();
+    assert!(matches!(e.root(), NockError::Diverged { .. }));
+  }
+}
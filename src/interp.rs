@@ -0,0 +1,2472 @@
+// nock(a)    ~> *a
+//
+// +0 ~> 1
+// +a ~> +a
+//
+// ?{a b} ~> 0
+// ?a     ~> 1
+//
+// ={a a} ~> 0
+// ={a b} ~> 1
+//
+// /{1 a}           ~> a
+// /{2 {a b}}       ~> a
+// /{3 {a b}}       ~> b
+// /{(a + a) b}     ~> /{2 /{a b}}
+// /{(a + a + 1) b} ~> /{3 /{a b}}
+// /a               ~> /a
+//
+// *{a {b c} d}    ~> {*{a b c} *{a d}}
+// *{a 0 b}        ~> /{b a}
+// *{a 1 b}        ~> b
+// *{a 2 b c}      ~> *{*{a b} *{a c}}
+// *{a 3 b}        ~> ?*{a b}
+// *{a 4 b}        ~> +*{a b}
+// *{a 5 b c}      ~> ={*{a b} *{a c}}
+// *{a 6 b c d}    ~> *{a *{{c d} 0 *{{2 3} 0 *{a 4 4 b}}}}
+// *{a 7 b c}      ~> *{*{a b} c}
+// *{a 8 b c}      ~> *{{*{a b} a} c}
+// *{a 9 b c}      ~> *{*{a c} 2 {0 1} 0 b}
+// *{a 10 {b c} d} ~> #{b *{a c} *{a d}}
+// *{a 11 {b c} d} ~> *{{*{a c} *{a d}} 0 3}
+// *{a 11 b c}     ~> *{a c}
+// *{a 12 b c}     ~> scry(*{a b}, *{a c})
+// *a              ~> *a
+
+#[cfg(feature = "sync")]
+use std::sync::{
+  Arc,
+  atomic::{AtomicBool, Ordering},
+};
+use std::{collections::HashMap, io::Write, time::Instant};
+
+#[cfg(feature = "sync")]
+use crate::fork::{self, Deferred, Fork};
+use crate::{
+  atom::{Atom, NAH, YES},
+  bytecode::Program,
+  cache::{EvalCache, Pending},
+  closure,
+  compile::CompiledFormula,
+  error::{Crash, NockError},
+  fast::Cold,
+  jet::{Dashboard, JetStats},
+  noun::{Equals, Noun, Rc, Sendable, Shareable, edit_in, noun_diff},
+  opcode::Opcode,
+};
+
+#[derive(Clone, Debug)]
+#[repr(transparent)]
+pub struct Subject(pub Noun);
+
+#[derive(Clone, Debug)]
+#[repr(transparent)]
+pub struct Formula(pub Noun);
+
+impl Subject {
+  pub fn into_noun(self) -> Noun {
+    self.0
+  }
+}
+
+impl Formula {
+  pub fn into_noun(self) -> Noun {
+    self.0
+  }
+}
+
+impl From<Noun> for Subject {
+  fn from(noun: Noun) -> Self {
+    Self(noun)
+  }
+}
+
+impl From<Noun> for Formula {
+  fn from(noun: Noun) -> Self {
+    Self(noun)
+  }
+}
+
+impl From<Subject> for Noun {
+  fn from(Subject(noun): Subject) -> Self {
+    noun
+  }
+}
+
+impl From<Formula> for Noun {
+  fn from(Formula(noun): Formula) -> Self {
+    noun
+  }
+}
+
+impl AsRef<Noun> for Subject {
+  fn as_ref(&self) -> &Noun {
+    &self.0
+  }
+}
+
+impl AsRef<Noun> for Formula {
+  fn as_ref(&self) -> &Noun {
+    &self.0
+  }
+}
+
+type TraceSink = Box<dyn_sendable!(FnMut(&Noun, &Noun))>;
+type SlogSink = Box<dyn_sendable!(FnMut(&Noun))>;
+type ScryHandler = Box<dyn_sendable!(FnMut(&Noun, &Noun) -> Option<Noun>)>;
+type Backend = Box<dyn_sendable!(FnMut(&Noun, &Noun) -> Result<Noun, NockError>)>;
+// called with the subject, the opcode's argument and a way to evaluate nock
+// within the same computation, which may use the extension again
+type Extension = Rc<dyn_shareable!(Fn(&Noun, &Noun, &mut Nock) -> Result<Noun, NockError>)>;
+pub type Nock<'a> = dyn FnMut(Noun, Noun) -> Result<Noun, NockError> + 'a;
+
+fn slog_to(mut out: impl Write + Sendable + 'static) -> SlogSink {
+  Box::new(move |clue| {
+    // debug output is best effort, a broken sink shouldn't crash the program
+    let _ = writeln!(out, "{}", clue.pretty());
+  })
+}
+
+// the outcome of a virtualized evaluation, which never fails outright
+#[derive(Clone, Debug)]
+pub enum SoftResult {
+  Done(Noun),
+  // a scry found nothing at this path
+  Blocked(Noun),
+  // the formula is wrong, and would crash anywhere
+  Crashed(Crash),
+  // the interpreter gave up, on fuel or depth say
+  Failed(NockError),
+}
+
+// what to do with `*{a b}` when `b` is an atom, where the spec says `*a ~> *a`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AtomFormula {
+  // crash straight away with `NockError::Exit`
+  #[default]
+  Exit,
+  // loop as written, burning fuel until `NockError::OutOfFuel`, or forever
+  // without a fuel limit
+  Loop,
+}
+
+// how `eval` runs a formula
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+  // reduce the formula's cells as they are
+  #[default]
+  Tree,
+  // compile each formula to closures on first use, and call those
+  Closure,
+  // compile each formula to bytecode on first use, and run that
+  Bytecode,
+  // bytecode, with hot programs compiled on to native code, see src/jit.rs
+  #[cfg(feature = "jit")]
+  Jit,
+}
+
+// reacts to hints as they're met, after the clue is evaluated and before the
+// hinted formula is
+pub trait HintHandler: Sendable {
+  fn hint(&mut self, tag: Atom, clue: Option<Noun>, subject: &Noun, formula: &Noun);
+}
+
+impl<F> HintHandler for F
+where
+  F: FnMut(Atom, Option<Noun>, &Noun, &Noun) + Sendable,
+{
+  fn hint(&mut self, tag: Atom, clue: Option<Noun>, subject: &Noun, formula: &Noun) {
+    self(tag, clue, subject, formula)
+  }
+}
+
+pub struct InterpreterBuilder {
+  depth_limit: Option<usize>,
+  fuel: Option<u64>,
+  hints: bool,
+  jets: bool,
+  check_jets: bool,
+  profile_jets: bool,
+  cold: Cold,
+  dash: Dashboard,
+  intern: bool,
+  #[cfg(feature = "road")]
+  road: bool,
+  #[cfg(feature = "sync")]
+  parallel: bool,
+  trace: Option<TraceSink>,
+  slog: SlogSink,
+  handlers: Vec<Box<dyn HintHandler>>,
+  scry: Option<ScryHandler>,
+  atom_formula: AtomFormula,
+  shadow: Option<Backend>,
+  extensions: HashMap<Atom, Extension>,
+  engine: Engine,
+  #[cfg(feature = "jit")]
+  jit_after: u32,
+  cache: Option<EvalCache>,
+}
+
+impl Default for InterpreterBuilder {
+  fn default() -> Self {
+    Self {
+      depth_limit: None,
+      fuel: None,
+      hints: true,
+      jets: true,
+      check_jets: false,
+      profile_jets: false,
+      cold: Cold::default(),
+      dash: Dashboard::builtin(),
+      intern: false,
+      #[cfg(feature = "road")]
+      road: false,
+      #[cfg(feature = "sync")]
+      parallel: false,
+      trace: None,
+      slog: slog_to(std::io::stderr()),
+      handlers: vec![],
+      scry: None,
+      atom_formula: AtomFormula::Exit,
+      shadow: None,
+      extensions: HashMap::new(),
+      engine: Engine::Tree,
+      #[cfg(feature = "jit")]
+      jit_after: 16,
+      cache: None,
+    }
+  }
+}
+
+impl InterpreterBuilder {
+  // maximum nesting of nock calls before `NockError::DepthLimit`; without one,
+  // only memory bounds how deep a computation goes
+  pub fn depth_limit(mut self, limit: usize) -> Self {
+    self.depth_limit = Some(limit);
+    self
+  }
+
+  // maximum number of reduction steps per evaluation before `NockError::OutOfFuel`
+  pub fn fuel(mut self, fuel: u64) -> Self {
+    self.fuel = Some(fuel);
+    self
+  }
+
+  // when disabled, `*{a 11 b c}` reduces straight to `*{a c}` without looking at `b`
+  pub fn hints(mut self, enabled: bool) -> Self {
+    self.hints = enabled;
+    self
+  }
+
+  pub fn jets(mut self, enabled: bool) -> Self {
+    self.jets = enabled;
+    self
+  }
+
+  // runs `jet` in place of the arm at `axis` of cores a `%fast` hint labels
+  // `label`, like `k140/one/dec`; see src/jet.rs
+  pub fn jet(
+    mut self,
+    label: &str,
+    axis: u64,
+    jet: impl Fn(&Noun) -> Option<Noun> + Shareable + 'static,
+  ) -> Self {
+    self.dash.label(label, axis, Rc::new(jet));
+    self
+  }
+
+  // runs `jet` in place of the arm at `axis` of cores with this battery,
+  // labeled or not
+  pub fn jet_battery(
+    mut self,
+    battery: Noun,
+    axis: u64,
+    jet: impl Fn(&Noun) -> Option<Noun> + Shareable + 'static,
+  ) -> Self {
+    self.dash.bind(battery, axis, Rc::new(jet));
+    self
+  }
+
+  // counts calls to jetted arms and to arms of labeled cores, for `jet_stats`
+  pub fn profile_jets(mut self, enabled: bool) -> Self {
+    self.profile_jets = enabled;
+    self
+  }
+
+  // starts with the cores another interpreter registered, say from
+  // `Cold::from_noun`, and their batteries' jets
+  pub fn cold(mut self, cold: Cold) -> Self {
+    self.cold = cold;
+    self
+  }
+
+  // runs the formula of every jetted arm as well, crashing with
+  // `NockError::Diverged` when the jet's product is different
+  pub fn check_jets(mut self, enabled: bool) -> Self {
+    self.check_jets = enabled;
+    self
+  }
+
+  // builds autocons products with `Noun::cell_interned`, so equal ones share
+  // their cells
+  pub fn intern(mut self, enabled: bool) -> Self {
+    self.intern = enabled;
+    self
+  }
+
+  // runs each evaluation on a road of its own when `road::Road` is the
+  // global allocator
+  #[cfg(feature = "road")]
+  pub fn road(mut self, enabled: bool) -> Self {
+    self.road = enabled;
+    self
+  }
+
+  // runs the tails of autocons on the rayon pool when both sides make calls,
+  // see src/fork.rs
+  #[cfg(feature = "sync")]
+  pub fn parallel(mut self, enabled: bool) -> Self {
+    self.parallel = enabled;
+    self
+  }
+
+  // called with the subject and formula of every reduction step
+  pub fn trace(mut self, sink: impl FnMut(&Noun, &Noun) + Sendable + 'static) -> Self {
+    self.trace = Some(Box::new(sink));
+    self
+  }
+
+  // called with the clue of every `%slog` hint, which goes to stderr by default
+  pub fn slog(mut self, sink: impl FnMut(&Noun) + Sendable + 'static) -> Self {
+    self.slog = Box::new(sink);
+    self
+  }
+
+  // writes each `%slog` clue on its own line
+  pub fn slog_writer(mut self, out: impl Write + Sendable + 'static) -> Self {
+    self.slog = slog_to(out);
+    self
+  }
+
+  // handlers see every hint with an atom tag, in the order they were added,
+  // including the ones the interpreter handles itself
+  pub fn hint_handler(mut self, handler: impl HintHandler + 'static) -> Self {
+    self.handlers.push(Box::new(handler));
+    self
+  }
+
+  // answers `*{a 12 b c}` with the reference `*{a b}` and path `*{a c}`;
+  // None blocks the computation with `NockError::Blocked`
+  pub fn scry(
+    mut self,
+    handler: impl FnMut(&Noun, &Noun) -> Option<Noun> + Sendable + 'static,
+  ) -> Self {
+    self.scry = Some(Box::new(handler));
+    self
+  }
+
+  pub fn atom_formula(mut self, behavior: AtomFormula) -> Self {
+    self.atom_formula = behavior;
+    self
+  }
+
+  // runs every evaluation through `backend` too, like `spec::eval`, and fails
+  // with `NockError::Diverged` when the two disagree
+  pub fn shadow(
+    mut self,
+    backend: impl FnMut(&Noun, &Noun) -> Result<Noun, NockError> + Sendable + 'static,
+  ) -> Self {
+    self.shadow = Some(Box::new(backend));
+    self
+  }
+
+  // handles `*{a opcode b}` for an opcode the spec doesn't define, which
+  // crashes with `NockError::UnknownOpcode` otherwise
+  pub fn extension(
+    mut self,
+    opcode: u64,
+    handler: impl Fn(&Noun, &Noun, &mut Nock) -> Result<Noun, NockError> + Shareable + 'static,
+  ) -> Self {
+    assert!(
+      Opcode::try_from(Atom::new(opcode)).is_err(),
+      "opcode {opcode} is already defined"
+    );
+    self.extensions.insert(Atom::new(opcode), Rc::new(handler));
+    self
+  }
+
+  pub fn engine(mut self, engine: Engine) -> Self {
+    self.engine = engine;
+    self
+  }
+
+  // calls a program takes on the jit engine before it's compiled to native
+  // code, 16 by default; programs called on cores a `%fast` hint labeled are
+  // compiled on their first
+  #[cfg(feature = "jit")]
+  pub fn jit_after(mut self, calls: u32) -> Self {
+    self.jit_after = calls;
+    self
+  }
+
+  // remembers the products of opcode 2 and 9 calls across evaluations, up to
+  // `entries` of them taking about `bytes`, see src/cache.rs
+  pub fn eval_cache(mut self, entries: usize, bytes: usize) -> Self {
+    self.cache = Some(EvalCache::new(entries, bytes));
+    self
+  }
+
+  pub fn build(mut self) -> Interpreter {
+    self.dash.warm(&self.cold);
+    Interpreter {
+      depth_limit: self.depth_limit,
+      fuel: self.fuel,
+      hints: self.hints,
+      jets: self.jets,
+      check_jets: self.check_jets,
+      intern: self.intern,
+      #[cfg(feature = "road")]
+      road: self.road,
+      #[cfg(feature = "sync")]
+      parallel: self.parallel,
+      trace: self.trace,
+      slog: self.slog,
+      handlers: self.handlers,
+      scry: self.scry,
+      atom_formula: self.atom_formula,
+      shadow: self.shadow,
+      extensions: self.extensions,
+      engine: self.engine,
+      #[cfg(feature = "jit")]
+      jit_after: self.jit_after,
+      memo: HashMap::new(),
+      cache: self.cache,
+      scries: 0,
+      equals: Equals::default(),
+      cold: Rc::new(self.cold),
+      dash: Rc::new(self.dash),
+      jet_stats: self.profile_jets.then(HashMap::new),
+      bypass: 0,
+      compiled: HashMap::new(),
+      programs: HashMap::new(),
+      closures: HashMap::new(),
+      #[cfg(feature = "sync")]
+      calls: HashMap::new(),
+      #[cfg(feature = "sync")]
+      deferred: None,
+      #[cfg(feature = "sync")]
+      cancel: None,
+      depth: 0,
+      fuel_left: None,
+      frames: vec![],
+      edit_stack: vec![],
+    }
+  }
+}
+
+pub struct Interpreter {
+  depth_limit: Option<usize>,
+  fuel: Option<u64>,
+  pub(crate) hints: bool,
+  jets: bool,
+  check_jets: bool,
+  intern: bool,
+  #[cfg(feature = "road")]
+  road: bool,
+  #[cfg(feature = "sync")]
+  parallel: bool,
+  trace: Option<TraceSink>,
+  slog: SlogSink,
+  handlers: Vec<Box<dyn HintHandler>>,
+  scry: Option<ScryHandler>,
+  atom_formula: AtomFormula,
+  shadow: Option<Backend>,
+  extensions: HashMap<Atom, Extension>,
+  engine: Engine,
+  #[cfg(feature = "jit")]
+  pub(crate) jit_after: u32,
+
+  // products of `%memo` hinted formulas, keyed by `{subject formula}`
+  memo: HashMap<Noun, Noun>,
+  pub(crate) cache: Option<EvalCache>,
+  // scries made so far, a product that needed one isn't cached
+  scries: u64,
+  // cells opcode 5 found equal
+  equals: Equals,
+  // cores labeled by `%fast` hints, shared with forked tails until either
+  // registers a core
+  pub(crate) cold: Rc<Cold>,
+  // and the jets of their arms
+  pub(crate) dash: Rc<Dashboard>,
+  // `%dont` hints the computation is inside of, which run without jets
+  pub(crate) bypass: usize,
+  // by battery and axis, when profiling jets
+  jet_stats: Option<HashMap<(Noun, u64), JetStats>>,
+  // formulas met at run time by `eval_compiled`
+  pub(crate) compiled: HashMap<Noun, CompiledFormula>,
+  // bytecode for every formula the bytecode engine has run
+  pub(crate) programs: HashMap<Noun, Rc<Program>>,
+  // and closures for the closure engine
+  pub(crate) closures: HashMap<Noun, Rc<closure::Tail>>,
+  // whether formulas make calls, for forking autocons
+  #[cfg(feature = "sync")]
+  calls: HashMap<Noun, bool>,
+  // effects held back, when this runs a forked tail
+  #[cfg(feature = "sync")]
+  pub(crate) deferred: Option<Vec<Deferred>>,
+  // set once the forked tail this runs isn't wanted any more
+  #[cfg(feature = "sync")]
+  pub(crate) cancel: Option<Arc<AtomicBool>>,
+
+  pub(crate) depth: usize,
+  fuel_left: Option<u64>,
+
+  // scratch buffers, kept between evaluations
+  frames: Vec<Frame>,
+  edit_stack: Vec<(bool, Noun)>,
+}
+
+impl Default for Interpreter {
+  fn default() -> Self {
+    Self::builder().build()
+  }
+}
+
+impl Interpreter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn builder() -> InterpreterBuilder {
+    InterpreterBuilder::default()
+  }
+
+  pub fn hints(&self) -> bool {
+    self.hints
+  }
+
+  pub fn jets(&self) -> bool {
+    self.jets
+  }
+
+  pub fn intern(&self) -> bool {
+    self.intern
+  }
+
+  pub fn engine(&self) -> Engine {
+    self.engine
+  }
+
+  pub fn cold(&self) -> &Cold {
+    &self.cold
+  }
+
+  // what calls to jetted arms and arms of labeled cores did so far, the most
+  // called first; empty unless built with `profile_jets`
+  pub fn jet_stats(&self) -> Vec<JetStats> {
+    let mut stats: Vec<_> = self
+      .jet_stats
+      .iter()
+      .flat_map(HashMap::values)
+      .cloned()
+      .collect();
+    for stats in &mut stats {
+      stats.label = self.cold.get(&stats.battery).map(|reg| reg.label.clone());
+    }
+    stats.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.label.cmp(&b.label)));
+    stats
+  }
+
+  // drops every product remembered through `%memo` hints
+  pub fn clear_memo(&mut self) {
+    self.memo.clear();
+  }
+
+  // drops every product in the eval cache
+  pub fn clear_eval_cache(&mut self) {
+    if let Some(cache) = &mut self.cache {
+      cache.clear();
+    }
+  }
+
+  pub fn eval(&mut self, subject: Subject, formula: Formula) -> Result<Noun, NockError> {
+    #[cfg(feature = "road")]
+    let road = self.road.then(crate::road::enter).flatten();
+    self.reset();
+
+    let prod = match self.engine {
+      Engine::Tree => self.nock(subject.0.clone(), formula.0.clone()),
+      Engine::Closure => self.run_closure(subject.0.clone(), formula.0.clone()),
+      Engine::Bytecode => self.vm(subject.0.clone(), formula.0.clone()),
+      #[cfg(feature = "jit")]
+      Engine::Jit => self.vm(subject.0.clone(), formula.0.clone()),
+    };
+    let prod = self.shadowed(&subject.0, formula.0, prod);
+
+    #[cfg(feature = "road")]
+    if let Some(road) = road {
+      return prod.map(|prod| road.leave(prod));
+    }
+    prod
+  }
+
+  // limits are per evaluation
+  pub(crate) fn reset(&mut self) {
+    self.depth = 0;
+    self.bypass = 0;
+    self.fuel_left = self.fuel;
+  }
+
+  // checks `prod` against the shadow backend, if there is one
+  pub(crate) fn shadowed(
+    &mut self,
+    subject: &Noun,
+    formula: Noun,
+    prod: Result<Noun, NockError>,
+  ) -> Result<Noun, NockError> {
+    // nothing to compare when the interpreter gave up or was blocked, and the
+    // shadow might never finish
+    let Some(shadow) = &mut self.shadow else {
+      return prod;
+    };
+    if prod.as_ref().is_err_and(|e| !e.is_deterministic()) {
+      return prod;
+    }
+
+    let divergence = match (&prod, shadow(subject, &formula)) {
+      (Ok(prod), Ok(shadowed)) => noun_diff(prod, &shadowed)
+        .into_iter()
+        .next()
+        .map(|divergence| Some(Box::new(divergence))),
+      (Ok(_), Err(_)) | (Err(_), Ok(_)) => Some(None),
+      (Err(_), Err(_)) => None,
+    };
+
+    if let Some(divergence) = divergence {
+      return Err(NockError::Diverged {
+        formula,
+        divergence,
+      });
+    }
+
+    prod
+  }
+
+  // like `eval`, but blocking and crashing are results rather than errors
+  pub fn eval_soft(&mut self, subject: Subject, formula: Formula) -> SoftResult {
+    match self.eval(subject, formula) {
+      Ok(prod) => SoftResult::Done(prod),
+      Err(error) => match error.root() {
+        NockError::Blocked { path } => SoftResult::Blocked(path.clone()),
+        _ => match error.into_crash() {
+          Ok(crash) => SoftResult::Crashed(crash),
+          Err(error) => SoftResult::Failed(error),
+        },
+      },
+    }
+  }
+
+  // every nock call starts here, before it counts towards the depth
+  pub(crate) fn enter(&mut self, subj: &Noun, form: &Noun) -> Result<(), NockError> {
+    self.burn()?;
+
+    if self.depth_limit.is_some_and(|limit| self.depth >= limit) {
+      return Err(NockError::DepthLimit);
+    }
+
+    if let Some(trace) = &mut self.trace {
+      trace(subj, form);
+    }
+
+    self.depth += 1;
+    Ok(())
+  }
+
+  fn burn(&mut self) -> Result<(), NockError> {
+    #[cfg(feature = "sync")]
+    if self
+      .cancel
+      .as_ref()
+      .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    {
+      return Err(NockError::Cancelled);
+    }
+
+    if let Some(fuel) = &mut self.fuel_left {
+      if *fuel == 0 {
+        return Err(NockError::OutOfFuel);
+      }
+      *fuel -= 1;
+    }
+
+    Ok(())
+  }
+
+  // runs on `frames` rather than the native stack, so how deep a computation
+  // goes is bounded by memory and `depth_limit`, not by the thread's stack
+  fn nock(&mut self, subj: Noun, form: Noun) -> Result<Noun, NockError> {
+    // extensions evaluate through here while an outer run is in progress
+    let base = self.frames.len();
+    let (depth, bypass) = (self.depth, self.bypass);
+    let mut next = Next::Call(subj, form);
+
+    loop {
+      let step = match next {
+        Next::Call(subj, form) => self.call(subj, form),
+        // the call being replaced would have wrapped errors in its opcode
+        Next::Tail(opcode, subj, form) => match self.tail_cached(opcode, &subj, &form, base) {
+          Some(prod) => Ok(Next::Ret(prod)),
+          None => self.call(subj, form).map_err(|e| e.in_opcode(opcode)),
+        },
+        Next::Ret(prod) if self.frames.len() == base => return Ok(prod),
+        Next::Ret(prod) => {
+          let frame = self.frames.pop().unwrap();
+          let opcode = frame.opcode();
+          self.resume(frame, prod).map_err(|e| match opcode {
+            Some(opcode) => e.in_opcode(opcode),
+            None => e,
+          })
+        }
+      };
+
+      next = match step {
+        Ok(next) => next,
+        Err(mut e) => {
+          while self.frames.len() > base {
+            e = self.frames.pop().unwrap().unwind(e);
+          }
+          self.depth = depth;
+          self.bypass = bypass;
+          return Err(e);
+        }
+      };
+    }
+  }
+
+  // a nock call that produces `prod` without waiting on any other
+  fn done(&mut self, prod: Noun) -> Next {
+    self.depth -= 1;
+    Next::Ret(prod)
+  }
+
+  // carries on with `frame` once `*{subj form}` is known
+  fn then(&mut self, frame: Frame, subj: Noun, form: Noun) -> Next {
+    self.frames.push(frame);
+    Next::Call(subj, form)
+  }
+
+  // a nock call whose product is that of `*{subj form}`, which takes its place
+  // instead of nesting under it
+  fn tail(&mut self, opcode: Opcode, subj: Noun, form: Noun) -> Next {
+    self.depth -= 1;
+    Next::Tail(opcode, subj, form)
+  }
+
+  // the product of an opcode 2 or 9 tail call from the eval cache, or else a
+  // frame to cache it from
+  fn tail_cached(&mut self, opcode: Opcode, subj: &Noun, form: &Noun, base: usize) -> Option<Noun> {
+    if !matches!(opcode, Opcode::Eval | Opcode::Invk) {
+      return None;
+    }
+    let cache = self.cache.as_mut()?;
+    if let Some(prod) = cache.get(subj, form) {
+      return Some(prod);
+    }
+
+    // calls in tail position of each other have the same product
+    if !matches!(self.frames.last(), Some(Frame::Cache { .. }) if self.frames.len() > base) {
+      self.frames.push(Frame::Cache { calls: vec![] });
+    }
+    if let Some(Frame::Cache { calls }) = self.frames.last_mut() {
+      cache.wait(calls, subj, form, self.scries);
+    }
+    None
+  }
+
+  fn call(&mut self, subj: Noun, form: Noun) -> Result<Next, NockError> {
+    self.enter(&subj, &form)?;
+
+    let Some((head, b)) = form.as_cell() else {
+      return Err(self.atom_formula(form));
+    };
+
+    // autocons, with the head reused as a formula as it is
+    let Some(inst) = head.as_atom() else {
+      #[cfg(feature = "sync")]
+      if self.forks(head, b) {
+        let fork = fork::fork(self.settings(), subj.clone(), b.clone());
+        let frame = Frame::Fork {
+          subj: subj.clone(),
+          tail: b.clone(),
+          fork,
+        };
+        return Ok(self.then(frame, subj, head.clone()));
+      }
+
+      let head = head.clone();
+      let frame = Frame::Head {
+        subj: subj.clone(),
+        tail: b.clone(),
+      };
+      return Ok(self.then(frame, subj, head));
+    };
+
+    let opcode = match Opcode::try_from(inst.clone()) {
+      Ok(opcode) => opcode,
+      Err(e) => match self.extension(inst, &subj, b) {
+        Some(prod) => return Ok(self.done(prod?)),
+        None => return Err(e),
+      },
+    };
+
+    self
+      .opcode(opcode, subj, b)
+      .map_err(|e| e.in_opcode(opcode))
+  }
+
+  fn opcode(&mut self, opcode: Opcode, subj: Noun, form: &Noun) -> Result<Next, NockError> {
+    let next = match opcode {
+      Opcode::Addr => {
+        let prod = subj.slot_atom(form.try_atom()?)?;
+        self.done(prod)
+      }
+      Opcode::Idty => self.done(form.clone()),
+      Opcode::Eval => {
+        let (b, c) = form.try_cell()?;
+        let frame = Frame::EvalSubject {
+          subj: subj.clone(),
+          c: c.clone(),
+        };
+        self.then(frame, subj, b.clone())
+      }
+      Opcode::Cell => self.then(Frame::Cell, subj, form.clone()),
+      Opcode::Incr => self.then(Frame::Incr, subj, form.clone()),
+      Opcode::Eqal => {
+        let (b, c) = form.try_cell()?;
+        let frame = Frame::EqalLeft {
+          subj: subj.clone(),
+          c: c.clone(),
+        };
+        self.then(frame, subj, b.clone())
+      }
+      Opcode::Brch => {
+        let (b, c, d) = noun_match!(form,
+          {b, {c, d}} => (b.clone(), c.clone(), d.clone()),
+          _ => return Err(NockError::CellRequired { found: form.clone() }),
+        );
+        let frame = Frame::BrchCond {
+          subj: subj.clone(),
+          c,
+          d,
+        };
+        self.then(frame, subj, b)
+      }
+      Opcode::Cmps => {
+        let (b, c) = form.try_cell()?;
+        self.then(Frame::Cmps { c: c.clone() }, subj, b.clone())
+      }
+      Opcode::Extn => {
+        let (b, c) = form.try_cell()?;
+        let frame = Frame::Extn {
+          subj: subj.clone(),
+          c: c.clone(),
+        };
+        self.then(frame, subj, b.clone())
+      }
+      Opcode::Invk => {
+        let (b, c) = form.try_cell()?;
+        self.then(Frame::Invk { b: b.clone() }, subj, c.clone())
+      }
+      Opcode::Rplc => {
+        let (b, c, d) = noun_match!(form,
+          {{b, c}, d} => (b.try_atom()?.clone(), c.clone(), d.clone()),
+          _ => return Err(NockError::CellRequired { found: form.clone() }),
+        );
+        let frame = Frame::RplcValue {
+          subj: subj.clone(),
+          b,
+          d,
+        };
+        self.then(frame, subj, c)
+      }
+      Opcode::Hint => {
+        let (b, c) = form.try_cell()?;
+
+        if !self.hints {
+          return Ok(self.tail(opcode, subj, c.clone()));
+        }
+
+        // *{a 11 {b c} d} is *{{*{a c} *{a d}} 0 3}: the clue's product is
+        // thrown away, but a crash in it is still a crash
+        match b.as_cell() {
+          Some((tag, clue)) => {
+            let frame = Frame::HintClue {
+              subj: subj.clone(),
+              tag: tag.clone(),
+              c: c.clone(),
+            };
+            self.then(frame, subj, clue.clone())
+          }
+          None => self.hint(subj, b.clone(), None, c.clone()),
+        }
+      }
+      Opcode::Scry => {
+        let (b, c) = form.try_cell()?;
+        let frame = Frame::ScryRef {
+          subj: subj.clone(),
+          c: c.clone(),
+        };
+        self.then(frame, subj, b.clone())
+      }
+    };
+
+    Ok(next)
+  }
+
+  fn hint(&mut self, subj: Noun, tag: Noun, clue: Option<Noun>, c: Noun) -> Next {
+    match self.hint_effect(&subj, &tag, clue, &c) {
+      Hinted::Run => self.tail(Opcode::Hint, subj, c),
+      Hinted::Fast(clue) => self.then(Frame::Fast { clue }, subj, c),
+      Hinted::Spot(clue) => self.then(Frame::Spot { clue }, subj, c),
+      Hinted::Memo(key) => self.then(Frame::Memo { key }, subj, c),
+      Hinted::Memoized(prod) => self.done(prod),
+      Hinted::Dont => self.then(Frame::Dont, subj, c),
+    }
+  }
+
+  // tells the handlers about a hint and does what it asks for up front
+  pub(crate) fn hint_effect(
+    &mut self,
+    subj: &Noun,
+    tag: &Noun,
+    clue: Option<Noun>,
+    c: &Noun,
+  ) -> Hinted {
+    if let Some(tag) = tag.as_atom() {
+      for handler in &mut self.handlers {
+        handler.hint(tag.clone(), clue.clone(), subj, c);
+      }
+    }
+
+    match (tag.as_atom().and_then(Atom::as_u64), clue) {
+      (Some(SLOG), Some(clue)) => {
+        self.emit_slog(clue);
+        Hinted::Run
+      }
+      (Some(FAST), Some(clue)) => Hinted::Fast(clue),
+      (Some(DONT), _) => {
+        self.bypass += 1;
+        Hinted::Dont
+      }
+      (Some(SPOT | MEAN), Some(clue)) => Hinted::Spot(clue),
+      (Some(MEMO), _) => {
+        let key = Noun::cell(subj.clone(), c.clone());
+        match self.memo.get(&key) {
+          Some(prod) => Hinted::Memoized(prod.clone()),
+          None => Hinted::Memo(key),
+        }
+      }
+      _ => Hinted::Run,
+    }
+  }
+
+  fn emit_slog(&mut self, clue: Noun) {
+    #[cfg(feature = "sync")]
+    if let Some(deferred) = &mut self.deferred {
+      deferred.push(Deferred::Slog(clue));
+      return;
+    }
+
+    (self.slog)(&clue);
+  }
+
+  // the product of a `%fast` hinted formula
+  pub(crate) fn register(&mut self, clue: &Noun, core: &Noun) {
+    #[cfg(feature = "sync")]
+    if let Some(deferred) = &mut self.deferred {
+      deferred.push(Deferred::Fast(clue.clone(), core.clone()));
+    }
+
+    let Some(registration) = self.cold.registration(clue, core) else {
+      return;
+    };
+    if self.cold.get(&registration.battery) == Some(&registration) {
+      return;
+    }
+    Rc::make_mut(&mut self.dash).registered(&registration.label, &registration.battery);
+    Rc::make_mut(&mut self.cold).register(clue, core);
+  }
+
+  // the product of the arm at `axis` of `core` from its jet, None when there's
+  // no jet or it gives up
+  pub(crate) fn run_jet(&mut self, core: &Noun, axis: &Noun) -> Result<Option<Noun>, NockError> {
+    if !self.jets || self.bypass > 0 {
+      return Ok(None);
+    }
+    let Some(axis) = axis.as_atom().and_then(Atom::as_u64) else {
+      return Ok(None);
+    };
+    let jet = self.dash.find(core, axis);
+    let start = self.jet_stats.is_some().then(Instant::now);
+    let prod = jet.as_ref().and_then(|jet| jet(core));
+    if let Some(start) = start {
+      self.profile(core, axis, jet.is_some(), prod.is_some(), start);
+    }
+    let Some(prod) = prod else {
+      return Ok(None);
+    };
+    if !self.check_jets {
+      return Ok(Some(prod));
+    }
+
+    // without jets, or every step of a recursive arm would be checked again
+    let arm = core.slot(axis).map_err(|e| e.in_opcode(Opcode::Addr))?;
+    self.bypass += 1;
+    let pure = self.nock(core.clone(), arm.clone());
+    self.bypass -= 1;
+    let divergence = match pure {
+      Ok(pure) => noun_diff(&prod, &pure)
+        .into_iter()
+        .next()
+        .map(|divergence| Some(Box::new(divergence))),
+      Err(e) if e.is_deterministic() => Some(None),
+      Err(e) => return Err(e),
+    };
+
+    match divergence {
+      Some(divergence) => Err(NockError::Diverged {
+        formula: arm,
+        divergence,
+      }),
+      None => Ok(Some(prod)),
+    }
+  }
+
+  fn profile(&mut self, core: &Noun, axis: u64, jetted: bool, hit: bool, start: Instant) {
+    let time = start.elapsed();
+    let Some((battery, _)) = core.as_cell() else {
+      return;
+    };
+    if !jetted && self.cold.get(battery).is_none() {
+      return;
+    }
+
+    let stats = self.jet_stats.as_mut().unwrap();
+    let stats = stats
+      .entry((battery.clone(), axis))
+      .or_insert_with(|| JetStats {
+        battery: battery.clone(),
+        axis,
+        label: None,
+        jetted,
+        calls: 0,
+        punts: 0,
+        time: Default::default(),
+      });
+    stats.jetted |= jetted;
+    stats.calls += 1;
+    stats.punts += u64::from(!hit);
+    if jetted {
+      stats.time += time;
+    }
+  }
+
+  // whether to run the tail of an autocons on the pool
+  #[cfg(feature = "sync")]
+  fn forks(&mut self, head: &Noun, tail: &Noun) -> bool {
+    if !self.parallel
+      || self.fuel.is_some()
+      || self.trace.is_some()
+      || !self.handlers.is_empty()
+      || self.jet_stats.is_some()
+    {
+      return false;
+    }
+
+    let mut calls = |formula: &Noun| {
+      *self
+        .calls
+        .entry(formula.clone())
+        .or_insert_with(|| fork::calls(formula))
+    };
+    calls(head) && calls(tail)
+  }
+
+  // for the interpreter of a forked tail, which can only go as deep as this
+  // one has left
+  #[cfg(feature = "sync")]
+  fn settings(&self) -> fork::Settings {
+    fork::Settings {
+      depth_limit: self
+        .depth_limit
+        .map(|limit| limit.saturating_sub(self.depth)),
+      hints: self.hints,
+      jets: self.jets && self.bypass == 0,
+      check_jets: self.check_jets,
+      intern: self.intern,
+      atom_formula: self.atom_formula,
+      cold: self.cold.clone(),
+      dash: self.dash.clone(),
+    }
+  }
+
+  // the product of a `%memo` hinted formula
+  pub(crate) fn remember(&mut self, key: Noun, prod: &Noun) {
+    #[cfg(feature = "sync")]
+    if let Some(deferred) = &mut self.deferred {
+      deferred.push(Deferred::Memo(key.clone(), prod.clone()));
+    }
+
+    self.memo.insert(key, prod.clone());
+  }
+
+  // the product of `*{subj formula}`, if the eval cache has it
+  pub(crate) fn cached(&mut self, subj: &Noun, formula: &Noun) -> Option<Noun> {
+    self.cache.as_mut()?.get(subj, formula)
+  }
+
+  // adds `*{subj formula}` to calls waiting on the same product, when there's
+  // an eval cache
+  pub(crate) fn wait(&self, calls: &mut Vec<Pending>, subj: &Noun, formula: &Noun) {
+    if let Some(cache) = &self.cache {
+      cache.wait(calls, subj, formula, self.scries);
+    }
+  }
+
+  pub(crate) fn cache(&mut self, calls: Vec<Pending>, prod: &Noun) {
+    let Some(cache) = &mut self.cache else {
+      return;
+    };
+    // a scry could answer differently next time
+    for call in calls {
+      if call.scries() == self.scries {
+        cache.insert(call, prod);
+      }
+    }
+  }
+
+  // the product of an autocons
+  pub(crate) fn cons(&self, head: Noun, tail: Noun) -> Noun {
+    if self.intern {
+      Noun::cell_interned(head, tail)
+    } else {
+      Noun::cell(head, tail)
+    }
+  }
+
+  pub(crate) fn eq(&mut self, a: &Noun, b: &Noun) -> bool {
+    self.equals.eq(a, b)
+  }
+
+  pub(crate) fn edit(
+    &mut self,
+    target: &Noun,
+    axis: &Atom,
+    value: Noun,
+  ) -> Result<Noun, NockError> {
+    edit_in(&mut self.edit_stack, target, axis, value)
+  }
+
+  pub(crate) fn scry_at(&mut self, reference: &Noun, path: Noun) -> Result<Noun, NockError> {
+    self.scries += 1;
+    self
+      .scry
+      .as_mut()
+      .and_then(|scry| scry(reference, &path))
+      .ok_or(NockError::Blocked { path })
+  }
+
+  // runs the extension for `opcode`, None if there isn't one
+  pub(crate) fn extension(
+    &mut self,
+    opcode: &Atom,
+    subj: &Noun,
+    arg: &Noun,
+  ) -> Option<Result<Noun, NockError>> {
+    let extension = self.extensions.get(opcode)?.clone();
+    Some(extension(subj, arg, &mut |subj, form| {
+      self.nock(subj, form)
+    }))
+  }
+
+  fn resume(&mut self, frame: Frame, prod: Noun) -> Result<Next, NockError> {
+    let next = match frame {
+      Frame::Head { subj, tail } => self.then(Frame::Tail { head: prod }, subj, tail),
+      Frame::Tail { head } => {
+        let prod = self.cons(head, prod);
+        self.done(prod)
+      }
+      #[cfg(feature = "sync")]
+      Frame::Fork { subj, tail, fork } => match fork.join() {
+        Some((tail, deferred)) => {
+          for effect in deferred {
+            match effect {
+              Deferred::Slog(clue) => self.emit_slog(clue),
+              Deferred::Fast(clue, core) => self.register(&clue, &core),
+              Deferred::Memo(key, prod) => self.remember(key, &prod),
+            }
+          }
+          let prod = self.cons(prod, tail);
+          self.done(prod)
+        }
+        None => self.then(Frame::Tail { head: prod }, subj, tail),
+      },
+      Frame::EvalSubject { subj, c } => self.then(Frame::EvalFormula { subj: prod }, subj, c),
+      Frame::EvalFormula { subj } => self.tail(Opcode::Eval, subj, prod),
+      Frame::Cell => self.done(Noun::from(prod.is_cell())),
+      Frame::Incr => {
+        let prod = Noun::atom(prod.try_atom()?.incr());
+        self.done(prod)
+      }
+      Frame::EqalLeft { subj, c } => self.then(Frame::EqalRight { left: prod }, subj, c),
+      Frame::EqalRight { left } => {
+        let eq = self.eq(&left, &prod);
+        self.done(Noun::from(eq))
+      }
+      // the spec picks the branch with `*{{c d} 0 *{{2 3} 0 *{a 4 4 b}}}`,
+      // which src/spec.rs keeps to, but that's the same as picking it here,
+      // and a crash there would be far from the actual mistake
+      Frame::BrchCond { subj, c, d } => match prod.as_atom().and_then(Atom::as_u64) {
+        Some(YES) => self.tail(Opcode::Brch, subj, c),
+        Some(NAH) => self.tail(Opcode::Brch, subj, d),
+        _ => return Err(NockError::NonLoobeanCondition { value: prod }),
+      },
+      Frame::Cmps { c } => self.tail(Opcode::Cmps, prod, c),
+      Frame::Extn { subj, c } => self.tail(Opcode::Extn, Noun::cell(prod, subj), c),
+      Frame::Invk { b } => {
+        if let Some(prod) = self.run_jet(&prod, &b)? {
+          return Ok(self.done(prod));
+        }
+        // `*{core 2 {0 1} 0 b}` is the arm at `b` on the core, and errors
+        // pulling it out are in the `{0 b}` it's pulled out with
+        let arm = b
+          .try_atom()
+          .and_then(|axis| prod.slot_atom(axis))
+          .map_err(|e| e.in_opcode(Opcode::Addr))?;
+        self.tail(Opcode::Invk, prod, arm)
+      }
+      Frame::RplcValue { subj, b, d } => self.then(Frame::RplcTarget { b, value: prod }, subj, d),
+      Frame::RplcTarget { b, value } => {
+        let prod = self.edit(&prod, &b, value)?;
+        self.done(prod)
+      }
+      Frame::HintClue { subj, tag, c } => self.hint(subj, tag, Some(prod), c),
+      Frame::Fast { clue } => {
+        self.register(&clue, &prod);
+        self.done(prod)
+      }
+      Frame::Spot { .. } => self.done(prod),
+      Frame::Memo { key } => {
+        self.remember(key, &prod);
+        self.done(prod)
+      }
+      Frame::Dont => {
+        self.bypass -= 1;
+        self.done(prod)
+      }
+      Frame::Cache { calls } => {
+        self.cache(calls, &prod);
+        Next::Ret(prod)
+      }
+      Frame::ScryRef { subj, c } => self.then(Frame::ScryPath { reference: prod }, subj, c),
+      Frame::ScryPath { reference } => {
+        let prod = self.scry_at(&reference, prod)?;
+        self.done(prod)
+      }
+    };
+
+    Ok(next)
+  }
+
+  // `*{a b}` for an atom `b`, which never produces anything
+  pub(crate) fn atom_formula(&mut self, form: Noun) -> NockError {
+    match self.atom_formula {
+      AtomFormula::Exit => NockError::Exit { formula: form },
+      AtomFormula::Loop => loop {
+        if let Err(e) = self.burn() {
+          return e;
+        }
+      },
+    }
+  }
+}
+
+// what a hint asks for before its formula runs
+pub(crate) enum Hinted {
+  Run,
+  Fast(Noun),
+  Spot(Noun),
+  // the product goes in the memo table under this key
+  Memo(Noun),
+  Memoized(Noun),
+  // jets are off until the body's product comes back
+  Dont,
+}
+
+enum Next {
+  // evaluate `*{subject formula}`
+  Call(Noun, Noun),
+  // evaluate it in place of a call to this opcode
+  Tail(Opcode, Noun, Noun),
+  // hand a product to the frame on top
+  Ret(Noun),
+}
+
+// what's left of a nock call while it waits on one of its sub-computations
+enum Frame {
+  Head {
+    subj: Noun,
+    tail: Noun,
+  },
+  Tail {
+    head: Noun,
+  },
+  // the head, with the tail running on the pool
+  #[cfg(feature = "sync")]
+  Fork {
+    subj: Noun,
+    tail: Noun,
+    fork: Fork,
+  },
+  EvalSubject {
+    subj: Noun,
+    c: Noun,
+  },
+  EvalFormula {
+    subj: Noun,
+  },
+  Cell,
+  Incr,
+  EqalLeft {
+    subj: Noun,
+    c: Noun,
+  },
+  EqalRight {
+    left: Noun,
+  },
+  BrchCond {
+    subj: Noun,
+    c: Noun,
+    d: Noun,
+  },
+  Cmps {
+    c: Noun,
+  },
+  Extn {
+    subj: Noun,
+    c: Noun,
+  },
+  Invk {
+    b: Noun,
+  },
+  RplcValue {
+    subj: Noun,
+    b: Atom,
+    d: Noun,
+  },
+  RplcTarget {
+    b: Atom,
+    value: Noun,
+  },
+  HintClue {
+    subj: Noun,
+    tag: Noun,
+    c: Noun,
+  },
+  Fast {
+    clue: Noun,
+  },
+  Spot {
+    clue: Noun,
+  },
+  Memo {
+    key: Noun,
+  },
+  Dont,
+  // tail calls waiting on their product, which isn't a call of its own
+  Cache {
+    calls: Vec<Pending>,
+  },
+  ScryRef {
+    subj: Noun,
+    c: Noun,
+  },
+  ScryPath {
+    reference: Noun,
+  },
+}
+
+impl Frame {
+  // the opcode of the waiting call, None for an autocons or the cache
+  fn opcode(&self) -> Option<Opcode> {
+    let opcode = match self {
+      Self::Head { .. } | Self::Tail { .. } | Self::Cache { .. } => return None,
+      #[cfg(feature = "sync")]
+      Self::Fork { .. } => return None,
+      Self::EvalSubject { .. } | Self::EvalFormula { .. } => Opcode::Eval,
+      Self::Cell => Opcode::Cell,
+      Self::Incr => Opcode::Incr,
+      Self::EqalLeft { .. } | Self::EqalRight { .. } => Opcode::Eqal,
+      Self::BrchCond { .. } => Opcode::Brch,
+      Self::Cmps { .. } => Opcode::Cmps,
+      Self::Extn { .. } => Opcode::Extn,
+      Self::Invk { .. } => Opcode::Invk,
+      Self::RplcValue { .. } | Self::RplcTarget { .. } => Opcode::Rplc,
+      Self::HintClue { .. }
+      | Self::Fast { .. }
+      | Self::Spot { .. }
+      | Self::Memo { .. }
+      | Self::Dont => Opcode::Hint,
+      Self::ScryRef { .. } | Self::ScryPath { .. } => Opcode::Scry,
+    };
+
+    Some(opcode)
+  }
+
+  // what an error passing through the waiting call becomes
+  fn unwind(self, error: NockError) -> NockError {
+    match self {
+      Self::Spot { clue } => error.traced(clue),
+      frame => match frame.opcode() {
+        Some(opcode) => error.in_opcode(opcode),
+        None => error,
+      },
+    }
+  }
+}
+
+// hint tags are short cords, which fit in a word
+const fn tag(name: &str) -> u64 {
+  let bytes = name.as_bytes();
+  let mut word = 0;
+  let mut i = bytes.len();
+  while i > 0 {
+    i -= 1;
+    word = word << 8 | bytes[i] as u64;
+  }
+  word
+}
+
+pub(crate) const DONT: u64 = tag("dont");
+pub(crate) const FAST: u64 = tag("fast");
+pub(crate) const MEAN: u64 = tag("mean");
+pub(crate) const MEMO: u64 = tag("memo");
+const SLOG: u64 = tag("slog");
+pub(crate) const SPOT: u64 = tag("spot");
+
+#[cfg(test)]
+mod test {
+  use super::{Formula, Interpreter, Subject};
+  use crate::atom::{NAH, YES};
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    error::NockError,
+    noun::{Noun, noun_eq},
+    opcode::Opcode,
+  };
+
+  fn nock(noun: Noun) -> Result<Noun, NockError> {
+    let (subject, formula) = noun.try_cell()?;
+    Interpreter::new().eval(Subject(subject.clone()), Formula(formula.clone()))
+  }
+
+  #[test]
+  fn test_addr() {
+    let a = noun!({{{{8, 42}, 5}, 2}, {addr, 9}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::atom(Atom::new(42));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_incr() {
+    let a = noun!({40, {incr, {incr, {addr, 1}}}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::atom(Atom::new(42));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_eval() {
+    let a = noun!({41, {eval, {{incr, {addr, 1}}, {idty, {addr, 1}}}}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::atom(Atom::new(42));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_brch_yes() {
+    let a = noun!({YES, {brch, {{addr, 1}, {{idty, 99}, {idty, 42}}}}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::atom(Atom::new(99));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_brch_nah() {
+    let a = noun!({NAH, {brch, {{addr, 1}, {{idty, 99}, {idty, 42}}}}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::atom(Atom::new(42));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_brch_non_loobean() {
+    for cond in [noun!(2), noun!({0, 1})] {
+      let a = Noun::cell(
+        cond.clone(),
+        noun!({brch, {{addr, 1}, {{idty, 99}, {idty, 42}}}}),
+      );
+
+      let e = nock(a).unwrap_err();
+      assert!(matches!(
+        e,
+        NockError::InOpcode {
+          opcode: Opcode::Brch,
+          ..
+        }
+      ));
+      assert!(matches!(e.root(), NockError::NonLoobeanCondition { value } if *value == cond));
+    }
+
+    let e = nock(noun!({7, {brch, {{addr, 1}, {{idty, 0}, {idty, 1}}}}})).unwrap_err();
+    assert_eq!(
+      e.to_string(),
+      "opcode 6: branch condition must be 0 or 1 but was 7"
+    );
+  }
+
+  #[test]
+  fn test_cmps() {
+    // compose is like eval when quoting 'c'
+    let a = noun!({41, {cmps, {{incr, {addr, 1}}, {addr, 1}}}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::atom(Atom::new(42));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_extn() {
+    let a = noun!({42, {extn, {{incr, {addr, 1}}, {addr, 1}}}});
+
+    let p = nock(a).unwrap();
+    let e = Noun::cell(Noun::atom(Atom::new(43)), Noun::atom(Atom::new(42)));
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_rplc() {
+    let t = noun!({{22, {89, 78}}, 44});
+    let r = t.edit(10, Noun::atom(Atom::new(55))).unwrap();
+    let e = noun!({{22, {55, 78}}, 44});
+
+    assert!(noun_eq(r, e));
+
+    let s = Subject(t.clone());
+    let rplc = |axis: Noun| Formula(noun!({rplc, {{(axis), {idty, 0}}, {addr, 1}}}));
+
+    let p = crate::eval(s.clone(), rplc(noun!(1))).unwrap();
+    assert!(noun_eq(p, noun!(0)));
+
+    let e = crate::eval(s.clone(), rplc(noun!(0))).unwrap_err();
+    assert!(matches!(e.root(), NockError::ZeroAxis));
+    let e = crate::eval(s.clone(), rplc(noun!(12))).unwrap_err();
+    assert!(matches!(e.root(), NockError::AxisOutOfTree { axis } if *axis == Atom::new(12)));
+    let e = crate::eval(s.clone(), rplc(noun!({1, 2}))).unwrap_err();
+    assert!(matches!(e.root(), NockError::AtomRequired { .. }));
+    let e = crate::eval(s.clone(), Formula(noun!({rplc, {5, {addr, 1}}}))).unwrap_err();
+    assert!(matches!(e.root(), NockError::CellRequired { .. }));
+
+    // the deepest leaf of a tree past 64 levels
+    let mut deep = noun!(7);
+    for _ in 0..70 {
+      deep = Noun::cell(noun!(0), deep);
+    }
+    let axis = Atom::from_words(&[u64::MAX, 0x7f]);
+    let p = crate::eval(Subject(deep), rplc(Noun::atom(axis.clone()))).unwrap();
+    assert!(noun_eq(p.slot_atom(&axis).unwrap(), noun!(0)));
+    assert!(noun_eq(
+      p.slot_atom(&Atom::from_words(&[u64::MAX, 0x3f])).unwrap(),
+      noun!({0, 0})
+    ));
+  }
+
+  #[test]
+  fn test_decr() {
+    // fn(a) {
+    //   let mut b = 0;
+    //   'trap: loop {
+    //     if +b = a {
+    //       return b;
+    //     } else {
+    //       b = +b;
+    //       continue 'trap;
+    //     }
+    //   }
+    // }
+    //
+    // core = [bat pay]
+    // where pay = [b a]
+    // and bat = loop
+
+    let test = noun!({eqal, {{addr, 7}, {incr, {addr, 6}}}});
+    let yes = noun!({addr, 6});
+    let new_core = noun!({{addr, 2}, {{incr, {addr, 6}}, {addr, 7}}});
+    let nah = Noun::cell(noun!(invk), Noun::cell(noun!(2), new_core));
+    let r#loop = Noun::cell(noun!(brch), Noun::cell(test, Noun::cell(yes, nah)));
+    let r#loop = Noun::cell(noun!(idty), r#loop);
+    let g = Noun::cell(
+      noun!(extn),
+      Noun::cell(
+        Noun::cell(noun!(idty), noun!(0)),
+        Noun::cell(
+          noun!(extn),
+          Noun::cell(r#loop, noun!({invk, {2, {addr, 1}}})),
+        ),
+      ),
+    );
+
+    let p = nock(Noun::cell(noun!(43), g.clone())).unwrap();
+    assert!(noun_eq(p, noun!(42)));
+
+    // `{9 2 ...}` is a tail call, so the loop doesn't nest any deeper
+    let mut it = Interpreter::builder().depth_limit(16).build();
+    let p = it.eval(Subject(noun!(100_000)), Formula(g)).unwrap();
+    assert!(noun_eq(p, noun!(99_999)));
+  }
+
+  #[test]
+  fn test_wide_autocons() {
+    // `{{0 1} {{0 1} ... {1 0}}}`, a list of the subject 100 000 times
+    let wide = (0..100_000).fold(noun!({idty, 0}), |f, _| Noun::cell(noun!({addr, 1}), f));
+    let p = nock(Noun::cell(noun!(7), wide)).unwrap();
+
+    let items = p.iter_list().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(items.len(), 100_000);
+    assert!(items.iter().all(|item| **item == noun!(7)));
+  }
+
+  #[test]
+  fn test_deep_formula() {
+    let deep = (0..100_000).fold(noun!({addr, 1}), |f, _| Noun::cell(noun!(incr), f));
+    let p = nock(Noun::cell(noun!(0), deep)).unwrap();
+    assert!(noun_eq(p, noun!(100_000)));
+
+    let deep = (0..100_000).fold(noun!({addr, 1}), |f, _| Noun::cell(f, noun!({idty, 0})));
+    let mut p = nock(Noun::cell(noun!(7), deep)).unwrap();
+    for _ in 0..100_000 {
+      p = p.slot(2).unwrap();
+    }
+    assert!(noun_eq(p, noun!(7)));
+  }
+
+  #[test]
+  fn test_eval_malformed() {
+    let s = Subject(noun!({42, 43}));
+
+    let e = crate::eval(s.clone(), Formula(noun!(0))).unwrap_err();
+    assert!(matches!(e, NockError::Exit { .. }));
+
+    let e = crate::eval(s.clone(), Formula(noun!({addr, 0}))).unwrap_err();
+    assert!(matches!(e.root(), NockError::ZeroAxis));
+
+    let e = crate::eval(s.clone(), Formula(noun!({addr, 4}))).unwrap_err();
+    assert!(matches!(e.root(), NockError::AxisOutOfTree { axis } if *axis == Atom::new(4)));
+
+    let e = crate::eval(s.clone(), Formula(noun!({incr, {addr, 1}}))).unwrap_err();
+    let NockError::InOpcode { opcode, error } = e else {
+      panic!("expected opcode context")
+    };
+    assert_eq!(opcode, Opcode::Incr);
+    let NockError::AtomRequired { found } = *error else {
+      panic!("expected an atom error")
+    };
+    assert!(noun_eq(found, s.clone().into_noun()));
+
+    let e = crate::eval(
+      s.clone(),
+      Formula(noun!({rplc, {{0, {addr, 1}}, {addr, 1}}})),
+    )
+    .unwrap_err();
+    assert!(matches!(e.root(), NockError::ZeroAxis));
+
+    // a tail call keeps the opcode that made it
+    let e = crate::eval(s.clone(), Formula(noun!({eval, {{addr, 1}, {idty, 5}}}))).unwrap_err();
+    assert!(matches!(
+      e,
+      NockError::InOpcode {
+        opcode: Opcode::Eval,
+        ..
+      }
+    ));
+    assert!(matches!(e.root(), NockError::Exit { .. }));
+
+    let e = crate::eval(s.clone(), Formula(noun!({99, {addr, 1}}))).unwrap_err();
+    assert!(matches!(e, NockError::UnknownOpcode(atom) if atom == Atom::new(99)));
+
+    // incrementing the largest word promotes to a bignum
+    let p = crate::eval(
+      s.clone(),
+      Formula(noun!({incr, {idty, 18446744073709551615}})),
+    )
+    .unwrap();
+    assert_eq!(p.as_atom().unwrap().words(), [0, 1]);
+
+    let big = Noun::atom(Atom::new(u64::MAX).incr());
+    let e = crate::eval(s.clone(), Formula(noun!({addr, (big.clone())}))).unwrap_err();
+    assert!(
+      matches!(e.root(), NockError::AxisOutOfTree { axis } if *axis == Atom::new(u64::MAX).incr())
+    );
+  }
+
+  #[test]
+  fn test_error_display() {
+    let e = crate::eval(Subject(noun!(42)), Formula(noun!({eval, 42}))).unwrap_err();
+    assert_eq!(e.to_string(), "opcode 2: expected a cell but found 42");
+
+    let e = crate::eval(
+      Subject(noun!(42)),
+      Formula(noun!({eval, {{addr, 1}, {addr, 1}}})),
+    )
+    .unwrap_err();
+    assert_eq!(e.to_string(), "opcode 2: formula 42 never reduces");
+  }
+
+  #[test]
+  fn test_interpreter_limits() {
+    let s = Subject(noun!(0));
+    let f = Formula(noun!({incr, {incr, {incr, {addr, 1}}}}));
+
+    let mut it = Interpreter::builder().fuel(3).build();
+    let e = it.eval(s.clone(), f.clone()).unwrap_err();
+    assert!(matches!(e.root(), NockError::OutOfFuel));
+
+    let mut it = Interpreter::builder().depth_limit(3).build();
+    let e = it.eval(s.clone(), f.clone()).unwrap_err();
+    assert!(matches!(e.root(), NockError::DepthLimit));
+
+    // the limit is checked before going deeper, so it holds well below the
+    // native stack
+    let deep = (0..100_000).fold(noun!({addr, 1}), |f, _| Noun::cell(noun!(incr), f));
+    let mut it = Interpreter::builder().depth_limit(100).build();
+    let e = it.eval(s.clone(), Formula(deep)).unwrap_err();
+    assert!(matches!(e.root(), NockError::DepthLimit));
+
+    // limits are per evaluation, and the interpreter is reusable
+    let mut it = Interpreter::builder().fuel(4).depth_limit(4).build();
+    for _ in 0..2 {
+      let p = it.eval(s.clone(), f.clone()).unwrap();
+      assert!(noun_eq(p, noun!(3)));
+    }
+  }
+
+  #[test]
+  fn test_interpreter_trace() {
+    use std::sync::{Arc, Mutex};
+
+    let steps = Arc::new(Mutex::new(vec![]));
+    let sink = steps.clone();
+    let mut it = Interpreter::builder()
+      .trace(move |_, form| sink.lock().unwrap().push(form.to_string()))
+      .build();
+
+    it.eval(Subject(noun!(41)), Formula(noun!({incr, {addr, 1}})))
+      .unwrap();
+
+    assert_eq!(*steps.lock().unwrap(), ["{4 0 1}", "{0 1}"]);
+
+    // a branch is picked without any formulas of its own
+    steps.lock().unwrap().clear();
+    let f = noun!({brch, {{addr, 1}, {{idty, 99}, {idty, 42}}}});
+    assert_eq!(it.eval(Subject(noun!(1)), Formula(f)).unwrap(), noun!(42));
+    assert_eq!(
+      *steps.lock().unwrap(),
+      ["{6 {0 1} {1 99} 1 42}", "{0 1}", "{1 42}"]
+    );
+
+    // and an arm is run on its core straight away
+    steps.lock().unwrap().clear();
+    let f = noun!({invk, {2, {idty, {{idty, 7}, 0}}}});
+    assert_eq!(it.eval(Subject(noun!(0)), Formula(f)).unwrap(), noun!(7));
+    assert_eq!(
+      *steps.lock().unwrap(),
+      ["{9 2 1 {1 7} 0}", "{1 {1 7} 0}", "{1 7}"]
+    );
+
+    let e = it
+      .eval(Subject(noun!(0)), Formula(noun!({invk, {6, {idty, 0}}})))
+      .unwrap_err();
+    assert!(matches!(
+      e,
+      NockError::InOpcode {
+        opcode: Opcode::Addr,
+        ..
+      }
+    ));
+  }
+
+  #[test]
+  fn test_hint() {
+    let s = Subject(noun!({42, 43}));
+
+    let p = crate::eval(s.clone(), Formula(noun!({hint, {7, {incr, {addr, 2}}}}))).unwrap();
+    assert!(noun_eq(p, noun!(43)));
+
+    let p = crate::eval(
+      s.clone(),
+      Formula(noun!({hint, {{7, {addr, 3}}, {incr, {addr, 2}}}})),
+    )
+    .unwrap();
+    assert!(noun_eq(p, noun!(43)));
+
+    // a crashing clue crashes the hint
+    let f = Formula(noun!({hint, {{7, {addr, 4}}, {incr, {addr, 2}}}}));
+    let e = crate::eval(s.clone(), f.clone()).unwrap_err();
+    assert!(matches!(e.root(), NockError::AxisOutOfTree { axis } if *axis == Atom::new(4)));
+
+    // unless hints are off, and the clue is never looked at
+    let mut it = Interpreter::builder().hints(false).build();
+    let p = it.eval(s.clone(), f).unwrap();
+    assert!(noun_eq(p, noun!(43)));
+  }
+
+  #[test]
+  fn test_hint_memo() {
+    use std::sync::{
+      Arc,
+      atomic::{AtomicUsize, Ordering},
+    };
+
+    // {*{a 11 {%memo 1 0} x} *{a 11 {%memo 1 0} x}}, where the second half is
+    // remembered from the first
+    let memo = Noun::atom(Atom::from_cord("memo"));
+    let x = noun!({incr, {incr, {incr, {addr, 1}}}});
+    let hinted = noun!({hint, {{(memo.clone()), {idty, 0}}, (x.clone())}});
+    let f = Formula(Noun::cell(hinted.clone(), hinted));
+
+    let steps = Arc::new(AtomicUsize::new(0));
+    let counter = steps.clone();
+    let mut it = Interpreter::builder()
+      .trace(move |_, _| {
+        counter.fetch_add(1, Ordering::Relaxed);
+      })
+      .build();
+
+    let p = it.eval(Subject(noun!(0)), f.clone()).unwrap();
+    assert!(noun_eq(p, noun!({3, 3})));
+    assert_eq!(steps.swap(0, Ordering::Relaxed), 1 + 2 * 2 + 4);
+
+    // the cache outlives a single evaluation, but not a different subject
+    it.eval(Subject(noun!(0)), f.clone()).unwrap();
+    assert_eq!(steps.swap(0, Ordering::Relaxed), 1 + 2 * 2);
+    let p = it.eval(Subject(noun!(1)), f.clone()).unwrap();
+    assert!(noun_eq(p, noun!({4, 4})));
+    assert_eq!(steps.swap(0, Ordering::Relaxed), 1 + 2 * 2 + 4);
+
+    it.clear_memo();
+    it.eval(Subject(noun!(0)), f).unwrap();
+    assert_eq!(steps.swap(0, Ordering::Relaxed), 1 + 2 * 2 + 4);
+  }
+
+  #[test]
+  fn test_eval_cache() {
+    use super::Engine;
+    use crate::compile::CompiledFormula;
+    use std::sync::{
+      Arc,
+      atomic::{AtomicUsize, Ordering},
+    };
+
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let call = noun!({eval, {{addr, 1}, {idty, (decrement)}}});
+    let twice = Noun::cell(call.clone(), call);
+
+    for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+      let steps = Arc::new(AtomicUsize::new(0));
+      let counter = steps.clone();
+      let mut it = Interpreter::builder()
+        .engine(engine)
+        .eval_cache(1 << 10, 1 << 20)
+        .depth_limit(8)
+        .trace(move |_, _| {
+          counter.fetch_add(1, Ordering::Relaxed);
+        })
+        .build();
+
+      // the second call is answered by the first, and the loop's tail calls
+      // don't nest
+      let p = it.eval(Subject(Noun::from(1_000u64)), Formula(twice.clone()));
+      assert_eq!(p.unwrap(), noun!({999, 999}), "{engine:?}");
+      let first = steps.swap(0, Ordering::Relaxed);
+
+      // and so is every call in another evaluation
+      it.eval(Subject(Noun::from(1_000u64)), Formula(twice.clone()))
+        .unwrap();
+      assert!(steps.swap(0, Ordering::Relaxed) < 10, "{engine:?}");
+
+      it.clear_eval_cache();
+      it.eval(Subject(Noun::from(1_000u64)), Formula(twice.clone()))
+        .unwrap();
+      assert_eq!(steps.swap(0, Ordering::Relaxed), first, "{engine:?}");
+    }
+
+    // compiled formulas share the cache
+    let steps = Arc::new(AtomicUsize::new(0));
+    let counter = steps.clone();
+    let mut it = Interpreter::builder()
+      .eval_cache(1 << 10, 1 << 20)
+      .trace(move |_, _| {
+        counter.fetch_add(1, Ordering::Relaxed);
+      })
+      .build();
+    it.eval(Subject(Noun::from(1_000u64)), Formula(twice.clone()))
+      .unwrap();
+    steps.store(0, Ordering::Relaxed);
+    let compiled = CompiledFormula::new(&twice);
+    let p = it.eval_compiled(Subject(Noun::from(1_000u64)), &compiled);
+    assert_eq!(p.unwrap(), noun!({999, 999}));
+    assert!(steps.load(Ordering::Relaxed) < 10);
+
+    // products that needed a scry aren't kept
+    let scries = Arc::new(AtomicUsize::new(0));
+    let counter = scries.clone();
+    let mut it = Interpreter::builder()
+      .eval_cache(1 << 10, 1 << 20)
+      .scry(move |_, _| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Some(noun!(0))
+      })
+      .build();
+    let scry = noun!({eval, {{addr, 1}, {idty, {scry, {{idty, 0}, {idty, 0}}}}}});
+    for _ in 0..2 {
+      it.eval(Subject(noun!(0)), Formula(scry.clone())).unwrap();
+    }
+    assert_eq!(scries.load(Ordering::Relaxed), 2);
+  }
+
+  #[test]
+  fn test_intern() {
+    use super::Engine;
+
+    // {{{1 2} 1 2} {1 2} 1 2}, each {1 2} built on its own
+    let pair = noun!({{idty, 1}, {idty, 2}});
+    let twice = Noun::cell(pair.clone(), pair);
+    let f = Noun::cell(twice.clone(), twice);
+
+    for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+      let mut it = Interpreter::builder().engine(engine).intern(true).build();
+      let p = it.eval(Subject(noun!(0)), Formula(f.clone())).unwrap();
+      let (head, tail) = p.as_cell().unwrap();
+      assert!(head.ptr_eq(tail), "{engine:?}");
+      assert!(head.slot(2).unwrap().ptr_eq(&tail.slot(3).unwrap()));
+
+      let mut it = Interpreter::builder().engine(engine).build();
+      let p = it.eval(Subject(noun!(0)), Formula(f.clone())).unwrap();
+      let (head, tail) = p.as_cell().unwrap();
+      assert!(!head.ptr_eq(tail), "{engine:?}");
+    }
+  }
+
+  #[test]
+  fn test_hint_fast() {
+    let fast = Noun::atom(Atom::from_cord("fast"));
+    let root = Noun::atom(Atom::from_cord("root"));
+    let kid = Noun::atom(Atom::from_cord("kid"));
+
+    // a root core, then a core holding it at axis 3
+    let root_core = noun!({hint, {{(fast.clone()), {idty, {(root), {{idty, 0}, 0}}}}, {idty, {{incr, {addr, 3}}, 0}}}});
+    let f =
+      noun!({hint, {{(fast), {idty, {(kid), {{addr, 3}, 0}}}}, {{idty, {addr, 7}}, (root_core)}}});
+
+    let mut it = Interpreter::new();
+    let p = it.eval(Subject(noun!(0)), Formula(f)).unwrap();
+    assert!(noun_eq(p, noun!({{addr, 7}, {{incr, {addr, 3}}, 0}})));
+
+    assert_eq!(it.cold().len(), 2);
+    let reg = it.cold().get(&noun!({addr, 7})).unwrap();
+    assert_eq!(reg.label, "root/kid");
+    assert_eq!(reg.parent, Some(3));
+  }
+
+  #[test]
+  fn test_jets() {
+    use super::Engine;
+    use crate::compile::CompiledFormula;
+    use std::sync::{
+      Arc,
+      atomic::{AtomicUsize, Ordering},
+    };
+
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let battery = decrement.slot(61).unwrap();
+    let dec = |core: &Noun| {
+      let n = core.slot(7).ok()?.as_atom()?.as_u64()?;
+      Some(Noun::from(n.checked_sub(1)?))
+    };
+
+    // a thousand steps of the loop are one
+    for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+      let mut it = Interpreter::builder()
+        .engine(engine)
+        .fuel(100)
+        .jet_battery(battery.clone(), 2, dec)
+        .build();
+      let p = it.eval(Subject(Noun::from(1000u64)), Formula(decrement.clone()));
+      assert_eq!(p.unwrap(), noun!(999), "{engine:?}");
+
+      let mut it = Interpreter::builder()
+        .engine(engine)
+        .fuel(100)
+        .jet_battery(battery.clone(), 2, dec)
+        .jets(false)
+        .build();
+      let e = it
+        .eval(Subject(Noun::from(1000u64)), Formula(decrement.clone()))
+        .unwrap_err();
+      assert!(matches!(e.root(), NockError::OutOfFuel), "{engine:?}");
+    }
+
+    let mut it = Interpreter::builder()
+      .fuel(100)
+      .jet_battery(battery.clone(), 2, dec)
+      .build();
+    let compiled = CompiledFormula::new(&decrement);
+    let p = it.eval_compiled(Subject(Noun::from(1000u64)), &compiled);
+    assert_eq!(p.unwrap(), noun!(999));
+
+    // labeled by a `%fast` hint on the core before its arm is pulled
+    let fast = Noun::atom(Atom::from_cord("fast"));
+    let clue = noun!({(Atom::from_cord("dec")), {{idty, 0}, 0}});
+    let labeled = noun!({extn, {{idty, 0}, {extn, {{idty, (battery)}, {invk, {2, {hint, {{(fast), {idty, (clue)}}, {addr, 1}}}}}}}}});
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut it = Interpreter::builder()
+      .jet("dec", 2, move |core| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        dec(core)
+      })
+      .build();
+    let p = it.eval(Subject(Noun::from(1000u64)), Formula(labeled.clone()));
+    assert_eq!(p.unwrap(), noun!(999));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+    // a jet that gives up has the arm run, which calls it again each step
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut it = Interpreter::builder()
+      .jet("dec", 2, move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        None
+      })
+      .build();
+    let p = it.eval(Subject(noun!(10)), Formula(labeled.clone()));
+    assert_eq!(p.unwrap(), noun!(9));
+    assert_eq!(calls.load(Ordering::Relaxed), 10);
+
+    // checked jets agree with their arms, or crash
+    let mut it = Interpreter::builder()
+      .jet("dec", 2, dec)
+      .check_jets(true)
+      .build();
+    let p = it.eval(Subject(Noun::from(1000u64)), Formula(labeled.clone()));
+    assert_eq!(p.unwrap(), noun!(999));
+
+    let mut it = Interpreter::builder()
+      .jet("dec", 2, |core| core.slot(7).ok())
+      .check_jets(true)
+      .build();
+    let e = it
+      .eval(Subject(Noun::from(1000u64)), Formula(labeled))
+      .unwrap_err();
+    assert!(matches!(
+      e.root(),
+      NockError::Diverged {
+        divergence: Some(_),
+        ..
+      }
+    ));
+  }
+
+  #[test]
+  fn test_hint_trace() {
+    let spot = Noun::atom(Atom::from_cord("spot"));
+    let mean = Noun::atom(Atom::from_cord("mean"));
+    let crash = noun!({hint, {{(mean), {idty, 2}}, {incr, {addr, 1}}}});
+    let f = noun!({hint, {{(spot.clone()), {idty, 1}}, {{addr, 1}, (crash)}}});
+    let f = noun!({hint, {{(spot), {idty, 0}}, {extn, {{idty, 3}, (f)}}}});
+
+    let e = crate::eval(Subject(noun!({4, 5})), Formula(f)).unwrap_err();
+    assert_eq!(e.trace(), [noun!(2), noun!(1), noun!(0)]);
+    assert!(matches!(e.root(), NockError::AtomRequired { .. }));
+    assert_eq!(
+      e.to_string(),
+      "opcode 4: expected an atom but found {3 4 5}\n  at 2\n  at 1\n  at 0"
+    );
+
+    // other hints leave the error alone
+    let e = crate::eval(Subject(noun!(0)), Formula(noun!({hint, {1, {addr, 2}}}))).unwrap_err();
+    assert!(e.trace().is_empty());
+  }
+
+  #[test]
+  fn test_hint_handler() {
+    use std::sync::{Arc, Mutex, mpsc};
+
+    use super::HintHandler;
+
+    struct Counter(Arc<Mutex<Vec<String>>>);
+
+    impl HintHandler for Counter {
+      fn hint(&mut self, tag: Atom, clue: Option<Noun>, _: &Noun, formula: &Noun) {
+        let clue = clue.map_or("~".to_string(), |clue| clue.to_string());
+        let tag = tag.to_cord().unwrap();
+        self
+          .0
+          .lock()
+          .unwrap()
+          .push(format!("{tag} {clue} {}", formula.pretty()));
+      }
+    }
+
+    let seen = Arc::new(Mutex::new(vec![]));
+    let (sink, tags) = mpsc::channel();
+    let mut it = Interpreter::builder()
+      .hint_handler(Counter(seen.clone()))
+      .hint_handler(move |tag: Atom, _: Option<Noun>, _: &Noun, _: &Noun| sink.send(tag).unwrap())
+      .build();
+
+    let mark = Noun::atom(Atom::from_cord("mark"));
+    let save = Noun::atom(Atom::from_cord("save"));
+    let f = noun!({hint, {(mark), {hint, {{(save), {incr, {addr, 1}}}, {addr, 1}}}}});
+    let p = it.eval(Subject(noun!(7)), Formula(f)).unwrap();
+    assert!(noun_eq(p, noun!(7)));
+
+    assert_eq!(
+      *seen.lock().unwrap(),
+      ["mark ~ {11 {%save 4 0 1} 0 1}", "save 8 {0 1}"]
+    );
+    assert_eq!(
+      tags.try_iter().collect::<Vec<_>>(),
+      [Atom::from_cord("mark"), Atom::from_cord("save")]
+    );
+  }
+
+  #[test]
+  fn test_extension() {
+    // 13 is `*{a 13 b c}` ~> `{*{a c} *{a b}}`, and 14 quotes its argument
+    let mut it = Interpreter::builder()
+      .extension(13, |subj, arg, nock| {
+        let (b, c) = arg.try_cell()?;
+        Ok(Noun::cell(
+          nock(subj.clone(), c.clone())?,
+          nock(subj.clone(), b.clone())?,
+        ))
+      })
+      .extension(14, |_, arg, _| Ok(arg.clone()))
+      .build();
+
+    let s = Subject(noun!({42, 43}));
+    let prod = it.eval(s.clone(), Formula(noun!({13, {{addr, 2}, {addr, 3}}})));
+    assert_eq!(prod.unwrap(), noun!({43, 42}));
+
+    // extensions nest, in themselves and in standard opcodes
+    let f = noun!({13, {{14, 7}, {incr, {13, {{addr, 2}, {addr, 3}}}}}});
+    let e = it.eval(s.clone(), Formula(f)).unwrap_err();
+    assert!(matches!(e.root(), NockError::AtomRequired { .. }));
+    let f = noun!({13, {{14, 7}, {13, {{addr, 2}, {addr, 3}}}}});
+    assert_eq!(
+      it.eval(s.clone(), Formula(f)).unwrap(),
+      noun!({{43, 42}, 7})
+    );
+
+    let e = it.eval(s, Formula(noun!({15, 0}))).unwrap_err();
+    assert!(matches!(e, NockError::UnknownOpcode(atom) if atom == Atom::new(15)));
+  }
+
+  #[test]
+  #[should_panic(expected = "opcode 4 is already defined")]
+  fn test_extension_standard() {
+    Interpreter::builder().extension(4, |_, arg, _| Ok(arg.clone()));
+  }
+
+  #[test]
+  fn test_scry() {
+    let f = Formula(noun!({scry, {{addr, 2}, {idty, [1 2 ~]}}}));
+    let s = Subject(noun!({7, 0}));
+
+    let e = crate::eval(s.clone(), f.clone()).unwrap_err();
+    let NockError::Blocked { path } = e.root() else {
+      panic!("expected to block")
+    };
+    assert!(noun_eq(path.clone(), noun!([1 2 ~])));
+
+    let mut it = Interpreter::builder()
+      .scry(|reference, path| {
+        let (head, _) = path.as_cell()?;
+        Some(Noun::cell(reference.clone(), head.clone()))
+      })
+      .build();
+    let p = it.eval(s.clone(), f).unwrap();
+    assert!(noun_eq(p, noun!({7, 1})));
+
+    let e = it
+      .eval(s, Formula(noun!({scry, {{addr, 2}, {idty, 0}}})))
+      .unwrap_err();
+    assert!(matches!(e.root(), NockError::Blocked { .. }));
+  }
+
+  #[test]
+  fn test_atom_formula() {
+    use super::AtomFormula;
+
+    let f = Formula(noun!({incr, 7}));
+    let e = crate::eval(Subject(noun!(0)), f.clone()).unwrap_err();
+    assert!(matches!(e.root(), NockError::Exit { formula } if *formula == noun!(7)));
+    assert!(e.is_deterministic());
+
+    let mut it = Interpreter::builder()
+      .atom_formula(AtomFormula::Loop)
+      .fuel(1000)
+      .build();
+    let e = it.eval(Subject(noun!(0)), f).unwrap_err();
+    assert!(matches!(e.root(), NockError::OutOfFuel));
+  }
+
+  #[test]
+  fn test_shadow() {
+    let f = Formula(noun!({{incr, {addr, 2}}, {cell, {addr, 3}}}));
+    let s = Subject(noun!({1, 2}));
+
+    let mut it = Interpreter::builder()
+      .shadow(|s, f| crate::spec::eval(s.clone(), f.clone()))
+      .build();
+    let p = it.eval(s.clone(), f.clone()).unwrap();
+    assert!(noun_eq(p, noun!({2, 1})));
+
+    let e = it.eval(s.clone(), Formula(noun!({addr, 0}))).unwrap_err();
+    assert!(matches!(e.root(), NockError::ZeroAxis));
+
+    // a backend that gets opcode 3 backwards
+    let mut it = Interpreter::builder()
+      .shadow(|s, f| {
+        let p = crate::spec::eval(s.clone(), f.clone())?;
+        Ok(p.edit(3, noun!(0)).unwrap())
+      })
+      .build();
+    let e = it.eval(s.clone(), f).unwrap_err();
+    let NockError::Diverged {
+      formula,
+      divergence: Some(divergence),
+    } = &e
+    else {
+      panic!("expected a divergence")
+    };
+    assert_eq!(*formula, noun!({{incr, {addr, 2}}, {cell, {addr, 3}}}));
+    assert_eq!(divergence.axis, Some(3));
+    assert_eq!(
+      e.to_string(),
+      "backends diverged on {{4 0 2} 3 0 3} at axis 3: 1 against 0"
+    );
+
+    let mut it = Interpreter::builder()
+      .shadow(|_, _| Err(NockError::ZeroAxis))
+      .build();
+    let e = it.eval(s, Formula(noun!({idty, 0}))).unwrap_err();
+    assert!(matches!(
+      e,
+      NockError::Diverged {
+        divergence: None,
+        ..
+      }
+    ));
+  }
+
+  #[test]
+  fn test_eval_soft() {
+    use super::SoftResult;
+
+    let spot = Noun::atom(Atom::from_cord("spot"));
+    let s = Subject(noun!({1, 2}));
+    let mut it = Interpreter::new();
+
+    let r = it.eval_soft(s.clone(), Formula(noun!({incr, {addr, 2}})));
+    assert!(matches!(r, SoftResult::Done(p) if p == noun!(2)));
+
+    let r = it.eval_soft(s.clone(), Formula(noun!({scry, {{idty, 0}, {addr, 3}}})));
+    assert!(matches!(r, SoftResult::Blocked(path) if path == noun!(2)));
+
+    let f = noun!({hint, {{(spot), {idty, 9}}, {incr, {addr, 1}}}});
+    let SoftResult::Crashed(crash) = it.eval_soft(s.clone(), Formula(f.clone())) else {
+      panic!("expected a crash")
+    };
+    assert_eq!(crash.trace, noun!([9 ~]));
+    assert!(matches!(crash.error.root(), NockError::AtomRequired { .. }));
+
+    let mut starved = Interpreter::builder().fuel(2).build();
+    let r = starved.eval_soft(s.clone(), Formula(f));
+    assert!(matches!(r, SoftResult::Failed(e) if matches!(e.root(), NockError::OutOfFuel)));
+
+    // the scry handler virtualizes the path as a formula of its own, and the
+    // outer computation blocks only when the inner one does
+    let mut inner = Interpreter::new();
+    let mut outer = Interpreter::builder()
+      .scry(
+        move |_, path| match inner.eval_soft(Subject(noun!(0)), Formula(path.clone())) {
+          SoftResult::Done(prod) => Some(prod),
+          _ => None,
+        },
+      )
+      .build();
+
+    let r = outer.eval_soft(
+      s.clone(),
+      Formula(noun!({scry, {{idty, 0}, {idty, {incr, {addr, 1}}}}})),
+    );
+    assert!(matches!(r, SoftResult::Done(p) if p == noun!(1)));
+
+    let f = noun!({scry, {{idty, 0}, {idty, {scry, {{idty, 0}, {idty, 5}}}}}});
+    let r = outer.eval_soft(s.clone(), Formula(f));
+    assert!(
+      matches!(r, SoftResult::Blocked(path) if path == noun!({scry, {{idty, 0}, {idty, 5}}}))
+    );
+  }
+
+  #[test]
+  fn test_hint_slog() {
+    use std::sync::{Arc, Mutex, mpsc};
+
+    let slog = Noun::atom(Atom::from_cord("slog"));
+    let memo = Noun::atom(Atom::from_cord("memo"));
+    let s = Subject(noun!({42, (Atom::from_cord("hello"))}));
+    let f = Formula(
+      noun!({hint, {{(slog), {{idty, 0}, {addr, 3}}}, {hint, {{(memo), {addr, 2}}, {addr, 2}}}}}),
+    );
+
+    let (sink, clues) = mpsc::channel();
+    let mut it = Interpreter::builder()
+      .slog(move |clue| sink.send(clue.clone()).unwrap())
+      .build();
+    let p = it.eval(s.clone(), f.clone()).unwrap();
+    assert!(noun_eq(p, noun!(42)));
+    assert_eq!(
+      clues.try_iter().collect::<Vec<_>>(),
+      [noun!({0, (Atom::from_cord("hello"))})]
+    );
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Shared {
+      fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+      }
+
+      fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+      }
+    }
+
+    let out = Shared::default();
+    let mut it = Interpreter::builder().slog_writer(out.clone()).build();
+    it.eval(s, f).unwrap();
+    assert_eq!(
+      String::from_utf8(std::mem::take(&mut *out.0.lock().unwrap())).unwrap(),
+      "{0 %hello}\n"
+    );
+  }
+
+  #[test]
+  fn test_eqal() {
+    use super::Engine;
+
+    let same = noun!({eqal, {{addr, 2}, {addr, 3}}});
+
+    for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+      let equal = noun!({{{1, 2}, {3, 4}}, {{1, 2}, {3, 4}}});
+      let unequal = noun!({{{1, 2}, {3, 4}}, {{1, 2}, {3, 5}}});
+      let mut it = Interpreter::builder().engine(engine).build();
+
+      // the second time from what the first found
+      for _ in 0..2 {
+        let p = it.eval(Subject(equal.clone()), Formula(same.clone()));
+        assert_eq!(p.unwrap(), Noun::YES, "{engine:?}");
+      }
+      let p = it.eval(Subject(unequal.clone()), Formula(same.clone()));
+      assert_eq!(p.unwrap(), Noun::NAH, "{engine:?}");
+    }
+  }
+
+  // with `sync`, an interpreter goes between threads with its caches and
+  // sinks, and a compiled formula is shared by as many as like
+  #[cfg(feature = "sync")]
+  #[test]
+  fn test_send_sync() {
+    use std::sync::mpsc;
+
+    use rayon::prelude::*;
+
+    use super::{Engine, SoftResult};
+    use crate::compile::CompiledFormula;
+
+    fn send<T: Send>() {}
+    fn send_sync<T: Send + Sync>() {}
+    send::<Interpreter>();
+    send_sync::<CompiledFormula>();
+    send_sync::<Noun>();
+    send_sync::<NockError>();
+    send_sync::<SoftResult>();
+
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let slog = noun!({hint, {{(Atom::from_cord("slog")), {idty, 7}}, (decrement.clone())}});
+
+    for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+      let (sink, slogs) = mpsc::channel();
+      let mut it = Interpreter::builder()
+        .engine(engine)
+        .slog(move |clue| sink.send(clue.clone()).unwrap())
+        .build();
+      it.eval(Subject(noun!(10)), Formula(slog.clone())).unwrap();
+
+      let slog = slog.clone();
+      let mut it = std::thread::spawn(move || {
+        let p = it.eval(Subject(noun!(20)), Formula(slog));
+        assert_eq!(p.unwrap(), noun!(19), "{engine:?}");
+        it
+      })
+      .join()
+      .unwrap();
+      assert_eq!(slogs.try_iter().collect::<Vec<_>>(), [noun!(7), noun!(7)]);
+      let p = it.eval(Subject(noun!(30)), Formula(decrement.clone()));
+      assert_eq!(p.unwrap(), noun!(29), "{engine:?}");
+    }
+
+    let compiled = CompiledFormula::new(&decrement);
+    let prods: Vec<_> = (1..=64u64)
+      .into_par_iter()
+      .map_init(Interpreter::new, |it, n| {
+        it.eval_compiled(Subject(Noun::from(n * 10)), &compiled)
+          .unwrap()
+      })
+      .collect();
+    assert_eq!(
+      prods,
+      (1..=64u64)
+        .map(|n| Noun::from(n * 10 - 1))
+        .collect::<Vec<_>>()
+    );
+  }
+}
@@ -0,0 +1,342 @@
+// Jets: native Rust in place of the arms of known cores. When opcode 9 pulls
+// an arm out of a core whose battery has a jet for that arm, the jet runs on
+// the core instead of the arm's formula, and the product is the jet's.
+//
+// The dashboard that finds them is in three parts:
+// - cold state, every core labeled by a `%fast` hint, see src/fast.rs. It's
+//   only nouns, so it can be kept with the kernel it labels, and handed to
+//   another interpreter with `InterpreterBuilder::cold`.
+// - hot state, the jets this program has, by the labels of their cores.
+// - warm state, jets by the batteries they run for. A core registered in the
+//   cold state has the hot jets for its label bound to its battery, so the
+//   warm state is rebuilt from the other two in every interpreter, and a
+//   kernel with new batteries under the same labels keeps its jets.
+//
+// Jets can also be bound to batteries directly, labeled or not. Batteries are
+// told apart by their digests, so matching one is a hash lookup, whatever the
+// battery's size. A jet can give up on a core by returning None, and the
+// formula runs as if there were no jet; with `check_jets`, the formula runs
+// anyway and a jet that disagrees with it is an error. Inside a `%dont` hint
+// no jets run at all, so a computation can be checked against pure nock.
+//
+// Hoon's arithmetic gates come with jets of their own, bound to any core
+// whose label ends in the gate's name, like `k140/one/dec`, whatever the
+// kelvin above it. A gate is `{battery {sample context}}` with its arm at
+// axis 2, and the two atoms of a binary gate's sample at axes 12 and 13.
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+  atom::{Atom, NAH, YES},
+  fast::Cold,
+  math,
+  noun::{Noun, Rc},
+};
+
+// the product of an arm of `core`, or None to run the arm's formula
+pub type Jet = Rc<dyn_shareable!(Fn(&Noun) -> Option<Noun>)>;
+
+// what calls to one arm did, for interpreters built with `profile_jets`.
+// Arms with a jet are counted, and so are arms of labeled cores without one,
+// which are the ones worth writing a jet for.
+#[derive(Clone, Debug)]
+pub struct JetStats {
+  pub battery: Noun,
+  pub axis: u64,
+  // of the core in the cold state, if it's there
+  pub label: Option<String>,
+  pub jetted: bool,
+  // calls to the arm, and how many of those ran its formula, because the jet
+  // gave up or there's no jet
+  pub calls: u64,
+  pub punts: u64,
+  // spent in the jet
+  pub time: Duration,
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct Dashboard {
+  hot: Hot,
+  // jets by battery and arm
+  warm: HashMap<(Noun, u64), Jet>,
+}
+
+#[derive(Default, Clone)]
+struct Hot {
+  // gate jets by the last name in their core's label
+  gates: HashMap<&'static str, Jet>,
+  // jets by the label of their core and the axis of their arm
+  labeled: HashMap<String, Vec<(u64, Jet)>>,
+}
+
+impl Dashboard {
+  // with the arithmetic gates jetted
+  pub(crate) fn builtin() -> Self {
+    Self {
+      hot: Hot {
+        gates: gates(),
+        ..Hot::default()
+      },
+      ..Self::default()
+    }
+  }
+
+  pub(crate) fn label(&mut self, label: &str, axis: u64, jet: Jet) {
+    self
+      .hot
+      .labeled
+      .entry(label.to_string())
+      .or_default()
+      .push((axis, jet));
+  }
+
+  pub(crate) fn bind(&mut self, battery: Noun, axis: u64, jet: Jet) {
+    self.warm.insert((battery, axis), jet);
+  }
+
+  // binds the hot jets for `label` to a core registered under it
+  pub(crate) fn registered(&mut self, label: &str, battery: &Noun) {
+    let name = label.rsplit('/').next().unwrap_or(label);
+    if let Some(jet) = self.hot.gates.get(name) {
+      self.warm.insert((battery.clone(), 2), jet.clone());
+    }
+    for (axis, jet) in self.hot.labeled.get(label).into_iter().flatten() {
+      self.warm.insert((battery.clone(), *axis), jet.clone());
+    }
+  }
+
+  // the warm state for every core in `cold`
+  pub(crate) fn warm(&mut self, cold: &Cold) {
+    for registration in cold.iter() {
+      self.registered(&registration.label, &registration.battery);
+    }
+  }
+
+  pub(crate) fn find(&self, core: &Noun, axis: u64) -> Option<Jet> {
+    if self.warm.is_empty() {
+      return None;
+    }
+
+    let (battery, _) = core.as_cell()?;
+    self.warm.get(&(battery.clone(), axis)).cloned()
+  }
+}
+
+fn gates() -> HashMap<&'static str, Jet> {
+  let mut gates: HashMap<&'static str, Jet> = HashMap::new();
+  gates.insert(
+    "dec",
+    Rc::new(|core| unary(core, |a| math::sub(a, &Atom::new(1)).ok())),
+  );
+
+  let mut binary = |name, jet: fn(&Atom, &Atom) -> Option<Atom>| {
+    gates.insert(name, Rc::new(move |core| binary(core, jet)));
+  };
+  binary("add", |a, b| Some(math::add(a, b)));
+  binary("sub", |a, b| math::sub(a, b).ok());
+  binary("mul", |a, b| Some(math::mul(a, b)));
+  binary("div", |a, b| math::div(a, b).ok());
+  binary("mod", |a, b| math::modulo(a, b).ok());
+  binary("lth", |a, b| Some(loobean(math::lth(a, b))));
+  binary("lte", |a, b| Some(loobean(!math::gth(a, b))));
+  binary("gth", |a, b| Some(loobean(math::gth(a, b))));
+  binary("gte", |a, b| Some(loobean(!math::lth(a, b))));
+  gates
+}
+
+// a gate's crashes are the formula's to make, so a jet gives up on them
+fn unary(core: &Noun, jet: impl Fn(&Atom) -> Option<Atom>) -> Option<Noun> {
+  let a = core.slot(6).ok()?;
+  jet(a.as_atom()?).map(Noun::atom)
+}
+
+fn binary(core: &Noun, jet: impl Fn(&Atom, &Atom) -> Option<Atom>) -> Option<Noun> {
+  let (a, b) = (core.slot(12).ok()?, core.slot(13).ok()?);
+  jet(a.as_atom()?, b.as_atom()?).map(Noun::atom)
+}
+
+fn loobean(yes: bool) -> Atom {
+  Atom::new(if yes { YES } else { NAH })
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{
+    atom::Atom,
+    error::NockError,
+    fast::Cold,
+    interp::{Formula, Interpreter, Subject},
+    noun::Noun,
+  };
+
+  // `|=(a=@ =+ b=0 |-(?:(=(a +(b)) b $(b +(b)))))`
+  const DEC: &str = "{8 {1 0} 8 {1 6 {5 {0 30} 4 0 6} {0 6} 9 2 {0 2} {4 0 6} 0 7} 9 2 0 1}";
+
+  // `battery` as a gate of `name` in a `k140` root core, slammed on the subject
+  fn slam(name: &str, battery: Noun) -> Noun {
+    let fast = Noun::atom(Atom::from_cord("fast"));
+    let k140 = noun!({(Atom::from_cord("k")), 140});
+    let root =
+      noun!({hint, {{(fast.clone()), {idty, {(k140), {{idty, 0}, 0}}}}, {idty, {{addr, 1}, 0}}}});
+    let clue = noun!({(Atom::from_cord(name)), {{addr, 7}, 0}});
+    let gate = noun!({hint, {{(fast), {idty, (clue)}}, {{idty, (battery)}, {{idty, 0}, (root)}}}});
+    noun!({invk, {2, {rplc, {{6, {addr, 1}}, (gate)}}}})
+  }
+
+  #[test]
+  fn test_gates() {
+    let dec: Noun = DEC.parse().unwrap();
+    let mut it = Interpreter::builder().fuel(200).build();
+    let p = it.eval(Subject(noun!(1000)), Formula(slam("dec", dec.clone())));
+    assert_eq!(p.unwrap(), noun!(999));
+    assert_eq!(it.cold().len(), 2);
+
+    let mut it = Interpreter::builder().check_jets(true).build();
+    let p = it.eval(Subject(noun!(1000)), Formula(slam("dec", dec.clone())));
+    assert_eq!(p.unwrap(), noun!(999));
+
+    // a crash is the formula's, here a loop that never ends
+    let mut it = Interpreter::builder().fuel(200).build();
+    let e = it
+      .eval(Subject(noun!(0)), Formula(slam("dec", dec.clone())))
+      .unwrap_err();
+    assert!(matches!(e.root(), NockError::OutOfFuel));
+
+    let mut it = Interpreter::builder().fuel(200).jets(false).build();
+    let e = it
+      .eval(Subject(noun!(1000)), Formula(slam("dec", dec)))
+      .unwrap_err();
+    assert!(matches!(e.root(), NockError::OutOfFuel));
+
+    // the others' batteries crash if they're ever run
+    let big = Noun::atom(Atom::from_words(&[0, 1]));
+    let mut it = Interpreter::new();
+    let mut eval = |name: &str, a: Noun, b: Noun| {
+      it.eval(
+        Subject(Noun::cell(a, b)),
+        Formula(slam(name, noun!({addr, 0}))),
+      )
+    };
+    assert_eq!(
+      eval("add", big.clone(), noun!(1)).unwrap(),
+      Noun::atom(Atom::from_words(&[1, 1]))
+    );
+    assert_eq!(
+      eval("sub", big.clone(), noun!(1)).unwrap(),
+      Noun::from(u64::MAX)
+    );
+    assert!(eval("sub", noun!(1), noun!(2)).is_err());
+    assert_eq!(
+      eval("mul", big.clone(), noun!(2)).unwrap(),
+      Noun::atom(Atom::from_words(&[0, 2]))
+    );
+    assert_eq!(eval("div", noun!(7), noun!(2)).unwrap(), noun!(3));
+    assert!(eval("div", noun!(7), noun!(0)).is_err());
+    assert_eq!(eval("mod", noun!(7), noun!(2)).unwrap(), noun!(1));
+    assert_eq!(eval("lth", noun!(1), big.clone()).unwrap(), noun!(0));
+    assert_eq!(eval("lte", noun!(2), noun!(2)).unwrap(), noun!(0));
+    assert_eq!(eval("gth", noun!(1), big).unwrap(), noun!(1));
+    assert_eq!(eval("gte", noun!(1), noun!(2)).unwrap(), noun!(1));
+  }
+
+  #[test]
+  fn test_warm() {
+    let dec: Noun = DEC.parse().unwrap();
+    let mut it = Interpreter::new();
+    it.eval(Subject(noun!(10)), Formula(slam("dec", dec.clone())))
+      .unwrap();
+    let saved = it.cold().to_noun();
+
+    // the same gate, without the hints that labeled it
+    let gate = noun!({(dec.clone()), {0, {{addr, 1}, 0}}});
+    let call = noun!({invk, {2, {rplc, {{6, {addr, 1}}, {idty, (gate)}}}}});
+    let mut it = Interpreter::builder().fuel(200).build();
+    let e = it
+      .eval(Subject(noun!(1000)), Formula(call.clone()))
+      .unwrap_err();
+    assert!(matches!(e.root(), NockError::OutOfFuel));
+
+    let cold = Cold::from_noun(&saved).unwrap();
+    let mut it = Interpreter::builder().fuel(200).cold(cold).build();
+    let p = it.eval(Subject(noun!(1000)), Formula(call));
+    assert_eq!(p.unwrap(), noun!(999));
+
+    // a new battery under the same label has the same jet
+    let upgraded = noun!({hint, {0, (dec)}});
+    let p = it.eval(Subject(noun!(1000)), Formula(slam("dec", upgraded)));
+    assert_eq!(p.unwrap(), noun!(999));
+    assert_eq!(it.cold().len(), 3);
+  }
+
+  #[test]
+  fn test_jet_stats() {
+    let dec: Noun = DEC.parse().unwrap();
+    let mut it = Interpreter::builder().profile_jets(true).build();
+    for n in [10u64, 20] {
+      it.eval(Subject(Noun::from(n)), Formula(slam("dec", dec.clone())))
+        .unwrap();
+    }
+    let foo = noun!({hint, {0, (dec.clone())}});
+    it.eval(Subject(noun!(10)), Formula(slam("foo", foo)))
+      .unwrap();
+    let crash = slam("sub", noun!({addr, 0}));
+    assert!(it.eval(Subject(noun!({1, 2})), Formula(crash)).is_err());
+
+    let stats = it.jet_stats();
+    let arms: Vec<_> = stats
+      .iter()
+      .map(|stats| {
+        (
+          stats.label.as_deref(),
+          stats.jetted,
+          stats.calls,
+          stats.punts,
+        )
+      })
+      .collect();
+    assert_eq!(
+      arms,
+      [
+        (Some("k140/dec"), true, 2, 0),
+        (Some("k140/foo"), false, 1, 1),
+        (Some("k140/sub"), true, 1, 1),
+      ]
+    );
+    assert_eq!(stats[0].axis, 2);
+    assert_eq!(stats[0].battery, dec);
+
+    assert!(Interpreter::new().jet_stats().is_empty());
+  }
+
+  #[test]
+  fn test_dont() {
+    use crate::{compile::CompiledFormula, interp::Engine};
+
+    // a jet that's wrong, so it's plain when it runs
+    let dec: Noun = DEC.parse().unwrap();
+    let dont = |formula: Noun| noun!({hint, {(Atom::from_cord("dont")), (formula)}});
+    let both = Noun::cell(dont(slam("dec", dec.clone())), slam("dec", dec.clone()));
+    let build = |engine| {
+      Interpreter::builder()
+        .engine(engine)
+        .jet("k140/dec", 2, |core| core.slot(6).ok())
+        .build()
+    };
+
+    for engine in [Engine::Tree, Engine::Closure, Engine::Bytecode] {
+      let mut it = build(engine);
+      let p = it.eval(Subject(noun!(10)), Formula(both.clone()));
+      assert_eq!(p.unwrap(), noun!({9, 10}), "{engine:?}");
+
+      // and jets are back after a crash inside the hint
+      let crash = dont(noun!({addr, 7}));
+      assert!(it.eval(Subject(noun!(10)), Formula(crash)).is_err());
+      let p = it.eval(Subject(noun!(10)), Formula(slam("dec", dec.clone())));
+      assert_eq!(p.unwrap(), noun!(10), "{engine:?}");
+    }
+
+    let mut it = build(Engine::Tree);
+    let p = it.eval_compiled(Subject(noun!(10)), &CompiledFormula::new(&both));
+    assert_eq!(p.unwrap(), noun!({9, 10}));
+  }
+}
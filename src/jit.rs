@@ -0,0 +1,725 @@
+// Native code for hot bytecode programs, through Cranelift, for the jit
+// engine. A program turns hot after `jit_after` calls, or on its first when
+// it's called on a core a `%fast` hint labeled, and runs on the bytecode
+// machine until then.
+//
+// Native code keeps the program's value stack in registers, as the one-word
+// nouns of src/tagged.rs, each holding its count. It does slots, quotes,
+// conses, cell tests, increments and equality of direct atoms, branches and
+// jumps, and the subject switches of opcodes 7 and 8 itself. At anything else
+// (calls, hints, edits, scries, returns, and the slow cases of the above) it
+// spills the stack back to the machine and stops there; the machine steps
+// that instruction and calls back in at the next. So errors, and every
+// product native code doesn't make itself, are the machine's.
+
+use std::{
+  collections::HashMap,
+  mem::{ManuallyDrop, offset_of},
+  sync::atomic::{AtomicU32, Ordering},
+};
+
+use cranelift_codegen::{
+  ir::{
+    AbiParam, Block, InstBuilder, JumpTableData, MemFlags, SigRef, Signature, UserFuncName, Value,
+    condcodes::IntCC,
+    types::{I32, I64},
+  },
+  isa::OwnedTargetIsa,
+  settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{Linkage, Module, default_libcall_names};
+
+use crate::{
+  bytecode::{Instr, Program, Step, Vm},
+  interp::Interpreter,
+  noun::{Cell, Noun, OnceCell},
+  tagged::{CELL, HEAP, TAG},
+};
+
+#[derive(Default)]
+pub(crate) struct Hot {
+  calls: AtomicU32,
+  // None once compiling failed, the program stays on the machine then
+  native: OnceCell<Option<Native>>,
+}
+
+impl Hot {
+  pub(crate) fn native(&self) -> Option<&Native> {
+    self.native.get().and_then(Option::as_ref)
+  }
+
+  // counts a call of the program on `subj`
+  pub(crate) fn warm(&self, program: &Program, subj: &Noun, interp: &Interpreter) {
+    if self.native.get().is_some() {
+      return;
+    }
+
+    let calls = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+    let fast = subj
+      .as_cell()
+      .is_some_and(|(battery, _)| interp.cold.get(battery).is_some());
+    if calls > interp.jit_after || fast {
+      self.native.get_or_init(|| Native::compile(&program.code));
+    }
+  }
+}
+
+impl std::fmt::Debug for Hot {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Hot")
+      .field("calls", &self.calls)
+      .field("native", &self.native().is_some())
+      .finish()
+  }
+}
+
+// what native code works on, behind the pointer it's called with
+#[repr(C)]
+pub(crate) struct Ctx<'a> {
+  subj: &'a mut Noun,
+  interp: &'a mut Interpreter,
+  vm: &'a mut Vm,
+}
+
+// takes the index of the instruction to start at, with the frame's stack on
+// the machine's, and gives back the index of the one the machine is to step
+type Entry = unsafe extern "C" fn(&mut Ctx<'_>, usize) -> usize;
+
+pub(crate) struct Native {
+  // owns the code `entry` points into
+  module: Option<JITModule>,
+  entry: Entry,
+}
+
+// SAFETY: the module is only touched again to free the code, which nothing
+// changes once it's finalized
+#[cfg(feature = "sync")]
+unsafe impl Send for Native {}
+#[cfg(feature = "sync")]
+unsafe impl Sync for Native {}
+
+impl Native {
+  // runs the frame from `pc` as far as native code goes, and gives back
+  // where the machine picks up
+  pub(crate) fn run(
+    &self,
+    interp: &mut Interpreter,
+    subj: &mut Noun,
+    vm: &mut Vm,
+    pc: usize,
+  ) -> usize {
+    let mut ctx = Ctx { subj, interp, vm };
+    // SAFETY: the code was compiled from the frame's program, and the frame
+    // left as many values on the stack as the program has there
+    unsafe { (self.entry)(&mut ctx, pc) }
+  }
+
+  fn compile(code: &[Step]) -> Option<Self> {
+    let mut module = JITModule::new(JITBuilder::with_isa(isa()?, default_libcall_names()));
+    let mut cx = module.make_context();
+    let word = AbiParam::new(I64);
+    cx.func.signature.params.extend([word, word]);
+    cx.func.signature.returns.push(word);
+
+    let mut fcx = FunctionBuilderContext::new();
+    Lower::new(FunctionBuilder::new(&mut cx.func, &mut fcx), code).lower();
+
+    let id = module
+      .declare_function("program", Linkage::Local, &cx.func.signature)
+      .ok()?;
+    cx.func.name = UserFuncName::user(0, id.as_u32());
+    module.define_function(id, &mut cx).ok()?;
+    module.clear_context(&mut cx);
+    module.finalize_definitions().ok()?;
+
+    let code = module.get_finalized_function(id);
+    Some(Native {
+      module: Some(module),
+      // SAFETY: compiled with `Entry`'s signature
+      entry: unsafe { std::mem::transmute::<*const u8, Entry>(code) },
+    })
+  }
+}
+
+impl Drop for Native {
+  fn drop(&mut self) {
+    if let Some(module) = self.module.take() {
+      // SAFETY: the program, and every frame running it, is gone
+      unsafe { module.free_memory() }
+    }
+  }
+}
+
+fn isa() -> Option<OwnedTargetIsa> {
+  let mut flags = settings::builder();
+  flags.set("use_colocated_libcalls", "false").ok()?;
+  flags.set("is_pic", "false").ok()?;
+  flags.set("opt_level", "speed").ok()?;
+  let builder = cranelift_native::builder().ok()?;
+  builder.finish(settings::Flags::new(flags)).ok()
+}
+
+// how many values the frame has on the stack before each instruction, None
+// for the ones nothing reaches
+fn heights(code: &[Step]) -> Vec<Option<usize>> {
+  let mut heights = vec![None; code.len()];
+  let mut work = vec![(0, 0)];
+
+  while let Some((pc, h)) = work.pop() {
+    if heights[pc].is_some() {
+      continue;
+    }
+    heights[pc] = Some(h);
+
+    let next = pc + 1;
+    match &code[pc].instr {
+      Instr::Slot(..) | Instr::Quote(..) | Instr::Other(..) => work.push((next, h + 1)),
+      Instr::IsCell | Instr::Incr | Instr::Leave | Instr::HintLeave => work.push((next, h)),
+      Instr::Cons | Instr::Eq | Instr::Enter | Instr::Pin | Instr::Edit(..) | Instr::Scry => {
+        work.push((next, h - 1))
+      }
+      Instr::Branch(to) => work.extend([(next, h - 1), (*to, h - 1)]),
+      Instr::Jump(to) => work.push((*to, h)),
+      // the product of a call takes the place of its formula and subject, or
+      // of the core
+      Instr::Call { tail: false } => work.push((next, h - 1)),
+      Instr::Invk { tail: false, .. } => work.push((next, h)),
+      Instr::HintEnter { clue, skip, .. } => {
+        let h = h - usize::from(*clue);
+        work.extend([(next, h), (*skip, h + 1)]);
+      }
+      Instr::Call { tail: true } | Instr::Invk { tail: true, .. } => {}
+      Instr::Exit(..) | Instr::Crash(..) | Instr::Ret => {}
+    }
+  }
+
+  heights
+}
+
+// what native code calls back into; the words are the stack's, and each one
+// passed in is given up
+extern "C" fn clone(word: u64) {
+  // SAFETY: the word is held, and only borrowed here
+  let noun = ManuallyDrop::new(unsafe { Noun::from_raw(word) });
+  std::mem::forget(Noun::clone(&noun));
+}
+
+extern "C" fn release(word: u64) {
+  // SAFETY: as above, and given up
+  drop(unsafe { Noun::from_raw(word) });
+}
+
+extern "C" fn push(ctx: &mut Ctx<'_>, word: u64) {
+  // SAFETY: as above
+  ctx.vm.stack.push(unsafe { Noun::from_raw(word) });
+}
+
+extern "C" fn pop(ctx: &mut Ctx<'_>) -> u64 {
+  ctx.vm.stack.pop().unwrap().into_raw()
+}
+
+extern "C" fn cons(ctx: &mut Ctx<'_>, head: u64, tail: u64) -> u64 {
+  // SAFETY: as above
+  let (head, tail) = unsafe { (Noun::from_raw(head), Noun::from_raw(tail)) };
+  ctx.interp.cons(head, tail).into_raw()
+}
+
+extern "C" fn enter(ctx: &mut Ctx<'_>, word: u64) {
+  // SAFETY: as above
+  let new = unsafe { Noun::from_raw(word) };
+  ctx.vm.subjects.push(std::mem::replace(ctx.subj, new));
+}
+
+extern "C" fn pin(ctx: &mut Ctx<'_>, word: u64) {
+  // SAFETY: as above
+  let new = Noun::cell(unsafe { Noun::from_raw(word) }, ctx.subj.clone());
+  ctx.vm.subjects.push(std::mem::replace(ctx.subj, new));
+}
+
+extern "C" fn leave(ctx: &mut Ctx<'_>) {
+  *ctx.subj = ctx.vm.subjects.pop().unwrap();
+}
+
+// whether native code does the instruction, at least when it's quick
+fn native(instr: &Instr) -> bool {
+  match instr {
+    Instr::Slot(axis) => axis.as_u64().is_some_and(|axis| axis > 0),
+    Instr::Quote(..)
+    | Instr::Cons
+    | Instr::IsCell
+    | Instr::Incr
+    | Instr::Eq
+    | Instr::Branch(..)
+    | Instr::Jump(..)
+    | Instr::Enter
+    | Instr::Pin
+    | Instr::Leave => true,
+    _ => false,
+  }
+}
+
+// the stack's values are variables, which Cranelift keeps in registers
+struct Lower<'a, 'f> {
+  b: FunctionBuilder<'f>,
+  code: &'a [Step],
+  heights: Vec<Option<usize>>,
+  ctx: Value,
+  start: Block,
+  // where each instruction's code starts
+  blocks: Vec<Block>,
+  // by number of parameters and whether there's a result
+  sigs: HashMap<(usize, bool), SigRef>,
+}
+
+fn var(i: usize) -> Variable {
+  Variable::from_u32(i as u32)
+}
+
+impl<'a, 'f> Lower<'a, 'f> {
+  fn new(mut b: FunctionBuilder<'f>, code: &'a [Step]) -> Self {
+    let heights = heights(code);
+    let depth = heights.iter().flatten().max().map_or(0, |h| h + 1);
+    for i in 0..depth {
+      b.declare_var(var(i), I64);
+    }
+
+    let start = b.create_block();
+    b.append_block_params_for_function_params(start);
+    let blocks = code.iter().map(|_| b.create_block()).collect();
+    Self {
+      ctx: b.block_params(start)[0],
+      b,
+      code,
+      heights,
+      start,
+      blocks,
+      sigs: HashMap::new(),
+    }
+  }
+
+  fn lower(mut self) {
+    let code = self.code;
+
+    // calls in at an instruction native code does, reloading the values on
+    // the stack there, or straight back out at any other
+    self.b.switch_to_block(self.start);
+    let at = self.b.block_params(self.start)[1];
+    let out = self.b.create_block();
+    let stop = self.b.append_block_param(out, I64);
+    let mut entries = vec![];
+    let mut table = vec![];
+    for (pc, step) in code.iter().enumerate() {
+      let call = match self.heights[pc] {
+        Some(h) if native(&step.instr) => {
+          let entry = self.b.create_block();
+          entries.push((entry, pc, h));
+          self.b.func.dfg.block_call(entry, &[])
+        }
+        _ => self.b.func.dfg.block_call(out, &[at]),
+      };
+      table.push(call);
+    }
+    let default = self.b.func.dfg.block_call(out, &[at]);
+    let table = self
+      .b
+      .create_jump_table(JumpTableData::new(default, &table));
+    let index = self.b.ins().ireduce(I32, at);
+    self.b.ins().br_table(index, table);
+
+    self.b.switch_to_block(out);
+    self.b.ins().return_(&[stop]);
+
+    for (entry, pc, h) in entries {
+      self.b.switch_to_block(entry);
+      for i in (0..h).rev() {
+        let word = self.call(pop as *const (), &[self.ctx], true).unwrap();
+        self.b.def_var(var(i), word);
+      }
+      self.b.ins().jump(self.blocks[pc], &[]);
+    }
+
+    for (pc, step) in code.iter().enumerate() {
+      if let Some(h) = self.heights[pc] {
+        self.b.switch_to_block(self.blocks[pc]);
+        self.instr(&step.instr, pc, h);
+      }
+    }
+
+    self.b.seal_all_blocks();
+    self.b.finalize();
+  }
+
+  // ends the instruction's block, in every case
+  fn instr(&mut self, instr: &Instr, pc: usize, h: usize) {
+    let next = self.blocks.get(pc + 1).copied();
+    let slow = self.b.create_block();
+
+    match instr {
+      Instr::Slot(axis) if native(instr) => {
+        let axis = axis.as_u64().unwrap();
+        let mut word = self.subject();
+        for bit in (0..axis.ilog2()).rev() {
+          let cell = self.is_cell(word);
+          let kid = self.b.create_block();
+          self.b.ins().brif(cell, kid, &[], slow, &[]);
+          self.b.switch_to_block(kid);
+          let at = self.b.ins().band_imm(word, !(HEAP | TAG) as i64);
+          let offset = match axis >> bit & 1 {
+            0 => offset_of!(Cell, head),
+            _ => offset_of!(Cell, tail),
+          };
+          word = self
+            .b
+            .ins()
+            .load(I64, MemFlags::trusted(), at, offset as i32);
+        }
+        self.retain(word);
+        self.b.def_var(var(h), word);
+      }
+      Instr::Quote(noun) => {
+        let word = self.b.ins().iconst(I64, noun.raw() as i64);
+        if noun.raw() >= HEAP {
+          self.call(clone as *const (), &[word], false);
+        }
+        self.b.def_var(var(h), word);
+      }
+      Instr::Cons => {
+        let (head, tail) = (self.b.use_var(var(h - 2)), self.b.use_var(var(h - 1)));
+        let cell = self.call(cons as *const (), &[self.ctx, head, tail], true);
+        self.b.def_var(var(h - 2), cell.unwrap());
+      }
+      Instr::IsCell => {
+        let word = self.b.use_var(var(h - 1));
+        let cell = self.is_cell(word);
+        let loobean = self.loobean(cell);
+        self.release(word);
+        self.b.def_var(var(h - 1), loobean);
+      }
+      Instr::Incr => {
+        // still direct
+        let word = self.b.use_var(var(h - 1));
+        let quick = (self.b.ins()).icmp_imm(IntCC::UnsignedLessThan, word, (HEAP - 1) as i64);
+        let ok = self.b.create_block();
+        self.b.ins().brif(quick, ok, &[], slow, &[]);
+        self.b.switch_to_block(ok);
+        let word = self.b.ins().iadd_imm(word, 1);
+        self.b.def_var(var(h - 1), word);
+      }
+      Instr::Eq => {
+        // both direct
+        let (a, b) = (self.b.use_var(var(h - 2)), self.b.use_var(var(h - 1)));
+        let either = self.b.ins().bor(a, b);
+        let quick = (self.b.ins()).icmp_imm(IntCC::UnsignedLessThan, either, HEAP as i64);
+        let ok = self.b.create_block();
+        self.b.ins().brif(quick, ok, &[], slow, &[]);
+        self.b.switch_to_block(ok);
+        let same = self.b.ins().icmp(IntCC::Equal, a, b);
+        let loobean = self.loobean(same);
+        self.b.def_var(var(h - 2), loobean);
+      }
+      Instr::Branch(nah) => {
+        let cond = self.b.use_var(var(h - 1));
+        let (yes, check) = (next.unwrap(), self.b.create_block());
+        let is_yes = self.b.ins().icmp_imm(IntCC::Equal, cond, 0);
+        self.b.ins().brif(is_yes, yes, &[], check, &[]);
+        self.b.switch_to_block(check);
+        let is_nah = self.b.ins().icmp_imm(IntCC::Equal, cond, 1);
+        self.b.ins().brif(is_nah, self.blocks[*nah], &[], slow, &[]);
+        self.b.switch_to_block(slow);
+        return self.stop(pc, h);
+      }
+      Instr::Jump(to) => {
+        self.b.ins().jump(self.blocks[*to], &[]);
+        return;
+      }
+      Instr::Enter | Instr::Pin => {
+        let word = self.b.use_var(var(h - 1));
+        let f = if let Instr::Enter = instr {
+          enter as *const ()
+        } else {
+          pin as *const ()
+        };
+        self.call(f, &[self.ctx, word], false);
+      }
+      Instr::Leave => {
+        self.call(leave as *const (), &[self.ctx], false);
+      }
+      _ => return self.stop(pc, h),
+    }
+
+    self.b.ins().jump(next.unwrap(), &[]);
+    // what the quick case couldn't do, the machine does
+    self.b.switch_to_block(slow);
+    self.stop(pc, h);
+  }
+
+  // spills the stack there and hands the machine the instruction at `pc`
+  fn stop(&mut self, pc: usize, h: usize) {
+    for i in 0..h {
+      let word = self.b.use_var(var(i));
+      self.call(push as *const (), &[self.ctx, word], false);
+    }
+    let pc = self.b.ins().iconst(I64, pc as i64);
+    self.b.ins().return_(&[pc]);
+  }
+
+  fn subject(&mut self) -> Value {
+    let flags = MemFlags::trusted();
+    let subj = (self.b.ins()).load(I64, flags, self.ctx, offset_of!(Ctx, subj) as i32);
+    self.b.ins().load(I64, flags, subj, 0)
+  }
+
+  fn is_cell(&mut self, word: Value) -> Value {
+    let tag = self.b.ins().band_imm(word, (HEAP | TAG) as i64);
+    self
+      .b
+      .ins()
+      .icmp_imm(IntCC::Equal, tag, (HEAP | CELL) as i64)
+  }
+
+  // `YES` when set
+  fn loobean(&mut self, cond: Value) -> Value {
+    let (yes, nah) = (self.b.ins().iconst(I64, 0), self.b.ins().iconst(I64, 1));
+    self.b.ins().select(cond, yes, nah)
+  }
+
+  // `f` on the word when it points to something
+  fn on_heap(&mut self, word: Value, f: *const ()) {
+    let (heap, after) = (self.b.create_block(), self.b.create_block());
+    let is_heap = (self.b.ins()).icmp_imm(IntCC::UnsignedGreaterThanOrEqual, word, HEAP as i64);
+    self.b.ins().brif(is_heap, heap, &[], after, &[]);
+    self.b.switch_to_block(heap);
+    self.call(f, &[word], false);
+    self.b.ins().jump(after, &[]);
+    self.b.switch_to_block(after);
+  }
+
+  fn retain(&mut self, word: Value) {
+    self.on_heap(word, clone as *const ());
+  }
+
+  fn release(&mut self, word: Value) {
+    self.on_heap(word, release as *const ());
+  }
+
+  fn call(&mut self, f: *const (), args: &[Value], result: bool) -> Option<Value> {
+    let call_conv = self.b.func.signature.call_conv;
+    let sig = *self.sigs.entry((args.len(), result)).or_insert_with(|| {
+      let mut sig = Signature::new(call_conv);
+      sig.params.extend(args.iter().map(|_| AbiParam::new(I64)));
+      sig.returns.extend(result.then(|| AbiParam::new(I64)));
+      self.b.import_signature(sig)
+    });
+    let f = self.b.ins().iconst(I64, f as i64);
+    let call = self.b.ins().call_indirect(sig, f, args);
+    self.b.inst_results(call).first().copied()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    error::NockError,
+    interp::{Engine, Formula, Interpreter, Subject},
+    noun::Noun,
+    tagged::HEAP,
+    testing::both,
+  };
+
+  #[test]
+  fn test_jit() {
+    let cord = |s| Noun::atom(Atom::from_cord(s));
+    let big = Noun::from(HEAP);
+    let run = |subject, formula| both(Engine::Jit, subject, formula);
+
+    assert_eq!(
+      run(
+        noun!({{1, 2}, 3}),
+        noun!({{addr, 5}, {{idty, 7}, {addr, 1}}})
+      ),
+      Ok(noun!({2, {7, {{1, 2}, 3}}}))
+    );
+    assert_eq!(
+      run(noun!(0), noun!({{idty, (big.clone())}, {idty, {1, 2}}})),
+      Ok(noun!({(big.clone()), {1, 2}}))
+    );
+    assert_eq!(
+      run(noun!({1, 2}), noun!({{cell, {addr, 1}}, {cell, {addr, 2}}})),
+      Ok(noun!({0, 1}))
+    );
+    // past the last direct atom, and on to two words
+    assert_eq!(
+      run(Noun::from(HEAP - 2), noun!({incr, {incr, {addr, 1}}})),
+      Ok(big.clone())
+    );
+    assert_eq!(
+      run(Noun::from(u64::MAX), noun!({incr, {addr, 1}})),
+      Ok(Noun::atom(Atom::from_words(&[0, 1])))
+    );
+    assert_eq!(
+      run(
+        noun!({{5, 5}, 6}),
+        noun!({{eqal, {{addr, 4}, {addr, 5}}}, {eqal, {{addr, 2}, {addr, 3}}}})
+      ),
+      Ok(noun!({0, 1}))
+    );
+    assert_eq!(
+      run(
+        big.clone(),
+        noun!({eqal, {{addr, 1}, {idty, (big.clone())}}})
+      ),
+      Ok(noun!(0))
+    );
+    assert_eq!(
+      run(
+        noun!(1),
+        noun!({{brch, {{addr, 1}, {{idty, 5}, {idty, 6}}}}, {idty, 7}})
+      ),
+      Ok(noun!({6, 7}))
+    );
+    assert_eq!(
+      run(
+        noun!({1, 2}),
+        noun!({{cmps, {{addr, 3}, {incr, {addr, 1}}}}, {addr, 1}})
+      ),
+      Ok(noun!({3, {1, 2}}))
+    );
+    assert_eq!(
+      run(noun!(7), noun!({{extn, {{idty, 1}, {addr, 1}}}, {addr, 1}})),
+      Ok(noun!({{1, 7}, 7}))
+    );
+    // through the machine and back
+    assert_eq!(
+      run(
+        noun!({1, 2}),
+        noun!({{addr, 2}, {rplc, {{2, {idty, 9}}, {addr, 1}}}})
+      ),
+      Ok(noun!({1, {9, 2}}))
+    );
+    assert_eq!(
+      run(
+        noun!(3),
+        noun!({{addr, 1}, {eval, {{addr, 1}, {idty, {incr, {addr, 1}}}}}})
+      ),
+      Ok(noun!({3, 4}))
+    );
+    assert_eq!(
+      run(
+        noun!(3),
+        noun!({hint, {{(cord("memo")), {idty, 0}}, {incr, {addr, 1}}}})
+      ),
+      Ok(noun!(4))
+    );
+
+    // errors are the machine's
+    assert!(run(noun!(0), noun!({{addr, 2}, {idty, 1}})).is_err());
+    assert!(run(noun!({1, 2}), noun!({incr, {addr, 1}})).is_err());
+    assert!(run(noun!(0), noun!({brch, {{idty, 2}, {{idty, 5}, {idty, 6}}}})).is_err());
+    assert!(
+      run(
+        noun!(0),
+        noun!({hint, {{(cord("spot")), {idty, 1}}, {{addr, 1}, {cmps, {{addr, 1}, {addr, 6}}}}}})
+      )
+      .is_err()
+    );
+
+    // and everything ran native
+    let formula = noun!({{addr, 2}, {eval, {{addr, 1}, {idty, {incr, {addr, 3}}}}}});
+    let mut it = Interpreter::builder()
+      .engine(Engine::Jit)
+      .jit_after(0)
+      .build();
+    let p = it.eval(Subject(noun!({1, 2})), Formula(formula));
+    assert_eq!(p.unwrap(), noun!({1, 3}));
+    assert_eq!(it.programs.len(), 2);
+    assert!(it.programs.values().all(|p| p.hot.native().is_some()));
+  }
+
+  #[test]
+  fn test_jit_hot() {
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let mut it = Interpreter::builder()
+      .engine(Engine::Jit)
+      .jit_after(1)
+      .build();
+
+    // the loop's arm once it's been called, the whole formula on its second
+    // call
+    let p = it.eval(Subject(noun!(10)), Formula(decrement.clone()));
+    assert_eq!(p.unwrap(), noun!(9));
+    assert!(it.programs[&decrement].hot.native().is_none());
+    assert_eq!(
+      it.programs
+        .values()
+        .filter(|p| p.hot.native().is_some())
+        .count(),
+      1
+    );
+    let p = it.eval(Subject(noun!(10)), Formula(decrement.clone()));
+    assert_eq!(p.unwrap(), noun!(9));
+    assert!(it.programs[&decrement].hot.native().is_some());
+
+    // an arm of a labeled core straight away
+    let fast = Noun::atom(Atom::from_cord("fast"));
+    let root = Noun::atom(Atom::from_cord("root"));
+    let core = noun!({hint, {{(fast), {idty, {(root), {{idty, 0}, 0}}}}, {idty, {{addr, 3}, 7}}}});
+    let mut it = Interpreter::builder()
+      .engine(Engine::Jit)
+      .jit_after(u32::MAX)
+      .build();
+    let core = it.eval(Subject(noun!(0)), Formula(core)).unwrap();
+    let p = it.eval(
+      Subject(noun!({0, (core)})),
+      Formula(noun!({invk, {2, {addr, 3}}})),
+    );
+    assert_eq!(p.unwrap(), noun!(7));
+    assert!(it.programs[&noun!({addr, 3})].hot.native().is_some());
+    assert!(
+      it.programs[&noun!({invk, {2, {addr, 3}}})]
+        .hot
+        .native()
+        .is_none()
+    );
+  }
+
+  #[test]
+  fn test_jit_deep() {
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let mut it = Interpreter::builder()
+      .engine(Engine::Jit)
+      .depth_limit(4)
+      .build();
+    assert_eq!(
+      it.eval(Subject(Noun::from(100_000u64)), Formula(decrement))
+        .unwrap(),
+      Noun::from(99_999u64)
+    );
+
+    // a native arm calling itself, not in tail position, nests on the
+    // machine rather than the native stack
+    let limit = Noun::from(100_000u64);
+    let battery = noun!({brch, {{eqal, {{addr, 3}, {idty, (limit)}}}, {{idty, 0}, {incr, {invk, {2, {{addr, 2}, {incr, {addr, 3}}}}}}}}});
+    let core = noun!({(battery.clone()), 0});
+    let formula = noun!({invk, {2, {addr, 1}}});
+    let mut it = Interpreter::builder().engine(Engine::Jit).build();
+    assert_eq!(
+      it.eval(Subject(core.clone()), Formula(formula.clone()))
+        .unwrap(),
+      Noun::from(100_000u64)
+    );
+    assert!(it.programs[&battery].hot.native().is_some());
+
+    let mut it = Interpreter::builder()
+      .engine(Engine::Jit)
+      .depth_limit(100)
+      .build();
+    assert!(matches!(
+      it.eval(Subject(core), Formula(formula)).unwrap_err().root(),
+      NockError::DepthLimit
+    ));
+  }
+}
@@ -0,0 +1,274 @@
+// JSON as hoon's `json` type has it, so nock code reading or writing JSON
+// with `de-json` and `en-json` gets the nouns it expects:
+//
+//   null     ~
+//   true     [%b &], and false [%b |]
+//   1.5      [%n '1.5'], the number as it's written
+//   "text"   [%s 'text']
+//   [...]    [%a <list>]
+//   {...}    [%o <map>], keyed by cords
+//
+// A map is hoon's treap: a node is `[[key value] left right]`, the keys in
+// `gor` order, mug first, and every node before its children in `mor` order,
+// by the mug of the mug. Either order falls back on `dor` when the mugs are
+// equal, which is the order nouns already sort in. There's only one such
+// tree for any set of keys, so it's the tree `put:by` would have built.
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+  atom::{Atom, NAH, YES},
+  noun::Noun,
+};
+
+const A: u64 = 0x61;
+const B: u64 = 0x62;
+const N: u64 = 0x6e;
+const O: u64 = 0x6f;
+const S: u64 = 0x73;
+
+#[derive(Clone, Debug)]
+pub struct JsonError {
+  pub found: Noun,
+  pub expected: &'static str,
+}
+
+impl std::fmt::Display for JsonError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "expected {}, found {}", self.expected, self.found)
+  }
+}
+
+impl std::error::Error for JsonError {}
+
+fn cord(text: &str) -> Noun {
+  Noun::from(Atom::from_cord(text))
+}
+
+fn text(noun: &Noun, expected: &'static str) -> Result<String, JsonError> {
+  let error = || JsonError {
+    found: noun.clone(),
+    expected,
+  };
+  noun
+    .as_atom()
+    .ok_or_else(error)?
+    .to_cord()
+    .map_err(|_| error())
+}
+
+// a map of `entries`, whose keys are all different
+fn map(mut entries: Vec<(Noun, Noun)>) -> Noun {
+  // the priority of a key, lowest first
+  let mor = |key: &Noun| Noun::from(u64::from(key.mug())).mug();
+
+  entries.sort_by(|(a, _), (b, _)| a.mug().cmp(&b.mug()).then_with(|| a.cmp(b)));
+  let entries: Vec<_> = entries
+    .into_iter()
+    .map(|(key, value)| (mor(&key), key, value))
+    .collect();
+
+  fn treap(entries: &[(u32, Noun, Noun)]) -> Noun {
+    let Some(root) = (0..entries.len()).min_by(|&i, &j| {
+      let ((a, a_key, _), (b, b_key, _)) = (&entries[i], &entries[j]);
+      a.cmp(b).then_with(|| a_key.cmp(b_key))
+    }) else {
+      return Noun::NULL;
+    };
+
+    let (_, key, value) = &entries[root];
+    let node = Noun::cell(key.clone(), value.clone());
+    let children = Noun::cell(treap(&entries[..root]), treap(&entries[root + 1..]));
+    Noun::cell(node, children)
+  }
+
+  treap(&entries)
+}
+
+// the entries of a map, in order
+fn entries<'a>(map: &'a Noun, out: &mut Vec<(&'a Noun, &'a Noun)>) -> Result<(), JsonError> {
+  if *map == Noun::NULL {
+    return Ok(());
+  }
+
+  let error = || JsonError {
+    found: map.clone(),
+    expected: "a map",
+  };
+  let (node, children) = map.as_cell().ok_or_else(error)?;
+  let (entry, (left, right)) = (
+    node.as_cell().ok_or_else(error)?,
+    children.as_cell().ok_or_else(error)?,
+  );
+
+  entries(left, out)?;
+  out.push(entry);
+  entries(right, out)
+}
+
+impl Noun {
+  pub fn from_json(json: &Value) -> Noun {
+    let tagged = |tag: u64, noun: Noun| Noun::cell(Noun::from(tag), noun);
+
+    match json {
+      Value::Null => Noun::NULL,
+      Value::Bool(true) => tagged(B, Noun::from(YES)),
+      Value::Bool(false) => tagged(B, Noun::from(NAH)),
+      Value::Number(number) => tagged(N, cord(&number.to_string())),
+      Value::String(string) => tagged(S, cord(string)),
+      Value::Array(items) => tagged(A, Noun::list(items.iter().map(Noun::from_json))),
+      Value::Object(object) => {
+        let entries = object
+          .iter()
+          .map(|(key, value)| (cord(key), Noun::from_json(value)))
+          .collect();
+        tagged(O, map(entries))
+      }
+    }
+  }
+
+  pub fn to_json(&self) -> Result<Value, JsonError> {
+    if *self == Noun::NULL {
+      return Ok(Value::Null);
+    }
+
+    let error = |expected| JsonError {
+      found: self.clone(),
+      expected,
+    };
+    let (tag, value) = self.as_cell().ok_or_else(|| error("json"))?;
+
+    match tag.as_atom().and_then(Atom::as_u64) {
+      Some(B) => match value.as_atom().and_then(Atom::as_u64) {
+        Some(YES) => Ok(Value::Bool(true)),
+        Some(NAH) => Ok(Value::Bool(false)),
+        _ => Err(error("a loobean")),
+      },
+      Some(N) => {
+        let number = text(value, "a number")?;
+        let number = number.parse::<Number>().map_err(|_| error("a number"))?;
+        Ok(Value::Number(number))
+      }
+      Some(S) => Ok(Value::String(text(value, "text")?)),
+      Some(A) => value
+        .iter_list()
+        .map(|item| item.map_err(|_| error("a list"))?.to_json())
+        .collect::<Result<_, _>>()
+        .map(Value::Array),
+      Some(O) => {
+        let mut pairs = vec![];
+        entries(value, &mut pairs)?;
+
+        let mut object = Map::new();
+        for (key, value) in pairs {
+          object.insert(text(key, "a key")?, value.to_json()?);
+        }
+        Ok(Value::Object(object))
+      }
+      _ => Err(error("json")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use serde_json::json;
+
+  use super::map;
+  use crate::{atom::Atom, noun::Noun};
+
+  fn cord(text: &str) -> Noun {
+    Noun::from(Atom::from_cord(text))
+  }
+
+  #[test]
+  fn test_json() {
+    let json = json!({
+      "name": "nuuk",
+      "tags": ["nock", 1, 2.5, null],
+      "fast": true,
+      "slow": false,
+      "nested": {"a": {}},
+    });
+    let noun = Noun::from_json(&json);
+    assert_eq!(noun.to_json().unwrap(), json);
+
+    assert_eq!(Noun::from_json(&json!(null)), noun!(0));
+    assert_eq!(Noun::from_json(&json!(true)), noun!({0x62, 0}));
+    let list = Noun::from_json(&json!(["a", 7]));
+    let a = Noun::cell(Noun::from(0x73u64), cord("a"));
+    let seven = Noun::cell(Noun::from(0x6eu64), cord("7"));
+    assert_eq!(list, Noun::cell(noun!(0x61), Noun::list([a, seven])));
+
+    // not json
+    for bad in [
+      noun!(1),
+      noun!({0x62, 2}),
+      noun!({0x6e, 0x78}),
+      noun!({0x61, {0, 1}}),
+      noun!({0x6f, 5}),
+      noun!({0x7a, 0}),
+    ] {
+      assert!(bad.to_json().is_err(), "{bad}");
+    }
+  }
+
+  // hoon's put:by, one entry at a time
+  fn put(map: &Noun, key: &Noun, value: &Noun) -> Noun {
+    let mug2 = |key: &Noun| Noun::from(u64::from(key.mug())).mug();
+    let gor = |a: &Noun, b: &Noun| (a.mug(), a) <= (b.mug(), b);
+    let mor = |a: &Noun, b: &Noun| (mug2(a), a) <= (mug2(b), b);
+    let parts = |map: &Noun| {
+      let (node, children) = map.as_cell().unwrap();
+      let (key, _) = node.as_cell().unwrap();
+      let (left, right) = children.as_cell().unwrap();
+      (node.clone(), key.clone(), left.clone(), right.clone())
+    };
+    let node =
+      |node: &Noun, left: Noun, right: Noun| Noun::cell(node.clone(), Noun::cell(left, right));
+
+    if *map == Noun::NULL {
+      return node(
+        &Noun::cell(key.clone(), value.clone()),
+        Noun::NULL,
+        Noun::NULL,
+      );
+    }
+    let (n, k, l, r) = parts(map);
+    if *key == k {
+      return node(&Noun::cell(key.clone(), value.clone()), l, r);
+    }
+    if gor(key, &k) {
+      let d = put(&l, key, value);
+      let (dn, dk, dl, dr) = parts(&d);
+      if mor(&k, &dk) {
+        node(&n, d, r)
+      } else {
+        node(&dn, dl, node(&n, dr, r))
+      }
+    } else {
+      let d = put(&r, key, value);
+      let (dn, dk, dl, dr) = parts(&d);
+      if mor(&k, &dk) {
+        node(&n, l, d)
+      } else {
+        node(&dn, node(&n, l, dl), dr)
+      }
+    }
+  }
+
+  #[test]
+  fn test_map() {
+    let entries: Vec<_> = (0..300u64)
+      .map(|n| (Noun::from(n * 7919 % 300), Noun::from(n)))
+      .chain([(noun!({1, 2}), noun!(0)), (cord("key"), noun!(1))])
+      .collect();
+
+    let put = entries
+      .iter()
+      .fold(Noun::NULL, |map, (key, value)| put(&map, key, value));
+    assert_eq!(map(entries.clone()), put);
+    assert_eq!(map(entries.into_iter().rev().collect()), put);
+    assert_eq!(map(vec![]), Noun::NULL);
+  }
+}
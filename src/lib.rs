@@ -0,0 +1,80 @@
+// lets `nock!` expansions name `::nuuk` from inside this crate
+extern crate self as nuuk;
+
+#[macro_use]
+mod macros;
+
+pub mod atom;
+pub mod aura;
+pub mod axis;
+pub mod bench;
+pub mod bits;
+mod bytecode;
+mod cache;
+pub mod cbor;
+mod closure;
+pub mod compile;
+mod convert;
+pub mod cursor;
+pub mod date;
+pub mod encoding;
+pub mod error;
+pub mod fast;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "sync")]
+mod fork;
+pub mod interp;
+pub mod jet;
+#[cfg(feature = "jit")]
+mod jit;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "loom")]
+pub mod loom;
+pub mod math;
+mod murmur;
+#[cfg(feature = "node")]
+mod node;
+pub mod noun;
+pub mod opcode;
+pub mod parse;
+pub mod patp;
+pub mod pill;
+#[cfg(feature = "sync")]
+pub mod pool;
+#[cfg(feature = "nuuk-py")]
+mod python;
+#[cfg(feature = "road")]
+pub mod road;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod serial;
+#[cfg(feature = "slab")]
+pub mod slab;
+pub mod snapshot;
+pub mod spec;
+#[cfg(feature = "tagged")]
+mod tagged;
+#[cfg(test)]
+mod testing;
+pub mod transpile;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "macros")]
+pub use nuuk_macros::nock;
+
+pub use atom::Atom;
+pub use compile::CompiledFormula;
+pub use error::{Crash, NockError};
+pub use interp::{AtomFormula, Engine, Formula, HintHandler, Interpreter, SoftResult, Subject};
+pub use noun::Noun;
+pub use opcode::Opcode;
+#[cfg(feature = "sync")]
+pub use pool::EvalPool;
+
+pub fn eval(subject: Subject, formula: Formula) -> Result<Noun, NockError> {
+  Interpreter::new().eval(subject, formula)
+}
@@ -0,0 +1,450 @@
+// Nouns kept in one contiguous buffer of words and addressed by offsets into
+// it instead of pointers, so a whole loom is saved with a single write and
+// loaded with a single mmap, with nothing to fix up. It's a snapshot format,
+// not a second representation: the interpreter only runs on `Noun`, so state
+// goes in with `put` before a `save` and comes out with `get` after a `map`.
+// `cell`, `atom` and `slot` read in place, to look around a snapshot without
+// copying it out.
+//
+// A `LoomNoun` is one word: an atom below 2^63 is the word itself, and
+// anything else has the top bit set, the next bit set for an indirect atom
+// and clear for a cell, and the offset of its words in the rest. A cell is
+// the words of its head and tail, an indirect atom its length in words and
+// then the words. Children are always written before their parents, which
+// is what lets a load check a loom without recursing.
+//
+// A `LoomNoun` is only an offset, and means something only to the loom it
+// came from. Given one from elsewhere, reads go as far as they stay in
+// bounds with children before parents, and are None past that.
+//
+// The buffer starts with a header, so it's also the file: a magic word, the
+// root, and the length of the buffer in words, all in the machine's byte
+// order.
+
+use std::{
+  collections::{HashMap, HashSet},
+  fs::File,
+  io,
+};
+
+use memmap2::Mmap;
+
+use crate::{
+  atom::Atom,
+  noun::{Cell, Noun, NounInner},
+};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"nuukloo1");
+const HEADER: usize = 3;
+const DIRECT_MAX: u64 = u64::MAX >> 1;
+const INDIRECT: u64 = 1 << 62;
+const OFFSET: u64 = INDIRECT - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LoomNoun(u64);
+
+enum Words {
+  Owned(Vec<u64>),
+  // read only; the first change copies it into memory
+  Mapped(Mmap),
+}
+
+pub struct Loom {
+  words: Words,
+}
+
+impl LoomNoun {
+  pub const NULL: LoomNoun = LoomNoun(0);
+
+  fn direct(self) -> Option<u64> {
+    (self.0 <= DIRECT_MAX).then_some(self.0)
+  }
+
+  fn offset(self) -> usize {
+    (self.0 & OFFSET) as usize
+  }
+
+  fn is_indirect(self) -> bool {
+    self.0 & INDIRECT != 0
+  }
+}
+
+impl Default for Loom {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Loom {
+  pub fn new() -> Self {
+    Self {
+      words: Words::Owned(vec![MAGIC, 0, HEADER as u64]),
+    }
+  }
+
+  fn words(&self) -> &[u64] {
+    match &self.words {
+      Words::Owned(words) => words,
+      // SAFETY: maps are page aligned, and a map is only kept once its
+      // length was checked to be whole words
+      Words::Mapped(map) => unsafe {
+        std::slice::from_raw_parts(map.as_ptr().cast::<u64>(), map.len() / 8)
+      },
+    }
+  }
+
+  fn words_mut(&mut self) -> &mut Vec<u64> {
+    if let Words::Mapped(..) = self.words {
+      self.words = Words::Owned(self.words().to_vec());
+    }
+    let Words::Owned(words) = &mut self.words else {
+      unreachable!()
+    };
+    words
+  }
+
+  fn push(&mut self, tag: u64, new: &[u64]) -> LoomNoun {
+    let words = self.words_mut();
+    let offset = words.len() as u64;
+    assert!(offset + new.len() as u64 <= OFFSET, "loom is full");
+    words.extend_from_slice(new);
+    words[2] = words.len() as u64;
+    LoomNoun(DIRECT_MAX + 1 + tag + offset)
+  }
+
+  // the loom's size in words, header included
+  pub fn len(&self) -> usize {
+    self.words().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == HEADER
+  }
+
+  // the noun a saved loom is loaded for, 0 until set
+  pub fn root(&self) -> LoomNoun {
+    LoomNoun(self.words()[1])
+  }
+
+  pub fn set_root(&mut self, root: LoomNoun) {
+    self.words_mut()[1] = root.0;
+  }
+
+  // copies `noun` in, keeping what it shares within itself shared
+  pub fn put(&mut self, noun: &Noun) -> LoomNoun {
+    enum Task<'a> {
+      Visit(&'a Noun),
+      Build(*const Cell),
+    }
+
+    let mut copies: HashMap<*const Cell, LoomNoun> = HashMap::new();
+    let mut tasks = vec![Task::Visit(noun)];
+    let mut done = vec![];
+
+    while let Some(task) = tasks.pop() {
+      match task {
+        Task::Visit(noun) => match noun.inner() {
+          NounInner::Atom(atom) => done.push(match atom.as_u64() {
+            Some(direct) if direct <= DIRECT_MAX => LoomNoun(direct),
+            _ => {
+              let words = atom.words();
+              let new: Vec<u64> = std::iter::once(words.len() as u64)
+                .chain(words.iter().copied())
+                .collect();
+              self.push(INDIRECT, &new)
+            }
+          }),
+          NounInner::Cell(cell) => match copies.get(&std::ptr::from_ref(cell)) {
+            Some(&copy) => done.push(copy),
+            None => {
+              tasks.push(Task::Build(cell));
+              tasks.push(Task::Visit(&cell.tail));
+              tasks.push(Task::Visit(&cell.head));
+            }
+          },
+        },
+        Task::Build(cell) => {
+          let cdr = done.pop().unwrap();
+          let car = done.pop().unwrap();
+          let copy = self.push(0, &[car.0, cdr.0]);
+          copies.insert(cell, copy);
+          done.push(copy);
+        }
+      }
+    }
+
+    done.pop().unwrap()
+  }
+
+  // copies `noun` out, keeping what it shares within the loom shared
+  pub fn get(&self, noun: LoomNoun) -> Option<Noun> {
+    enum Task {
+      Visit(LoomNoun),
+      Build(usize),
+    }
+
+    let mut copies: HashMap<usize, Noun> = HashMap::new();
+    let mut tasks = vec![Task::Visit(noun)];
+    let mut done = vec![];
+
+    while let Some(task) = tasks.pop() {
+      match task {
+        Task::Visit(noun) => {
+          if noun.direct().is_none()
+            && let Some(copy) = copies.get(&noun.offset())
+          {
+            done.push(copy.clone());
+            continue;
+          }
+          match self.cell(noun) {
+            Some((car, cdr)) => {
+              tasks.push(Task::Build(noun.offset()));
+              tasks.push(Task::Visit(cdr));
+              tasks.push(Task::Visit(car));
+            }
+            None => done.push(Noun::atom(self.atom(noun)?)),
+          }
+        }
+        Task::Build(offset) => {
+          let cdr = done.pop().unwrap();
+          let car = done.pop().unwrap();
+          let copy = Noun::cell(car, cdr);
+          copies.insert(offset, copy.clone());
+          done.push(copy);
+        }
+      }
+    }
+
+    done.pop()
+  }
+
+  // None for atoms, and for what isn't a cell in this loom; children always
+  // come first, so walking down can't go round in circles
+  pub fn cell(&self, noun: LoomNoun) -> Option<(LoomNoun, LoomNoun)> {
+    if noun.direct().is_some() || noun.is_indirect() {
+      return None;
+    }
+
+    let offset = noun.offset();
+    let kids = self.words().get(offset..offset + 2)?;
+    let (car, cdr) = (LoomNoun(kids[0]), LoomNoun(kids[1]));
+    let before = |kid: LoomNoun| kid.direct().is_some() || kid.offset() < offset;
+    (offset >= HEADER && before(car) && before(cdr)).then_some((car, cdr))
+  }
+
+  pub fn atom(&self, noun: LoomNoun) -> Option<Atom> {
+    if let Some(direct) = noun.direct() {
+      return Some(Atom::new(direct));
+    }
+    if !noun.is_indirect() {
+      return None;
+    }
+
+    let offset = noun.offset();
+    let words = self.words();
+    if offset < HEADER {
+      return None;
+    }
+    let len = usize::try_from(*words.get(offset)?).ok()?;
+    let words = words.get(offset + 1..)?.get(..len)?;
+    Some(Atom::from_words(words))
+  }
+
+  // the subtree at `axis`, without copying anything out
+  pub fn slot(&self, noun: LoomNoun, axis: u64) -> Option<LoomNoun> {
+    if axis == 0 {
+      return None;
+    }
+
+    let mut noun = noun;
+    for right in crate::axis::path(&Atom::new(axis)) {
+      let (car, cdr) = self.cell(noun)?;
+      noun = if right { cdr } else { car };
+    }
+
+    Some(noun)
+  }
+
+  // the whole loom in a single write
+  pub fn save(&self, mut writer: impl io::Write) -> io::Result<()> {
+    let words = self.words();
+    // SAFETY: any initialized memory is valid bytes
+    let bytes = unsafe { std::slice::from_raw_parts(words.as_ptr().cast::<u8>(), words.len() * 8) };
+    writer.write_all(bytes)
+  }
+
+  // a saved loom, read into memory in one go
+  pub fn read(mut reader: impl io::Read) -> io::Result<Loom> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() % 8 != 0 {
+      return Err(invalid("not whole words"));
+    }
+
+    let words = bytes
+      .chunks_exact(8)
+      .map(|word| u64::from_ne_bytes(word.try_into().unwrap()))
+      .collect();
+    let loom = Loom {
+      words: Words::Owned(words),
+    };
+    loom.check()?;
+    Ok(loom)
+  }
+
+  /// A saved loom, mapped from `file` rather than read. Nouns are read
+  /// straight out of the map, and the first change copies it into memory.
+  ///
+  /// # Safety
+  ///
+  /// Nothing else may write to `file` until the loom is dropped, see
+  /// `memmap2::Mmap::map`.
+  pub unsafe fn map(file: &File) -> io::Result<Loom> {
+    // SAFETY: as the caller promised
+    let map = unsafe { Mmap::map(file)? };
+    if map.len() % 8 != 0 || map.len() < HEADER * 8 {
+      return Err(invalid("not a loom"));
+    }
+
+    let loom = Loom {
+      words: Words::Mapped(map),
+    };
+    loom.check()?;
+    Ok(loom)
+  }
+
+  // everything reachable from the root is within the loom, and children come
+  // before their parents, so there are no cycles
+  fn check(&self) -> io::Result<()> {
+    let words = self.words();
+    if words.len() < HEADER || words[0] != MAGIC {
+      return Err(invalid("not a loom"));
+    }
+    if words[2] != words.len() as u64 {
+      return Err(invalid("wrong length"));
+    }
+
+    // cells whose children are checked, or waiting to be
+    let mut seen = HashSet::new();
+    // nouns, and where the noun referring to them starts
+    let mut pending = vec![(LoomNoun(words[1]), words.len())];
+    while let Some((noun, below)) = pending.pop() {
+      if noun.direct().is_some() {
+        continue;
+      }
+
+      let offset = noun.offset();
+      let size = if noun.is_indirect() {
+        words
+          .get(offset)
+          .map_or(u64::MAX, |&len| len.saturating_add(1))
+      } else {
+        2
+      };
+      if offset < HEADER || (offset as u64).saturating_add(size) > below as u64 {
+        return Err(invalid("noun out of bounds"));
+      }
+
+      if !noun.is_indirect() && seen.insert(noun) {
+        pending.push((LoomNoun(words[offset]), offset));
+        pending.push((LoomNoun(words[offset + 1]), offset));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Loom, LoomNoun};
+  use crate::{atom::Atom, noun::Noun};
+
+  #[test]
+  fn test_loom() {
+    let big = Noun::atom(Atom::from_words(&[1, 2, 3]));
+    let shared = noun!({(big), {1, 2}});
+    let noun = noun!({(shared.clone()), {(shared), {(u64::MAX), 0}}});
+
+    let mut loom = Loom::new();
+    let root = loom.put(&noun);
+    // the shared cell is in once
+    assert_eq!(loom.len(), 3 + (4 + 2) + 5 * 2);
+    loom.set_root(root);
+
+    let out = loom.get(root).unwrap();
+    assert_eq!(out, noun);
+    assert!(out.slot(2).unwrap().ptr_eq(&out.slot(6).unwrap()));
+    assert_eq!(
+      loom
+        .slot(root, 7)
+        .and_then(|n| loom.cell(n))
+        .map(|(car, _)| loom.atom(car)),
+      Some(Some(Atom::new(u64::MAX)))
+    );
+    assert_eq!(loom.slot(root, 15), Some(LoomNoun::NULL));
+    assert_eq!(loom.slot(root, 31), None);
+
+    // saved and read, or mapped
+    let mut bytes = vec![];
+    loom.save(&mut bytes).unwrap();
+    let read = Loom::read(&bytes[..]).unwrap();
+    assert_eq!(read.get(read.root()), Some(noun.clone()));
+
+    let path = std::env::temp_dir().join(format!("nuuk-loom-{}", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    // SAFETY: the file is this test's own
+    let mut mapped = unsafe { Loom::map(&file) }.unwrap();
+    assert_eq!(mapped.get(mapped.root()), Some(noun));
+    // changes go to memory, not to the file
+    let more = mapped.put(&noun!({7, 8}));
+    mapped.set_root(more);
+    assert_eq!(mapped.get(mapped.root()), Some(noun!({7, 8})));
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_loom_check() {
+    let mut loom = Loom::new();
+    let root = loom.put(&noun!({1, {2, 3}}));
+    loom.set_root(root);
+    let mut bytes = vec![];
+    loom.save(&mut bytes).unwrap();
+
+    assert!(Loom::read(&bytes[..bytes.len() - 8]).is_err());
+    assert!(Loom::read(&bytes[1..]).is_err());
+    assert!(Loom::read(&b"not a loom, sorry"[..]).is_err());
+
+    // the outer cell's tail pointing at itself
+    let mut cycle = bytes.clone();
+    let tail = bytes.len() - 8;
+    cycle[tail..].copy_from_slice(&root.0.to_ne_bytes());
+    assert!(Loom::read(&cycle[..]).is_err());
+
+    // another loom's nouns, or made up ones, read as nothing
+    let small = Loom::new();
+    assert_eq!(small.get(root), None);
+    assert_eq!(small.cell(root), None);
+    assert_eq!(small.slot(root, 3), None);
+    let indirect = Loom::new().put(&Noun::atom(Atom::from_words(&[1, 2])));
+    assert_eq!(small.atom(indirect), None);
+    assert_eq!(loom.atom(LoomNoun(u64::MAX)), None);
+    assert_eq!(loom.get(LoomNoun(1 << 63)), None);
+
+    // deep nouns go in and out without recursing
+    let deep = (0..100_000u64).fold(noun!(0), |n, i| Noun::cell(Noun::from(i), n));
+    let mut loom = Loom::new();
+    let root = loom.put(&deep);
+    loom.set_root(root);
+    bytes.clear();
+    loom.save(&mut bytes).unwrap();
+    let read = Loom::read(&bytes[..]).unwrap();
+    assert_eq!(read.get(read.root()), Some(deep));
+  }
+}
@@ -0,0 +1,206 @@
+// Matches a noun against cell/atom shapes, trying each arm in order:
+//
+//   noun_match!(formula,
+//     {{b, c}, d} => autocons(b, c, d),
+//     {0, b} => slot(b),
+//     _ => Err(..),
+//   )
+//
+// Identifiers bind `&Noun`s, integer literals match atoms, `_` matches
+// anything, and `{p, q}` matches a cell. The last arm must be `_` or a
+// binding.
+#[macro_export]
+macro_rules! noun_match {
+  (@arms $noun:ident $done:lifetime _ => $else:expr $(,)?) => {
+    $else
+  };
+  (@arms $noun:ident $done:lifetime $name:ident => $else:expr $(,)?) => {{
+    let $name: &$crate::Noun = $noun;
+    $else
+  }};
+  (@arms $noun:ident $done:lifetime) => {
+    compile_error!("noun_match! needs a final catch-all arm")
+  };
+  (@arms $noun:ident $done:lifetime $pat:tt => $body:expr, $($rest:tt)*) => {{
+    'arm: {
+      $crate::noun_match!(@bind $noun 'arm $pat);
+      #[allow(unreachable_code)]
+      break $done ($body);
+    }
+    $crate::noun_match!(@arms $noun $done $($rest)*)
+  }};
+
+  (@bind $noun:ident $fail:lifetime _) => {};
+  (@bind $noun:ident $fail:lifetime { $car:tt, $cdr:tt }) => {
+    #[allow(unused_variables)]
+    let Some((car, cdr)) = $noun.as_cell() else {
+      break $fail;
+    };
+    $crate::noun_match!(@bind car $fail $car);
+    $crate::noun_match!(@bind cdr $fail $cdr);
+  };
+  (@bind $noun:ident $fail:lifetime $atom:literal) => {
+    if $noun.as_atom() != Some(&$crate::Atom::new($atom)) {
+      break $fail;
+    }
+  };
+  (@bind $noun:ident $fail:lifetime $name:ident) => {
+    let $name: &$crate::Noun = $noun;
+  };
+
+  ($noun:expr, $($arms:tt)*) => {{
+    let noun: &$crate::Noun = &$noun;
+    'noun_match: {
+      $crate::noun_match!(@arms noun 'noun_match $($arms)*)
+    }
+  }};
+}
+
+// Builds a noun from a terse literal syntax:
+//
+//   noun!({a, b})      cell of `a` and `b`
+//   noun!([a b c])     right-nested tuple, {a, {b, c}}
+//   noun!([a b c ~])   null-terminated list
+//   noun!(~)           0
+//   noun!(addr)        an opcode by name, `addr` through `scry`
+//   noun!(42)          an atom
+//   noun!(x)           splices anything `Into<Noun>`, use `(expr)` for
+//                      arbitrary expressions
+#[macro_export]
+macro_rules! noun {
+  ({ $a:tt, $b:tt }) => {
+    $crate::Noun::cell($crate::noun!($a), $crate::noun!($b))
+  };
+  ([ $a:tt ]) => {
+    $crate::noun!($a)
+  };
+  ([ $a:tt $($rest:tt)+ ]) => {
+    $crate::Noun::cell($crate::noun!($a), $crate::noun!([$($rest)+]))
+  };
+  (~) => {
+    $crate::Noun::NULL
+  };
+  (addr) => {
+    $crate::Noun::from($crate::Opcode::Addr)
+  };
+  (idty) => {
+    $crate::Noun::from($crate::Opcode::Idty)
+  };
+  (eval) => {
+    $crate::Noun::from($crate::Opcode::Eval)
+  };
+  (cell) => {
+    $crate::Noun::from($crate::Opcode::Cell)
+  };
+  (incr) => {
+    $crate::Noun::from($crate::Opcode::Incr)
+  };
+  (eqal) => {
+    $crate::Noun::from($crate::Opcode::Eqal)
+  };
+  (brch) => {
+    $crate::Noun::from($crate::Opcode::Brch)
+  };
+  (cmps) => {
+    $crate::Noun::from($crate::Opcode::Cmps)
+  };
+  (extn) => {
+    $crate::Noun::from($crate::Opcode::Extn)
+  };
+  (invk) => {
+    $crate::Noun::from($crate::Opcode::Invk)
+  };
+  (rplc) => {
+    $crate::Noun::from($crate::Opcode::Rplc)
+  };
+  (hint) => {
+    $crate::Noun::from($crate::Opcode::Hint)
+  };
+  (scry) => {
+    $crate::Noun::from($crate::Opcode::Scry)
+  };
+  ($e:expr) => {
+    $crate::Noun::from($e)
+  };
+}
+
+// `dyn` of the bounds given, and `Sendable` or `Shareable` too, see
+// src/noun.rs
+#[cfg(not(feature = "sync"))]
+macro_rules! dyn_sendable {
+  ($($bounds:tt)*) => { dyn $($bounds)* };
+}
+#[cfg(feature = "sync")]
+macro_rules! dyn_sendable {
+  ($($bounds:tt)*) => { dyn $($bounds)* + Send };
+}
+
+#[cfg(not(feature = "sync"))]
+macro_rules! dyn_shareable {
+  ($($bounds:tt)*) => { dyn $($bounds)* };
+}
+#[cfg(feature = "sync")]
+macro_rules! dyn_shareable {
+  ($($bounds:tt)*) => { dyn $($bounds)* + Send + Sync };
+}
+
+#[cfg(test)]
+mod test {
+  use crate::noun::Noun;
+
+  fn shape(noun: &Noun) -> String {
+    noun_match!(noun,
+      {{a, _}, 0} => format!("pair head {a}"),
+      {0, {b, c}} => format!("slot {b} {c}"),
+      {x, 7} => format!("seven {x}"),
+      42 => "answer".to_string(),
+      n => format!("other {n}"),
+    )
+  }
+
+  #[test]
+  fn test_noun_match() {
+    assert_eq!(shape(&noun!({{1, 2}, 0})), "pair head 1");
+    assert_eq!(shape(&noun!({0, {3, 4}})), "slot 3 4");
+    assert_eq!(shape(&noun!({{1, 2}, 7})), "seven {1 2}");
+    assert_eq!(shape(&noun!(42)), "answer");
+    assert_eq!(shape(&noun!({0, 5})), "other {0 5}");
+  }
+
+  #[test]
+  fn test_noun_splicing() {
+    let x = noun!({1, 2});
+    let n = 5;
+
+    assert_eq!(noun!({(x.clone()), (n + 1)}), noun!({{1, 2}, 6}));
+    assert_eq!(noun!([x (n * 2) true]), noun!({{1, 2}, {10, 0}}));
+    assert_eq!(noun!([1 2 3 ~]), Noun::list([1, 2, 3]));
+    assert_eq!(noun!([{incr, [0 1]}]), noun!({4, {0, 1}}));
+    assert_eq!(noun!(~), noun!(0));
+  }
+
+  #[test]
+  fn test_nock_literal() {
+    use nuuk_macros::nock;
+
+    let f = noun!({incr, {addr, 1}});
+
+    assert_eq!(nock!([4 0 1]), f);
+    assert_eq!(nock!([[0 2] 1 0x2a]), noun!({{addr, 2}, {idty, 42}}));
+    assert_eq!(
+      nock!([6 [0 1] [1 ~] 1 1]),
+      noun!([brch [addr 1] [idty ~] idty 1])
+    );
+    assert_eq!(
+      nock!([7(f.clone())(f)]),
+      noun!([cmps [incr addr 1] incr addr 1])
+    );
+  }
+
+  #[test]
+  fn test_noun_match_fallback() {
+    let n = noun!(1);
+    let r: Result<&Noun, ()> = noun_match!(n, {a, _} => Ok(a), _ => Err(()));
+    assert!(r.is_err());
+  }
+}
@@ -0,0 +1,195 @@
+// Arithmetic over atoms, named after the hoon gates it can stand in for.
+
+use std::cmp::Ordering;
+
+use crate::{
+  atom::{Atom, cmp_words},
+  error::NockError,
+};
+
+pub fn add(a: &Atom, b: &Atom) -> Atom {
+  if let (Some(x), Some(y)) = (a.as_u64(), b.as_u64())
+    && let Some(sum) = x.checked_add(y)
+  {
+    return Atom::new(sum);
+  }
+
+  let (a, b) = (a.words(), b.words());
+  let mut words = Vec::with_capacity(a.len().max(b.len()) + 1);
+  let mut carry = false;
+
+  for i in 0..a.len().max(b.len()) {
+    let x = a.get(i).copied().unwrap_or(0);
+    let y = b.get(i).copied().unwrap_or(0);
+
+    let (sum, c1) = x.overflowing_add(y);
+    let (sum, c2) = sum.overflowing_add(u64::from(carry));
+    words.push(sum);
+    carry = c1 || c2;
+  }
+  words.push(u64::from(carry));
+
+  Atom::from_words(&words)
+}
+
+pub fn sub(a: &Atom, b: &Atom) -> Result<Atom, NockError> {
+  if a < b {
+    return Err(NockError::SubtractUnderflow);
+  }
+
+  let mut words = a.words().to_vec();
+  sub_in(&mut words, b.words());
+  Ok(Atom::from_words(&words))
+}
+
+// a -= b, where a >= b
+fn sub_in(a: &mut [u64], b: &[u64]) {
+  let mut borrow = false;
+
+  for (i, word) in a.iter_mut().enumerate() {
+    let y = b.get(i).copied().unwrap_or(0);
+
+    let (diff, b1) = word.overflowing_sub(y);
+    let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+    *word = diff;
+    borrow = b1 || b2;
+  }
+}
+
+pub fn mul(a: &Atom, b: &Atom) -> Atom {
+  if let (Some(x), Some(y)) = (a.as_u64(), b.as_u64()) {
+    let product = u128::from(x) * u128::from(y);
+    return Atom::from_words(&[product as u64, (product >> 64) as u64]);
+  }
+
+  let (a, b) = (a.words(), b.words());
+  let mut words = vec![0u64; a.len() + b.len()];
+
+  for (i, &x) in a.iter().enumerate() {
+    let mut carry = 0u128;
+
+    for (j, &y) in b.iter().enumerate() {
+      let wide = u128::from(x) * u128::from(y) + u128::from(words[i + j]) + carry;
+      words[i + j] = wide as u64;
+      carry = wide >> 64;
+    }
+
+    words[i + b.len()] = carry as u64;
+  }
+
+  Atom::from_words(&words)
+}
+
+// quotient and remainder
+pub fn div_mod(a: &Atom, b: &Atom) -> Result<(Atom, Atom), NockError> {
+  if b.is_zero() {
+    return Err(NockError::DivideByZero);
+  }
+
+  if let (Some(x), Some(y)) = (a.as_u64(), b.as_u64()) {
+    return Ok((Atom::new(x / y), Atom::new(x % y)));
+  }
+
+  if a < b {
+    return Ok((Atom::new(0), a.clone()));
+  }
+
+  // shift and subtract, one bit of `a` at a time
+  let (a, b) = (a.words(), b.words());
+  let mut quotient = vec![0u64; a.len()];
+  let mut rem: Vec<u64> = vec![0; b.len() + 1];
+
+  for bit in (0..a.len() * 64).rev() {
+    let mut carry = (a[bit / 64] >> (bit % 64)) & 1;
+    for word in rem.iter_mut() {
+      let next = *word >> 63;
+      *word = (*word << 1) | carry;
+      carry = next;
+    }
+
+    if cmp_words(&rem, b) != Ordering::Less {
+      sub_in(&mut rem, b);
+      quotient[bit / 64] |= 1 << (bit % 64);
+    }
+  }
+
+  Ok((Atom::from_words(&quotient), Atom::from_words(&rem)))
+}
+
+pub fn div(a: &Atom, b: &Atom) -> Result<Atom, NockError> {
+  div_mod(a, b).map(|(quotient, _)| quotient)
+}
+
+pub fn modulo(a: &Atom, b: &Atom) -> Result<Atom, NockError> {
+  div_mod(a, b).map(|(_, rem)| rem)
+}
+
+pub fn lth(a: &Atom, b: &Atom) -> bool {
+  a < b
+}
+
+pub fn gth(a: &Atom, b: &Atom) -> bool {
+  a > b
+}
+
+#[cfg(test)]
+mod test {
+  use super::{add, div, div_mod, gth, lth, modulo, mul, sub};
+  use crate::{atom::Atom, error::NockError};
+
+  fn big(digits: &str) -> Atom {
+    Atom::from_digits(digits, 10).unwrap()
+  }
+
+  #[test]
+  fn test_add_sub() {
+    let max = Atom::new(u64::MAX);
+
+    assert_eq!(add(&Atom::new(2), &Atom::new(3)), Atom::new(5));
+    assert_eq!(add(&max, &Atom::new(1)), max.incr());
+    assert_eq!(
+      add(
+        &big("340282366920938463463374607431768211455"),
+        &Atom::new(1)
+      ),
+      big("340282366920938463463374607431768211456")
+    );
+
+    assert_eq!(sub(&max.incr(), &Atom::new(1)).unwrap(), max);
+    assert_eq!(sub(&Atom::new(5), &Atom::new(5)).unwrap(), Atom::new(0));
+    assert!(matches!(
+      sub(&Atom::new(1), &Atom::new(2)),
+      Err(NockError::SubtractUnderflow)
+    ));
+  }
+
+  #[test]
+  fn test_mul_div() {
+    let a = big("123456789012345678901234567890");
+    let b = big("987654321098765432109876543210");
+    let product = big("121932631137021795226185032733622923332237463801111263526900");
+
+    assert_eq!(mul(&a, &b), product);
+    assert_eq!(div(&product, &a).unwrap(), b);
+    assert_eq!(modulo(&product, &a).unwrap(), Atom::new(0));
+    assert_eq!(mul(&Atom::new(6), &Atom::new(7)), Atom::new(42));
+
+    let (q, r) = div_mod(&add(&product, &Atom::new(5)), &b).unwrap();
+    assert_eq!((q, r), (a.clone(), Atom::new(5)));
+    assert_eq!(div(&Atom::new(7), &a).unwrap(), Atom::new(0));
+    assert_eq!(modulo(&Atom::new(7), &Atom::new(4)).unwrap(), Atom::new(3));
+    assert!(matches!(
+      div(&a, &Atom::new(0)),
+      Err(NockError::DivideByZero)
+    ));
+  }
+
+  #[test]
+  fn test_compare() {
+    let a = Atom::new(u64::MAX);
+
+    assert!(lth(&a, &a.incr()));
+    assert!(gth(&a.incr(), &a));
+    assert!(!lth(&a, &a));
+  }
+}
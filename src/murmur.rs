@@ -0,0 +1,51 @@
+// MurmurHash3, x86 32-bit variant, as used by urbit for `muk` and `mug`
+
+pub(crate) fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+  const C1: u32 = 0xcc9e_2d51;
+  const C2: u32 = 0x1b87_3593;
+
+  let mut h = seed;
+  let mut blocks = data.chunks_exact(4);
+
+  for block in &mut blocks {
+    let mut k = u32::from_le_bytes(block.try_into().unwrap());
+    k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    h ^= k;
+    h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+  }
+
+  let tail = blocks.remainder();
+  if !tail.is_empty() {
+    let mut k = tail
+      .iter()
+      .rev()
+      .fold(0u32, |k, &byte| (k << 8) | u32::from(byte));
+    k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    h ^= k;
+  }
+
+  h ^= data.len() as u32;
+  h ^= h >> 16;
+  h = h.wrapping_mul(0x85eb_ca6b);
+  h ^= h >> 13;
+  h = h.wrapping_mul(0xc2b2_ae35);
+  h ^ (h >> 16)
+}
+
+#[cfg(test)]
+mod test {
+  use super::murmur3_32;
+
+  #[test]
+  fn test_murmur3() {
+    assert_eq!(murmur3_32(b"", 0), 0);
+    assert_eq!(murmur3_32(b"", 1), 0x514e_28b7);
+    assert_eq!(murmur3_32(b"test", 0), 0xba6b_d213);
+    assert_eq!(murmur3_32(b"Hello, world!", 1234), 0xfaf6_cdb3);
+    assert_eq!(
+      murmur3_32(b"The quick brown fox jumps over the lazy dog", 0),
+      0x2e4f_f723
+    );
+  }
+}
@@ -0,0 +1,191 @@
+// A Node.js addon, built into the cdylib with the `node` feature, for tooling
+// that wants the interpreter behind it. A `Noun` is a handle to one kept on
+// this side, the same as in the browser, see src/wasm.rs, and jams cross as
+// Buffers.
+//
+// Nouns can't leave the thread they were made on without `sync`, so
+// `evaluateAsync` jams its subject and formula, evaluates on a libuv worker,
+// off the event loop, and cues the product back on it.
+//
+// The TypeScript declarations are include/nuuk.d.ts.
+
+use napi::{Env, Error, Result, Task, bindgen_prelude::*};
+use napi_derive::napi;
+
+use crate::{
+  interp::Interpreter,
+  noun::Noun,
+  serial::{CueError, cue_bytes, jam_bytes},
+};
+
+#[napi(js_name = "Noun")]
+#[derive(Clone)]
+pub struct JsNoun {
+  noun: Noun,
+}
+
+fn error(e: impl std::fmt::Display) -> Error {
+  Error::from_reason(e.to_string())
+}
+
+fn eval_noun(subject: &Noun, formula: &Noun) -> Result<Noun> {
+  Interpreter::new()
+    .eval(subject.clone().into(), formula.clone().into())
+    .map_err(error)
+}
+
+fn eval_jammed(subject: &[u8], formula: &[u8]) -> Result<Vec<u8>> {
+  let cue = |bytes| cue_bytes(bytes).map_err(|e: CueError| error(e));
+  Ok(jam_bytes(&eval_noun(&cue(subject)?, &cue(formula)?)?))
+}
+
+impl From<Noun> for JsNoun {
+  fn from(noun: Noun) -> Self {
+    JsNoun { noun }
+  }
+}
+
+#[napi]
+impl JsNoun {
+  #[napi(factory)]
+  pub fn parse(text: String) -> Result<Self> {
+    text.parse::<Noun>().map(Self::from).map_err(error)
+  }
+
+  #[napi(factory)]
+  pub fn cue(bytes: Buffer) -> Result<Self> {
+    cue_bytes(&bytes).map(Self::from).map_err(error)
+  }
+
+  #[napi(factory)]
+  pub fn cell(head: &JsNoun, tail: &JsNoun) -> Self {
+    Noun::cell(head.noun.clone(), tail.noun.clone()).into()
+  }
+
+  #[napi]
+  pub fn jam(&self) -> Buffer {
+    jam_bytes(&self.noun).into()
+  }
+
+  #[napi(js_name = "toString")]
+  pub fn print(&self) -> String {
+    self.noun.to_string()
+  }
+
+  #[napi]
+  pub fn is_cell(&self) -> bool {
+    self.noun.is_cell()
+  }
+
+  // null for an atom
+  #[napi]
+  pub fn head(&self) -> Option<JsNoun> {
+    self.noun.as_cell().map(|(head, _)| head.clone().into())
+  }
+
+  #[napi]
+  pub fn tail(&self) -> Option<JsNoun> {
+    self.noun.as_cell().map(|(_, tail)| tail.clone().into())
+  }
+
+  #[napi]
+  pub fn equals(&self, other: &JsNoun) -> bool {
+    self.noun == other.noun
+  }
+}
+
+// the product of `formula` against `subject`, blocking the event loop until
+// there is one
+#[napi]
+pub fn evaluate(subject: &JsNoun, formula: &JsNoun) -> Result<JsNoun> {
+  eval_noun(&subject.noun, &formula.noun).map(JsNoun::from)
+}
+
+pub struct Evaluate {
+  subject: Vec<u8>,
+  formula: Vec<u8>,
+}
+
+impl Evaluate {
+  fn new(subject: &Noun, formula: &Noun) -> Self {
+    Evaluate {
+      subject: jam_bytes(subject),
+      formula: jam_bytes(formula),
+    }
+  }
+}
+
+impl Task for Evaluate {
+  type Output = Vec<u8>;
+  type JsValue = JsNoun;
+
+  fn compute(&mut self) -> Result<Vec<u8>> {
+    eval_jammed(&self.subject, &self.formula)
+  }
+
+  fn resolve(&mut self, _: Env, product: Vec<u8>) -> Result<JsNoun> {
+    cue_bytes(&product).map(JsNoun::from).map_err(error)
+  }
+}
+
+// the same, as a promise, on a worker
+#[napi(ts_return_type = "Promise<Noun>")]
+pub fn evaluate_async(subject: &JsNoun, formula: &JsNoun) -> AsyncTask<Evaluate> {
+  AsyncTask::new(Evaluate::new(&subject.noun, &formula.noun))
+}
+
+#[napi]
+pub fn parse(text: String) -> Result<JsNoun> {
+  JsNoun::parse(text)
+}
+
+#[napi]
+pub fn print(noun: &JsNoun) -> String {
+  noun.print()
+}
+
+#[napi]
+pub fn jam(noun: &JsNoun) -> Buffer {
+  noun.jam()
+}
+
+#[napi]
+pub fn cue(bytes: Buffer) -> Result<JsNoun> {
+  JsNoun::cue(bytes)
+}
+
+// Buffers and errors made here don't need node, the rest is left to it
+#[cfg(test)]
+mod test {
+  use super::{Evaluate, JsNoun, cue, eval_jammed, evaluate, evaluate_async, jam, parse, print};
+  use crate::serial::jam_bytes;
+  use napi::Task;
+
+  fn noun(text: &str) -> JsNoun {
+    parse(text.to_string()).unwrap()
+  }
+
+  #[test]
+  fn test_node() {
+    let subject = noun("{4 5}");
+    let formula = noun("{4 0 3}");
+    let product = evaluate(&subject, &formula).unwrap();
+    assert_eq!(print(&product), "6");
+    assert!(product.equals(&noun("6")));
+    assert!(evaluate(&noun("1"), &noun("{0 7}")).is_err());
+
+    let cell = JsNoun::cell(&subject, &formula);
+    assert!(cell.is_cell() && !product.is_cell());
+    assert_eq!(cell.head().unwrap().print(), "{4 5}");
+    assert_eq!(cell.tail().unwrap().print(), "{4 0 3}");
+    assert!(product.head().is_none());
+    assert!(cue(jam(&cell)).unwrap().equals(&cell));
+    assert!(parse("{1".to_string()).is_err());
+
+    // what the worker does, node running the task itself
+    let _task = evaluate_async(&subject, &formula);
+    let mut task = Evaluate::new(&subject.noun, &formula.noun);
+    assert_eq!(task.compute().unwrap(), jam_bytes(&noun!(6)));
+    assert!(eval_jammed(&jam_bytes(&noun!(1)), &[]).is_err());
+  }
+}
@@ -0,0 +1,1345 @@
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet, hash_map::Entry},
+  fmt::Write,
+  sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{
+  atom::{Atom, NAH, YES},
+  aura,
+  axis::path,
+  error::NockError,
+  murmur::murmur3_32,
+};
+
+// nouns are shared between threads with the `sync` feature, and only
+// counted on one otherwise
+#[cfg(not(feature = "sync"))]
+pub(crate) use std::cell::OnceCell;
+#[cfg(not(feature = "sync"))]
+pub(crate) use std::rc::{Rc, Weak};
+#[cfg(feature = "sync")]
+pub(crate) use std::sync::OnceLock as OnceCell;
+#[cfg(feature = "sync")]
+pub(crate) use std::sync::{Arc as Rc, Weak};
+
+// what an interpreter keeps has to be for it to go between threads with the
+// `sync` feature, and anything otherwise: `Send` for what only it calls,
+// like sinks, and `Send + Sync` for what it shares, like jets
+#[cfg(not(feature = "sync"))]
+pub trait Sendable {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> Sendable for T {}
+#[cfg(feature = "sync")]
+pub trait Sendable: Send {}
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send> Sendable for T {}
+
+#[cfg(not(feature = "sync"))]
+pub trait Shareable {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> Shareable for T {}
+#[cfg(feature = "sync")]
+pub trait Shareable: Send + Sync {}
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send + Sync> Shareable for T {}
+
+#[derive(Debug)]
+pub(crate) struct Cell {
+  pub(crate) head: Noun,
+  pub(crate) tail: Noun,
+  // see `Noun::digest`
+  digest: u64,
+  // worked out the first time it's asked for, 0 until then
+  mug: AtomicU32,
+  // these two saturate
+  depth: u32,
+  nodes: u64,
+}
+
+impl Drop for Cell {
+  // unlink children onto a work list instead of recursing into them, each
+  // cell left behind holds only atoms; children someone else holds are only
+  // released, and the list is only needed where both children are ours
+  fn drop(&mut self) {
+    let mut pending = vec![];
+    let mut next = self.unlink(&mut pending);
+
+    while let Some(mut cell) = next.take().or_else(|| pending.pop()) {
+      next = cell.unlink(&mut pending);
+    }
+  }
+}
+
+impl Cell {
+  // takes the children nobody else holds, returning one and leaving the
+  // other in `pending`
+  fn unlink(&mut self, pending: &mut Vec<Cell>) -> Option<Cell> {
+    let mut unlinked = None;
+
+    for child in [&mut self.head, &mut self.tail] {
+      let cell = child.0.take_unique();
+      match unlinked {
+        None => unlinked = cell,
+        Some(_) => pending.extend(cell),
+      }
+    }
+
+    unlinked
+  }
+}
+
+// a noun looked into, however it's stored, see `Noun::inner`
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum NounInner<'a> {
+  Atom(&'a Atom),
+  Cell(&'a Cell),
+}
+
+#[derive(Clone, Debug)]
+#[repr(transparent)]
+pub struct Noun(Repr);
+
+// with the tagged feature, a noun is one word, see src/tagged.rs
+#[cfg(feature = "tagged")]
+use crate::tagged::Word as Repr;
+
+#[cfg(not(feature = "tagged"))]
+#[derive(Clone, Debug)]
+enum Repr {
+  // atoms are stored inline, only cells are shared
+  Atom(Atom),
+  Cell(Rc<Cell>),
+}
+
+#[cfg(not(feature = "tagged"))]
+impl Repr {
+  const fn atom(atom: Atom) -> Self {
+    Repr::Atom(atom)
+  }
+
+  fn cell(cell: Cell) -> Self {
+    Repr::Cell(Rc::new(cell))
+  }
+
+  fn inner(&self) -> NounInner<'_> {
+    match self {
+      Repr::Atom(atom) => NounInner::Atom(atom),
+      Repr::Cell(cell) => NounInner::Cell(cell),
+    }
+  }
+
+  fn downgrade(&self) -> Option<Weak<Cell>> {
+    match self {
+      Repr::Cell(cell) => Some(Rc::downgrade(cell)),
+      Repr::Atom(..) => None,
+    }
+  }
+
+  fn upgrade(cell: &Weak<Cell>) -> Option<Self> {
+    cell.upgrade().map(Repr::Cell)
+  }
+
+  // the cell, if nothing else holds it, leaving 0 in its place
+  fn take_unique(&mut self) -> Option<Cell> {
+    if !matches!(self, Repr::Cell(cell) if Rc::strong_count(cell) == 1) {
+      return None;
+    }
+    let Repr::Cell(cell) = std::mem::replace(self, Repr::Atom(Atom::small(0))) else {
+      unreachable!()
+    };
+    Rc::into_inner(cell)
+  }
+}
+
+impl Noun {
+  pub const NULL: Noun = Noun::small(0);
+  pub const YES: Noun = Noun::small(YES as u8);
+  pub const NAH: Noun = Noun::small(NAH as u8);
+
+  pub const fn atom(atom: Atom) -> Self {
+    Self(Repr::atom(atom))
+  }
+
+  // atoms live inline, so small ones are as cheap as a shared table entry
+  pub const fn small(atom: u8) -> Self {
+    Self::atom(Atom::small(atom))
+  }
+
+  pub fn cell(car: Noun, cdr: Noun) -> Self {
+    let digest = mix(mix(CELL, car.digest()), cdr.digest());
+    let depth = car.depth().max(cdr.depth()) + 1;
+    let nodes = car.nodes().saturating_add(cdr.nodes()).saturating_add(1);
+    Self(Repr::cell(Cell {
+      head: car,
+      tail: cdr,
+      digest,
+      mug: AtomicU32::new(0),
+      depth: depth.try_into().unwrap_or(u32::MAX),
+      nodes,
+    }))
+  }
+
+  pub(crate) fn inner(&self) -> NounInner<'_> {
+    self.0.inner()
+  }
+
+  // the noun's word, see src/tagged.rs
+  #[cfg(feature = "jit")]
+  pub(crate) fn raw(&self) -> u64 {
+    self.0.raw()
+  }
+
+  #[cfg(feature = "jit")]
+  pub(crate) fn into_raw(self) -> u64 {
+    self.0.into_raw()
+  }
+
+  /// # Safety
+  ///
+  /// `word` has to come from `into_raw`, and is given up.
+  #[cfg(feature = "jit")]
+  pub(crate) unsafe fn from_raw(word: u64) -> Self {
+    // SAFETY: passed on
+    Self(unsafe { Repr::from_raw(word) })
+  }
+
+  // a pointer to the cell that doesn't keep it alive, None for an atom
+  pub(crate) fn downgrade(&self) -> Option<Weak<Cell>> {
+    self.0.downgrade()
+  }
+
+  // like `cell`, but shares the allocation with any live cell interned from
+  // the same children. Children are told apart by identity, so equal cells
+  // end up as one as far down as they were built this way.
+  pub fn cell_interned(car: Noun, cdr: Noun) -> Self {
+    INTERNED.with_borrow_mut(|interned| interned.cell(car, cdr))
+  }
+
+  // a structural hash, worked out once when a cell is built: nouns with
+  // different digests are certainly different
+  pub(crate) fn digest(&self) -> u64 {
+    match self.inner() {
+      NounInner::Atom(atom) => atom.fold_words(ATOM, mix),
+      NounInner::Cell(cell) => cell.digest,
+    }
+  }
+
+  // urbit's 31-bit hash. A cell works its mug out the first time it's asked,
+  // and keeps it, so no subtree is hashed twice; nouns never change, so it
+  // never goes stale.
+  pub fn mug(&self) -> u32 {
+    let cell = match self.inner() {
+      NounInner::Atom(atom) => return atom_mug(atom),
+      NounInner::Cell(cell) => cell,
+    };
+
+    // children before their parents, without recursing. The cell itself
+    // isn't on the stack, which is only allocated for children that aren't
+    // hashed yet.
+    let root = cell;
+    let mut stack = vec![];
+    loop {
+      let cell = stack.last().copied().unwrap_or(root);
+      if cell.mug.load(Ordering::Relaxed) != 0 {
+        if stack.pop().is_none() {
+          break;
+        }
+        continue;
+      }
+
+      let pending = stack.len();
+      for kid in [&cell.tail, &cell.head] {
+        if let NounInner::Cell(kid) = kid.inner()
+          && kid.mug.load(Ordering::Relaxed) == 0
+        {
+          stack.push(kid);
+        }
+      }
+      if stack.len() == pending {
+        let both = u64::from(cell.head.mug()) | u64::from(cell.tail.mug()) << 32;
+        let bytes = both.to_le_bytes();
+        let len = 8 - both.leading_zeros() as usize / 8;
+        let mug = mum(0xdead_beef, 0xfffe, &bytes[..len]);
+        cell.mug.store(mug, Ordering::Relaxed);
+        if stack.pop().is_none() {
+          break;
+        }
+      }
+    }
+
+    cell.mug.load(Ordering::Relaxed)
+  }
+
+  pub fn is_cell(&self) -> bool {
+    matches!(self.inner(), NounInner::Cell(..))
+  }
+
+  // same cell allocation or same atom, so certainly equal
+  pub(crate) fn ptr_eq(&self, other: &Noun) -> bool {
+    match (self.inner(), other.inner()) {
+      (NounInner::Atom(a), NounInner::Atom(b)) => a == b,
+      (NounInner::Cell(a), NounInner::Cell(b)) => std::ptr::eq(a, b),
+      _ => false,
+    }
+  }
+
+  pub fn as_atom(&self) -> Option<&Atom> {
+    match self.inner() {
+      NounInner::Atom(atom) => Some(atom),
+      NounInner::Cell(..) => None,
+    }
+  }
+
+  pub fn as_cell(&self) -> Option<(&Noun, &Noun)> {
+    match self.inner() {
+      NounInner::Cell(cell) => Some((&cell.head, &cell.tail)),
+      NounInner::Atom(..) => None,
+    }
+  }
+
+  pub fn try_atom(&self) -> Result<&Atom, NockError> {
+    self.as_atom().ok_or_else(|| NockError::AtomRequired {
+      found: self.clone(),
+    })
+  }
+
+  pub fn try_cell(&self) -> Result<(&Noun, &Noun), NockError> {
+    self.as_cell().ok_or_else(|| NockError::CellRequired {
+      found: self.clone(),
+    })
+  }
+
+  // /{axis self}
+  pub fn slot(&self, axis: u64) -> Result<Noun, NockError> {
+    self.slot_atom(&Atom::new(axis))
+  }
+
+  // `slot` for axes of any size, as deep cores need
+  pub fn slot_atom(&self, axis: &Atom) -> Result<Noun, NockError> {
+    if axis.is_zero() {
+      return Err(NockError::ZeroAxis);
+    }
+
+    self
+      .slot_ref(axis)
+      .cloned()
+      .ok_or_else(|| NockError::AxisOutOfTree { axis: axis.clone() })
+  }
+
+  // `slot` for probing, None for axis 0 or outside the tree
+  pub fn slot_opt(&self, axis: u64) -> Option<Noun> {
+    self.slot_ref(&Atom::new(axis)).cloned()
+  }
+
+  fn slot_ref(&self, axis: &Atom) -> Option<&Noun> {
+    if axis.is_zero() {
+      return None;
+    }
+
+    let mut noun = self;
+    for right in path(axis) {
+      let (car, cdr) = noun.as_cell()?;
+      noun = if right { cdr } else { car };
+    }
+
+    Some(noun)
+  }
+
+  // #{axis replacement self}
+  pub fn edit(&self, axis: u64, replacement: Noun) -> Result<Noun, NockError> {
+    self.edit_atom(&Atom::new(axis), replacement)
+  }
+
+  pub fn edit_atom(&self, axis: &Atom, replacement: Noun) -> Result<Noun, NockError> {
+    edit_in(&mut vec![], self, axis, replacement)
+  }
+
+  // {a b c 0}
+  pub fn list<I>(items: I) -> Noun
+  where
+    I: IntoIterator,
+    I::Item: Into<Noun>,
+  {
+    let items: Vec<Noun> = items.into_iter().map(Into::into).collect();
+
+    items
+      .into_iter()
+      .rev()
+      .fold(Noun::NULL, |list, item| Noun::cell(item, list))
+  }
+
+  // pre-order depth-first traversal; returning false from `visit` skips the
+  // children of that node. Axes are `None` below depth 63.
+  pub fn walk(&self, mut visit: impl FnMut(Option<u64>, &Noun) -> bool) {
+    let mut stack = vec![(Some(1u64), self)];
+
+    while let Some((axis, noun)) = stack.pop() {
+      if !visit(axis, noun) {
+        continue;
+      }
+
+      if let Some((car, cdr)) = noun.as_cell() {
+        let left = axis.and_then(|axis| axis.checked_mul(2));
+        stack.push((left.and_then(|axis| axis.checked_add(1)), cdr));
+        stack.push((left, car));
+      }
+    }
+  }
+
+  // pre-order depth-first iterator of (axis, subtree) pairs, see `walk`
+  pub fn dfs(&self) -> Dfs<'_> {
+    Dfs {
+      stack: vec![(Some(1), self)],
+    }
+  }
+
+  // the elements of a null-terminated list, ending with an error if the list
+  // is terminated by anything other than 0
+  pub fn iter_list(&self) -> ListIter<'_> {
+    ListIter { rest: Some(self) }
+  }
+
+  // displays atoms as whichever of a number, text or hex looks most likely
+  pub fn pretty(&self) -> Pretty<'_> {
+    Pretty(self)
+  }
+
+  // sizes of the noun as a tree and as the shared structure it's stored in.
+  // Tree counts saturate, since sharing lets them grow exponentially.
+  pub fn metrics(&self) -> Metrics {
+    // only sharing needs a walk, cells know the rest
+    let mut seen = HashSet::new();
+    let mut stack = vec![self];
+    while let Some(noun) = stack.pop() {
+      if seen.insert(SubtreeKey::of(noun))
+        && let NounInner::Cell(cell) = noun.inner()
+      {
+        stack.extend([&cell.tail, &cell.head]);
+      }
+    }
+
+    let nodes = self.nodes();
+    Metrics {
+      nodes,
+      depth: self.depth(),
+      // every cell has two children, so there's one more atom than cells
+      atoms: if nodes == u64::MAX {
+        nodes
+      } else {
+        nodes / 2 + 1
+      },
+      unique: seen.len() as u64,
+    }
+  }
+
+  // cells and atoms in the noun as a tree, saturating
+  pub fn nodes(&self) -> u64 {
+    match self.inner() {
+      NounInner::Atom(..) => 1,
+      NounInner::Cell(cell) => cell.nodes,
+    }
+  }
+
+  // longest path from the root, 0 for an atom
+  pub fn depth(&self) -> u64 {
+    match self.inner() {
+      NounInner::Atom(..) => 0,
+      NounInner::Cell(cell) => u64::from(cell.depth),
+    }
+  }
+
+  // a copy in allocations of its own, made in one go, in which equal
+  // subtrees are one. Nothing in it holds on to the allocations of `self`,
+  // like chunks of an arena or intermediates kept alive by a shared cell.
+  pub fn compact(&self) -> Noun {
+    enum Task<'a> {
+      Visit(&'a Noun),
+      Build(*const Cell),
+    }
+
+    let mut atoms: HashMap<Atom, Noun> = HashMap::new();
+    // copies by the cell they copy, and by their children
+    let mut copies: HashMap<*const Cell, Noun> = HashMap::new();
+    let mut cells: HashMap<(SubtreeKey, SubtreeKey), Noun> = HashMap::new();
+    let mut tasks = vec![Task::Visit(self)];
+    let mut done = vec![];
+
+    while let Some(task) = tasks.pop() {
+      match task {
+        Task::Visit(noun) => match noun.inner() {
+          NounInner::Atom(atom) if atom.words().len() == 1 => done.push(noun.clone()),
+          NounInner::Atom(atom) => {
+            let copy = atoms
+              .entry(atom.clone())
+              .or_insert_with(|| Noun::atom(Atom::from_words(atom.words())));
+            done.push(copy.clone());
+          }
+          NounInner::Cell(cell) => match copies.get(&std::ptr::from_ref(cell)) {
+            Some(copy) => done.push(copy.clone()),
+            None => {
+              tasks.push(Task::Build(cell));
+              tasks.push(Task::Visit(&cell.tail));
+              tasks.push(Task::Visit(&cell.head));
+            }
+          },
+        },
+        Task::Build(cell) => {
+          let cdr = done.pop().unwrap();
+          let car = done.pop().unwrap();
+          let key = (SubtreeKey::of(&car), SubtreeKey::of(&cdr));
+          let copy = cells
+            .entry(key)
+            .or_insert_with(|| Noun::cell(car, cdr))
+            .clone();
+          copies.insert(cell, copy.clone());
+          done.push(copy);
+        }
+      }
+    }
+
+    done.pop().unwrap()
+  }
+
+  // the noun as a graphviz graph, every subtree `metrics` counts as unique
+  // drawn once, however many cells point to it. Cells are points with their
+  // head below left and their tail below right, and atoms boxes.
+  pub fn to_dot(&self) -> String {
+    // the id of a subtree, writing it out when it's new
+    fn node<'a>(
+      noun: &'a Noun,
+      ids: &mut HashMap<SubtreeKey, usize>,
+      cells: &mut Vec<&'a Noun>,
+      out: &mut String,
+    ) -> usize {
+      let id = ids.len();
+      match ids.entry(SubtreeKey::of(noun)) {
+        Entry::Occupied(entry) => return *entry.get(),
+        Entry::Vacant(entry) => entry.insert(id),
+      };
+
+      match noun.inner() {
+        NounInner::Atom(atom) => writeln!(out, "  {id} [shape=box, label=\"{atom}\"];"),
+        NounInner::Cell(..) => {
+          cells.push(noun);
+          writeln!(out, "  {id};")
+        }
+      }
+      .unwrap();
+      id
+    }
+
+    let mut out = String::from("digraph noun {\n  node [shape=point];\n");
+    let mut ids = HashMap::new();
+    let mut cells = vec![];
+    node(self, &mut ids, &mut cells, &mut out);
+
+    while let Some(cell) = cells.pop() {
+      let id = ids[&SubtreeKey::of(cell)];
+      let (car, cdr) = cell.as_cell().unwrap();
+      for (port, child) in [("sw", car), ("se", cdr)] {
+        let child = node(child, &mut ids, &mut cells, &mut out);
+        writeln!(out, "  {id}:{port} -> {child};").unwrap();
+      }
+    }
+
+    out.push_str("}\n");
+    out
+  }
+}
+
+// cells are told apart by allocation, atoms by value
+#[derive(PartialEq, Eq, Hash)]
+enum SubtreeKey {
+  Atom(Atom),
+  Cell(*const Cell),
+}
+
+impl SubtreeKey {
+  fn of(noun: &Noun) -> Self {
+    match noun.inner() {
+      NounInner::Atom(atom) => Self::Atom(atom.clone()),
+      NounInner::Cell(cell) => Self::Cell(cell),
+    }
+  }
+}
+
+thread_local! {
+  static INTERNED: RefCell<Interned> = RefCell::default();
+}
+
+// cells by the identities of their children. A live cell keeps its children
+// alive, so their addresses can't have been reused for anything else.
+#[derive(Default)]
+struct Interned {
+  cells: HashMap<(SubtreeKey, SubtreeKey), Weak<Cell>>,
+  // entries left after dropping dead cells the last time
+  live: usize,
+}
+
+impl Interned {
+  fn cell(&mut self, car: Noun, cdr: Noun) -> Noun {
+    let key = (SubtreeKey::of(&car), SubtreeKey::of(&cdr));
+    if let Some(cell) = self.cells.get(&key).and_then(Repr::upgrade) {
+      return Noun(cell);
+    }
+
+    let noun = Noun::cell(car, cdr);
+    self.cells.extend(noun.downgrade().map(|cell| (key, cell)));
+
+    // dead cells are dropped once they could be half the table
+    if self.cells.len() > 2 * self.live.max(1 << 10) {
+      self.cells.retain(|_, cell| cell.strong_count() > 0);
+      self.live = self.cells.len();
+    }
+    noun
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metrics {
+  // cells and atoms in the noun as a tree
+  pub nodes: u64,
+  // longest path from the root, 0 for an atom
+  pub depth: u64,
+  pub atoms: u64,
+  // distinct subtrees, each shared cell counted once
+  pub unique: u64,
+}
+
+pub struct Dfs<'a> {
+  stack: Vec<(Option<u64>, &'a Noun)>,
+}
+
+impl<'a> Iterator for Dfs<'a> {
+  type Item = (Option<u64>, &'a Noun);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (axis, noun) = self.stack.pop()?;
+
+    if let Some((car, cdr)) = noun.as_cell() {
+      let left = axis.and_then(|axis| axis.checked_mul(2));
+      self
+        .stack
+        .push((left.and_then(|axis| axis.checked_add(1)), cdr));
+      self.stack.push((left, car));
+    }
+
+    Some((axis, noun))
+  }
+}
+
+pub struct ListIter<'a> {
+  rest: Option<&'a Noun>,
+}
+
+impl<'a> Iterator for ListIter<'a> {
+  type Item = Result<&'a Noun, NockError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let rest = self.rest.take()?;
+
+    match rest.inner() {
+      NounInner::Cell(cell) => {
+        let Cell {
+          head: item,
+          tail: rest,
+          ..
+        } = cell;
+        self.rest = Some(rest);
+        Some(Ok(item))
+      }
+      NounInner::Atom(atom) if atom.is_zero() => None,
+      NounInner::Atom(..) => Some(Err(NockError::ImproperList { tail: rest.clone() })),
+    }
+  }
+}
+
+// `stack` is scratch space for the siblings along the path to `axis`
+pub(crate) fn edit_in(
+  stack: &mut Vec<(bool, Noun)>,
+  target: &Noun,
+  axis: &Atom,
+  replacement: Noun,
+) -> Result<Noun, NockError> {
+  if axis.is_zero() {
+    return Err(NockError::ZeroAxis);
+  }
+
+  stack.clear();
+  let mut current = target;
+
+  for right in path(axis) {
+    let Some((car, cdr)) = current.as_cell() else {
+      stack.clear();
+      return Err(NockError::AxisOutOfTree { axis: axis.clone() });
+    };
+
+    if right {
+      stack.push((right, car.clone()));
+      current = cdr;
+    } else {
+      stack.push((right, cdr.clone()));
+      current = car;
+    }
+  }
+
+  let mut result = replacement;
+
+  while let Some((right, sibling)) = stack.pop() {
+    result = if right {
+      Noun::cell(sibling, result)
+    } else {
+      Noun::cell(result, sibling)
+    }
+  }
+
+  Ok(result)
+}
+
+pub fn noun_eq(a: Noun, b: Noun) -> bool {
+  noun_eq_in(&mut vec![], &a, &b)
+}
+
+const ATOM: u64 = 0x243f_6a88_85a3_08d3;
+const CELL: u64 = 0x1319_8a2e_0370_7344;
+
+// murmur3 over `key`, folded to 31 bits, and retried with the next seed
+// while that's 0
+fn mum(seed: u32, fallback: u32, key: &[u8]) -> u32 {
+  (0..8)
+    .map(|i| murmur3_32(key, seed.wrapping_add(i)))
+    .map(|hash| (hash >> 31) ^ (hash & 0x7fff_ffff))
+    .find(|&hash| hash != 0)
+    .unwrap_or(fallback)
+}
+
+fn mix(hash: u64, word: u64) -> u64 {
+  (hash.rotate_left(5) ^ word).wrapping_mul(0x517c_c1b7_2722_0a95)
+}
+
+// `stack` is scratch space, left empty on return
+pub(crate) fn noun_eq_in(stack: &mut Vec<(Noun, Noun)>, a: &Noun, b: &Noun) -> bool {
+  stack.clear();
+
+  if a.ptr_eq(b) {
+    return true;
+  }
+
+  stack.push((a.clone(), b.clone()));
+
+  while let Some((a, b)) = stack.pop() {
+    if a.ptr_eq(&b) {
+      continue;
+    }
+
+    match (a.inner(), b.inner()) {
+      (NounInner::Atom(a), NounInner::Atom(b)) if a == b => {}
+      // unequal digests settle it without walking either tree
+      (NounInner::Cell(a), NounInner::Cell(b)) if a.digest == b.digest => {
+        stack.push((a.tail.clone(), b.tail.clone()));
+        stack.push((a.head.clone(), b.head.clone()));
+      }
+      _ => {
+        stack.clear();
+        return false;
+      }
+    }
+  }
+
+  true
+}
+
+// pairs of cells opcode 5 found equal, so comparing them again is a lookup.
+// Nouns are shared and never change, so neither copy can be pointed at the
+// other to free it, and this is all that's kept of a comparison. The weak
+// pointers stop the addresses being reused while they're here, without
+// keeping the cells' children alive.
+#[derive(Default)]
+pub(crate) struct Equals {
+  pairs: HashMap<(usize, usize), (Weak<Cell>, Weak<Cell>)>,
+  stack: Vec<(Noun, Noun)>,
+}
+
+// pairs kept before starting over
+const EQUALS: usize = 1 << 12;
+
+impl Equals {
+  pub(crate) fn eq(&mut self, a: &Noun, b: &Noun) -> bool {
+    let (Some(x), Some(y)) = (a.downgrade(), b.downgrade()) else {
+      return noun_eq_in(&mut self.stack, a, b);
+    };
+    let (x_at, y_at) = (x.as_ptr() as usize, y.as_ptr() as usize);
+    let key = (x_at.min(y_at), x_at.max(y_at));
+    if x_at == y_at || self.pairs.contains_key(&key) {
+      return true;
+    }
+    if !noun_eq_in(&mut self.stack, a, b) {
+      return false;
+    }
+
+    if self.pairs.len() == EQUALS {
+      self.pairs.clear();
+    }
+    self.pairs.insert(key, (x, y));
+    true
+  }
+}
+
+// where `left` and `right` differ, with cells that differ only in their
+// children reported as their children's divergences
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+  // `None` below depth 63, see `Noun::walk`
+  pub axis: Option<u64>,
+  pub left: Noun,
+  pub right: Noun,
+}
+
+// the outermost divergences between `a` and `b` in pre-order, empty if equal
+pub fn noun_diff(a: &Noun, b: &Noun) -> Vec<Divergence> {
+  let mut divergences = vec![];
+  let mut stack = vec![(Some(1u64), a, b)];
+
+  while let Some((axis, a, b)) = stack.pop() {
+    if a.ptr_eq(b) {
+      continue;
+    }
+
+    match (a.inner(), b.inner()) {
+      (NounInner::Atom(x), NounInner::Atom(y)) if x == y => {}
+      (NounInner::Cell(x), NounInner::Cell(y)) => {
+        let left = axis.and_then(|axis| axis.checked_mul(2));
+        stack.push((left.and_then(|axis| axis.checked_add(1)), &x.tail, &y.tail));
+        stack.push((left, &x.head, &y.head));
+      }
+      _ => divergences.push(Divergence {
+        axis,
+        left: a.clone(),
+        right: b.clone(),
+      }),
+    }
+  }
+
+  divergences
+}
+
+// the bytes of an atom of a few words are hashed on the stack
+fn atom_mug(atom: &Atom) -> u32 {
+  let words = atom.words();
+  if words.len() > 4 {
+    return mum(0xcafe_babe, 0x7fff, &atom.as_le_bytes());
+  }
+
+  let mut bytes = [0; 32];
+  for (chunk, word) in bytes.chunks_exact_mut(8).zip(words) {
+    chunk.copy_from_slice(&word.to_le_bytes());
+  }
+  let top = words[words.len() - 1];
+  let len = (words.len() - 1) * 8 + (64 - top.leading_zeros()).div_ceil(8) as usize;
+  mum(0xcafe_babe, 0x7fff, &bytes[..len])
+}
+
+impl PartialEq for Noun {
+  fn eq(&self, other: &Self) -> bool {
+    noun_eq_in(&mut vec![], self, other)
+  }
+}
+
+impl Eq for Noun {}
+
+impl std::hash::Hash for Noun {
+  // the mug, so maps and caches are keyed the way other nock runtimes key
+  // them. A cell keeps its mug, so only new cells are walked.
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    state.write_u32(self.mug());
+  }
+}
+
+impl PartialOrd for Noun {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+// atoms sort before cells, cells compare head first and then tail
+impl Ord for Noun {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let mut stack = vec![(self, other)];
+
+    while let Some((a, b)) = stack.pop() {
+      if a.ptr_eq(b) {
+        continue;
+      }
+
+      match (a.inner(), b.inner()) {
+        (NounInner::Atom(a), NounInner::Atom(b)) => match a.cmp(b) {
+          Ordering::Equal => {}
+          ordering => return ordering,
+        },
+        (NounInner::Atom(..), NounInner::Cell(..)) => return Ordering::Less,
+        (NounInner::Cell(..), NounInner::Atom(..)) => return Ordering::Greater,
+        (NounInner::Cell(a), NounInner::Cell(b)) => {
+          stack.push((&a.tail, &b.tail));
+          stack.push((&a.head, &b.head));
+        }
+      }
+    }
+
+    Ordering::Equal
+  }
+}
+
+type WriteAtom = fn(&Atom, &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+
+// `{a b c}`, with every atom written by `atom`
+fn write_noun(f: &mut std::fmt::Formatter<'_>, noun: &Noun, atom: WriteAtom) -> std::fmt::Result {
+  let mut current = match noun.inner() {
+    NounInner::Atom(a) => return atom(a, f),
+    NounInner::Cell(cell) => cell,
+  };
+
+  write!(f, "{{")?;
+
+  loop {
+    write_noun(f, &current.head, atom)?;
+    write!(f, " ")?;
+
+    match current.tail.inner() {
+      NounInner::Cell(cell) => current = cell,
+      NounInner::Atom(a) => {
+        atom(a, f)?;
+        return write!(f, "}}");
+      }
+    }
+  }
+}
+
+impl std::fmt::Display for Noun {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write_noun(f, self, |atom, f| write!(f, "{atom}"))
+  }
+}
+
+pub struct Pretty<'a>(&'a Noun);
+
+impl std::fmt::Display for Pretty<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write_noun(f, self.0, |atom, f| write!(f, "{}", aura::Guess(atom)))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::hash::{DefaultHasher, Hash, Hasher};
+
+  use super::{Divergence, Equals, Noun, atom_mug, mum, noun_diff, noun_eq};
+  use crate::{atom::Atom, error::NockError};
+
+  #[test]
+  fn test_mug() {
+    // urbit's own
+    for (noun, mug) in [
+      (noun!(0), 0x79ff_04e8),
+      (noun!(1), 0x715c_2a60),
+      (noun!(2), 0x718b_9468),
+      (noun!({0, 0}), 0x192f_5588),
+      (noun!({1, 1}), 0x6b32_ec46),
+      (noun!({2, 2}), 0x02ef_fe10),
+    ] {
+      assert_eq!(noun.mug(), mug, "{noun}");
+    }
+
+    // atoms of a few words are hashed off the stack, bigger ones aren't
+    for len in 1..=6 {
+      let atom = Atom::from_words(&vec![0x0001_0203_0405_0607; len]);
+      let bytes = atom.as_le_bytes();
+      assert_eq!(atom_mug(&atom), mum(0xcafe_babe, 0x7fff, &bytes));
+    }
+
+    // and what maps hash nouns by
+    let hash = |hash: &dyn Fn(&mut DefaultHasher)| {
+      let mut hasher = DefaultHasher::new();
+      hash(&mut hasher);
+      hasher.finish()
+    };
+    let big = Noun::from(Atom::from_words(&[u64::MAX, 1]));
+    for noun in [noun!(0), noun!({{1, 2}, 3}), big] {
+      assert_eq!(
+        hash(&|hasher| noun.hash(hasher)),
+        hash(&|hasher| hasher.write_u32(noun.mug()))
+      );
+    }
+
+    // equal trees built apart
+    let a = noun!({{1, 2}, {{3, 4}, 5}});
+    let b = Noun::cell(noun!({1, 2}), Noun::cell(noun!({3, 4}), noun!(5)));
+    assert!(!a.ptr_eq(&b));
+    assert_eq!(a.mug(), b.mug());
+    assert_ne!(a.mug(), noun!({{1, 2}, {{3, 4}, 6}}).mug());
+    assert_ne!(noun!({1, 2}).mug(), noun!({2, 1}).mug());
+    assert!(a.mug() < 1 << 31);
+
+    // children are hashed once, and deep nouns don't recurse
+    let (head, _) = b.as_cell().unwrap();
+    assert_eq!(head.mug(), noun!({1, 2}).mug());
+    let deep = (0..100_000u64).fold(noun!(0), |list, i| Noun::cell(Noun::from(i), list));
+    let again = (0..100_000u64).fold(noun!(0), |list, i| Noun::cell(Noun::from(i), list));
+    assert_eq!(deep.mug(), again.mug());
+  }
+
+  #[test]
+  fn test_cell_interned() {
+    let pair = || Noun::cell_interned(noun!(1), noun!(2));
+    let a = Noun::cell_interned(pair(), pair());
+    let b = Noun::cell_interned(pair(), pair());
+    assert!(a.ptr_eq(&b));
+    let (head, tail) = a.as_cell().unwrap();
+    assert!(head.ptr_eq(tail));
+
+    // children built with `cell` are different children
+    let c = Noun::cell_interned(noun!({1, 2}), noun!({1, 2}));
+    assert!(!a.ptr_eq(&c));
+    assert_eq!(a, c);
+
+    // a dropped cell isn't held on to
+    let weak = pair().downgrade().unwrap();
+    drop((a, b));
+    assert_eq!(weak.strong_count(), 0);
+    for n in 0..5_000u64 {
+      Noun::cell_interned(Noun::from(n), noun!(0));
+    }
+    super::INTERNED.with_borrow(|interned| assert!(interned.cells.len() < 5_000));
+  }
+
+  #[test]
+  fn test_dfs() {
+    let n = noun!({{4, 5}, {6, 7}});
+    let visited: Vec<_> = n
+      .dfs()
+      .map(|(axis, noun)| (axis.unwrap(), noun.to_string()))
+      .collect();
+
+    assert_eq!(
+      visited,
+      [
+        (1, "{{4 5} 6 7}".to_string()),
+        (2, "{4 5}".to_string()),
+        (4, "4".to_string()),
+        (5, "5".to_string()),
+        (3, "{6 7}".to_string()),
+        (6, "6".to_string()),
+        (7, "7".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_walk_deep() {
+    let deep = (0..100_000).fold(noun!(0), |n, _| Noun::cell(noun!(1), n));
+
+    let mut atoms = 0;
+    let mut last_axis = None;
+    deep.walk(|axis, noun| {
+      if noun.as_atom().is_some() {
+        atoms += 1;
+        last_axis = axis;
+      }
+      true
+    });
+
+    assert_eq!(atoms, 100_001);
+    assert_eq!(last_axis, None);
+
+    // pruning at the root visits only the root
+    let mut visited = 0;
+    deep.walk(|_, _| {
+      visited += 1;
+      false
+    });
+    assert_eq!(visited, 1);
+  }
+
+  #[test]
+  fn test_drop_deep() {
+    let deep = (0..1_000_000).fold(noun!(0), |n, _| Noun::cell(n, noun!(1)));
+    let shared = deep.slot(2).unwrap();
+    drop(deep);
+    assert_eq!(shared.slot(2).unwrap().slot(3).unwrap(), noun!(1));
+    drop(shared);
+  }
+
+  #[test]
+  fn test_metrics() {
+    let n = noun!({{4, 5}, 6});
+    let m = n.metrics();
+    assert_eq!((m.nodes, m.depth, m.atoms, m.unique), (5, 2, 3, 5));
+
+    let m = noun!(42).metrics();
+    assert_eq!((m.nodes, m.depth, m.atoms, m.unique), (1, 0, 1, 1));
+
+    // doubling a shared subtree 100 times
+    let shared = (0..100).fold(noun!(0), |n, _| Noun::cell(n.clone(), n));
+    let m = shared.metrics();
+    assert_eq!(m.depth, 100);
+    assert_eq!(m.unique, 101);
+    assert_eq!(m.nodes, u64::MAX);
+    assert_eq!(m.atoms, u64::MAX);
+
+    // cells know their own, without a walk
+    assert_eq!(shared.depth(), 100);
+    assert_eq!(shared.nodes(), u64::MAX);
+    let list = Noun::list(0..10u64);
+    assert_eq!((list.nodes(), list.depth()), (21, 10));
+    assert_eq!((noun!(7).nodes(), noun!(7).depth()), (1, 0));
+  }
+
+  #[test]
+  fn test_to_dot() {
+    let pair = noun!({1, 2});
+    let n = Noun::cell(pair.clone(), pair);
+    assert_eq!(
+      n.to_dot(),
+      "digraph noun {
+  node [shape=point];
+  0;
+  1;
+  0:sw -> 1;
+  0:se -> 1;
+  2 [shape=box, label=\"1\"];
+  1:sw -> 2;
+  3 [shape=box, label=\"2\"];
+  1:se -> 3;
+}
+"
+    );
+    assert_eq!(
+      noun!(42).to_dot(),
+      "digraph noun {\n  node [shape=point];\n  0 [shape=box, label=\"42\"];\n}\n"
+    );
+
+    // a node for each unique subtree, and two edges for each cell of them
+    let shared = (0..100).fold(noun!({0, 0}), |n, _| Noun::cell(n.clone(), n));
+    let dot = shared.to_dot();
+    let unique = shared.metrics().unique as usize;
+    let lines = dot.lines().count() - 3;
+    assert_eq!(lines, unique + 2 * (unique - 1));
+  }
+
+  #[test]
+  fn test_compact() {
+    let big = Noun::atom(Atom::from_words(&[1, 2, 3]));
+    let n = noun!({{(big.clone()), {1, 2}}, {(big), {1, 2}}});
+    assert_eq!(n.metrics().unique, 8);
+
+    let c = n.compact();
+    assert_eq!(c, n);
+    assert_eq!(c.metrics().unique, 6);
+    let (head, tail) = c.as_cell().unwrap();
+    assert!(head.ptr_eq(tail));
+    let old = n.slot(4).unwrap();
+    let new = c.slot(4).unwrap();
+    assert_ne!(
+      old.as_atom().unwrap().words().as_ptr(),
+      new.as_atom().unwrap().words().as_ptr()
+    );
+    assert!(!c.slot(5).unwrap().ptr_eq(&n.slot(5).unwrap()));
+
+    // sharing across the noun is kept, and deep nouns don't recurse
+    let shared = (0..100).fold(noun!(0), |n, _| Noun::cell(n.clone(), n));
+    assert_eq!(shared.compact().metrics().unique, 101);
+    let deep = (0..100_000u64).fold(noun!(0), |n, i| Noun::cell(Noun::from(i), n));
+    assert_eq!(deep.compact(), deep);
+    assert_eq!(noun!(7).compact(), noun!(7));
+  }
+
+  #[test]
+  fn test_inline_atoms() {
+    const ANSWER: Noun = Noun::atom(Atom::small(42));
+
+    // atoms are values, so equal atoms are interchangeable
+    assert!(ANSWER.ptr_eq(&noun!(42)));
+    assert!(Noun::YES.ptr_eq(&noun!(0)));
+    assert!(Noun::small(255).ptr_eq(&noun!(255)));
+    assert!(!noun!({1, 2}).ptr_eq(&noun!({1, 2})));
+
+    let n = noun!({1, 2});
+    assert!(n.ptr_eq(&n.clone()));
+  }
+
+  #[test]
+  fn test_list() {
+    let l = Noun::list([1, 2, 3]);
+    assert_eq!(l, noun!({1, {2, {3, 0}}}));
+    assert_eq!(Noun::list(Vec::<Noun>::new()), noun!(0));
+
+    let items: Vec<_> = l.iter_list().map(|item| item.unwrap().clone()).collect();
+    assert_eq!(items, [noun!(1), noun!(2), noun!(3)]);
+
+    let improper = noun!({1, {2, 3}});
+    let mut improper = improper.iter_list();
+    assert_eq!(improper.next().unwrap().unwrap(), &noun!(1));
+    assert_eq!(improper.next().unwrap().unwrap(), &noun!(2));
+    assert!(matches!(
+      improper.next(),
+      Some(Err(NockError::ImproperList { .. }))
+    ));
+    assert!(improper.next().is_none());
+  }
+
+  #[test]
+  fn test_eq_hash() {
+    use std::collections::HashMap;
+
+    let a = noun!({{1, 2}, 3});
+    let b = noun!({{1, 2}, 3});
+    let c = noun!({1, {2, 3}});
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.digest(), b.digest());
+    assert_ne!(a.digest(), c.digest());
+    assert_ne!(noun!({0, 0}).digest(), noun!(0).digest());
+
+    let mut map = HashMap::new();
+    map.insert(a, "a");
+    map.insert(c, "c");
+
+    assert_eq!(map.get(&b), Some(&"a"));
+    assert_eq!(map.len(), 2);
+  }
+
+  #[test]
+  fn test_equals() {
+    let mut equals = Equals::default();
+    let (a, b) = (noun!({{1, 2}, 3}), noun!({{1, 2}, 3}));
+
+    assert!(equals.eq(&a, &b));
+    assert!(equals.eq(&b, &a));
+    assert_eq!(equals.pairs.len(), 1);
+    assert!(!a.ptr_eq(&b));
+
+    assert!(!equals.eq(&a, &noun!({{1, 2}, 4})));
+    assert!(equals.eq(&noun!(7), &noun!(7)));
+    assert_eq!(equals.pairs.len(), 1);
+
+    // a pair held there doesn't keep its cells' children
+    let head = a.slot(2).unwrap();
+    drop((a, b));
+    assert_eq!(head.downgrade().unwrap().strong_count(), 1);
+  }
+
+  #[test]
+  fn test_diff() {
+    let a = noun!({{1, 2}, {3, 4}});
+    assert!(noun_diff(&a, &a.clone()).is_empty());
+    assert!(noun_diff(&a, &noun!({{1, 2}, {3, 4}})).is_empty());
+
+    let b = noun!({{1, 9}, 5});
+    let diff = noun_diff(&a, &b);
+    assert_eq!(
+      diff,
+      [
+        Divergence {
+          axis: Some(5),
+          left: noun!(2),
+          right: noun!(9),
+        },
+        Divergence {
+          axis: Some(3),
+          left: noun!({3, 4}),
+          right: noun!(5),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_ord() {
+    use std::collections::BTreeSet;
+
+    let set: BTreeSet<_> = [
+      noun!({2, 1}),
+      noun!({1, 2}),
+      noun!(7),
+      noun!({1, {0, 0}}),
+      noun!(3),
+      noun!({1, 2}),
+    ]
+    .into_iter()
+    .collect();
+    let sorted: Vec<_> = set.iter().map(ToString::to_string).collect();
+
+    assert_eq!(sorted, ["3", "7", "{1 2}", "{1 0 0}", "{2 1}"]);
+  }
+
+  #[test]
+  fn test_slot() {
+    let n = noun!({{4, 5}, {6, {14, 15}}});
+
+    assert!(noun_eq(n.slot(1).unwrap(), n.clone()));
+    assert!(noun_eq(n.slot(2).unwrap(), noun!({4, 5})));
+    assert!(noun_eq(n.slot(4).unwrap(), noun!(4)));
+    assert!(noun_eq(n.slot(6).unwrap(), noun!(6)));
+    assert!(noun_eq(n.slot(15).unwrap(), noun!(15)));
+
+    assert!(matches!(n.slot(0), Err(NockError::ZeroAxis)));
+    assert!(matches!(
+      n.slot(12),
+      Err(NockError::AxisOutOfTree { axis }) if axis == Atom::new(12)
+    ));
+    assert!(matches!(
+      n.slot(u64::MAX),
+      Err(NockError::AxisOutOfTree { .. })
+    ));
+
+    assert_eq!(n.slot_opt(7), Some(noun!({14, 15})));
+    assert_eq!(n.slot_opt(0), None);
+    assert_eq!(n.slot_opt(12), None);
+    assert_eq!(n.slot_opt(u64::MAX), None);
+
+    // 2^65 + 2^64 - 1 goes left once and then right 64 times
+    let mut deep = noun!(7);
+    for _ in 0..64 {
+      deep = Noun::cell(noun!(0), deep);
+    }
+    let deep = Noun::cell(deep, noun!(0));
+    let axis = Atom::from_words(&[u64::MAX, 2]);
+    assert!(noun_eq(deep.slot_atom(&axis).unwrap(), noun!(7)));
+    assert!(noun_eq(
+      deep
+        .slot_atom(&Atom::from_words(&[u64::MAX >> 1, 1]))
+        .unwrap(),
+      noun!({0, 7})
+    ));
+
+    let e = deep
+      .slot_atom(&Atom::from_words(&[u64::MAX, 3]))
+      .unwrap_err();
+    assert!(matches!(e, NockError::AxisOutOfTree { axis } if axis.words() == [u64::MAX, 3]));
+  }
+
+  #[test]
+  fn test_edit() {
+    let n = noun!({{4, 5}, {6, {14, 15}}});
+
+    assert!(noun_eq(n.edit(1, noun!(0)).unwrap(), noun!(0)));
+    assert!(noun_eq(
+      n.edit(5, noun!(0)).unwrap(),
+      noun!({{4, 0}, {6, {14, 15}}})
+    ));
+    assert!(noun_eq(
+      n.edit(14, noun!({1, 2})).unwrap(),
+      noun!({{4, 5}, {6, {{1, 2}, 15}}})
+    ));
+
+    assert!(matches!(n.edit(0, noun!(0)), Err(NockError::ZeroAxis)));
+    assert!(matches!(
+      n.edit(13, noun!(0)),
+      Err(NockError::AxisOutOfTree { axis }) if axis == Atom::new(13)
+    ));
+  }
+}
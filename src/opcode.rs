@@ -0,0 +1,106 @@
+use crate::{atom::Atom, error::NockError, noun::Noun};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Opcode {
+  Addr = 0,
+  Idty = 1,
+  Eval = 2,
+  Cell = 3,
+  Incr = 4,
+  Eqal = 5,
+  Brch = 6,
+  Cmps = 7,
+  Extn = 8,
+  Invk = 9,
+  Rplc = 10,
+  Hint = 11,
+  // not part of nock 4k proper, reads from a namespace outside the subject
+  Scry = 12,
+}
+
+impl Opcode {
+  pub const ALL: [Opcode; 13] = [
+    Opcode::Addr,
+    Opcode::Idty,
+    Opcode::Eval,
+    Opcode::Cell,
+    Opcode::Incr,
+    Opcode::Eqal,
+    Opcode::Brch,
+    Opcode::Cmps,
+    Opcode::Extn,
+    Opcode::Invk,
+    Opcode::Rplc,
+    Opcode::Hint,
+    Opcode::Scry,
+  ];
+
+  pub const fn atom(self) -> Atom {
+    Atom::small(self as u8)
+  }
+
+  pub const fn name(self) -> &'static str {
+    match self {
+      Opcode::Addr => "addr",
+      Opcode::Idty => "idty",
+      Opcode::Eval => "eval",
+      Opcode::Cell => "cell",
+      Opcode::Incr => "incr",
+      Opcode::Eqal => "eqal",
+      Opcode::Brch => "brch",
+      Opcode::Cmps => "cmps",
+      Opcode::Extn => "extn",
+      Opcode::Invk => "invk",
+      Opcode::Rplc => "rplc",
+      Opcode::Hint => "hint",
+      Opcode::Scry => "scry",
+    }
+  }
+}
+
+impl TryFrom<Atom> for Opcode {
+  type Error = NockError;
+
+  fn try_from(atom: Atom) -> Result<Self, Self::Error> {
+    atom
+      .as_u64()
+      .and_then(|atom| usize::try_from(atom).ok())
+      .and_then(|index| Opcode::ALL.get(index).copied())
+      .ok_or(NockError::UnknownOpcode(atom))
+  }
+}
+
+impl From<Opcode> for Atom {
+  fn from(opcode: Opcode) -> Self {
+    opcode.atom()
+  }
+}
+
+impl From<Opcode> for Noun {
+  fn from(opcode: Opcode) -> Self {
+    Noun::atom(opcode.atom())
+  }
+}
+
+impl std::fmt::Display for Opcode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", *self as u8)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::Opcode;
+  use crate::atom::Atom;
+
+  #[test]
+  fn test_opcode_roundtrip() {
+    for opcode in Opcode::ALL {
+      assert_eq!(Opcode::try_from(Atom::from(opcode)).unwrap(), opcode);
+    }
+
+    assert!(Opcode::try_from(Atom::new(13)).is_err());
+    assert!(Opcode::try_from(Atom::new(u64::MAX)).is_err());
+  }
+}
@@ -0,0 +1,198 @@
+// noun   ::= atom | '{' noun noun+ '}'
+// atom   ::= digits(0-9) | '0x' digits(0-9a-f) | '0b' digits(01)
+// digits ::= d+ (('.' | '_') d+)*
+//
+// {a b c} ~> {a {b c}}
+
+use crate::{atom::Atom, noun::Noun};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+  pub offset: usize,
+  pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} at offset {}", self.message, self.offset)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(input: &str) -> Result<Noun, ParseError> {
+  let mut parser = Parser { input, offset: 0 };
+  let noun = parser.noun()?;
+
+  parser.skip_whitespace();
+  if parser.offset != input.len() {
+    return Err(parser.error("expected end of input"));
+  }
+
+  Ok(noun)
+}
+
+impl std::str::FromStr for Noun {
+  type Err = ParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    parse(s)
+  }
+}
+
+struct Parser<'a> {
+  input: &'a str,
+  offset: usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, message: &str) -> ParseError {
+    ParseError {
+      offset: self.offset,
+      message: message.to_string(),
+    }
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.input[self.offset..].chars().next()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while let Some(c) = self.peek() {
+      if !c.is_whitespace() {
+        break;
+      }
+      self.offset += c.len_utf8();
+    }
+  }
+
+  fn noun(&mut self) -> Result<Noun, ParseError> {
+    self.skip_whitespace();
+
+    match self.peek() {
+      Some('{') => self.cell(),
+      Some(c) if c.is_ascii_digit() => self.atom(),
+      Some(_) => Err(self.error("unexpected character")),
+      None => Err(self.error("unexpected end of input")),
+    }
+  }
+
+  fn atom(&mut self) -> Result<Noun, ParseError> {
+    let start = self.offset;
+
+    while let Some(c) = self.peek() {
+      if !(c.is_ascii_alphanumeric() || c == '.' || c == '_') {
+        break;
+      }
+      self.offset += 1;
+    }
+
+    let literal = &self.input[start..self.offset];
+    let (radix, digits) = match literal {
+      _ if literal.starts_with("0x") => (16, &literal[2..]),
+      _ if literal.starts_with("0b") => (2, &literal[2..]),
+      _ => (10, literal),
+    };
+
+    // separators only go between digits
+    let grouped = digits.split(['.', '_']).all(|group| !group.is_empty());
+
+    let atom = grouped
+      .then(|| digits.replace(['.', '_'], ""))
+      .and_then(|digits| Atom::from_digits(&digits, radix));
+
+    match atom {
+      Some(atom) => Ok(Noun::atom(atom)),
+      None => Err(ParseError {
+        offset: start,
+        message: "invalid atom literal".to_string(),
+      }),
+    }
+  }
+
+  fn cell(&mut self) -> Result<Noun, ParseError> {
+    let start = self.offset;
+    self.offset += 1;
+
+    let mut items = vec![];
+
+    loop {
+      self.skip_whitespace();
+
+      if let Some('}') = self.peek() {
+        self.offset += 1;
+        break;
+      }
+
+      items.push(self.noun()?);
+    }
+
+    if items.len() < 2 {
+      return Err(ParseError {
+        offset: start,
+        message: "a cell needs at least two nouns".to_string(),
+      });
+    }
+
+    let mut items = items.into_iter().rev();
+    let mut cell = items.next().unwrap();
+
+    for item in items {
+      cell = Noun::cell(item, cell);
+    }
+
+    Ok(cell)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::parse;
+  use crate::noun::noun_eq;
+
+  #[test]
+  fn test_parse_cell() {
+    let p = parse("{{8 42} 5 2}").unwrap();
+    let e = noun!({{8, 42}, {5, 2}});
+
+    assert!(noun_eq(p, e));
+  }
+
+  #[test]
+  fn test_parse_roundtrip() {
+    let a = noun!({{{8, 42}, 5}, {addr, 9}});
+
+    assert_eq!(parse(&a.to_string()).unwrap().to_string(), a.to_string());
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert!(parse("{1}").is_err());
+    assert!(parse("{1 2").is_err());
+    assert!(parse("1 2").is_err());
+    assert!(parse("").is_err());
+  }
+
+  #[test]
+  fn test_parse_literals() {
+    assert_eq!(parse("0x1f").unwrap(), noun!(31));
+    assert_eq!(parse("0b1010").unwrap(), noun!(10));
+    assert_eq!(parse("1.000.000").unwrap(), noun!(1_000_000));
+    assert_eq!(parse("1_000").unwrap(), noun!(1000));
+    assert_eq!(parse("0xdead.beef").unwrap(), noun!(0xdead_beef));
+    assert_eq!(parse("{0x10 0b1}").unwrap(), noun!({16, 1}));
+
+    for bad in ["0x", "1..0", "1.", "_1", "0b2", "12ab", "0xg"] {
+      assert_eq!(parse(bad).unwrap_err().offset, 0, "{bad}");
+    }
+  }
+
+  #[test]
+  fn test_parse_big_atom() {
+    let digits = "340282366920938463463374607431768211456";
+    let p = parse(digits).unwrap();
+
+    assert_eq!(p.as_atom().unwrap().words(), [0, 0, 1]);
+    assert_eq!(p.to_string(), digits);
+  }
+}
@@ -0,0 +1,236 @@
+// Phonemic (@p) names for atoms, e.g. `~zod`, `~marzod` and `~sampel-palnet`.
+//
+// Each byte is a syllable, with prefixes for the high byte of every 16-bit
+// word and suffixes for the low one. Atoms between 16 and 64 bits are first
+// scrambled with the same feistel cipher urbit uses, so neighbouring numbers
+// get unrelated names.
+
+use crate::{atom::Atom, bits, murmur::murmur3_32, parse::ParseError};
+
+const PREFIXES: &str = "\
+dozmarbinwansamlitsighidfidlissogdirwacsabwissibrigsoldopmodfoglidhopdardorlorhodfolrintogsilmirho\
+lpaslacrovlivdalsatlibtabhanticpidtorbolfosdotlosdilforpilramtirwintadbicdifrocwidbisdasmidloprilna\
+rdapmolsanlocnovsitnidtipsicropwitnatpanminritpodmottamtolsavposnapnopsomfinfonbanmorworsipronnorbo\
+twicsocwatdolmagpicdavbidbaltimtasmalligsivtagpadsaldivdactansidfabtarmonranniswolmispallasdismapra\
+btobrollatlonnodnavfignomnibpagsopralbilhaddocridmocpacravripfaltodtiltinhapmicfanpattaclabmogsimso\
+npinlomrictapfirhasbosbatpochactidhavsaplindibhosdabbitbarracparloddosbortochilmactomdigfilfasmitho\
+bharmighinradmashalraglagfadtopmophabnilnosmilfopfamdatnoldinhatnacrisfotribhocnimlarfitwalrapsarna\
+lmoslandondanladdovrivbacpollaptalpitnambonrostonfodponsovnocsorlavmatmipfip";
+
+const SUFFIXES: &str = "\
+zodnecbudwessevpersutletfulpensytdurwepserwylsunrypsyxdyrnuphebpeglupdepdysputlughecryttyvsydnexlu\
+nmeplutseppesdelsulpedtemledtulmetwenbynhexfebpyldulhetmevruttylwydtepbesdexsefwycburderneppurrysre\
+bdennutsubpetrulsynregtydsupsemwynrecmegnetsecmulnymtevwebsummutnyxrextebfushepbenmuswyxsymselrucde\
+cwexsyrwetdylmynmesdetbetbeltuxtugmyrpelsyptermebsetdutdegtexsurfeltudnuxruxrenwytnubmedlytdusnebru\
+mtynseglyxpunresredfunrevrefmectedrusbexlebduxrynnumpyxrygryxfeptyrtustyclegnemfermertenlusnussylte\
+cmexpubrymtucfyllepdebbermughuttunbylsudpemdevlurdefbusbeprunmelpexdytbyttyplevmylwedducfurfexnullu\
+clennerlexrupnedlecrydlydfenwelnydhusrelrudneshesfetdesretdunlernyrsebhulrylludremlysfynwerrycsugny\
+snyllyndyndemluxfedsedbecmunlyrtesmudnytbyrsenwegfyrmurtelreptegpecnelnevfes";
+
+fn syllable(table: &str, byte: u64) -> &str {
+  let i = byte as usize * 3;
+  &table[i..i + 3]
+}
+
+fn byte_of(table: &str, syllable: &str) -> Option<u64> {
+  (0..256).find(|&byte| self::syllable(table, byte) == syllable)
+}
+
+pub fn render(atom: &Atom) -> String {
+  let atom = match atom.as_u64() {
+    Some(n) => Atom::new(fein(n)),
+    None => atom.clone(),
+  };
+
+  if bits::met(3, &atom) <= 1 {
+    return format!("~{}", syllable(SUFFIXES, atom.as_u64().unwrap()));
+  }
+
+  let words = bits::met(4, &atom);
+  let mut rendered = String::from("~");
+
+  for i in (0..words).rev() {
+    let word = bits::cut(4, i, 1, &atom).as_u64().unwrap();
+
+    rendered.push_str(syllable(PREFIXES, word >> 8));
+    rendered.push_str(syllable(SUFFIXES, word & 0xff));
+
+    if i != 0 {
+      rendered.push_str(if i % 4 == 0 { "--" } else { "-" });
+    }
+  }
+
+  rendered
+}
+
+pub fn parse(input: &str) -> Result<Atom, ParseError> {
+  let error = |message: &str| ParseError {
+    offset: 0,
+    message: message.to_string(),
+  };
+
+  let name = input
+    .strip_prefix('~')
+    .ok_or_else(|| error("expected '~'"))?;
+
+  let atom = if name.len() == 3 {
+    let byte = byte_of(SUFFIXES, name).ok_or_else(|| error("unknown syllable"))?;
+    Atom::new(byte)
+  } else {
+    let mut atom = Atom::new(0);
+
+    for word in name.split('-').filter(|word| !word.is_empty()) {
+      let (Some(prefix), Some(suffix)) = (word.get(..3), word.get(3..)) else {
+        return Err(error("expected a pair of syllables"));
+      };
+
+      let hi = byte_of(PREFIXES, prefix).ok_or_else(|| error("unknown syllable"))?;
+      let lo = byte_of(SUFFIXES, suffix).ok_or_else(|| error("unknown syllable"))?;
+      let shifted = bits::lsh(4, 1, &atom).expect("a name's worth of bits fits");
+      atom = bits::con(&shifted, &Atom::new((hi << 8) | lo));
+    }
+
+    atom
+  };
+
+  let atom = match atom.as_u64() {
+    Some(n) => Atom::new(fynd(n)),
+    None => atom,
+  };
+
+  // rejects leading zero words and misplaced dashes
+  if render(&atom) != input {
+    return Err(error("not a canonical @p"));
+  }
+
+  Ok(atom)
+}
+
+// the cipher only touches the low 32 bits, and leaves galaxies and stars be
+fn fein(n: u64) -> u64 {
+  match n {
+    0x1_0000..=0xffff_ffff => 0x1_0000 + feis(n - 0x1_0000),
+    0x1_0000_0000.. => (n & !0xffff_ffff) | fein(n & 0xffff_ffff),
+    _ => n,
+  }
+}
+
+fn fynd(n: u64) -> u64 {
+  match n {
+    0x1_0000..=0xffff_ffff => 0x1_0000 + tail(n - 0x1_0000),
+    0x1_0000_0000.. => (n & !0xffff_ffff) | fynd(n & 0xffff_ffff),
+    _ => n,
+  }
+}
+
+const A: u64 = 0xffff;
+const B: u64 = 0x1_0000;
+const K: u64 = 0xffff * 0x1_0000;
+const ROUNDS: u64 = 4;
+
+fn eff(j: u64, r: u64) -> u64 {
+  const RAKU: [u32; 4] = [0xb76d_5eed, 0xee28_1300, 0x85bc_ae01, 0x4b38_7af7];
+  u64::from(murmur3_32(&(r as u16).to_le_bytes(), RAKU[j as usize]))
+}
+
+// cycle walking keeps the output below `K`
+fn feis(m: u64) -> u64 {
+  let c = fe(m);
+  if c < K { c } else { fe(c) }
+}
+
+fn tail(m: u64) -> u64 {
+  let c = fen(m);
+  if c < K { c } else { fen(c) }
+}
+
+// four feistel rounds, alternating modulus between `A` and `B`
+fn fe(m: u64) -> u64 {
+  let (mut ell, mut arr) = (m % A, m / A);
+
+  for j in 1..=ROUNDS {
+    let f = eff(j - 1, arr);
+    let tmp = if j % 2 == 1 {
+      (f + ell) % A
+    } else {
+      (f + ell) % B
+    };
+    (ell, arr) = (arr, tmp);
+  }
+
+  if arr == A {
+    arr * A + ell
+  } else {
+    ell * A + arr
+  }
+}
+
+// `fe` run backwards
+fn fen(m: u64) -> u64 {
+  let (mut ell, mut arr) = if m / A == A {
+    (m % A, A)
+  } else {
+    (m / A, m % A)
+  };
+
+  for j in (1..=ROUNDS).rev() {
+    let f = eff(j - 1, ell);
+    let tmp = if j % 2 == 1 {
+      (arr + A - f % A) % A
+    } else {
+      (arr + B - f % B) % B
+    };
+    (ell, arr) = (tmp, ell);
+  }
+
+  arr * A + ell
+}
+
+#[cfg(test)]
+mod test {
+  use super::{parse, render};
+  use crate::atom::Atom;
+
+  #[test]
+  fn test_render() {
+    let cases: [(u64, &str); 11] = [
+      (0, "~zod"),
+      (1, "~nec"),
+      (255, "~fes"),
+      (256, "~marzod"),
+      (65535, "~fipfes"),
+      (65536, "~dapnep-ronmyl"),
+      (14287616, "~rosmur-hobrem"),
+      (14287617, "~sallus-nodlut"),
+      (1624961343, "~sampel-palnet"),
+      (4294967295, "~dostec-risfen"),
+      (4294967296, "~doznec-dozzod-dozzod"),
+    ];
+
+    for (n, name) in cases {
+      assert_eq!(render(&Atom::new(n)), name);
+      assert_eq!(parse(name).unwrap(), Atom::new(n));
+    }
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    for n in (0..1 << 40).step_by(0x1357_9bdf) {
+      assert_eq!(parse(&render(&Atom::new(n))).unwrap(), Atom::new(n));
+    }
+
+    let big = Atom::from_words(&[0x1234, 0x5678]);
+    assert_eq!(render(&big), "~posfel--dozzod-dozzod-dozzod-doprut");
+    assert_eq!(parse(&render(&big)).unwrap(), big);
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert!(parse("zod").is_err());
+    assert!(parse("~zo").is_err());
+    assert!(parse("~zodmar").is_err());
+    assert!(parse("~dozmarzod").is_err());
+    assert!(parse("~dozzod-marzod").is_err());
+    assert!(parse("~sampel--palnet").is_err());
+  }
+}
@@ -0,0 +1,184 @@
+// Urbit pills, the jammed nouns a ship boots from, as `lib/pill` has them:
+//
+//   [%pill nam=term boot=(list) kernel=(list) userspace=(list)]
+//   [%ivory boot=(list)]
+//
+// The boot formulas build the kernel, and the kernel and userspace events
+// are fed to it after. An ivory pill is only the formulas, so it has no
+// events, and its name is `%ivory`.
+
+use std::io::{self, Read};
+
+use crate::{
+  atom::Atom,
+  noun::Noun,
+  serial::{CueError, CueReadError, cue_bytes, cue_reader},
+};
+
+const PILL: &str = "pill";
+const IVORY: &str = "ivory";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pill {
+  pub name: Atom,
+  pub boot: Vec<Noun>,
+  pub kernel: Vec<Noun>,
+  pub userspace: Vec<Noun>,
+}
+
+#[derive(Debug)]
+pub enum PillError {
+  Io(io::Error),
+  Cue(CueError),
+  // the noun isn't a pill, and where it differs is `expected` instead
+  NotPill { expected: &'static str },
+}
+
+impl std::fmt::Display for PillError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "{e}"),
+      Self::Cue(e) => write!(f, "{e}"),
+      Self::NotPill { expected } => write!(f, "not a pill, expected {expected}"),
+    }
+  }
+}
+
+impl std::error::Error for PillError {}
+
+impl From<io::Error> for PillError {
+  fn from(e: io::Error) -> Self {
+    Self::Io(e)
+  }
+}
+
+impl From<CueError> for PillError {
+  fn from(e: CueError) -> Self {
+    Self::Cue(e)
+  }
+}
+
+impl From<CueReadError> for PillError {
+  fn from(e: CueReadError) -> Self {
+    match e {
+      CueReadError::Io(e) => Self::Io(e),
+      CueReadError::Cue(e) => Self::Cue(e),
+    }
+  }
+}
+
+fn list(noun: &Noun, expected: &'static str) -> Result<Vec<Noun>, PillError> {
+  noun
+    .iter_list()
+    .map(|item| item.cloned().map_err(|_| PillError::NotPill { expected }))
+    .collect()
+}
+
+impl Pill {
+  pub fn from_noun(noun: &Noun) -> Result<Pill, PillError> {
+    let not_pill = |expected| PillError::NotPill { expected };
+    let (tag, rest) = noun.as_cell().ok_or(not_pill("a tagged cell"))?;
+    let tag = tag.as_atom().ok_or(not_pill("%pill or %ivory"))?;
+
+    if *tag == Atom::from_cord(IVORY) {
+      return Ok(Pill {
+        name: tag.clone(),
+        boot: list(rest, "a list of boot formulas")?,
+        kernel: vec![],
+        userspace: vec![],
+      });
+    }
+    if *tag != Atom::from_cord(PILL) {
+      return Err(not_pill("%pill or %ivory"));
+    }
+
+    let shape = "[name boot kernel userspace]";
+    let (name, rest) = rest.as_cell().ok_or(not_pill(shape))?;
+    let (boot, rest) = rest.as_cell().ok_or(not_pill(shape))?;
+    let (kernel, userspace) = rest.as_cell().ok_or(not_pill(shape))?;
+
+    Ok(Pill {
+      name: name
+        .as_atom()
+        .ok_or(not_pill("a term for the name"))?
+        .clone(),
+      boot: list(boot, "a list of boot formulas")?,
+      kernel: list(kernel, "a list of kernel events")?,
+      userspace: list(userspace, "a list of userspace events")?,
+    })
+  }
+
+  // a pill file's little-endian bytes
+  pub fn from_bytes(bytes: &[u8]) -> Result<Pill, PillError> {
+    Pill::from_noun(&cue_bytes(bytes)?)
+  }
+
+  pub fn read(input: impl Read) -> Result<Pill, PillError> {
+    Pill::from_noun(&cue_reader(input)?)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Pill, PillError};
+  use crate::{
+    atom::Atom,
+    noun::Noun,
+    serial::{CueError, jam_bytes},
+  };
+
+  fn cord(text: &str) -> Noun {
+    Noun::from(Atom::from_cord(text))
+  }
+
+  #[test]
+  fn test_pill() {
+    let boot = Noun::list([noun!({2, {{0, 3}, {0, 2}}}), noun!({1, 0})]);
+    let kernel = Noun::list([noun!({{0, 0}, {1, 2}})]);
+    // the userspace events are the list the tuple ends in
+    let bytes = jam_bytes(&Noun::list([cord("pill"), cord("solid"), boot, kernel]));
+
+    let pill = Pill::from_bytes(&bytes).unwrap();
+    assert_eq!(pill.name, Atom::from_cord("solid"));
+    assert_eq!(pill.boot, [noun!({2, {{0, 3}, {0, 2}}}), noun!({1, 0})]);
+    assert_eq!(pill.kernel, [noun!({{0, 0}, {1, 2}})]);
+    assert!(pill.userspace.is_empty());
+    assert_eq!(Pill::read(&bytes[..]).unwrap(), pill);
+
+    let ivory = Noun::cell(cord("ivory"), Noun::list([noun!({1, 7})]));
+    let pill = Pill::from_noun(&ivory).unwrap();
+    assert_eq!(pill.name, Atom::from_cord("ivory"));
+    assert_eq!(pill.boot, [noun!({1, 7})]);
+    assert!(pill.kernel.is_empty() && pill.userspace.is_empty());
+  }
+
+  #[test]
+  fn test_pill_errors() {
+    for (noun, expected) in [
+      (noun!(7), "a tagged cell"),
+      (Noun::cell(cord("brass"), Noun::NULL), "%pill or %ivory"),
+      (
+        Noun::cell(cord("pill"), noun!({1, 2})),
+        "[name boot kernel userspace]",
+      ),
+      (
+        Noun::list([cord("pill"), cord("solid"), noun!(3), Noun::NULL]),
+        "a list of boot formulas",
+      ),
+      (
+        Noun::cell(cord("ivory"), noun!({1, 2})),
+        "a list of boot formulas",
+      ),
+    ] {
+      match Pill::from_noun(&noun) {
+        Err(PillError::NotPill { expected: e }) => assert_eq!(e, expected, "{noun}"),
+        other => panic!("{noun}: {other:?}"),
+      }
+    }
+
+    assert!(matches!(
+      Pill::from_bytes(&[]),
+      Err(PillError::Cue(CueError::Truncated { .. }))
+    ));
+  }
+}
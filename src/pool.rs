@@ -0,0 +1,170 @@
+// Independent evaluations spread over a fixed set of threads, each with an
+// interpreter of its own that's kept between jobs, so whatever it caches
+// carries over. Jobs are taken in the order they're submitted, by whichever
+// thread is free, and every job's product comes back on a channel of its own.
+
+use std::{
+  panic::{AssertUnwindSafe, catch_unwind},
+  sync::{
+    Arc, Mutex,
+    mpsc::{self, Receiver, Sender},
+  },
+  thread::JoinHandle,
+};
+
+use crate::{
+  error::NockError,
+  interp::{Formula, Interpreter, Subject},
+  noun::Noun,
+};
+
+type Product = Result<Noun, NockError>;
+type Job = (Subject, Formula, Sender<Product>);
+
+pub struct EvalPool {
+  // None once the pool is being dropped, which lets the threads finish
+  jobs: Option<Sender<Job>>,
+  threads: Vec<JoinHandle<()>>,
+}
+
+impl EvalPool {
+  // `threads` threads with default interpreters
+  pub fn new(threads: usize) -> Self {
+    Self::with(threads, Interpreter::new)
+  }
+
+  // `threads` threads, each with an interpreter made by `build`
+  pub fn with(threads: usize, build: impl Fn() -> Interpreter + Send + Sync + 'static) -> Self {
+    let (jobs, queue) = mpsc::channel::<Job>();
+    let queue = Arc::new(Mutex::new(queue));
+    let build = Arc::new(build);
+
+    let threads = (0..threads.max(1))
+      .map(|_| {
+        let queue = queue.clone();
+        let build = build.clone();
+        std::thread::spawn(move || work(&queue, &*build))
+      })
+      .collect();
+
+    Self {
+      jobs: Some(jobs),
+      threads,
+    }
+  }
+
+  pub fn threads(&self) -> usize {
+    self.threads.len()
+  }
+
+  // queues `*{subject formula}`, whose product comes on the channel returned,
+  // `NockError::Panicked` if the interpreter panics
+  pub fn submit(&self, subject: Subject, formula: Formula) -> Receiver<Product> {
+    let (product, receiver) = mpsc::channel();
+    // the threads only stop once the pool is dropped
+    self
+      .jobs
+      .as_ref()
+      .unwrap()
+      .send((subject, formula, product))
+      .unwrap();
+    receiver
+  }
+
+  // the products of `jobs`, in order, waiting for all of them
+  pub fn eval_all(&self, jobs: impl IntoIterator<Item = (Subject, Formula)>) -> Vec<Product> {
+    let receivers: Vec<_> = jobs
+      .into_iter()
+      .map(|(subject, formula)| self.submit(subject, formula))
+      .collect();
+
+    receivers
+      .into_iter()
+      .map(|receiver| receiver.recv().unwrap_or(Err(NockError::Panicked)))
+      .collect()
+  }
+}
+
+impl Drop for EvalPool {
+  // finishes the jobs already queued
+  fn drop(&mut self) {
+    drop(self.jobs.take());
+    for thread in self.threads.drain(..) {
+      let _ = thread.join();
+    }
+  }
+}
+
+fn work(queue: &Mutex<Receiver<Job>>, build: &dyn Fn() -> Interpreter) {
+  let mut it = build();
+
+  loop {
+    // the lock is only held while waiting for a job
+    let job = queue.lock().unwrap().recv();
+    let Ok((subject, formula, product)) = job else {
+      return;
+    };
+
+    match catch_unwind(AssertUnwindSafe(|| it.eval(subject, formula))) {
+      // nobody waiting on it is fine
+      Ok(prod) => drop(product.send(prod)),
+      // who knows what state the interpreter was left in
+      Err(_) => {
+        drop(product.send(Err(NockError::Panicked)));
+        it = build();
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::EvalPool;
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    error::NockError,
+    interp::{Formula, Interpreter, Subject},
+    noun::Noun,
+  };
+
+  #[test]
+  fn test_eval_pool() {
+    let pool = EvalPool::new(4);
+    assert_eq!(pool.threads(), 4);
+
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let jobs = (1..300u64).map(|n| (Subject(Noun::from(n)), Formula(decrement.clone())));
+    let prods = pool.eval_all(jobs);
+    assert_eq!(prods.len(), 299);
+    for (n, prod) in (1..300u64).zip(prods) {
+      assert_eq!(prod.unwrap(), Noun::from(n - 1));
+    }
+
+    // jobs fail on their own
+    let crash = pool.submit(Subject(noun!(0)), Formula(noun!({addr, 7})));
+    let fine = pool.submit(Subject(noun!({1, 2})), Formula(noun!({addr, 3})));
+    assert!(crash.recv().unwrap().is_err());
+    assert_eq!(fine.recv().unwrap().unwrap(), noun!(2));
+
+    // interpreters are built by the pool's threads
+    let pool = EvalPool::with(2, || Interpreter::builder().fuel(100).build());
+    let prod = pool.submit(Subject(Noun::from(1000u64)), Formula(decrement));
+    assert!(matches!(prod.recv().unwrap(), Err(e) if matches!(e.root(), NockError::OutOfFuel)));
+
+    // a job that panics the interpreter is an error, and the rest go on
+    let pool = EvalPool::with(1, || {
+      Interpreter::builder().slog(|_| panic!("slog")).build()
+    });
+    let slog = Noun::atom(Atom::from_cord("slog"));
+    let prods = pool.eval_all([
+      (
+        Subject(noun!(0)),
+        Formula(noun!({hint, {{(slog), {idty, 0}}, {idty, 1}}})),
+      ),
+      (Subject(noun!({1, 2})), Formula(noun!({addr, 2}))),
+    ]);
+    assert!(matches!(prods[0], Err(NockError::Panicked)));
+    assert_eq!(*prods[1].as_ref().unwrap(), noun!(1));
+  }
+}
@@ -0,0 +1,298 @@
+// A Python module, `nuuk`, built into the cdylib with the `nuuk-py` feature,
+// by maturin, which pyproject.toml sets up. It has `eval`, `jam` and `cue`,
+// and a `Noun` class that's made from an int, a `Noun`, or a tuple or list
+// of two or more of those, right nested, and goes back with `int()` or
+// `to_python()`.
+//
+// A noun stays on this side, so `Noun`s can't go to other threads.
+
+use pyo3::{
+  create_exception,
+  exceptions::{PyException, PyIndexError, PyTypeError, PyValueError},
+  prelude::*,
+  types::{PyBytes, PyInt, PyList, PyTuple},
+};
+
+use crate::{
+  atom::Atom,
+  interp::Interpreter,
+  noun::Noun,
+  serial::{cue_bytes, jam_bytes},
+};
+
+create_exception!(nuuk, NockError, PyException, "A formula crashed.");
+
+#[pyclass(
+  name = "Noun",
+  module = "nuuk",
+  frozen,
+  eq,
+  ord,
+  hash,
+  unsendable,
+  skip_from_py_object
+)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PyNoun(Noun);
+
+fn value_error(e: impl std::fmt::Display) -> PyErr {
+  PyValueError::new_err(e.to_string())
+}
+
+fn atom(int: &Bound<'_, PyInt>) -> PyResult<Atom> {
+  if let Ok(atom) = int.extract::<u64>() {
+    return Ok(Atom::new(atom));
+  }
+  if int.lt(0)? {
+    return Err(value_error("an atom can't be negative"));
+  }
+
+  let len = int
+    .call_method0("bit_length")?
+    .extract::<usize>()?
+    .div_ceil(8);
+  let bytes = int.call_method1("to_bytes", (len, "little"))?;
+  Ok(Atom::from_le_bytes(bytes.cast::<PyBytes>()?.as_bytes()))
+}
+
+fn to_noun(value: &Bound<'_, PyAny>) -> PyResult<Noun> {
+  if let Ok(noun) = value.cast::<PyNoun>() {
+    return Ok(noun.borrow().0.clone());
+  }
+  if let Ok(int) = value.cast::<PyInt>() {
+    return atom(int).map(Noun::from);
+  }
+
+  let items = if let Ok(tuple) = value.cast::<PyTuple>() {
+    tuple.as_sequence().clone()
+  } else if let Ok(list) = value.cast::<PyList>() {
+    list.as_sequence().clone()
+  } else {
+    return Err(PyTypeError::new_err(format!(
+      "can't make a noun of {}",
+      value.get_type().name()?
+    )));
+  };
+  if items.len()? < 2 {
+    return Err(value_error("a cell has two or more items"));
+  }
+
+  let items = items
+    .try_iter()?
+    .map(|item| to_noun(&item?))
+    .collect::<PyResult<Vec<_>>>()?;
+  let (last, items) = items.split_last().unwrap();
+  Ok(
+    items
+      .iter()
+      .rev()
+      .fold(last.clone(), |tail, head| Noun::cell(head.clone(), tail)),
+  )
+}
+
+fn to_int<'py>(py: Python<'py>, atom: &Atom) -> PyResult<Bound<'py, PyAny>> {
+  if let Some(atom) = atom.as_u64() {
+    return Ok(atom.into_pyobject(py)?.into_any());
+  }
+  let bytes = PyBytes::new(py, &atom.as_le_bytes());
+  py.get_type::<PyInt>()
+    .call_method1("from_bytes", (bytes, "little"))
+}
+
+#[pymethods]
+impl PyNoun {
+  #[new]
+  fn new(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+    to_noun(value).map(PyNoun)
+  }
+
+  #[staticmethod]
+  fn parse(text: &str) -> PyResult<Self> {
+    text.parse().map(PyNoun).map_err(value_error)
+  }
+
+  #[staticmethod]
+  fn cue(bytes: &[u8]) -> PyResult<Self> {
+    cue_bytes(bytes).map(PyNoun).map_err(value_error)
+  }
+
+  fn jam<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+    PyBytes::new(py, &jam_bytes(&self.0))
+  }
+
+  #[getter]
+  fn is_cell(&self) -> bool {
+    self.0.is_cell()
+  }
+
+  #[getter]
+  fn is_atom(&self) -> bool {
+    !self.0.is_cell()
+  }
+
+  #[getter]
+  fn head(&self) -> PyResult<Self> {
+    let (head, _) = self
+      .0
+      .as_cell()
+      .ok_or_else(|| PyTypeError::new_err("an atom has no head"))?;
+    Ok(PyNoun(head.clone()))
+  }
+
+  #[getter]
+  fn tail(&self) -> PyResult<Self> {
+    let (_, tail) = self
+      .0
+      .as_cell()
+      .ok_or_else(|| PyTypeError::new_err("an atom has no tail"))?;
+    Ok(PyNoun(tail.clone()))
+  }
+
+  #[getter]
+  fn mug(&self) -> u32 {
+    self.0.mug()
+  }
+
+  // the subtree at `axis`, `noun[1]` being all of it
+  fn __getitem__(&self, axis: u64) -> PyResult<Self> {
+    self
+      .0
+      .slot_opt(axis)
+      .map(PyNoun)
+      .ok_or_else(|| PyIndexError::new_err(format!("no axis {axis}")))
+  }
+
+  fn __int__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+    let atom = self
+      .0
+      .as_atom()
+      .ok_or_else(|| PyTypeError::new_err("a cell isn't an int"))?;
+    to_int(py, atom)
+  }
+
+  // ints and pairs of them, without recursing, so long lists are fine
+  fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+    let mut stack = vec![(&self.0, false)];
+    let mut done = vec![];
+
+    while let Some((noun, built)) = stack.pop() {
+      match noun.as_cell() {
+        None => done.push(to_int(py, noun.as_atom().unwrap())?),
+        Some(_) if built => {
+          let tail = done.pop().unwrap();
+          let head = done.pop().unwrap();
+          done.push(PyTuple::new(py, [head, tail])?.into_any());
+        }
+        Some((head, tail)) => stack.extend([(noun, true), (tail, false), (head, false)]),
+      }
+    }
+
+    Ok(done.pop().unwrap())
+  }
+
+  fn __str__(&self) -> String {
+    self.0.to_string()
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Noun.parse('{}')", self.0)
+  }
+}
+
+// the product of `formula` against `subject`, either one anything `Noun`
+// takes
+#[pyfunction]
+fn eval(subject: &Bound<'_, PyAny>, formula: &Bound<'_, PyAny>) -> PyResult<PyNoun> {
+  let (subject, formula) = (to_noun(subject)?, to_noun(formula)?);
+  Interpreter::new()
+    .eval(subject.into(), formula.into())
+    .map(PyNoun)
+    .map_err(|e| NockError::new_err(e.to_string()))
+}
+
+#[pyfunction]
+fn jam<'py>(noun: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+  Ok(PyBytes::new(noun.py(), &jam_bytes(&to_noun(noun)?)))
+}
+
+#[pyfunction]
+fn cue(bytes: &[u8]) -> PyResult<PyNoun> {
+  PyNoun::cue(bytes)
+}
+
+#[pymodule]
+fn nuuk(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add_class::<PyNoun>()?;
+  m.add("NockError", m.py().get_type::<NockError>())?;
+  m.add_function(wrap_pyfunction!(eval, m)?)?;
+  m.add_function(wrap_pyfunction!(jam, m)?)?;
+  m.add_function(wrap_pyfunction!(cue, m)?)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use pyo3::{prelude::*, types::PyDict};
+
+  #[test]
+  fn test_python() {
+    Python::initialize();
+    Python::attach(|py| {
+      let module = PyModule::new(py, "nuuk").unwrap();
+      super::nuuk(&module).unwrap();
+      // as if it had been imported
+      let modules = py.import("sys").unwrap().getattr("modules").unwrap();
+      modules.set_item("nuuk", module).unwrap();
+      let globals = PyDict::new(py);
+
+      py.run(
+        cr#"
+from nuuk import Noun, NockError, eval, jam, cue
+
+assert eval((4, 5), (4, 0, 3)) == Noun(6)
+assert eval(Noun.parse("{4 5}"), [4, 0, 3]).to_python() == 6
+big = 2**200 + 7
+assert int(Noun(big)) == big
+assert Noun((1, [2, 3])) == Noun((1, 2, 3)) == Noun.parse("{1 2 3}")
+assert Noun((1, 2, 3)).to_python() == (1, (2, 3))
+assert cue(jam((1, big))).to_python() == (1, big)
+assert Noun.cue(Noun(big).jam()) == Noun(big)
+
+noun = Noun(((4, 5), 6))
+assert noun.is_cell and not noun.head.head.is_cell and noun.head.is_cell
+assert noun[2] == Noun((4, 5)) and noun[5] == Noun(5) and int(noun.tail) == 6
+assert str(noun) == "{{4 5} 6}" and repr(Noun(7)) == "Noun.parse('7')"
+assert len({Noun(1), Noun(1), Noun((1, 1))}) == 2
+assert Noun(1) < Noun(2) < Noun((0, 0))
+
+# long lists don't recurse
+items = Noun(list(range(100_000)) + [0])
+assert items.to_python()[0] == 0
+
+def raises(error, f):
+  try:
+    f()
+  except error:
+    return
+  raise AssertionError(f"no {error.__name__}")
+
+raises(NockError, lambda: eval(1, (0, 7)))
+raises(ValueError, lambda: Noun(-1))
+raises(ValueError, lambda: Noun((1,)))
+raises(ValueError, lambda: Noun.parse("{1"))
+raises(ValueError, lambda: cue(b""))
+raises(TypeError, lambda: Noun("1"))
+raises(TypeError, lambda: int(Noun((1, 2))))
+raises(TypeError, lambda: Noun(1).head)
+raises(IndexError, lambda: Noun(1)[2])
+"#,
+        Some(&globals),
+        None,
+      )
+      .unwrap_or_else(|e| {
+        e.display(py);
+        panic!("{e}")
+      });
+    });
+  }
+}
@@ -0,0 +1,315 @@
+// Small allocations carved out of big aligned chunks, for programs that make
+// `Road` their global allocator:
+//
+//   #[global_allocator]
+//   static ALLOC: nuuk::road::Road = nuuk::road::Road;
+//
+// Allocating is bumping a pointer, and freeing counts down the live blocks of
+// the block's chunk, which goes back to the system once none are left. An
+// interpreter built with `road(true)` starts every evaluation on a road of its
+// own, with fresh chunks for the cells it makes along the way, and copies the
+// product onto the road it came from, so the chunks of the intermediates die
+// together, like an inner road in a mature nock runtime. What the interpreter
+// keeps between evaluations, like `%memo` products or eval cache entries,
+// keeps the chunks it's on alive.
+//
+// Without `Road` installed, roads do nothing.
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  cell::Cell,
+  collections::HashMap,
+  ptr::null_mut,
+  sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use crate::{
+  atom::Atom,
+  noun::{self, Noun, NounInner},
+};
+
+const CHUNK: usize = 1 << 18;
+const CHUNK_LAYOUT: Layout = match Layout::from_size_align(CHUNK, CHUNK) {
+  Ok(layout) => layout,
+  Err(_) => panic!("bad chunk layout"),
+};
+// allocations up to this size and alignment come from chunks, and no others
+const SMALL: usize = 128;
+const ALIGN: usize = 16;
+// a chunk's count of live blocks starts this high, so frees can't bring it to
+// zero while blocks are still handed out from it
+const OPEN: usize = usize::MAX / 2;
+// where blocks start, after the header
+const START: usize = size_of::<Header>();
+
+#[repr(C, align(64))]
+struct Header {
+  live: AtomicUsize,
+  road: u64,
+}
+
+// `Rc` keeps its two counts in front of the value, so cells are small
+const _: () = assert!(size_of::<noun::Cell>() + 2 * size_of::<usize>() <= SMALL);
+
+// set with the first chunk, after which every small block is in one
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+pub struct Road;
+
+#[derive(Clone, Copy)]
+struct Bump {
+  chunk: *mut u8,
+  next: usize,
+  blocks: usize,
+  road: u64,
+}
+
+const EMPTY: Bump = Bump {
+  chunk: null_mut(),
+  next: CHUNK,
+  blocks: 0,
+  road: 0,
+};
+
+// nothing here needs dropping, so it's there until the thread is gone; the
+// chunk being bumped and the spare are lost with the thread
+thread_local! {
+  static BUMP: Cell<Bump> = const { Cell::new(EMPTY) };
+  // roads started on this thread, the first is road 1
+  static ROADS: Cell<u64> = const { Cell::new(0) };
+  // a dead chunk kept for the next one, saving a trip to the system
+  static SPARE: Cell<*mut u8> = const { Cell::new(null_mut()) };
+  static FREED: Cell<usize> = const { Cell::new(0) };
+}
+
+fn small(layout: &Layout) -> bool {
+  layout.size() <= SMALL && layout.align() <= ALIGN
+}
+
+fn header<'a>(ptr: *const u8) -> &'a Header {
+  let chunk = ptr.map_addr(|addr| addr & !(CHUNK - 1));
+  // SAFETY: only called on small blocks, which all sit in a chunk that
+  // starts with its header and lives as long as they do
+  unsafe { &*chunk.cast::<Header>() }
+}
+
+fn open(road: u64) -> Option<*mut u8> {
+  let mut chunk = SPARE.replace(null_mut());
+  if chunk.is_null() {
+    // SAFETY: the layout isn't zero sized
+    chunk = unsafe { System.alloc(CHUNK_LAYOUT) };
+  }
+  if chunk.is_null() {
+    return None;
+  }
+
+  INSTALLED.store(true, Ordering::Relaxed);
+  let header = Header {
+    live: AtomicUsize::new(OPEN),
+    road,
+  };
+  // SAFETY: the chunk is ours, and aligned for a header
+  unsafe { chunk.cast::<Header>().write(header) };
+  Some(chunk)
+}
+
+// no more blocks come from `chunk`, which handed out `blocks`
+fn retire(chunk: *mut u8, blocks: usize) {
+  if !chunk.is_null()
+    && header(chunk)
+      .live
+      .fetch_sub(OPEN - blocks, Ordering::AcqRel)
+      == OPEN - blocks
+  {
+    free(chunk);
+  }
+}
+
+fn free(chunk: *mut u8) {
+  FREED.set(FREED.get() + 1);
+  let spare = SPARE.replace(chunk);
+  if !spare.is_null() {
+    // SAFETY: chunks come from the system with this layout
+    unsafe { System.dealloc(spare, CHUNK_LAYOUT) };
+  }
+}
+
+// SAFETY: small blocks are carved out of chunks no other block overlaps, and
+// everything else goes to the system allocator
+unsafe impl GlobalAlloc for Road {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    if !small(&layout) {
+      // SAFETY: as the caller promised
+      return unsafe { System.alloc(layout) };
+    }
+
+    let size = layout.size().max(1).next_multiple_of(ALIGN);
+    let mut bump = BUMP.get();
+    if bump.next + size > CHUNK {
+      let Some(chunk) = open(bump.road) else {
+        return null_mut();
+      };
+      retire(bump.chunk, bump.blocks);
+      bump = Bump {
+        chunk,
+        next: START,
+        blocks: 0,
+        road: bump.road,
+      };
+    }
+
+    // SAFETY: in bounds, there was room for the block
+    let ptr = unsafe { bump.chunk.add(bump.next) };
+    bump.next += size;
+    bump.blocks += 1;
+    BUMP.set(bump);
+    ptr
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    if !small(&layout) {
+      // SAFETY: as the caller promised
+      return unsafe { System.dealloc(ptr, layout) };
+    }
+
+    let chunk = ptr.map_addr(|addr| addr & !(CHUNK - 1));
+    if header(ptr).live.fetch_sub(1, Ordering::AcqRel) == 1 {
+      free(chunk);
+    }
+  }
+}
+
+// an evaluation's road, left when this is dropped
+pub(crate) struct Guard {
+  outer: Bump,
+  road: u64,
+}
+
+pub(crate) fn enter() -> Option<Guard> {
+  if !INSTALLED.load(Ordering::Relaxed) {
+    return None;
+  }
+
+  let road = ROADS.get() + 1;
+  ROADS.set(road);
+  let outer = BUMP.replace(Bump { road, ..EMPTY });
+  Some(Guard { outer, road })
+}
+
+impl Guard {
+  // `noun`, with whatever of it is on this road copied onto the outer one
+  pub(crate) fn leave(self, noun: Noun) -> Noun {
+    let road = self.road;
+    drop(self);
+    copy_out(&noun, road)
+  }
+}
+
+impl Drop for Guard {
+  fn drop(&mut self) {
+    let inner = BUMP.replace(self.outer);
+    retire(inner.chunk, inner.blocks);
+  }
+}
+
+// the road of the cell or indirect atom at the top of `noun`, None for
+// anything that isn't in a chunk
+fn road_of(noun: &Noun) -> Option<u64> {
+  let ptr = match noun.inner() {
+    NounInner::Cell(cell) => std::ptr::from_ref(cell).cast::<u8>(),
+    NounInner::Atom(atom) => match atom.block() {
+      Some((words, size)) if size <= SMALL => words,
+      _ => return None,
+    },
+  };
+
+  Some(header(ptr).road)
+}
+
+// without recursing, keeping whatever is shared shared
+fn copy_out(noun: &Noun, road: u64) -> Noun {
+  enum Task<'a> {
+    Visit(&'a Noun),
+    Build(*const noun::Cell),
+  }
+
+  let mut copies: HashMap<*const noun::Cell, Noun> = HashMap::new();
+  let mut tasks = vec![Task::Visit(noun)];
+  let mut done = vec![];
+
+  while let Some(task) = tasks.pop() {
+    match task {
+      Task::Visit(noun) if road_of(noun) != Some(road) => done.push(noun.clone()),
+      Task::Visit(noun) => match noun.inner() {
+        NounInner::Atom(atom) => done.push(Noun::atom(Atom::from_words(atom.words()))),
+        NounInner::Cell(cell) => match copies.get(&std::ptr::from_ref(cell)) {
+          Some(copy) => done.push(copy.clone()),
+          None => {
+            tasks.push(Task::Build(cell));
+            tasks.push(Task::Visit(&cell.tail));
+            tasks.push(Task::Visit(&cell.head));
+          }
+        },
+      },
+      Task::Build(cell) => {
+        let cdr = done.pop().unwrap();
+        let car = done.pop().unwrap();
+        let copy = Noun::cell(car, cdr);
+        copies.insert(cell, copy.clone());
+        done.push(copy);
+      }
+    }
+  }
+
+  done.pop().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+  use super::{FREED, Road, road_of};
+  use crate::{
+    atom::Atom,
+    bench::DECREMENT,
+    interp::{Formula, Interpreter, Subject},
+    noun::Noun,
+  };
+
+  // every other test runs on it too
+  #[global_allocator]
+  static ALLOC: Road = Road;
+
+  #[test]
+  fn test_road() {
+    let mut it = Interpreter::builder().road(true).build();
+    let outer = road_of(&noun!({1, 2}));
+
+    // the product comes off the evaluation's road, but what it shares with
+    // the subject isn't copied, and what it shares within itself stays shared
+    let subject = noun!({1, 2});
+    let big = Noun::atom(Atom::from_words(&[1, 2, 3]));
+    let bigger = Noun::atom(Atom::from_words(&[2, 2, 3]));
+    let pin = noun!({{incr, {idty, (big)}}, {incr, {idty, 41}}});
+    let formula = noun!({extn, {(pin), {{addr, 2}, {{addr, 2}, {addr, 3}}}}});
+    let prod = it.eval(Subject(subject.clone()), Formula(formula)).unwrap();
+    assert_eq!(
+      prod,
+      noun!({{(bigger.clone()), 42}, {{(bigger), 42}, {1, 2}}})
+    );
+
+    let (head, tail) = prod.as_cell().unwrap();
+    let (again, shared) = tail.as_cell().unwrap();
+    assert_eq!(road_of(&prod), outer);
+    assert_eq!(road_of(head.as_cell().unwrap().0), outer);
+    assert!(head.ptr_eq(again));
+    assert!(shared.ptr_eq(&subject));
+
+    // the intermediates of a long loop are gone with their chunks
+    let decrement: Noun = DECREMENT.parse().unwrap();
+    let freed = FREED.get();
+    let prod = it
+      .eval(Subject(Noun::from(100_000u64)), Formula(decrement))
+      .unwrap();
+    assert_eq!(prod, Noun::from(99_999u64));
+    assert!(FREED.get() - freed > 10);
+  }
+}
@@ -0,0 +1,253 @@
+// Nouns in serde formats: an atom is an integer when it fits in a word, and
+// otherwise its little-endian bytes, or its decimal digits in formats meant
+// for people, where bytes would look like a list of small atoms; a cell is a
+// sequence of its head and tail. A longer sequence reads as a tuple, right
+// nested, so `[1, 2, 3]` is `{1 2 3}`.
+//
+// Serde recurses, so a noun is only read as deep as the format lets it, and
+// only written `DEPTH` cells deep, past which it's an error rather than a
+// stack overflow; a long list is as deep as it is long.
+
+use std::fmt;
+
+use serde::{
+  Deserialize, Deserializer, Serialize, Serializer,
+  de::{self, SeqAccess, Visitor},
+  ser::{self, SerializeTuple},
+};
+
+use crate::{atom::Atom, noun::Noun};
+
+impl Serialize for Atom {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match self.as_u64() {
+      Some(atom) => serializer.serialize_u64(atom),
+      None if serializer.is_human_readable() => serializer.collect_str(self),
+      None => serializer.serialize_bytes(&self.as_le_bytes()),
+    }
+  }
+}
+
+// how deep a noun is written before giving up
+const DEPTH: usize = 1 << 10;
+
+impl Serialize for Noun {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Nested(self, 0).serialize(serializer)
+  }
+}
+
+// a noun `depth` cells into the one being written
+struct Nested<'a>(&'a Noun, usize);
+
+impl Serialize for Nested<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let Nested(noun, depth) = *self;
+    let Some((head, tail)) = noun.as_cell() else {
+      return noun.as_atom().unwrap().serialize(serializer);
+    };
+    if depth == DEPTH {
+      return Err(ser::Error::custom(format!(
+        "noun nested more than {DEPTH} cells deep"
+      )));
+    }
+
+    let mut cell = serializer.serialize_tuple(2)?;
+    cell.serialize_element(&Nested(head, depth + 1))?;
+    cell.serialize_element(&Nested(tail, depth + 1))?;
+    cell.end()
+  }
+}
+
+struct AtomVisitor;
+
+impl Visitor<'_> for AtomVisitor {
+  type Value = Atom;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "a natural number, in digits or little-endian bytes")
+  }
+
+  fn visit_u64<E: de::Error>(self, atom: u64) -> Result<Atom, E> {
+    Ok(Atom::new(atom))
+  }
+
+  fn visit_u128<E: de::Error>(self, atom: u128) -> Result<Atom, E> {
+    Ok(Atom::from(atom))
+  }
+
+  fn visit_i64<E: de::Error>(self, atom: i64) -> Result<Atom, E> {
+    u64::try_from(atom)
+      .map(Atom::new)
+      .map_err(|_| E::invalid_value(de::Unexpected::Signed(atom), &self))
+  }
+
+  fn visit_str<E: de::Error>(self, digits: &str) -> Result<Atom, E> {
+    Atom::from_digits(digits, 10)
+      .ok_or_else(|| E::invalid_value(de::Unexpected::Str(digits), &self))
+  }
+
+  fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Atom, E> {
+    Ok(Atom::from_le_bytes(bytes))
+  }
+}
+
+impl<'de> Deserialize<'de> for Atom {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(AtomVisitor)
+  }
+}
+
+struct NounVisitor;
+
+impl<'de> Visitor<'de> for NounVisitor {
+  type Value = Noun;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "an atom, or a sequence of at least two nouns")
+  }
+
+  fn visit_u64<E: de::Error>(self, atom: u64) -> Result<Noun, E> {
+    AtomVisitor.visit_u64(atom).map(Noun::from)
+  }
+
+  fn visit_u128<E: de::Error>(self, atom: u128) -> Result<Noun, E> {
+    AtomVisitor.visit_u128(atom).map(Noun::from)
+  }
+
+  fn visit_i64<E: de::Error>(self, atom: i64) -> Result<Noun, E> {
+    AtomVisitor.visit_i64(atom).map(Noun::from)
+  }
+
+  fn visit_str<E: de::Error>(self, digits: &str) -> Result<Noun, E> {
+    AtomVisitor.visit_str(digits).map(Noun::from)
+  }
+
+  fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Noun, E> {
+    AtomVisitor.visit_bytes(bytes).map(Noun::from)
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Noun, A::Error> {
+    let mut items = vec![];
+    while let Some(item) = seq.next_element::<Noun>()? {
+      items.push(item);
+    }
+    if items.len() < 2 {
+      return Err(de::Error::invalid_length(items.len(), &self));
+    }
+
+    let last = items.pop().unwrap();
+    Ok(
+      items
+        .into_iter()
+        .rev()
+        .fold(last, |tail, head| Noun::cell(head, tail)),
+    )
+  }
+}
+
+impl<'de> Deserialize<'de> for Noun {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(NounVisitor)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use serde_test::{
+    Configure, Token, assert_de_tokens, assert_de_tokens_error, assert_ser_tokens,
+    assert_ser_tokens_error, assert_tokens,
+  };
+
+  use super::DEPTH;
+  use crate::{atom::Atom, noun::Noun};
+
+  // 2^128
+  const DIGITS: &str = "340282366920938463463374607431768211456";
+  const BYTES: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+
+  #[test]
+  fn test_serde() {
+    let big = Atom::from_words(&[0, 0, 1]);
+    let noun = Noun::cell(noun!({1, 2}), Noun::from(big.clone()));
+    let tokens = |big| {
+      [
+        Token::Tuple { len: 2 },
+        Token::Tuple { len: 2 },
+        Token::U64(1),
+        Token::U64(2),
+        Token::TupleEnd,
+        big,
+        Token::TupleEnd,
+      ]
+    };
+
+    // digits where people read them, bytes where they don't
+    assert_tokens(&noun.clone().readable(), &tokens(Token::Str(DIGITS)));
+    assert_tokens(&noun.compact(), &tokens(Token::Bytes(BYTES)));
+    assert_tokens(&big.readable(), &[Token::Str(DIGITS)]);
+    assert_tokens(&Atom::new(7).compact(), &[Token::U64(7)]);
+
+    // longer sequences are tuples, right nested
+    assert_de_tokens(
+      &noun!({1, {{2, 3}, 4}}),
+      &[
+        Token::Seq { len: Some(3) },
+        Token::U64(1),
+        Token::Seq { len: Some(2) },
+        Token::U64(2),
+        Token::U64(3),
+        Token::SeqEnd,
+        Token::U64(4),
+        Token::SeqEnd,
+      ],
+    );
+  }
+
+  #[test]
+  fn test_serde_errors() {
+    let expected = "expected an atom, or a sequence of at least two nouns";
+    assert_de_tokens_error::<Noun>(
+      &[Token::Seq { len: Some(1) }, Token::U64(1), Token::SeqEnd],
+      &format!("invalid length 1, {expected}"),
+    );
+    assert_de_tokens_error::<Noun>(
+      &[Token::Unit],
+      &format!("invalid type: unit value, {expected}"),
+    );
+
+    // atoms are natural numbers
+    let expected = "expected a natural number, in digits or little-endian bytes";
+    assert_de_tokens_error::<Atom>(
+      &[Token::I64(-1)],
+      &format!("invalid value: integer `-1`, {expected}"),
+    );
+    assert_de_tokens_error::<Atom>(
+      &[Token::Str("12a")],
+      &format!("invalid value: string \"12a\", {expected}"),
+    );
+    assert_de_tokens(&Atom::new(7), &[Token::I64(7)]);
+  }
+
+  #[test]
+  fn test_serde_deep() {
+    // `{0 1 ... DEPTH-1 0}`, a cell in every tail
+    let list = Noun::list((0..DEPTH as u64).map(Noun::from));
+    let mut tokens = vec![];
+    for n in 0..DEPTH as u64 {
+      tokens.extend([Token::Tuple { len: 2 }, Token::U64(n)]);
+    }
+    let heads = tokens.len();
+    tokens.push(Token::U64(0));
+    tokens.extend((0..DEPTH).map(|_| Token::TupleEnd));
+    assert_ser_tokens(&list, &tokens);
+
+    // a longer list is too deep, rather than a stack overflow
+    let list = Noun::list((0..100_000u64).map(Noun::from));
+    assert_ser_tokens_error(
+      &list,
+      &tokens[..heads],
+      &format!("noun nested more than {DEPTH} cells deep"),
+    );
+  }
+}
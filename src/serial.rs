@@ -0,0 +1,921 @@
+// jam and cue, the canonical bit-level serialization of nouns. A noun is written
+// head first, each part tagged in its lowest bits:
+//
+//   0   an atom, then its mat
+//   01  a cell, then its head and tail
+//   11  a backreference, then the mat of the bit offset the same noun was
+//       written at before
+//
+// mat is a length-prefixed atom: 0 is a single 1 bit; anything else is as many
+// 0 bits as its length has bits, a 1 bit, its length without the top bit, and
+// then the atom's bits. Bits are packed from the bottom of the jammed atom, so
+// the first thing written is its lowest bit.
+//
+// cue reads a noun back, with every backreference sharing the noun it points
+// to, so what was shared when it was jammed is shared again.
+
+use std::{
+  collections::HashMap,
+  io::{self, Read},
+};
+
+#[cfg(feature = "mmap")]
+use crate::noun::{OnceCell, Rc};
+use crate::{
+  atom::Atom,
+  bits::bits,
+  encoding::{decode_base64, decode_uw, encode_base64, encode_uw},
+  noun::{Noun, NounInner},
+  parse::ParseError,
+};
+
+// bits written from the bottom up
+#[derive(Default)]
+struct Writer {
+  words: Vec<u64>,
+  len: u64,
+}
+
+impl Writer {
+  // the low `n` bits of `word`
+  fn push(&mut self, word: u64, n: u32) {
+    if n == 0 {
+      return;
+    }
+
+    let word = if n < 64 { word & ((1 << n) - 1) } else { word };
+    let used = (self.len % 64) as u32;
+    match self.words.last_mut() {
+      Some(last) if used > 0 => {
+        *last |= word << used;
+        if used + n > 64 {
+          self.words.push(word >> (64 - used));
+        }
+      }
+      _ => self.words.push(word),
+    }
+    self.len += u64::from(n);
+  }
+
+  // the low `n` bits of `atom`
+  fn atom(&mut self, atom: &Atom, mut n: u64) {
+    for &word in atom.words() {
+      if n == 0 {
+        break;
+      }
+      let take = n.min(64);
+      self.push(word, take as u32);
+      n -= take;
+    }
+  }
+
+  fn mat(&mut self, atom: &Atom) {
+    if atom.is_zero() {
+      return self.push(1, 1);
+    }
+
+    let len = bits(atom);
+    let len_len = 64 - len.leading_zeros();
+    self.push(0, len_len);
+    self.push(1, 1);
+    self.push(len, len_len - 1);
+    self.atom(atom, len);
+  }
+
+  fn finish(self) -> Atom {
+    Atom::from_words(&self.words)
+  }
+}
+
+// the jammed noun, with every repeated subtree after the first written as a
+// backreference, unless writing an atom out again is no longer
+pub fn jam(noun: &Noun) -> Atom {
+  let mut out = Writer::default();
+  // where each noun was first written
+  let mut seen: HashMap<Noun, u64> = HashMap::new();
+  let mut stack = vec![noun];
+
+  while let Some(noun) = stack.pop() {
+    if let Some(&offset) = seen.get(noun) {
+      let offset = Atom::new(offset);
+      match noun.inner() {
+        NounInner::Atom(atom) if bits(atom) <= bits(&offset) => {
+          out.push(0, 1);
+          out.mat(atom);
+        }
+        _ => {
+          out.push(0b11, 2);
+          out.mat(&offset);
+        }
+      }
+      continue;
+    }
+
+    seen.insert(noun.clone(), out.len);
+    match noun.inner() {
+      NounInner::Atom(atom) => {
+        out.push(0, 1);
+        out.mat(atom);
+      }
+      NounInner::Cell(cell) => {
+        out.push(0b01, 2);
+        stack.push(&cell.tail);
+        stack.push(&cell.head);
+      }
+    }
+  }
+
+  out.finish()
+}
+
+// the jammed noun as little-endian bytes, the way it's written to a file
+pub fn jam_bytes(noun: &Noun) -> Vec<u8> {
+  jam(noun).as_le_bytes()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CueError {
+  // the input ran out in the middle of the noun that starts at bit `offset`
+  Truncated { offset: u64 },
+  // the length at bit `offset` is too long to be the length of anything
+  BadLength { offset: u64 },
+  // the backreference at bit `offset` points to `target`, where no noun
+  // before it starts
+  BadBackref { offset: u64, target: Atom },
+}
+
+impl std::fmt::Display for CueError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Truncated { offset } => write!(f, "input ends inside the noun at bit {offset}"),
+      Self::BadLength { offset } => write!(f, "length too long at bit {offset}"),
+      Self::BadBackref { offset, target } => {
+        write!(
+          f,
+          "backreference at bit {offset} to {target}, where no noun starts"
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for CueError {}
+
+// little-endian input, a word at a time
+trait Words {
+  // the `i`th word, 0 past the end
+  fn word(&self, i: usize) -> u64;
+}
+
+impl Words for [u64] {
+  fn word(&self, i: usize) -> u64 {
+    self.get(i).copied().unwrap_or(0)
+  }
+}
+
+impl Words for [u8] {
+  fn word(&self, i: usize) -> u64 {
+    let bytes = self.get(i * 8..).unwrap_or_default();
+    if let Some(word) = bytes.first_chunk::<8>() {
+      return u64::from_le_bytes(*word);
+    }
+
+    let mut word = [0; 8];
+    word[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(word)
+  }
+}
+
+// bits read from the bottom up
+struct Reader<'a, W: Words + ?Sized> {
+  words: &'a W,
+  len: u64,
+  pos: u64,
+  // how far into the whole input `words` starts, for offsets
+  base: u64,
+  // where the noun being read starts
+  start: u64,
+}
+
+impl<W: Words + ?Sized> Reader<'_, W> {
+  // where the next bit is in the whole input
+  fn offset(&self) -> u64 {
+    self.base + self.pos
+  }
+
+  fn truncated(&self) -> CueError {
+    CueError::Truncated {
+      offset: self.base + self.start,
+    }
+  }
+
+  fn bit(&mut self) -> Result<bool, CueError> {
+    Ok(self.bits(1)? == 1)
+  }
+
+  // the next `n` bits, at most a word
+  fn bits(&mut self, n: u32) -> Result<u64, CueError> {
+    if n == 0 {
+      return Ok(0);
+    }
+    if self.len - self.pos < u64::from(n) {
+      return Err(self.truncated());
+    }
+
+    let (word, used) = ((self.pos / 64) as usize, (self.pos % 64) as u32);
+    let mut bits = self.words.word(word) >> used;
+    if used + n > 64 {
+      bits |= self.words.word(word + 1) << (64 - used);
+    }
+    self.pos += u64::from(n);
+    Ok(if n < 64 { bits & ((1 << n) - 1) } else { bits })
+  }
+
+  // the next `n` bits, as an atom
+  fn atom(&mut self, n: u64) -> Result<Atom, CueError> {
+    if self.len - self.pos < n {
+      return Err(self.truncated());
+    }
+
+    let mut words = Vec::with_capacity(n.div_ceil(64) as usize);
+    let mut left = n;
+    while left > 0 {
+      let take = left.min(64);
+      words.push(self.bits(take as u32)?);
+      left -= take;
+    }
+    Ok(Atom::from_words(&words))
+  }
+
+  // an atom written by mat
+  fn rub(&mut self) -> Result<Atom, CueError> {
+    let len = self.mat()?;
+    self.atom(len)
+  }
+
+  // the length of an atom written by mat, leaving its bits to be read
+  fn mat(&mut self) -> Result<u64, CueError> {
+    let offset = self.offset();
+    let mut len_len = 0;
+    while !self.bit()? {
+      len_len += 1;
+      if len_len > 64 {
+        return Err(CueError::BadLength { offset });
+      }
+    }
+    if len_len == 0 {
+      return Ok(0);
+    }
+
+    Ok(self.bits(len_len - 1)? | 1 << (len_len - 1))
+  }
+}
+
+enum Frame {
+  Head { offset: u64 },
+  Tail { offset: u64, head: Noun },
+}
+
+// a cue in progress, which can stop wherever its input runs out and go on
+// once there's more
+#[derive(Default)]
+struct Cue {
+  // every noun read so far, by where it starts
+  read: HashMap<u64, Noun>,
+  // the cells whose head or tail is being read
+  stack: Vec<Frame>,
+  // the file the input is a map of, for big atoms to be left in
+  #[cfg(feature = "mmap")]
+  map: Option<Rc<memmap2::Mmap>>,
+}
+
+impl Cue {
+  // reads on until the noun is done. If the input runs out first, it's left
+  // at the start of the atom, backreference or cell tag it ran out in, to be
+  // read again from there.
+  fn read<W: Words + ?Sized>(&mut self, input: &mut Reader<W>) -> Result<Noun, CueError> {
+    loop {
+      input.start = input.pos;
+      let offset = input.offset();
+      let mut noun = match self.leaf(input, offset) {
+        Ok(Some(noun)) => noun,
+        Ok(None) => continue,
+        Err(e) => {
+          input.pos = input.start;
+          return Err(e);
+        }
+      };
+
+      loop {
+        match self.stack.pop() {
+          None => return Ok(noun),
+          Some(Frame::Head { offset }) => {
+            self.stack.push(Frame::Tail { offset, head: noun });
+            break;
+          }
+          Some(Frame::Tail { offset, head }) => {
+            noun = Noun::cell(head, noun);
+            self.read.insert(offset, noun.clone());
+          }
+        }
+      }
+    }
+  }
+
+  // the atom or backreference at `offset`, or None for the start of a cell
+  fn leaf<W: Words + ?Sized>(
+    &mut self,
+    input: &mut Reader<W>,
+    offset: u64,
+  ) -> Result<Option<Noun>, CueError> {
+    if !input.bit()? {
+      let atom = Noun::from(self.atom(input)?);
+      self.read.insert(offset, atom.clone());
+      return Ok(Some(atom));
+    }
+    if !input.bit()? {
+      self.stack.push(Frame::Head { offset });
+      return Ok(None);
+    }
+
+    let target = input.rub()?;
+    let noun = target.as_u64().and_then(|target| self.read.get(&target));
+    Ok(Some(
+      noun.ok_or(CueError::BadBackref { offset, target })?.clone(),
+    ))
+  }
+
+  // an atom written by mat, left where it is if it's in a map and big enough
+  fn atom<W: Words + ?Sized>(&self, input: &mut Reader<W>) -> Result<Atom, CueError> {
+    let len = input.mat()?;
+
+    #[cfg(feature = "mmap")]
+    if let Some(map) = &self.map
+      && len > MAPPED
+      && input.len - input.pos >= len
+    {
+      let mapped = Mapped {
+        map: map.clone(),
+        at: input.offset(),
+        len,
+        words: OnceCell::new(),
+      };
+      // jam writes an atom's exact length, so its top bit is set. An atom
+      // whose isn't is copied out, so equal atoms still have the same words.
+      if mapped.word((len - 1) / 64) >> ((len - 1) % 64) == 1 {
+        input.pos += len;
+        return Ok(Atom::mapped(mapped));
+      }
+    }
+
+    input.atom(len)
+  }
+}
+
+// atoms of more bits than this are left in a mapped file by `cue_mapped`,
+// smaller ones aren't worth keeping the map for
+#[cfg(feature = "mmap")]
+const MAPPED: u64 = 1 << 12;
+
+// an atom whose bits are still in a mapped jam file, see `cue_mapped`. They're
+// copied out into words the first time anything asks for them, and kept; until
+// then the atom is only the pages of the file it's in.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub(crate) struct Mapped {
+  map: Rc<memmap2::Mmap>,
+  // where its bits start in the file, and how many there are
+  at: u64,
+  len: u64,
+  words: OnceCell<Box<[u64]>>,
+}
+
+#[cfg(feature = "mmap")]
+impl Mapped {
+  // the `i`th word, read out of the map
+  fn word(&self, i: u64) -> u64 {
+    let bytes: &[u8] = &self.map;
+    let bit = self.at + i * 64;
+    let (word, used) = ((bit / 64) as usize, (bit % 64) as u32);
+
+    let mut bits = bytes.word(word) >> used;
+    if used > 0 {
+      bits |= bytes.word(word + 1) << (64 - used);
+    }
+    let left = self.len - i * 64;
+    if left < 64 {
+      bits & ((1 << left) - 1)
+    } else {
+      bits
+    }
+  }
+
+  pub(crate) fn words(&self) -> &[u64] {
+    self
+      .words
+      .get_or_init(|| (0..self.len.div_ceil(64)).map(|i| self.word(i)).collect())
+  }
+
+  // `f` over the words, read out of the map if they haven't been copied yet
+  pub(crate) fn fold(&self, init: u64, f: impl Fn(u64, u64) -> u64) -> u64 {
+    match self.words.get() {
+      Some(words) => words.iter().fold(init, |acc, &word| f(acc, word)),
+      None => (0..self.len.div_ceil(64)).fold(init, |acc, i| f(acc, self.word(i))),
+    }
+  }
+
+  #[cfg(test)]
+  pub(crate) fn in_map(&self) -> bool {
+    self.words.get().is_none()
+  }
+}
+
+// the noun `jammed` is the jam of
+pub fn cue(jammed: &Atom) -> Result<Noun, CueError> {
+  let mut input = Reader {
+    words: jammed.words(),
+    len: bits(jammed),
+    pos: 0,
+    base: 0,
+    start: 0,
+  };
+  Cue::default().read(&mut input)
+}
+
+// the bits of little-endian `bytes`, up to the top one set
+fn bytes_len(bytes: &[u8]) -> u64 {
+  match bytes.iter().rposition(|&byte| byte != 0) {
+    Some(last) => last as u64 * 8 + u64::from(8 - bytes[last].leading_zeros()),
+    None => 0,
+  }
+}
+
+// the noun jammed into little-endian `bytes`, read where they are
+pub fn cue_bytes(bytes: &[u8]) -> Result<Noun, CueError> {
+  let mut input = Reader {
+    words: bytes,
+    len: bytes_len(bytes),
+    pos: 0,
+    base: 0,
+    start: 0,
+  };
+  Cue::default().read(&mut input)
+}
+
+// the noun jammed into the file at `path`, read in whole first, see
+// `cue_mapped` for files too big to be in memory twice
+pub fn cue_file(path: impl AsRef<std::path::Path>) -> Result<Noun, CueReadError> {
+  Ok(cue_bytes(&std::fs::read(path)?)?)
+}
+
+/// The noun jammed into `file`, cued straight out of a map of it. Atoms of
+/// more than `MAPPED` bits stay in the map until they're read, see `Mapped`,
+/// so the big atoms of a pill are never in memory as well as in the file's
+/// pages. The file is unmapped once the last of them is dropped.
+///
+/// # Safety
+///
+/// Nothing may write to or truncate `file` while the noun, or any atom out of
+/// it, is alive, see `memmap2::Mmap::map`.
+#[cfg(feature = "mmap")]
+pub unsafe fn cue_mapped(file: &std::fs::File) -> Result<Noun, CueReadError> {
+  // SAFETY: as the caller promised
+  let map = Rc::new(unsafe { memmap2::Mmap::map(file)? });
+  let mut input = Reader {
+    words: &map[..],
+    len: bytes_len(&map),
+    pos: 0,
+    base: 0,
+    start: 0,
+  };
+  let mut cue = Cue {
+    map: Some(map.clone()),
+    ..Cue::default()
+  };
+  Ok(cue.read(&mut input)?)
+}
+
+// cue for input that comes a chunk at a time. Only the bits of the atom or
+// backreference being read are kept, never the input that's been read, so
+// what it holds is about the size of the noun being made.
+#[derive(Default)]
+pub struct CueDecoder {
+  cue: Cue,
+  // the input not read yet, from the bottom of the first word
+  words: Vec<u64>,
+  len: u64,
+  pos: u64,
+  // the bits of input dropped before `words`
+  base: u64,
+  done: bool,
+}
+
+impl CueDecoder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // the next chunk of little-endian jammed bytes, and the noun once it's all
+  // there. Anything after the noun is ignored, as is any chunk fed after it.
+  pub fn feed(&mut self, bytes: &[u8]) -> Result<Option<Noun>, CueError> {
+    if self.done {
+      return Ok(None);
+    }
+
+    let mut bytes = bytes;
+    while let [byte, rest @ ..] = bytes {
+      if self.len.is_multiple_of(64) {
+        if let Some((word, rest)) = bytes.split_first_chunk::<8>() {
+          self.words.push(u64::from_le_bytes(*word));
+          self.len += 64;
+          bytes = rest;
+          continue;
+        }
+        self.words.push(0);
+      }
+      *self.words.last_mut().unwrap() |= u64::from(*byte) << (self.len % 64);
+      self.len += 8;
+      bytes = rest;
+    }
+
+    let mut input = Reader {
+      words: &self.words[..],
+      len: self.len,
+      pos: self.pos,
+      base: self.base,
+      start: self.pos,
+    };
+    let read = self.cue.read(&mut input);
+    self.pos = input.pos;
+
+    // the words that have been read are never needed again
+    let drop = (self.pos / 64) as usize;
+    self.words.drain(..drop);
+    self.pos -= drop as u64 * 64;
+    self.len -= drop as u64 * 64;
+    self.base += drop as u64 * 64;
+
+    match read {
+      Ok(noun) => {
+        self.done = true;
+        self.words = vec![];
+        Ok(Some(noun))
+      }
+      Err(CueError::Truncated { .. }) => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+
+  // the error for input that ends before the noun does, None once it's done
+  pub fn finish(&self) -> Option<CueError> {
+    (!self.done).then(|| CueError::Truncated {
+      offset: self.base + self.pos,
+    })
+  }
+}
+
+#[derive(Debug)]
+pub enum CueReadError {
+  Io(io::Error),
+  Cue(CueError),
+}
+
+impl std::fmt::Display for CueReadError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "{e}"),
+      Self::Cue(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl std::error::Error for CueReadError {}
+
+impl From<io::Error> for CueReadError {
+  fn from(e: io::Error) -> Self {
+    Self::Io(e)
+  }
+}
+
+impl From<CueError> for CueReadError {
+  fn from(e: CueError) -> Self {
+    Self::Cue(e)
+  }
+}
+
+// the noun jammed into what `input` has, read a chunk at a time until the
+// noun is done
+pub fn cue_reader(mut input: impl Read) -> Result<Noun, CueReadError> {
+  let mut decoder = CueDecoder::new();
+  let mut chunk = vec![0; 1 << 16];
+
+  loop {
+    let n = match input.read(&mut chunk) {
+      Ok(n) => n,
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+      Err(e) => return Err(e.into()),
+    };
+    if n == 0 {
+      let e = decoder
+        .finish()
+        .expect("the decoder returns the noun when it's done");
+      return Err(e.into());
+    }
+
+    if let Some(noun) = decoder.feed(&chunk[..n])? {
+      return Ok(noun);
+    }
+  }
+}
+
+// jams as text that survives shells and chat: base64 of the little-endian
+// bytes, or the jam as @uw, the way dojo prints it
+pub fn jam_b64(noun: &Noun) -> String {
+  encode_base64(&jam_bytes(noun))
+}
+
+pub fn jam_uw(noun: &Noun) -> String {
+  encode_uw(&jam(noun))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CueTextError {
+  Text(ParseError),
+  Cue(CueError),
+}
+
+impl std::fmt::Display for CueTextError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Text(e) => write!(f, "{e}"),
+      Self::Cue(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl std::error::Error for CueTextError {}
+
+impl From<ParseError> for CueTextError {
+  fn from(e: ParseError) -> Self {
+    Self::Text(e)
+  }
+}
+
+impl From<CueError> for CueTextError {
+  fn from(e: CueError) -> Self {
+    Self::Cue(e)
+  }
+}
+
+pub fn cue_b64(text: &str) -> Result<Noun, CueTextError> {
+  Ok(cue_bytes(&decode_base64(text)?)?)
+}
+
+pub fn cue_uw(text: &str) -> Result<Noun, CueTextError> {
+  Ok(cue(&decode_uw(text.trim())?)?)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{
+    CueDecoder, CueError, CueReadError, CueTextError, cue, cue_b64, cue_bytes, cue_reader, cue_uw,
+    jam, jam_b64, jam_bytes, jam_uw,
+  };
+  use crate::{atom::Atom, noun::Noun};
+
+  #[test]
+  fn test_jam() {
+    for (noun, jammed) in [
+      (noun!(0), 2),
+      (noun!(1), 12),
+      (noun!(2), 72),
+      (noun!(19), 2480),
+      (noun!({0, 19}), 39689),
+      (noun!({1, 1}), 817),
+    ] {
+      assert_eq!(jam(&noun), Atom::new(jammed), "{noun}");
+    }
+    assert_eq!(jam_bytes(&noun!(1)), [12]);
+
+    // with backreferences
+    for (noun, jammed) in [
+      ("{{{1 2} 3} {1 2} 3}", "2479956757"),
+      ("{{{1 2} 1 2} {1 2} 5}", "25371284546325"),
+      (
+        "{12345678901234567890123 7 12345678901234567890123}",
+        "375149443557945151883262897312769",
+      ),
+    ] {
+      let noun: Noun = noun.parse().unwrap();
+      assert_eq!(jam(&noun).to_string(), jammed, "{noun}");
+    }
+
+    // a repeated cell is written once, equal or shared
+    let pair = noun!({{1, 2}, 3});
+    let shared = Noun::cell(pair.clone(), pair.clone());
+    let equal = Noun::cell(pair.clone(), noun!({{1, 2}, 3}));
+    assert_eq!(jam(&shared), jam(&equal));
+    assert!(jam_bytes(&shared).len() < 2 * jam_bytes(&pair).len());
+
+    // and so is a big atom
+    let big = Noun::from(Atom::from_words(&[u64::MAX; 4]));
+    let twice = Noun::cell(big.clone(), big.clone());
+    assert!(jam_bytes(&twice).len() < jam_bytes(&big).len() + 8);
+
+    let long = Noun::list((0..1000u64).map(Noun::from));
+    assert_eq!(jam(&long), jam(&long.clone()));
+  }
+
+  #[test]
+  fn test_cue() {
+    for noun in [
+      "0",
+      "19",
+      "{0 19}",
+      "{{{1 2} 3} {1 2} 3}",
+      "{12345678901234567890123 7 12345678901234567890123}",
+      "{{{1 2} 1 2} {1 2} 5}",
+    ] {
+      let noun: Noun = noun.parse().unwrap();
+      assert_eq!(cue(&jam(&noun)), Ok(noun.clone()));
+      assert_eq!(cue_bytes(&jam_bytes(&noun)), Ok(noun));
+    }
+
+    let long = Noun::list((0..1000u64).map(|n| Noun::cell(Noun::from(n), noun!({1, 2}))));
+    assert_eq!(cue(&jam(&long)).unwrap(), long);
+
+    // backreferences share what they point to
+    let pair = noun!({{1, 2}, 3});
+    let cued = cue(&jam(&Noun::cell(pair.clone(), pair))).unwrap();
+    let (head, tail) = cued.as_cell().unwrap();
+    assert!(head.ptr_eq(tail));
+  }
+
+  #[test]
+  fn test_cue_errors() {
+    // nothing, or a length with no end
+    assert_eq!(cue_bytes(&[]), Err(CueError::Truncated { offset: 0 }));
+    // a cell with no tail
+    let head = jam(&noun!(19)).words()[0];
+    assert_eq!(
+      cue(&Atom::new(head << 2 | 0b01)),
+      Err(CueError::Truncated { offset: 14 })
+    );
+    // the atom's bits run out
+    assert_eq!(
+      cue(&Atom::new(2480 ^ 1 << 11)),
+      Err(CueError::Truncated { offset: 0 })
+    );
+
+    // 65 zeros before the length
+    let long = Atom::from_words(&[0, 1 << 2]);
+    assert_eq!(cue(&long), Err(CueError::BadLength { offset: 1 }));
+
+    // a backreference to the cell it's in, and one to nowhere
+    assert_eq!(
+      cue(&Atom::new(0b1_11_01)),
+      Err(CueError::BadBackref {
+        offset: 2,
+        target: Atom::new(0)
+      })
+    );
+    assert_eq!(
+      cue(&Atom::new(0b1_10_11_10_01)),
+      Err(CueError::BadBackref {
+        offset: 4,
+        target: Atom::new(1)
+      })
+    );
+  }
+
+  #[test]
+  fn test_cue_decoder() {
+    let big = Noun::from(Atom::from_words(&[7; 100]));
+    let items = (0..2000u64).map(|n| Noun::cell(Noun::from(n), big.clone()));
+    let noun = Noun::cell(Noun::list(items), big.clone());
+    let bytes = jam_bytes(&noun);
+
+    for size in [1, 3, 8, 13, 1000] {
+      let mut decoder = CueDecoder::new();
+      let mut chunks = bytes.chunks(size);
+      let cued = loop {
+        let chunk = chunks.next().unwrap();
+        if let Some(cued) = decoder.feed(chunk).unwrap() {
+          break cued;
+        }
+        // only the big atom is ever held whole
+        assert!(decoder.words.len() <= 100 + size.div_ceil(8) + 1);
+      };
+      assert_eq!(cued, noun, "chunks of {size}");
+      assert_eq!(chunks.next(), None);
+      assert_eq!(decoder.finish(), None);
+      assert_eq!(decoder.feed(&[1, 2, 3]), Ok(None));
+    }
+
+    assert_eq!(cue_reader(&bytes[..]).unwrap(), noun);
+    assert!(matches!(
+      cue_reader(&bytes[..bytes.len() - 1]),
+      Err(CueReadError::Cue(CueError::Truncated { .. }))
+    ));
+
+    // errors are where they'd be in the whole input
+    let mut decoder = CueDecoder::new();
+    assert_eq!(decoder.feed(&[0b01]), Ok(None));
+    assert_eq!(decoder.finish(), Some(CueError::Truncated { offset: 2 }));
+    assert_eq!(
+      decoder.feed(&[0, 0, 0, 0, 0, 0, 0, 0, 0]),
+      Err(CueError::BadLength { offset: 3 })
+    );
+
+    let mut decoder = CueDecoder::new();
+    assert_eq!(decoder.feed(&[0b1011_1001]), Ok(None));
+    assert_eq!(
+      decoder.feed(&[0b1]),
+      Err(CueError::BadBackref {
+        offset: 4,
+        target: Atom::new(1)
+      })
+    );
+  }
+
+  #[test]
+  fn test_cue_file() {
+    let big = Noun::from(Atom::from_words(&[9; 50]));
+    let noun = Noun::list((0..500u64).map(|n| Noun::cell(Noun::from(n), big.clone())));
+
+    let path = std::env::temp_dir().join(format!("nuuk-jam-{}", std::process::id()));
+    std::fs::write(&path, jam_bytes(&noun)).unwrap();
+    assert_eq!(super::cue_file(&path).unwrap(), noun);
+
+    std::fs::write(&path, &jam_bytes(&noun)[..100]).unwrap();
+    assert!(matches!(
+      super::cue_file(&path),
+      Err(CueReadError::Cue(CueError::Truncated { .. }))
+    ));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(super::cue_file(&path), Err(CueReadError::Io(_))));
+  }
+
+  #[cfg(feature = "mmap")]
+  #[test]
+  fn test_cue_mapped() {
+    // at odd bit offsets, one jammed twice and one too small to be mapped
+    let big = Noun::from(Atom::from_words(&[9; 100]));
+    let odd = Noun::from(Atom::from_words(&(1..=200).collect::<Vec<_>>()));
+    let small = Noun::from(Atom::from_words(&[7; 3]));
+    let noun = Noun::list([big.clone(), odd.clone(), small.clone(), big.clone()]);
+
+    let path = std::env::temp_dir().join(format!("nuuk-mapped-{}", std::process::id()));
+    std::fs::write(&path, jam_bytes(&noun)).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    // SAFETY: the file is this test's own, and only removed
+    let cued = unsafe { super::cue_mapped(&file) }.unwrap();
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+
+    let atoms: Vec<&Atom> = std::iter::successors(Some(&cued), |noun| Some(noun.as_cell()?.1))
+      .filter_map(|noun| noun.as_cell()?.0.as_atom())
+      .collect();
+    assert!(atoms[0].is_mapped() && atoms[1].is_mapped() && atoms[3].is_mapped());
+    assert!(!atoms[2].is_mapped());
+
+    // a cell of it is made without copying it out, reading it copies it once
+    let pair = Noun::cell(cued.clone(), Noun::from(atoms[1].clone()));
+    assert!(atoms[1].is_mapped());
+    assert_eq!(pair, Noun::cell(noun.clone(), odd));
+    assert_eq!(cued, noun);
+    assert!(!atoms[0].is_mapped() && !atoms[3].is_mapped());
+    assert_eq!(jam(&cued), jam(&noun));
+
+    std::fs::write(&path, &jam_bytes(&noun)[..1000]).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    // SAFETY: as above
+    let cued = unsafe { super::cue_mapped(&file) };
+    assert!(matches!(
+      cued,
+      Err(CueReadError::Cue(CueError::Truncated { .. }))
+    ));
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_jam_text() {
+    assert_eq!(jam_uw(&noun!(0)), "0w2");
+    assert_eq!(jam_b64(&noun!(0)), "Ag==");
+    assert_eq!(jam_uw(&noun!({0, 0})), "0wF");
+    assert_eq!(jam_b64(&noun!({0, 0})), "KQ==");
+
+    let big = Noun::from(Atom::from_words(&[u64::MAX, 7]));
+    let noun = Noun::list((0..300u64).map(|n| Noun::cell(Noun::from(n), big.clone())));
+    assert_eq!(cue_b64(&jam_b64(&noun)).unwrap(), noun);
+    assert_eq!(cue_uw(&jam_uw(&noun)).unwrap(), noun);
+    assert_eq!(cue_uw(&format!(" {}\n", jam_uw(&noun))).unwrap(), noun);
+
+    assert!(matches!(cue_b64("KQ!="), Err(CueTextError::Text(e)) if e.offset == 2));
+    assert!(matches!(cue_uw("0wF.G"), Err(CueTextError::Text(..))));
+    assert_eq!(
+      cue_b64(""),
+      Err(CueTextError::Cue(CueError::Truncated { offset: 0 }))
+    );
+  }
+}
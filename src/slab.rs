@@ -0,0 +1,209 @@
+// Small allocations from slabs, with a free list per size, for programs that
+// make `Slab` their global allocator:
+//
+//   #[global_allocator]
+//   static ALLOC: nuuk::slab::Slab = nuuk::slab::Slab;
+//
+// Cells, and the words of small indirect atoms, are a handful of sizes that
+// are made and dropped all the time, so a freed block is kept for the next
+// allocation of its size instead of going back to the system allocator. Each
+// thread has its own free lists and takes nothing to allocate or free; a block
+// freed on another thread joins that thread's list. Slabs are never given back
+// to the system, and a thread's free blocks are lost with it.
+//
+// `stats` counts what the calling thread's allocations did, so the benefit
+// shows up in a benchmark.
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  cell::Cell,
+  ptr::null_mut,
+};
+
+// blocks of 16, 32, ... 128 bytes
+const GRAIN: usize = 16;
+const CLASSES: usize = 8;
+const SLAB: usize = 1 << 16;
+const SLAB_LAYOUT: Layout = match Layout::from_size_align(SLAB, GRAIN) {
+  Ok(layout) => layout,
+  Err(_) => panic!("bad slab layout"),
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlabStats {
+  // small blocks handed out, and how many of those were freed ones
+  pub allocs: u64,
+  pub reused: u64,
+  pub frees: u64,
+  // slabs taken from the system allocator
+  pub slabs: u64,
+}
+
+pub struct Slab;
+
+// nothing here needs dropping, so it's there until the thread is gone
+thread_local! {
+  // a free block starts with the address of the next one
+  static FREE: [Cell<*mut u8>; CLASSES] = const { [const { Cell::new(null_mut()) }; CLASSES] };
+  // the part of the last slab of each size no block has come from yet
+  static FRESH: [Cell<(*mut u8, usize)>; CLASSES] =
+    const { [const { Cell::new((null_mut(), 0)) }; CLASSES] };
+  static STATS: Cell<SlabStats> = const {
+    Cell::new(SlabStats {
+      allocs: 0,
+      reused: 0,
+      frees: 0,
+      slabs: 0,
+    })
+  };
+}
+
+// what the calling thread's allocations did so far
+pub fn stats() -> SlabStats {
+  STATS.get()
+}
+
+fn count(f: impl FnOnce(&mut SlabStats)) {
+  let mut stats = STATS.get();
+  f(&mut stats);
+  STATS.set(stats);
+}
+
+// the class of blocks `layout` comes from, None for the system allocator
+fn class(layout: &Layout) -> Option<usize> {
+  (layout.size() <= GRAIN * CLASSES && layout.align() <= GRAIN)
+    .then(|| layout.size().max(1).div_ceil(GRAIN) - 1)
+}
+
+fn fresh(class: usize) -> *mut u8 {
+  let size = (class + 1) * GRAIN;
+  let (mut next, mut left) = FRESH.with(|fresh| fresh[class].get());
+  if left < size {
+    // SAFETY: the layout isn't zero sized
+    next = unsafe { System.alloc(SLAB_LAYOUT) };
+    if next.is_null() {
+      return next;
+    }
+    left = SLAB;
+    count(|stats| stats.slabs += 1);
+  }
+
+  // SAFETY: in bounds, there was room for the block
+  let rest = unsafe { next.add(size) };
+  FRESH.with(|fresh| fresh[class].set((rest, left - size)));
+  next
+}
+
+// SAFETY: every block is in a slab of its own class, handed out once until
+// it's freed, and everything else goes to the system allocator
+unsafe impl GlobalAlloc for Slab {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let Some(class) = class(&layout) else {
+      // SAFETY: as the caller promised
+      return unsafe { System.alloc(layout) };
+    };
+
+    let block = FREE.with(|free| {
+      let block = free[class].get();
+      if !block.is_null() {
+        // SAFETY: a free block holds the next one's address
+        free[class].set(unsafe { block.cast::<*mut u8>().read() });
+      }
+      block
+    });
+    if block.is_null() {
+      let block = fresh(class);
+      count(|stats| stats.allocs += 1);
+      return block;
+    }
+
+    count(|stats| {
+      stats.allocs += 1;
+      stats.reused += 1;
+    });
+    block
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    let Some(class) = class(&layout) else {
+      // SAFETY: as the caller promised
+      return unsafe { System.dealloc(ptr, layout) };
+    };
+
+    FREE.with(|free| {
+      // SAFETY: the block is at least a pointer big and aligned for one, and
+      // nobody else has it any more
+      unsafe { ptr.cast::<*mut u8>().write(free[class].get()) };
+      free[class].set(ptr);
+    });
+    count(|stats| stats.frees += 1);
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    // SAFETY: as the caller promised
+    let new = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+    if class(&layout).is_none() && class(&new).is_none() {
+      // SAFETY: as the caller promised
+      return unsafe { System.realloc(ptr, layout, new_size) };
+    }
+
+    // SAFETY: as the caller promised
+    let new_ptr = unsafe { self.alloc(new) };
+    if !new_ptr.is_null() {
+      // SAFETY: both blocks are at least this big, and they're different
+      // blocks
+      unsafe {
+        std::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+        self.dealloc(ptr, layout);
+      }
+    }
+    new_ptr
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::alloc::{GlobalAlloc, Layout};
+
+  use super::{Slab, stats};
+
+  #[test]
+  fn test_slab() {
+    // not the global allocator here, the road allocator can be
+    let cell = Layout::from_size_align(72, 8).unwrap();
+    let before = stats();
+
+    // SAFETY: blocks are freed once, with the layout they were made with
+    unsafe {
+      let a = Slab.alloc(cell);
+      let b = Slab.alloc(cell);
+      assert_ne!(a, b);
+      a.write_bytes(1, 72);
+      Slab.dealloc(a, cell);
+
+      // freed blocks are reused, by their own size only
+      let small = Layout::from_size_align(8, 8).unwrap();
+      let c = Slab.alloc(small);
+      assert_ne!(a, c);
+      assert_eq!(Slab.alloc(cell), a);
+
+      // anything big goes to the system
+      let big = Layout::from_size_align(4096, 8).unwrap();
+      let d = Slab.alloc(big);
+      let d = Slab.realloc(d, big, 8192);
+      Slab.dealloc(d, Layout::from_size_align(8192, 8).unwrap());
+
+      // and what grows out of a block is moved
+      let e = Slab.realloc(c, small, 100);
+      assert_ne!(e, c);
+      Slab.dealloc(e, Layout::from_size_align(100, 8).unwrap());
+      Slab.dealloc(a, cell);
+      Slab.dealloc(b, cell);
+    }
+
+    let after = stats();
+    assert_eq!(after.allocs - before.allocs, 5);
+    assert_eq!(after.reused - before.reused, 1);
+    assert_eq!(after.frees - before.frees, 5);
+  }
+}
@@ -0,0 +1,294 @@
+// Nouns saved to files that later versions of the crate can still load, and
+// that say so when they've been damaged. The payload is the noun jammed,
+// which doesn't change with how nouns are kept in memory, and the rest is
+// little-endian:
+//
+//   magic        8 bytes, `nuuksnap`
+//   version      2 bytes, which layout follows
+//   compression  1 byte, 0 for none and 1 for deflate
+//   length       8 bytes, of the payload as stored
+//   payload      the jam, deflated or not
+//   checksum     4 bytes, murmur3 of everything before it
+//
+// Deflate needs the `compress` feature, to write and to read.
+
+use std::{
+  ffi::OsString,
+  fs::{self, File},
+  io::{self, Write},
+  path::Path,
+};
+
+use crate::{
+  murmur::murmur3_32,
+  noun::Noun,
+  serial::{CueError, cue_bytes, jam_bytes},
+};
+
+const MAGIC: [u8; 8] = *b"nuuksnap";
+const VERSION: u16 = 1;
+const HEADER: usize = 19;
+const CHECKSUM: usize = 4;
+const SEED: u32 = 0x6e75_756b;
+
+// deflate when there is one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+  #[cfg_attr(not(feature = "compress"), default)]
+  None,
+  #[cfg(feature = "compress")]
+  #[default]
+  Deflate,
+}
+
+impl Compression {
+  fn byte(self) -> u8 {
+    match self {
+      Self::None => 0,
+      #[cfg(feature = "compress")]
+      Self::Deflate => 1,
+    }
+  }
+
+  fn from_byte(byte: u8) -> Option<Self> {
+    match byte {
+      0 => Some(Self::None),
+      #[cfg(feature = "compress")]
+      1 => Some(Self::Deflate),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+  Io(io::Error),
+  // no magic, or not even a header
+  NotSnapshot,
+  // a layout newer than this crate knows
+  Version(u16),
+  // compression this crate doesn't know, or wasn't built with
+  Compression(u8),
+  // the file's size isn't what its header says it should be
+  Length { expected: u64, found: u64 },
+  Checksum { stored: u32, computed: u32 },
+  // a payload that checks out but doesn't inflate
+  Inflate,
+  Cue(CueError),
+}
+
+impl std::fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "{e}"),
+      Self::NotSnapshot => write!(f, "not a snapshot"),
+      Self::Version(version) => write!(f, "snapshot version {version} is newer than {VERSION}"),
+      Self::Compression(byte) => write!(f, "unsupported compression {byte}"),
+      Self::Length { expected, found } => {
+        write!(f, "snapshot is {found} bytes, its header says {expected}")
+      }
+      Self::Checksum { stored, computed } => {
+        write!(
+          f,
+          "checksum {computed:#010x} doesn't match {stored:#010x}, the snapshot is damaged"
+        )
+      }
+      Self::Inflate => write!(f, "payload doesn't inflate"),
+      Self::Cue(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+  fn from(e: io::Error) -> Self {
+    Self::Io(e)
+  }
+}
+
+impl From<CueError> for SnapshotError {
+  fn from(e: CueError) -> Self {
+    Self::Cue(e)
+  }
+}
+
+pub fn to_bytes(noun: &Noun, compression: Compression) -> Vec<u8> {
+  let jam = jam_bytes(noun);
+  let payload = match compression {
+    Compression::None => jam,
+    #[cfg(feature = "compress")]
+    Compression::Deflate => miniz_oxide::deflate::compress_to_vec(&jam, 6),
+  };
+
+  let mut bytes = Vec::with_capacity(HEADER + payload.len() + CHECKSUM);
+  bytes.extend(MAGIC);
+  bytes.extend(VERSION.to_le_bytes());
+  bytes.push(compression.byte());
+  bytes.extend((payload.len() as u64).to_le_bytes());
+  bytes.extend(payload);
+  bytes.extend(murmur3_32(&bytes, SEED).to_le_bytes());
+  bytes
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<Noun, SnapshotError> {
+  if bytes.len() < HEADER || bytes[..8] != MAGIC {
+    return Err(SnapshotError::NotSnapshot);
+  }
+  let version = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+  if version > VERSION {
+    return Err(SnapshotError::Version(version));
+  }
+
+  let len = u64::from_le_bytes(bytes[11..HEADER].try_into().unwrap());
+  let expected = len.saturating_add((HEADER + CHECKSUM) as u64);
+  if expected != bytes.len() as u64 {
+    return Err(SnapshotError::Length {
+      expected,
+      found: bytes.len() as u64,
+    });
+  }
+
+  // damage to the compression byte or the payload
+  let (checked, stored) = bytes.split_at(bytes.len() - CHECKSUM);
+  let stored = u32::from_le_bytes(stored.try_into().unwrap());
+  let computed = murmur3_32(checked, SEED);
+  if stored != computed {
+    return Err(SnapshotError::Checksum { stored, computed });
+  }
+
+  let payload = &checked[HEADER..];
+  match Compression::from_byte(bytes[10]).ok_or(SnapshotError::Compression(bytes[10]))? {
+    Compression::None => Ok(cue_bytes(payload)?),
+    #[cfg(feature = "compress")]
+    Compression::Deflate => {
+      let jam =
+        miniz_oxide::inflate::decompress_to_vec(payload).map_err(|_| SnapshotError::Inflate)?;
+      Ok(cue_bytes(&jam)?)
+    }
+  }
+}
+
+// the noun saved to `path`, compressed if the crate can. It's written next to
+// `path` first and moved over it once it's all on disk, so a crash partway
+// leaves the old snapshot as it was.
+pub fn save_noun(path: impl AsRef<Path>, noun: &Noun) -> io::Result<()> {
+  save_noun_with(path, noun, Compression::default())
+}
+
+pub fn save_noun_with(
+  path: impl AsRef<Path>,
+  noun: &Noun,
+  compression: Compression,
+) -> io::Result<()> {
+  let path = path.as_ref();
+  let mut temp = OsString::from(path);
+  temp.push(".tmp");
+
+  let mut file = File::create(&temp)?;
+  file.write_all(&to_bytes(noun, compression))?;
+  file.sync_all()?;
+  fs::rename(&temp, path)
+}
+
+pub fn load_noun(path: impl AsRef<Path>) -> Result<Noun, SnapshotError> {
+  from_bytes(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{
+    Compression, HEADER, SnapshotError, from_bytes, load_noun, save_noun, save_noun_with, to_bytes,
+  };
+  use crate::{atom::Atom, noun::Noun, serial::CueError};
+
+  fn noun() -> Noun {
+    let big = Noun::from(Atom::from_words(&[1, 2, 3]));
+    Noun::list((0..1000u64).map(|n| Noun::cell(Noun::from(n % 7), big.clone())))
+  }
+
+  #[test]
+  fn test_snapshot() {
+    let noun = noun();
+    let bytes = to_bytes(&noun, Compression::None);
+    assert_eq!(&bytes[..11], b"nuuksnap\x01\x00\x00");
+    let len = u64::from_le_bytes(bytes[11..HEADER].try_into().unwrap());
+    assert_eq!(len as usize, bytes.len() - HEADER - 4);
+    assert_eq!(from_bytes(&bytes).unwrap(), noun);
+
+    let path = std::env::temp_dir().join(format!("nuuk-snapshot-{}", std::process::id()));
+    save_noun_with(&path, &noun, Compression::None).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    save_noun(&path, &noun!({1, 2})).unwrap();
+    assert_eq!(load_noun(&path).unwrap(), noun!({1, 2}));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(load_noun(&path), Err(SnapshotError::Io(..))));
+  }
+
+  #[cfg(feature = "compress")]
+  #[test]
+  fn test_snapshot_deflate() {
+    let noun = noun();
+    let bytes = to_bytes(&noun, Compression::Deflate);
+    assert_eq!(bytes[10], 1);
+    assert!(bytes.len() < to_bytes(&noun, Compression::None).len() / 4);
+    assert_eq!(from_bytes(&bytes).unwrap(), noun);
+    assert_eq!(Compression::default(), Compression::Deflate);
+  }
+
+  #[test]
+  fn test_snapshot_errors() {
+    let bytes = to_bytes(&noun!({1, 2}), Compression::None);
+    let patched = |at: usize, byte: u8| {
+      let mut bytes = bytes.clone();
+      bytes[at] = byte;
+      bytes
+    };
+
+    assert!(matches!(
+      from_bytes(&bytes[..10]),
+      Err(SnapshotError::NotSnapshot)
+    ));
+    assert!(matches!(
+      from_bytes(&patched(0, b'N')),
+      Err(SnapshotError::NotSnapshot)
+    ));
+    assert!(matches!(
+      from_bytes(&patched(8, 2)),
+      Err(SnapshotError::Version(2))
+    ));
+    let len = bytes.len() as u64;
+    assert!(matches!(
+      from_bytes(&bytes[..bytes.len() - 1]),
+      Err(SnapshotError::Length { expected, found }) if expected == len && found == len - 1
+    ));
+
+    // any other damaged byte is caught by the checksum
+    for at in (10..11).chain(HEADER..bytes.len()) {
+      let damaged = patched(at, bytes[at] ^ 0x10);
+      assert!(
+        matches!(from_bytes(&damaged), Err(SnapshotError::Checksum { .. })),
+        "{at}"
+      );
+    }
+
+    // an unknown compression, or a payload that isn't a jam, with checksums
+    // that match
+    let resum = |mut bytes: Vec<u8>| {
+      let at = bytes.len() - 4;
+      let sum = super::murmur3_32(&bytes[..at], super::SEED);
+      bytes[at..].copy_from_slice(&sum.to_le_bytes());
+      bytes
+    };
+    assert!(matches!(
+      from_bytes(&resum(patched(10, 9))),
+      Err(SnapshotError::Compression(9))
+    ));
+    let mut zeroed = bytes.clone();
+    zeroed[HEADER..bytes.len() - 4].fill(0);
+    assert!(matches!(
+      from_bytes(&resum(zeroed)),
+      Err(SnapshotError::Cue(CueError::Truncated { .. }))
+    ));
+  }
+}
@@ -0,0 +1,448 @@
+// A small-step reducer that rewrites one rule at a time, exactly as the rules
+// at the top of `interp.rs` are written. It's slow on purpose: every
+// intermediate expression can be looked at, which makes it a reference to
+// test the interpreter against and a backend for stepping through formulas.
+//
+// Rules that would loop forever (`*a ~> *a` and friends) are reported as the
+// crash they stand for instead.
+
+use crate::{atom::Atom, bits, error::NockError, noun::Noun};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+  // *
+  Nock,
+  // ?
+  Wut,
+  // +
+  Lus,
+  // =
+  Tis,
+  // /
+  Fas,
+  // #
+  Hax,
+}
+
+impl Op {
+  pub const fn symbol(self) -> char {
+    match self {
+      Op::Nock => '*',
+      Op::Wut => '?',
+      Op::Lus => '+',
+      Op::Tis => '=',
+      Op::Fas => '/',
+      Op::Hax => '#',
+    }
+  }
+}
+
+// an expression of the spec, cells of values are always folded into a noun
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+  Noun(Noun),
+  Cell(Box<Term>, Box<Term>),
+  Apply(Op, Box<Term>),
+}
+
+impl Term {
+  pub fn cell(head: Term, tail: Term) -> Term {
+    match (head, tail) {
+      (Term::Noun(head), Term::Noun(tail)) => Term::Noun(Noun::cell(head, tail)),
+      (head, tail) => Term::Cell(Box::new(head), Box::new(tail)),
+    }
+  }
+
+  pub fn apply(op: Op, arg: Term) -> Term {
+    Term::Apply(op, Box::new(arg))
+  }
+
+  pub fn as_noun(&self) -> Option<&Noun> {
+    match self {
+      Term::Noun(noun) => Some(noun),
+      _ => None,
+    }
+  }
+}
+
+fn noun(noun: impl Into<Noun>) -> Term {
+  Term::Noun(noun.into())
+}
+
+// *{a b}
+fn nock(subject: Term, formula: Term) -> Term {
+  Term::apply(Op::Nock, Term::cell(subject, formula))
+}
+
+// rewrites the leftmost innermost application whose argument is a value
+fn step(term: Term) -> Result<Term, NockError> {
+  match term {
+    Term::Noun(_) => Ok(term),
+    Term::Cell(head, tail) => match *head {
+      Term::Noun(head) => Ok(Term::cell(Term::Noun(head), step(*tail)?)),
+      head => Ok(Term::cell(step(head)?, *tail)),
+    },
+    Term::Apply(op, arg) => match *arg {
+      Term::Noun(arg) => rewrite(op, arg),
+      arg => Ok(Term::apply(op, step(arg)?)),
+    },
+  }
+}
+
+fn rewrite(op: Op, arg: Noun) -> Result<Term, NockError> {
+  match op {
+    Op::Nock => rewrite_nock(arg),
+    // ?{a b} ~> 0, ?a ~> 1
+    Op::Wut => Ok(noun(arg.is_cell())),
+    // +a ~> 1 + a
+    Op::Lus => Ok(noun(arg.try_atom()?.incr())),
+    // ={a a} ~> 0, ={a b} ~> 1
+    Op::Tis => {
+      let (a, b) = arg.try_cell()?;
+      Ok(noun(a == b))
+    }
+    Op::Fas => rewrite_fas(arg),
+    Op::Hax => rewrite_hax(arg),
+  }
+}
+
+fn rewrite_nock(arg: Noun) -> Result<Term, NockError> {
+  let (a, formula) = arg.try_cell()?;
+  let subject = || noun(a.clone());
+  let (op, args) = noun_match!(formula,
+    {{b, c}, d} => {
+      let b = noun(Noun::cell(b.clone(), c.clone()));
+      return Ok(Term::cell(nock(subject(), b), nock(subject(), noun(d.clone()))));
+    },
+    {op, args} => (op.try_atom()?, args),
+    _ => return Err(NockError::Exit { formula: formula.clone() }),
+  );
+
+  let pair = || {
+    args
+      .try_cell()
+      .map(|(b, c)| (noun(b.clone()), noun(c.clone())))
+  };
+
+  let term = match op.as_u64() {
+    // *{a 0 b} ~> /{b a}
+    Some(0) => Term::apply(Op::Fas, noun(Noun::cell(args.clone(), a.clone()))),
+    // *{a 1 b} ~> b
+    Some(1) => noun(args.clone()),
+    // *{a 2 b c} ~> *{*{a b} *{a c}}
+    Some(2) => {
+      let (b, c) = pair()?;
+      nock(nock(subject(), b), nock(subject(), c))
+    }
+    // *{a 3 b} ~> ?*{a b}
+    Some(3) => Term::apply(Op::Wut, nock(subject(), noun(args.clone()))),
+    // *{a 4 b} ~> +*{a b}
+    Some(4) => Term::apply(Op::Lus, nock(subject(), noun(args.clone()))),
+    // *{a 5 b c} ~> ={*{a b} *{a c}}
+    Some(5) => {
+      let (b, c) = pair()?;
+      Term::apply(Op::Tis, Term::cell(nock(subject(), b), nock(subject(), c)))
+    }
+    // *{a 6 b c d} ~> *{a *{{c d} 0 *{{2 3} 0 *{a 4 4 b}}}}
+    Some(6) => {
+      let (b, cd) = args.try_cell()?;
+      cd.try_cell()?;
+
+      let test = nock(subject(), noun(noun!({4, {4, (b.clone())}})));
+      let pick = nock(noun(noun!({2, 3})), Term::cell(noun(0u64), test));
+      let branch = nock(noun(cd.clone()), Term::cell(noun(0u64), pick));
+      nock(subject(), branch)
+    }
+    // *{a 7 b c} ~> *{*{a b} c}
+    Some(7) => {
+      let (b, c) = pair()?;
+      nock(nock(subject(), b), c)
+    }
+    // *{a 8 b c} ~> *{{*{a b} a} c}
+    Some(8) => {
+      let (b, c) = pair()?;
+      nock(Term::cell(nock(subject(), b), subject()), c)
+    }
+    // *{a 9 b c} ~> *{*{a c} 2 {0 1} 0 b}
+    Some(9) => {
+      let (b, c) = args.try_cell()?;
+      let arm = noun!({2, {{0, 1}, {0, (b.clone())}}});
+      nock(nock(subject(), noun(c.clone())), noun(arm))
+    }
+    // *{a 10 {b c} d} ~> #{b *{a c} *{a d}}
+    Some(10) => {
+      let (bc, d) = args.try_cell()?;
+      let (b, c) = bc.try_cell()?;
+      let edit = Term::cell(
+        nock(subject(), noun(c.clone())),
+        nock(subject(), noun(d.clone())),
+      );
+      Term::apply(Op::Hax, Term::cell(noun(b.clone()), edit))
+    }
+    // *{a 11 {b c} d} ~> *{{*{a c} *{a d}} 0 3}
+    // *{a 11 b c}     ~> *{a c}
+    Some(11) => {
+      let (b, d) = args.try_cell()?;
+      match b.as_cell() {
+        Some((_, c)) => {
+          let both = Term::cell(
+            nock(subject(), noun(c.clone())),
+            nock(subject(), noun(d.clone())),
+          );
+          nock(both, noun(noun!({0, 3})))
+        }
+        None => nock(subject(), noun(d.clone())),
+      }
+    }
+    // scry has no rule of its own here, there's no namespace to ask
+    _ => return Err(NockError::UnknownOpcode(op.clone())),
+  };
+
+  Ok(term)
+}
+
+fn is_even(atom: &Atom) -> bool {
+  atom.words()[0] & 1 == 0
+}
+
+// /{1 a} ~> a
+// /{2 {a b}} ~> a
+// /{3 {a b}} ~> b
+// /{(a + a) b} ~> /{2 /{a b}}
+// /{(a + a + 1) b} ~> /{3 /{a b}}
+fn rewrite_fas(arg: Noun) -> Result<Term, NockError> {
+  let (axis, tree) = arg.try_cell()?;
+  let axis = axis.try_atom()?;
+
+  match axis.as_u64() {
+    Some(0) => Err(NockError::ZeroAxis),
+    Some(1) => Ok(noun(tree.clone())),
+    Some(2 | 3) => {
+      let Some((head, tail)) = tree.as_cell() else {
+        return Err(NockError::AxisOutOfTree { axis: axis.clone() });
+      };
+      Ok(noun(if is_even(axis) { head } else { tail }.clone()))
+    }
+    _ => {
+      let half = noun(Noun::cell(Noun::atom(bits::rsh(0, 1, axis)), tree.clone()));
+      let side = if is_even(axis) { 2u64 } else { 3 };
+      Ok(Term::apply(
+        Op::Fas,
+        Term::cell(noun(side), Term::apply(Op::Fas, half)),
+      ))
+    }
+  }
+}
+
+// #{1 a b} ~> a
+// #{(a + a) b c} ~> #{a {b /{(a + a + 1) c}} c}
+// #{(a + a + 1) b c} ~> #{a {/{(a + a) c} b} c}
+fn rewrite_hax(arg: Noun) -> Result<Term, NockError> {
+  let (axis, bc) = arg.try_cell()?;
+  let (b, c) = bc.try_cell()?;
+  let axis = axis.try_atom()?;
+
+  if axis.is_zero() {
+    return Err(NockError::ZeroAxis);
+  }
+  if axis.as_u64() == Some(1) {
+    return Ok(noun(b.clone()));
+  }
+
+  let parent = Noun::atom(bits::rsh(0, 1, axis));
+  // the sibling's axis only differs in the lowest bit
+  let sibling = Noun::atom(bits::mix(axis, &Atom::new(1)));
+  let sibling = Term::apply(Op::Fas, noun(Noun::cell(sibling, c.clone())));
+
+  let pair = if is_even(axis) {
+    Term::cell(noun(b.clone()), sibling)
+  } else {
+    Term::cell(sibling, noun(b.clone()))
+  };
+
+  Ok(Term::apply(
+    Op::Hax,
+    Term::cell(noun(parent), Term::cell(pair, noun(c.clone()))),
+  ))
+}
+
+// every expression from `*{subject formula}` on, the last being the product
+// or the crash it got stuck on
+pub struct Reduction {
+  term: Option<Term>,
+  started: bool,
+}
+
+pub fn reduce(subject: Noun, formula: Noun) -> Reduction {
+  Reduction {
+    term: Some(nock(noun(subject), noun(formula))),
+    started: false,
+  }
+}
+
+impl Iterator for Reduction {
+  type Item = Result<Term, NockError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if !self.started {
+      self.started = true;
+      return self.term.clone().map(Ok);
+    }
+
+    let term = self.term.take()?;
+    if term.as_noun().is_some() {
+      return None;
+    }
+
+    match step(term) {
+      Ok(term) => {
+        self.term = Some(term.clone());
+        Some(Ok(term))
+      }
+      Err(e) => Some(Err(e)),
+    }
+  }
+}
+
+// runs a reduction to the end
+pub fn eval(subject: Noun, formula: Noun) -> Result<Noun, NockError> {
+  let mut product = None;
+  for term in reduce(subject, formula) {
+    product = Some(term?);
+  }
+
+  match product {
+    Some(Term::Noun(noun)) => Ok(noun),
+    _ => unreachable!("a reduction ends in a noun or a crash"),
+  }
+}
+
+fn write_term(f: &mut std::fmt::Formatter<'_>, term: &Term, tail: bool) -> std::fmt::Result {
+  match term {
+    Term::Noun(noun) if tail && noun.is_cell() => {
+      let noun = noun.to_string();
+      write!(f, "{}", &noun[1..noun.len() - 1])
+    }
+    Term::Noun(noun) => write!(f, "{noun}"),
+    Term::Cell(head, rest) => {
+      if !tail {
+        write!(f, "{{")?;
+      }
+      write_term(f, head, false)?;
+      write!(f, " ")?;
+      write_term(f, rest, true)?;
+      if !tail {
+        write!(f, "}}")?;
+      }
+      Ok(())
+    }
+    Term::Apply(op, arg) => {
+      write!(f, "{}", op.symbol())?;
+      write_term(f, arg, false)
+    }
+  }
+}
+
+// in the notation of the rules, `+*{{42 43} 0 2}`
+impl std::fmt::Display for Term {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write_term(f, self, false)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{eval, reduce};
+  use crate::{error::NockError, interp::Formula, interp::Subject, noun::Noun};
+
+  #[test]
+  fn test_steps() {
+    let steps: Vec<_> = reduce(noun!({42, 43}), noun!({incr, {addr, 2}}))
+      .map(|term| term.unwrap().to_string())
+      .collect();
+
+    assert_eq!(
+      steps,
+      [
+        "*{{42 43} 4 0 2}",
+        "+*{{42 43} 0 2}",
+        "+/{2 42 43}",
+        "+42",
+        "43"
+      ]
+    );
+
+    let steps: Vec<_> = reduce(noun!({4, 5}), noun!({rplc, {{2, {idty, 9}}, {addr, 1}}}))
+      .map(|term| term.unwrap().to_string())
+      .collect();
+    assert_eq!(steps[1], "#{2 *{{4 5} 1 9} *{{4 5} 0 1}}");
+    assert_eq!(steps[5], "#{1 {9 /{3 4 5}} 4 5}");
+    assert_eq!(steps.last().unwrap(), "{9 5}");
+  }
+
+  #[test]
+  fn test_against_interpreter() {
+    // decrement, as in `interp::test::test_decr`
+    let test = noun!({eqal, {{addr, 7}, {incr, {addr, 6}}}});
+    let nah = noun!({invk, {2, {{addr, 2}, {{incr, {addr, 6}}, {addr, 7}}}}});
+    let r#loop = noun!({idty, {brch, {(test), {{addr, 6}, (nah)}}}});
+    let decr = noun!({extn, {{idty, 0}, {extn, {(r#loop), {invk, {2, {addr, 1}}}}}}});
+
+    let cases = [
+      (noun!(5), decr),
+      (
+        noun!({{4, 5}, {6, 7}}),
+        noun!({{addr, 7}, {cmps, {{addr, 2}, {eval, {{addr, 1}, {idty, {addr, 3}}}}}}}),
+      ),
+      (
+        noun!({1, 2}),
+        noun!({hint, {{3, {cell, {addr, 1}}}, {cell, {addr, 3}}}}),
+      ),
+      (
+        noun!({0, 0}),
+        noun!({brch, {{eqal, {{addr, 2}, {addr, 3}}}, {{idty, 11}, {idty, 12}}}}),
+      ),
+      (
+        noun!({0, 1}),
+        noun!({brch, {{eqal, {{addr, 2}, {addr, 3}}}, {{idty, 11}, {idty, 12}}}}),
+      ),
+      (
+        noun!(0),
+        noun!({rplc, {{6, {idty, 1}}, {idty, {1, {2, 3}}}}}),
+      ),
+    ];
+
+    for (subject, formula) in cases {
+      let expected = crate::eval(Subject(subject.clone()), Formula(formula.clone())).unwrap();
+      assert_eq!(eval(subject, formula).unwrap(), expected);
+    }
+  }
+
+  #[test]
+  fn test_crashes() {
+    let crash = |subject: Noun, formula: Noun| eval(subject, formula).unwrap_err();
+
+    assert!(matches!(
+      crash(noun!(0), noun!({addr, 0})),
+      NockError::ZeroAxis
+    ));
+    assert!(matches!(
+      crash(noun!(0), noun!({addr, 2})),
+      NockError::AxisOutOfTree { .. }
+    ));
+    assert!(matches!(
+      crash(noun!(0), noun!({incr, {idty, {1, 2}}})),
+      NockError::AtomRequired { .. }
+    ));
+    assert!(matches!(
+      crash(noun!(0), noun!({99, 0})),
+      NockError::UnknownOpcode(_)
+    ));
+    assert!(matches!(crash(noun!(0), noun!(7)), NockError::Exit { .. }));
+    // a non-loobean condition crashes picking a branch
+    assert!(matches!(
+      crash(noun!(0), noun!({brch, {{idty, 2}, {{idty, 0}, {idty, 1}}}})),
+      NockError::AxisOutOfTree { .. }
+    ));
+  }
+}
@@ -0,0 +1,295 @@
+// Nouns and atoms in one word each, for the `tagged` feature, the way
+// production nock runtimes keep them. An atom below 2^63 is the word itself,
+// so it's never allocated, and a noun of one is copied, compared and dropped
+// as an integer. Anything else is a pointer to an `Rc` the word holds a count
+// of, with the top bit set, which no user-space address has, and what it
+// points to in the low bits, which alignment leaves clear:
+//
+//   0  an indirect atom, its words after how many there are
+//   1  a mapped atom, see src/serial.rs
+//   2  a cell
+//
+// An `Atom` is the same word as a noun of it, so `Noun::as_atom` borrows it
+// straight out of the noun, and a direct atom's word is its only word.
+
+use std::{marker::PhantomData, mem::ManuallyDrop, ptr};
+
+#[cfg(feature = "mmap")]
+use crate::serial::Mapped;
+use crate::{
+  atom::Atom,
+  noun::{Cell, NounInner, Rc, Weak},
+};
+
+pub(crate) const HEAP: u64 = 1 << 63;
+pub(crate) const TAG: u64 = 0b11;
+const WORDS: u64 = 0;
+#[cfg(feature = "mmap")]
+const MAPPED: u64 = 1;
+pub(crate) const CELL: u64 = 2;
+
+const _: () = assert!(size_of::<usize>() == 8, "tagged nouns need 64-bit pointers");
+const _: () = assert!(align_of::<u64>() > TAG as usize && align_of::<Cell>() > TAG as usize);
+
+#[repr(transparent)]
+pub(crate) struct Word {
+  word: u64,
+  // holds a count of what it points to, and is only as `Send` and `Sync` as
+  // the cells it might
+  _heap: PhantomData<Rc<Cell>>,
+}
+
+impl Word {
+  const fn from_word(word: u64) -> Self {
+    Self {
+      word,
+      _heap: PhantomData,
+    }
+  }
+
+  fn heap<T: ?Sized>(rc: Rc<T>, tag: u64) -> Self {
+    let addr = Rc::into_raw(rc).cast::<u8>().expose_provenance() as u64;
+    assert!(addr & (HEAP | TAG) == 0, "no room to tag {addr:#x}");
+    Self::from_word(addr | HEAP | tag)
+  }
+
+  // what it points to, None for a direct atom
+  fn tag(&self) -> Option<u64> {
+    (self.word >= HEAP).then_some(self.word & TAG)
+  }
+
+  fn at<T>(&self) -> *const T {
+    ptr::with_exposed_provenance((self.word & !(HEAP | TAG)) as usize)
+  }
+
+  // an indirect atom's words, after how many there are
+  fn words_ptr(&self) -> *const [u64] {
+    let block = self.at::<u64>();
+    // SAFETY: only called on indirect atoms, whose blocks start with that
+    let len = unsafe { *block } as usize;
+    ptr::slice_from_raw_parts(block, len + 1)
+  }
+
+  // native code holds words as they are, see src/jit.rs
+  #[cfg(feature = "jit")]
+  pub(crate) fn raw(&self) -> u64 {
+    self.word
+  }
+
+  // and takes over their counts
+  #[cfg(feature = "jit")]
+  pub(crate) fn into_raw(self) -> u64 {
+    ManuallyDrop::new(self).word
+  }
+
+  /// # Safety
+  ///
+  /// `word` has to come from `into_raw`, and is given up.
+  #[cfg(feature = "jit")]
+  pub(crate) unsafe fn from_raw(word: u64) -> Self {
+    Self::from_word(word)
+  }
+
+  pub(crate) fn new(atom: u64) -> Self {
+    if atom < HEAP {
+      Self::from_word(atom)
+    } else {
+      Self::indirect(&[atom])
+    }
+  }
+
+  pub(crate) const fn small(atom: u8) -> Self {
+    Self::from_word(atom as u64)
+  }
+
+  // an atom of 2^63 or more, without trailing zero words
+  pub(crate) fn indirect(words: &[u64]) -> Self {
+    let block: Rc<[u64]> = std::iter::once(words.len() as u64)
+      .chain(words.iter().copied())
+      .collect();
+    Self::heap(block, WORDS)
+  }
+
+  #[cfg(feature = "mmap")]
+  pub(crate) fn mapped(mapped: Mapped) -> Self {
+    Self::heap(Rc::new(mapped), MAPPED)
+  }
+
+  pub(crate) const fn atom(atom: Atom) -> Self {
+    // SAFETY: an `Atom` is only its word
+    unsafe { std::mem::transmute::<Atom, Word>(atom) }
+  }
+
+  pub(crate) fn cell(cell: Cell) -> Self {
+    Self::heap(Rc::new(cell), CELL)
+  }
+
+  pub(crate) fn as_u64(&self) -> Option<u64> {
+    match self.tag() {
+      None => Some(self.word),
+      Some(WORDS) => match *self.words() {
+        [word] => Some(word),
+        _ => None,
+      },
+      Some(_) => None,
+    }
+  }
+
+  pub(crate) fn words(&self) -> &[u64] {
+    match self.tag() {
+      None => std::slice::from_ref(&self.word),
+      Some(WORDS) => {
+        // SAFETY: the word holds a count of the block, so it outlives `self`
+        let block = unsafe { &*self.words_ptr() };
+        &block[1..]
+      }
+      #[cfg(feature = "mmap")]
+      Some(MAPPED) => self.as_mapped().unwrap().words(),
+      Some(_) => unreachable!("a cell has no words"),
+    }
+  }
+
+  #[cfg(feature = "mmap")]
+  pub(crate) fn as_mapped(&self) -> Option<&Mapped> {
+    // SAFETY: the word holds a count of the mapped atom
+    (self.tag() == Some(MAPPED)).then(|| unsafe { &*self.at::<Mapped>() })
+  }
+
+  // whether both point to the same thing
+  pub(crate) fn same(&self, other: &Self) -> bool {
+    self.word >= HEAP && self.word == other.word
+  }
+
+  // `Rc` keeps its counts in front of the block
+  #[cfg(feature = "road")]
+  pub(crate) fn block(&self) -> Option<(*const u8, usize)> {
+    (self.tag() == Some(WORDS)).then(|| {
+      let words = self.words_ptr();
+      (words.cast::<u8>(), (words.len() + 2) * size_of::<u64>())
+    })
+  }
+
+  pub(crate) fn inner(&self) -> NounInner<'_> {
+    if self.tag() == Some(CELL) {
+      // SAFETY: the word holds a count of the cell
+      return NounInner::Cell(unsafe { &*self.at::<Cell>() });
+    }
+
+    // SAFETY: an `Atom` is only its word, and this one isn't a cell's
+    NounInner::Atom(unsafe { &*ptr::from_ref(self).cast::<Atom>() })
+  }
+
+  pub(crate) fn downgrade(&self) -> Option<Weak<Cell>> {
+    if self.tag() != Some(CELL) {
+      return None;
+    }
+
+    // SAFETY: the word holds a count of the cell, which isn't given up
+    let cell = ManuallyDrop::new(unsafe { Rc::from_raw(self.at::<Cell>()) });
+    Some(Rc::downgrade(&cell))
+  }
+
+  pub(crate) fn upgrade(cell: &Weak<Cell>) -> Option<Self> {
+    Some(Self::heap(cell.upgrade()?, CELL))
+  }
+
+  // the cell, if nothing else holds it, leaving 0 in its place
+  pub(crate) fn take_unique(&mut self) -> Option<Cell> {
+    if self.tag() != Some(CELL) {
+      return None;
+    }
+
+    // SAFETY: as in `downgrade`
+    let cell = ManuallyDrop::new(unsafe { Rc::from_raw(self.at::<Cell>()) });
+    if Rc::strong_count(&cell) != 1 {
+      return None;
+    }
+    self.word = 0;
+    Rc::into_inner(ManuallyDrop::into_inner(cell))
+  }
+}
+
+impl Clone for Word {
+  fn clone(&self) -> Self {
+    // SAFETY: the word holds a count of what it points to, so it's alive
+    unsafe {
+      match self.tag() {
+        None => {}
+        Some(WORDS) => Rc::increment_strong_count(self.words_ptr()),
+        Some(CELL) => Rc::increment_strong_count(self.at::<Cell>()),
+        #[cfg(feature = "mmap")]
+        Some(MAPPED) => Rc::increment_strong_count(self.at::<Mapped>()),
+        Some(_) => unreachable!(),
+      }
+    }
+    Self::from_word(self.word)
+  }
+}
+
+impl Drop for Word {
+  fn drop(&mut self) {
+    // SAFETY: the word's count is given back, and the word is gone after
+    unsafe {
+      match self.tag() {
+        None => {}
+        Some(WORDS) => drop(Rc::from_raw(self.words_ptr())),
+        Some(CELL) => drop(Rc::from_raw(self.at::<Cell>())),
+        #[cfg(feature = "mmap")]
+        Some(MAPPED) => drop(Rc::from_raw(self.at::<Mapped>())),
+        Some(_) => unreachable!(),
+      }
+    }
+  }
+}
+
+impl std::fmt::Debug for Word {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.inner() {
+      NounInner::Cell(cell) => f.debug_tuple("Cell").field(cell).finish(),
+      NounInner::Atom(..) if self.tag().is_none() => {
+        f.debug_tuple("Direct").field(&self.word).finish()
+      }
+      NounInner::Atom(atom) => f.debug_tuple("Indirect").field(&atom.words()).finish(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::HEAP;
+  use crate::{atom::Atom, noun::Noun};
+
+  #[test]
+  fn test_tagged() {
+    assert_eq!(size_of::<Noun>(), 8);
+    assert_eq!(size_of::<Atom>(), 8);
+
+    // below 2^63 in the word, the rest on the heap, as the same atoms
+    let direct = Atom::new(HEAP - 1);
+    let big = Atom::new(HEAP);
+    let within = |atom: &Atom| std::ptr::eq(atom.words().as_ptr(), std::ptr::from_ref(atom).cast());
+    assert!(within(&direct) && !within(&big));
+    assert_eq!(big.as_u64(), Some(HEAP));
+    assert_eq!(big.words(), [HEAP]);
+    assert_eq!(Atom::from_words(&[HEAP, 0]), big);
+    assert_eq!(direct.incr(), big);
+    assert_eq!(big.incr(), Atom::new(HEAP + 1));
+    assert_eq!(Atom::new(u64::MAX).incr().words(), [0, 1]);
+
+    // an atom borrowed out of a noun is the noun's own word
+    let noun = Noun::from(big.clone());
+    let atom = noun.as_atom().unwrap();
+    assert!(std::ptr::eq(atom, std::ptr::from_ref(&noun).cast()));
+    assert_eq!(atom.words().as_ptr(), big.words().as_ptr());
+
+    // clones count, and the last one frees the cell
+    let cell = Noun::cell(noun, Noun::from(big));
+    let weak = cell.downgrade().unwrap();
+    let copy = cell.clone();
+    assert_eq!(weak.strong_count(), 2);
+    drop(cell);
+    assert_eq!(copy.slot(2).unwrap(), Noun::from(Atom::new(HEAP)));
+    drop(copy);
+    assert_eq!(weak.strong_count(), 0);
+  }
+}
@@ -0,0 +1,46 @@
+// What the engines' tests share.
+
+use crate::{
+  compile::CompiledFormula,
+  interp::{Engine, Formula, Interpreter, Subject},
+  noun::Noun,
+};
+
+// a way of running formulas that has to agree with the interpreter
+pub(crate) enum Way {
+  Engine(Engine),
+  // `eval_compiled`
+  Compiled,
+}
+
+impl From<Engine> for Way {
+  fn from(engine: Engine) -> Self {
+    Way::Engine(engine)
+  }
+}
+
+// `formula` on `subject` run `way` and by the tree interpreter, errors
+// compared by display
+pub(crate) fn both(way: impl Into<Way>, subject: Noun, formula: Noun) -> Result<Noun, String> {
+  let mut it = Interpreter::new();
+  let expected = it
+    .eval(Subject(subject.clone()), Formula(formula.clone()))
+    .map_err(|e| e.to_string());
+
+  let prod = match way.into() {
+    Way::Engine(engine) => {
+      let builder = Interpreter::builder().engine(engine);
+      // every program native from its first call
+      #[cfg(feature = "jit")]
+      let builder = builder.jit_after(0);
+      builder
+        .build()
+        .eval(Subject(subject), Formula(formula.clone()))
+    }
+    Way::Compiled => it.eval_compiled(Subject(subject), &CompiledFormula::new(&formula)),
+  };
+  let prod = prod.map_err(|e| e.to_string());
+
+  assert_eq!(prod, expected, "{formula}");
+  prod
+}
@@ -0,0 +1,393 @@
+// Rust source for a fixed formula, against the library's `Noun` API, as
+// `nuuk compile foo.nock --emit rust` writes it. The output is a module with
+// an `eval(interpreter, subject)` function.
+//
+// Besides the formula itself, every formula it quotes gets a function of its
+// own, and so does each arm of a quoted battery, so opcodes 2 and 9 land on
+// compiled code whenever they pick a formula known ahead of time. Anything
+// else, like scries, extensions or formulas made up at run time, goes to the
+// interpreter, and so do formulas nested deeper than `DEPTH`, which would
+// only make the transpiler and then rustc overflow their stacks. Hints are
+// skipped as with `hints(false)`, errors don't say which opcode they came
+// from, and calls not in tail position recurse on the native stack.
+
+use std::collections::HashMap;
+
+use crate::{
+  axis::peg,
+  compile::{Op, shape},
+  noun::Noun,
+};
+
+// how deep expressions nest before the rest of a formula is interpreted
+const DEPTH: usize = 200;
+
+enum Source {
+  Text,
+  // at an axis of another constant, sharing its cells
+  Slot(usize, u64),
+}
+
+struct Transpiler {
+  consts: Vec<(Noun, Source)>,
+  known: HashMap<Noun, usize>,
+  // constants that get a function, in the order they're written
+  entries: Vec<usize>,
+  is_entry: Vec<bool>,
+  vars: usize,
+  // of the expression being written
+  depth: usize,
+}
+
+impl Transpiler {
+  fn constant(&mut self, noun: &Noun) -> usize {
+    if let Some(&i) = self.known.get(noun) {
+      return i;
+    }
+
+    self.push(noun, Source::Text)
+  }
+
+  fn push(&mut self, noun: &Noun, source: Source) -> usize {
+    self.consts.push((noun.clone(), source));
+    self.is_entry.push(false);
+    self.known.insert(noun.clone(), self.consts.len() - 1);
+    self.consts.len() - 1
+  }
+
+  fn entry(&mut self, i: usize) {
+    if !self.is_entry[i] {
+      self.is_entry[i] = true;
+      self.entries.push(i);
+    }
+  }
+
+  // a quoted noun, and the formulas within it that get functions
+  fn quoted(&mut self, noun: &Noun) -> usize {
+    let i = self.constant(noun);
+    let mut pending = vec![(noun.clone(), 1)];
+
+    while let Some((noun, axis)) = pending.pop() {
+      let Some((head, tail)) = noun.as_cell() else {
+        continue;
+      };
+      if head.is_cell() {
+        pending.extend(peg(axis, 3).map(|axis| (tail.clone(), axis)));
+        pending.extend(peg(axis, 2).map(|axis| (head.clone(), axis)));
+        continue;
+      }
+
+      let j = match self.known.get(&noun) {
+        Some(&j) => j,
+        None => self.push(&noun, Source::Slot(i, axis)),
+      };
+      self.entry(j);
+    }
+
+    i
+  }
+
+  fn var(&mut self, prefix: &str) -> String {
+    self.vars += 1;
+    format!("{prefix}{}", self.vars)
+  }
+
+  fn fallback(&mut self, formula: &Noun, subj: &str) -> String {
+    let i = self.constant(formula);
+    format!("it.eval(Subject({subj}.clone()), Formula(k[{i}].clone()))?")
+  }
+
+  // an expression for the product of `formula`
+  fn expr(&mut self, formula: &Noun, subj: &str) -> String {
+    if self.depth == DEPTH {
+      return self.fallback(formula, subj);
+    }
+    self.depth += 1;
+    let expr = self.nested(formula, subj);
+    self.depth -= 1;
+    expr
+  }
+
+  fn nested(&mut self, formula: &Noun, subj: &str) -> String {
+    let (op, kids) = shape(formula);
+
+    match op {
+      Op::Cons => format!(
+        "Noun::cell({}, {})",
+        self.expr(&kids[0], subj),
+        self.expr(&kids[1], subj)
+      ),
+      Op::Slot(axis) => match axis.as_u64() {
+        Some(axis) => format!("{subj}.slot({axis})?"),
+        None => self.fallback(formula, subj),
+      },
+      Op::Quote(noun) => format!("k[{}].clone()", self.quoted(&noun)),
+      Op::Eval => format!(
+        "run(it, k, {}, {})?",
+        self.expr(&kids[0], subj),
+        self.expr(&kids[1], subj)
+      ),
+      Op::Cell => format!("Noun::from(({}).is_cell())", self.expr(&kids[0], subj)),
+      Op::Incr => format!(
+        "Noun::atom(({}).try_atom()?.incr())",
+        self.expr(&kids[0], subj)
+      ),
+      Op::Eqal => format!(
+        "Noun::from({} == {})",
+        self.expr(&kids[0], subj),
+        self.expr(&kids[1], subj)
+      ),
+      Op::Brch => format!(
+        "if cond({})? {{ {} }} else {{ {} }}",
+        self.expr(&kids[0], subj),
+        self.expr(&kids[1], subj),
+        self.expr(&kids[2], subj)
+      ),
+      Op::Cmps | Op::Extn => {
+        let (bind, var) = self.bind(&op, &kids[0], subj);
+        format!("{{ {bind} {} }}", self.expr(&kids[1], &var))
+      }
+      Op::Invk(ref axis) => match axis.as_atom().and_then(|axis| axis.as_u64()) {
+        Some(axis) => {
+          let core = self.var("c");
+          format!(
+            "{{ let {core} = {}; let arm = {core}.slot({axis})?; run(it, k, {core}, arm)? }}",
+            self.expr(&kids[0], subj)
+          )
+        }
+        None => self.fallback(formula, subj),
+      },
+      Op::Rplc(axis) => match axis.as_u64() {
+        Some(axis) => {
+          let value = self.var("v");
+          format!(
+            "{{ let {value} = {}; ({}).edit({axis}, {value})? }}",
+            self.expr(&kids[0], subj),
+            self.expr(&kids[1], subj)
+          )
+        }
+        None => self.fallback(formula, subj),
+      },
+      Op::Hint { .. } => self.expr(kids.last().unwrap(), subj),
+      Op::Atom | Op::Scry | Op::Other(..) | Op::Crash(_) => self.fallback(formula, subj),
+    }
+  }
+
+  // the new subject of opcode 7 or 8
+  fn bind(&mut self, op: &Op, b: &Noun, subj: &str) -> (String, String) {
+    let var = self.var("s");
+    let value = self.expr(b, subj);
+    let bind = match op {
+      Op::Extn => format!("let {var} = Noun::cell({value}, {subj}.clone());"),
+      _ => format!("let {var} = {value};"),
+    };
+
+    (bind, var)
+  }
+
+  // a statement returning `Next` for `formula` in tail position
+  fn tail(&mut self, formula: &Noun, subj: &str) -> String {
+    if self.depth == DEPTH {
+      return format!("return Ok(Next::Done({}));", self.fallback(formula, subj));
+    }
+    self.depth += 1;
+    let tail = self.nested_tail(formula, subj);
+    self.depth -= 1;
+    tail
+  }
+
+  fn nested_tail(&mut self, formula: &Noun, subj: &str) -> String {
+    let (op, kids) = shape(formula);
+
+    match op {
+      Op::Eval => format!(
+        "return Ok(Next::Tail({}, {}));",
+        self.expr(&kids[0], subj),
+        self.expr(&kids[1], subj)
+      ),
+      Op::Brch => format!(
+        "if cond({})? {{ {} }} else {{ {} }}",
+        self.expr(&kids[0], subj),
+        self.tail(&kids[1], subj),
+        self.tail(&kids[2], subj)
+      ),
+      Op::Cmps | Op::Extn => {
+        let (bind, var) = self.bind(&op, &kids[0], subj);
+        format!("{{ {bind} {} }}", self.tail(&kids[1], &var))
+      }
+      Op::Invk(ref axis) => match axis.as_atom().and_then(|axis| axis.as_u64()) {
+        Some(axis) => {
+          let core = self.var("c");
+          format!(
+            "{{ let {core} = {}; let arm = {core}.slot({axis})?; return Ok(Next::Tail({core}, arm)); }}",
+            self.expr(&kids[0], subj)
+          )
+        }
+        None => format!("return Ok(Next::Done({}));", self.fallback(formula, subj)),
+      },
+      Op::Hint { .. } => self.tail(kids.last().unwrap(), subj),
+      _ => format!("return Ok(Next::Done({}));", self.expr(formula, subj)),
+    }
+  }
+}
+
+const TEMPLATE: &str = "// generated by `nuuk compile --emit rust`, don't edit
+
+#[allow(unused, clippy::all)]
+pub mod $name {
+  use std::rc::Rc;
+
+  use nuuk::{Formula, Interpreter, NockError, Noun, Subject};
+
+  pub fn eval(it: &mut Interpreter, subject: Noun) -> Result<Noun, NockError> {
+    let k = consts();
+    run(it, &k, subject, k[0].clone())
+  }
+
+  enum Next {
+    Done(Noun),
+    Tail(Noun, Noun),
+  }
+
+  fn run(it: &mut Interpreter, k: &[Noun], mut s: Noun, mut f: Noun) -> Result<Noun, NockError> {
+    loop {
+      let next = $dispatch {
+        return it.eval(Subject(s), Formula(f));
+      };
+      match next {
+        Next::Done(product) => return Ok(product),
+        Next::Tail(subject, formula) => (s, f) = (subject, formula),
+      }
+    }
+  }
+
+  fn cond(noun: Noun) -> Result<bool, NockError> {
+    match noun.as_atom().and_then(|atom| atom.as_u64()) {
+      Some(0) => Ok(true),
+      Some(1) => Ok(false),
+      _ => Err(NockError::NonLoobeanCondition { value: noun }),
+    }
+  }
+
+  fn consts() -> Rc<[Noun]> {
+    thread_local! {
+      static K: Rc<[Noun]> = {
+        let mut k: Vec<Noun> = vec![];
+$consts        k.into()
+      };
+    }
+    K.with(Rc::clone)
+  }
+$functions}
+";
+
+pub fn to_rust(formula: &Noun, name: &str) -> String {
+  let mut t = Transpiler {
+    consts: vec![],
+    known: HashMap::new(),
+    entries: vec![],
+    is_entry: vec![],
+    vars: 0,
+    depth: 0,
+  };
+  // the formula itself is always constant 0
+  t.constant(formula);
+  t.entry(0);
+
+  let mut functions = vec![];
+  let mut next = 0;
+  while let Some(&i) = t.entries.get(next) {
+    next += 1;
+    t.vars = 0;
+    let formula = t.consts[i].0.clone();
+    functions.push((i, t.tail(&formula, "s")));
+  }
+
+  let dispatch: Vec<String> = functions
+    .iter()
+    .map(|(i, _)| format!("if f == k[{i}] {{\n        f{i}(it, k, s)?\n      }}"))
+    .collect();
+  let consts: String = t
+    .consts
+    .iter()
+    .map(|(noun, source)| match source {
+      Source::Text => format!("        k.push(\"{noun}\".parse().unwrap());\n"),
+      Source::Slot(i, axis) => format!("        k.push(k[{i}].slot({axis}).unwrap());\n"),
+    })
+    .collect();
+  let functions: String = functions
+    .iter()
+    .map(|(i, body)| {
+      format!(
+        "\n  fn f{i}(it: &mut Interpreter, k: &[Noun], s: Noun) -> Result<Next, NockError> {{\n    {body}\n  }}\n"
+      )
+    })
+    .collect();
+
+  TEMPLATE
+    .replace("$name", name)
+    .replace("$dispatch", &(dispatch.join(" else ") + " else"))
+    .replace("$consts", &consts)
+    .replace("$functions", &functions)
+}
+
+#[cfg(test)]
+mod test {
+  use super::to_rust;
+  use crate::{interp::Interpreter, noun::Noun};
+
+  include!("../benches/kernels/decrement.rs");
+
+  #[test]
+  fn test_to_rust() {
+    let formula: Noun = include_str!("../benches/kernels/decrement.nock")
+      .trim()
+      .parse()
+      .unwrap();
+    assert_eq!(
+      to_rust(&formula, "decrement"),
+      include_str!("../benches/kernels/decrement.rs")
+    );
+
+    let mut it = Interpreter::new();
+    for n in [1u64, 10, 100_000] {
+      assert_eq!(
+        decrement::eval(&mut it, Noun::from(n)).unwrap(),
+        Noun::from(n - 1)
+      );
+    }
+  }
+
+  #[test]
+  fn test_to_rust_fallback() {
+    // scries and unknown opcodes go to the interpreter, hints are dropped
+    let rust = to_rust(
+      &noun!({{12, {{addr, 1}, {addr, 1}}}, {hint, {{1, {99, 0}}, {addr, 1}}}}),
+      "k",
+    );
+    assert!(rust.contains("it.eval(Subject(s.clone()), Formula(k[1].clone()))?"));
+    assert!(rust.contains("k.push(\"{12 {0 1} 0 1}\".parse().unwrap());"));
+    // only in the formula's own text
+    assert_eq!(rust.matches("99").count(), 1);
+  }
+
+  #[test]
+  fn test_to_rust_deep() {
+    // past `DEPTH` the rest goes to the interpreter, once
+    let mut formula = noun!({addr, 1});
+    for _ in 0..200_000 {
+      formula = noun!({incr, (formula)});
+    }
+    let rust = to_rust(&formula, "deep");
+    assert_eq!(rust.matches("it.eval(Subject(s.clone())").count(), 1);
+    assert_eq!(rust.matches(".incr()").count(), super::DEPTH - 1);
+
+    let mut formula = noun!({addr, 1});
+    for _ in 0..200_000 {
+      formula = noun!({cmps, {{addr, 1}, (formula)}});
+    }
+    let rust = to_rust(&formula, "deep");
+    assert_eq!(rust.matches("let s").count(), super::DEPTH);
+    assert!(rust.contains("return Ok(Next::Done(it.eval(Subject(s200.clone())"));
+  }
+}
@@ -0,0 +1,198 @@
+// Static checks on formulas: every opcode gets the arity and shapes Nock 4K
+// expects, without evaluating anything. A formula that passes can still
+// crash, but one that fails always would once it got there.
+
+use crate::{axis::peg, interp::Formula, noun::Noun, opcode::Opcode};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+  // within the formula, `None` below depth 63
+  pub axis: Option<u64>,
+  pub message: String,
+}
+
+impl std::fmt::Display for Issue {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.axis {
+      Some(axis) => write!(f, "{} at axis {axis}", self.message),
+      None => write!(f, "{} deep in the formula", self.message),
+    }
+  }
+}
+
+struct Checker {
+  formulas: Vec<(Option<u64>, Noun)>,
+  issues: Vec<Issue>,
+}
+
+impl Checker {
+  fn issue(&mut self, axis: Option<u64>, message: impl Into<String>) {
+    self.issues.push(Issue {
+      axis,
+      message: message.into(),
+    });
+  }
+
+  // the head and tail of `noun`, or an issue if it's an atom
+  fn pair(&mut self, axis: Option<u64>, noun: &Noun, opcode: Opcode) -> Option<(Noun, Noun)> {
+    let Some((head, tail)) = noun.as_cell() else {
+      self.issue(axis, format!("opcode {opcode} needs more arguments"));
+      return None;
+    };
+
+    Some((head.clone(), tail.clone()))
+  }
+
+  fn axis(&mut self, axis: Option<u64>, noun: &Noun) {
+    match noun.as_atom() {
+      Some(atom) if atom.is_zero() => self.issue(axis, "axis can't be zero"),
+      Some(_) => {}
+      None => self.issue(axis, "an axis must be an atom"),
+    }
+  }
+
+  fn formula(&mut self, axis: Option<u64>, noun: Noun) {
+    self.formulas.push((axis, noun));
+  }
+
+  fn check(&mut self, axis: Option<u64>, formula: &Noun) {
+    let head = axis.and_then(|axis| peg(axis, 2));
+    let tail = axis.and_then(|axis| peg(axis, 3));
+
+    let Some((op, arg)) = formula.as_cell() else {
+      return self.issue(axis, "a formula must be a cell");
+    };
+
+    if op.is_cell() {
+      self.formula(head, op.clone());
+      self.formula(tail, arg.clone());
+      return;
+    }
+
+    let Ok(opcode) = Opcode::try_from(op.as_atom().unwrap().clone()) else {
+      return self.issue(head, format!("unknown opcode {op}"));
+    };
+
+    // axes of the head and tail of `arg`
+    let (b, c) = (tail.and_then(|t| peg(t, 2)), tail.and_then(|t| peg(t, 3)));
+
+    match opcode {
+      Opcode::Addr => self.axis(tail, arg),
+      Opcode::Idty => {}
+      Opcode::Cell | Opcode::Incr => self.formula(tail, arg.clone()),
+      Opcode::Eval | Opcode::Eqal | Opcode::Cmps | Opcode::Extn | Opcode::Scry => {
+        if let Some((x, y)) = self.pair(tail, arg, opcode) {
+          self.formula(b, x);
+          self.formula(c, y);
+        }
+      }
+      Opcode::Brch => {
+        if let Some((x, yz)) = self.pair(tail, arg, opcode) {
+          self.formula(b, x);
+          if let Some((y, z)) = self.pair(c, &yz, opcode) {
+            self.formula(c.and_then(|c| peg(c, 2)), y);
+            self.formula(c.and_then(|c| peg(c, 3)), z);
+          }
+        }
+      }
+      Opcode::Invk => {
+        if let Some((x, y)) = self.pair(tail, arg, opcode) {
+          self.axis(b, &x);
+          self.formula(c, y);
+        }
+      }
+      Opcode::Rplc => {
+        if let Some((xy, z)) = self.pair(tail, arg, opcode) {
+          if let Some((x, y)) = self.pair(b, &xy, opcode) {
+            self.axis(b.and_then(|b| peg(b, 2)), &x);
+            self.formula(b.and_then(|b| peg(b, 3)), y);
+          }
+          self.formula(c, z);
+        }
+      }
+      Opcode::Hint => {
+        if let Some((x, y)) = self.pair(tail, arg, opcode) {
+          if let Some((tag, clue)) = x.as_cell() {
+            if tag.is_cell() {
+              self.issue(b.and_then(|b| peg(b, 2)), "a hint tag must be an atom");
+            }
+            self.formula(b.and_then(|b| peg(b, 3)), clue.clone());
+          }
+          self.formula(c, y);
+        }
+      }
+    }
+  }
+}
+
+impl Formula {
+  // every issue in the formula, empty if it looks well formed; each formula's
+  // own issues come before those of its sub-formulas
+  pub fn validate(formula: &Noun) -> Vec<Issue> {
+    let mut checker = Checker {
+      formulas: vec![(Some(1), formula.clone())],
+      issues: vec![],
+    };
+
+    while let Some((axis, formula)) = checker.formulas.pop() {
+      let pending = checker.formulas.len();
+      checker.check(axis, &formula);
+      // visit children left to right
+      checker.formulas[pending..].reverse();
+    }
+
+    checker.issues
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::interp::Formula;
+
+  fn issues(formula: crate::Noun) -> Vec<String> {
+    Formula::validate(&formula)
+      .iter()
+      .map(ToString::to_string)
+      .collect()
+  }
+
+  #[test]
+  fn test_validate() {
+    assert!(
+      issues(noun!({brch, {{eqal, {{addr, 2}, {idty, 0}}}, {{addr, 3}, {incr, {addr, 1}}}}}))
+        .is_empty()
+    );
+    assert!(issues(noun!({{addr, 1}, {rplc, {{2, {idty, 0}}, {addr, 1}}}})).is_empty());
+    assert!(issues(noun!({hint, {{7, {addr, 1}}, {addr, 1}}})).is_empty());
+
+    assert_eq!(issues(noun!(0)), ["a formula must be a cell at axis 1"]);
+    assert_eq!(issues(noun!({addr, 0})), ["axis can't be zero at axis 3"]);
+    assert_eq!(issues(noun!({99, 0})), ["unknown opcode 99 at axis 2"]);
+    assert_eq!(
+      issues(noun!({brch, {addr, 1}})),
+      [
+        "opcode 6 needs more arguments at axis 7",
+        "a formula must be a cell at axis 6"
+      ]
+    );
+    assert_eq!(
+      issues(noun!({rplc, {{addr, 1}, {addr, 1}}})),
+      [
+        "axis can't be zero at axis 12",
+        "a formula must be a cell at axis 13"
+      ]
+    );
+
+    // every issue is reported, left to right
+    assert_eq!(
+      issues(noun!({{invk, {{addr, 1}, 5}}, {{hint, {{{1, 2}, 7}, {idty, 0}}}, {eval, 3}}})),
+      [
+        "an axis must be an atom at axis 10",
+        "a formula must be a cell at axis 11",
+        "a hint tag must be an atom at axis 52",
+        "a formula must be a cell at axis 53",
+        "opcode 2 needs more arguments at axis 15",
+      ]
+    );
+  }
+}
@@ -0,0 +1,143 @@
+// JavaScript bindings, for running formulas in a browser. Nouns cross either
+// jammed, as a Uint8Array, or as a `Noun`, a handle to one kept on this side
+// that's parsed, printed, jammed and cued without copying it out. Errors are
+// thrown as JS `Error`s.
+//
+// `eval` is reserved in the strict mode modules are in, so evaluation is
+// `evaluate` on the JS side.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+  interp::Interpreter,
+  noun::Noun,
+  serial::{CueError, cue_bytes, jam_bytes},
+};
+
+#[wasm_bindgen(js_name = Noun)]
+#[derive(Clone)]
+pub struct JsNoun(Noun);
+
+fn error(e: impl std::fmt::Display) -> JsError {
+  JsError::new(&e.to_string())
+}
+
+fn eval_noun(subject: &Noun, formula: &Noun) -> Result<Noun, String> {
+  Interpreter::new()
+    .eval(subject.clone().into(), formula.clone().into())
+    .map_err(|e| e.to_string())
+}
+
+fn eval_jammed(subject: &[u8], formula: &[u8]) -> Result<Vec<u8>, String> {
+  let cue = |bytes| cue_bytes(bytes).map_err(|e: CueError| e.to_string());
+  Ok(jam_bytes(&eval_noun(&cue(subject)?, &cue(formula)?)?))
+}
+
+#[wasm_bindgen(js_class = Noun)]
+impl JsNoun {
+  pub fn parse(text: &str) -> Result<JsNoun, JsError> {
+    text.parse().map(JsNoun).map_err(error)
+  }
+
+  pub fn cue(bytes: &[u8]) -> Result<JsNoun, JsError> {
+    cue_bytes(bytes).map(JsNoun).map_err(error)
+  }
+
+  pub fn cell(head: &JsNoun, tail: &JsNoun) -> JsNoun {
+    JsNoun(Noun::cell(head.0.clone(), tail.0.clone()))
+  }
+
+  pub fn jam(&self) -> Vec<u8> {
+    jam_bytes(&self.0)
+  }
+
+  #[wasm_bindgen(js_name = toString)]
+  pub fn print(&self) -> String {
+    self.0.to_string()
+  }
+
+  #[wasm_bindgen(js_name = isCell)]
+  pub fn is_cell(&self) -> bool {
+    self.0.is_cell()
+  }
+
+  // undefined for an atom
+  pub fn head(&self) -> Option<JsNoun> {
+    self.0.as_cell().map(|(head, _)| JsNoun(head.clone()))
+  }
+
+  pub fn tail(&self) -> Option<JsNoun> {
+    self.0.as_cell().map(|(_, tail)| JsNoun(tail.clone()))
+  }
+
+  pub fn equals(&self, other: &JsNoun) -> bool {
+    self.0 == other.0
+  }
+}
+
+// the product of `formula` against `subject`
+#[wasm_bindgen]
+pub fn evaluate(subject: &JsNoun, formula: &JsNoun) -> Result<JsNoun, JsError> {
+  eval_noun(&subject.0, &formula.0).map(JsNoun).map_err(error)
+}
+
+// the same, all jammed
+#[wasm_bindgen(js_name = evaluateJammed)]
+pub fn evaluate_jammed(subject: &[u8], formula: &[u8]) -> Result<Vec<u8>, JsError> {
+  eval_jammed(subject, formula).map_err(error)
+}
+
+#[wasm_bindgen]
+pub fn parse(text: &str) -> Result<JsNoun, JsError> {
+  JsNoun::parse(text)
+}
+
+#[wasm_bindgen]
+pub fn print(noun: &JsNoun) -> String {
+  noun.print()
+}
+
+#[wasm_bindgen]
+pub fn jam(noun: &JsNoun) -> Vec<u8> {
+  noun.jam()
+}
+
+#[wasm_bindgen]
+pub fn cue(bytes: &[u8]) -> Result<JsNoun, JsError> {
+  JsNoun::cue(bytes)
+}
+
+// JsError can only be made on wasm, so only what succeeds is tested here
+#[cfg(test)]
+mod test {
+  use super::{JsNoun, cue, eval_jammed, eval_noun, evaluate, evaluate_jammed, jam, parse, print};
+  use crate::serial::jam_bytes;
+
+  fn noun(text: &str) -> JsNoun {
+    parse(text).ok().unwrap()
+  }
+
+  #[test]
+  fn test_wasm() {
+    let subject = noun("{4 5}");
+    let formula = noun("{4 0 3}");
+    let product = evaluate(&subject, &formula).ok().unwrap();
+    assert_eq!(print(&product), "6");
+    assert!(product.equals(&noun("6")));
+
+    let jammed = evaluate_jammed(&jam(&subject), &formula.jam())
+      .ok()
+      .unwrap();
+    assert_eq!(jammed, jam_bytes(&noun!(6)));
+    assert!(cue(&jammed).ok().unwrap().equals(&product));
+
+    let cell = JsNoun::cell(&subject, &formula);
+    assert!(cell.is_cell() && !product.is_cell());
+    assert_eq!(cell.head().unwrap().print(), "{4 5}");
+    assert_eq!(cell.tail().unwrap().print(), "{4 0 3}");
+    assert!(product.head().is_none());
+
+    assert!(eval_noun(&noun!(1), &noun!({0, 7})).is_err());
+    assert!(eval_jammed(&jam_bytes(&noun!(1)), &[]).is_err());
+  }
+}